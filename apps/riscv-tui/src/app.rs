@@ -4,33 +4,71 @@ use std::sync::mpsc::{self, Receiver};
 
 use anyhow::Result;
 
-use riscv_core::RiscV;
+use riscv_core::{Isa, MachineConfig, RiscV, UartInput, UartOutput};
 #[cfg(not(feature = "zicsr"))]
 use riscv_core::RiscVError;
 use riscv_core::debug::DebugInterface;
 use riscv_disasm::disasm;
 use riscv_loader::LoadInfo;
 
+use crate::console::Console;
 use crate::event::{self, EmuEvent};
 use crate::event::key::KeyControl;
 use crate::state::{EmuMode, EmuState};
 use crate::ui;
 use crate::ui::terminal::EmuTerminal;
 
+/// What gets loaded besides the program
+#[derive(Debug, Default)]
+pub struct Boot {
+    /// Loaded after the program, like a kernel after the firmware
+    pub images: Vec<LoadInfo>,
+    pub initrd: Option<LoadInfo>,
+    pub bootargs: Option<String>,
+    /// Generated from the machine when not given
+    pub dtb: Option<Vec<u8>>,
+}
+
 #[derive(Debug)]
 pub struct EmuApp {
     mach: RiscV,
     info: LoadInfo,
+    images: Vec<LoadInfo>,
     state: EmuState,
+    console: Console,
+    uart_input: UartInput,
     should_quit: bool,
     event_rx: Receiver<EmuEvent>,
 }
 
 impl EmuApp {
-    pub fn new(info: LoadInfo) -> Result<Self> {
-        let mut mach = RiscV::default();
+    pub fn new(info: LoadInfo, isa: Option<Isa>, config: &MachineConfig, boot: Boot) -> Result<Self> {
+        let mut mach = config.build()?;
+        if let Some(isa) = isa {
+            mach.set_isa(isa);
+        }
         mach.load_info(&info)?;
 
+        let mut images = boot.images;
+        if let Some(initrd) = boot.initrd {
+            if let Some((data, addr)) = initrd.code.first() {
+                mach.set_initrd(*addr, data.len())?;
+            }
+            images.push(initrd);
+        }
+        for image in images.iter() {
+            mach.load_image(image)?;
+        }
+        if let Some(bootargs) = &boot.bootargs {
+            mach.set_bootargs(bootargs);
+        }
+        let dtb = boot.dtb.unwrap_or_else(|| mach.fdt());
+        mach.load_dtb(dtb)?;
+
+        let console = Console::default();
+        mach.set_uart_output(UartOutput::new(console.clone()));
+        let uart_input = mach.uart_input();
+
         let ins_list = disasm::disassembler(&info);
         let state = EmuState::new(&mach, ins_list);
         
@@ -39,7 +77,7 @@ impl EmuApp {
         event::spawn_event_thread(event_tx);
 
         Ok(EmuApp { 
-            mach, info, state, 
+            mach, info, images, state, console, uart_input,
             should_quit: false, event_rx 
         })
     }
@@ -56,12 +94,13 @@ impl EmuApp {
     }
 
     fn step(&mut self) -> Result<()> {
-        if let Some(except) = self.mach.step()? {
-            self.state.mach_snap.update_exception(except);
+        if let Some(trap) = self.mach.step()? {
+            self.state.mach_snap.update_exception(trap);
             #[cfg(not(feature = "zicsr"))]
             return Err(anyhow::Error::new(RiscVError::Exception));
         }
         self.state.mach_snap.update_snapshot(&self.mach);
+        self.state.console = self.console.text();
         Ok(())
     }
 
@@ -76,6 +115,7 @@ impl EmuApp {
                         EmuMode::BusPopup    => self.key_popup(key),
                     },
                     KeyControl::Edit(key) => self.key_editting(key),
+                    KeyControl::Console(key) => self.key_console(key),
                 }
                 
             },
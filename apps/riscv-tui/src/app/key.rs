@@ -20,9 +20,7 @@ impl EmuApp {
             #[cfg(feature = "zicsr")]
             ChangeMid   => self.state.change_mid(),
             ChangeView  => self.state.change_view(),
-            BreakPoint  => if self.state.selected == Selected::Ins {
-                self.state.breakpoint()
-            },
+            BreakPoint if self.state.selected == Selected::Ins => self.state.breakpoint(),
             SearchBus   => {
                 self.state.show_search_popup = true;
                 self.state.input.mode.edit();
@@ -42,6 +40,7 @@ impl EmuApp {
             GoNext     => self.state.next(),
             GoPrev     => self.state.prev(),
             ShowInfo   => self.state.show_info_popup = !self.state.show_info_popup,
+            Console    => self.state.console_focus = true,
             ChangeMode => {
                 self.state.observation_mode_selected();
                 self.state.mode.observation();
@@ -49,8 +48,13 @@ impl EmuApp {
             Reset => {
                 self.mach.reset();
                 self.mach.load_info(&self.info)?;
+                for image in self.images.iter() {
+                    self.mach.load_image(image)?;
+                }
                 self.state.mach_snap.update_snapshot(&self.mach);
                 self.state.mach_snap.reset_exception();
+                self.console.clear();
+                self.state.console.clear();
             },
             Step => {
                 #[cfg(not(feature = "zicsr"))]
@@ -77,6 +81,7 @@ impl EmuApp {
         match key {
             Quit => self.should_quit = true,
             RunToEnd => self.state.mode.stay(),
+            Console  => self.state.console_focus = true,
             ChangeMode => {
                 self.state.observation_mode_selected();
                 self.state.mode.observation();
@@ -98,6 +103,21 @@ impl EmuApp {
         }
     }
 
+    pub(super) fn key_console(&mut self, key: EditKeyControl) {
+        use EditKeyControl::*;
+        match key {
+            Enter       => self.uart_input.push(b"\r"),
+            Delete      => self.uart_input.push(&[0x7f]),
+            CursorLeft  => self.uart_input.push(b"\x1b[D"),
+            CursorRight => self.uart_input.push(b"\x1b[C"),
+            Char(new_char) => {
+                let mut buf = [0; 4];
+                self.uart_input.push(new_char.encode_utf8(&mut buf).as_bytes());
+            },
+            Exit => self.state.console_focus = false,
+        }
+    }
+
     pub(super) fn key_editting(&mut self, key: EditKeyControl) {
         use EditKeyControl::*;
         match key {
@@ -5,6 +5,7 @@ use anyhow::Result;
 
 use riscv_core::{Exception, RiscV, RiscVError};
 use riscv_core::debug::{DebugInterface, MachineInfo};
+use riscv_core::XReg;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EmuCmd {
@@ -24,10 +25,10 @@ pub enum MachineMode {
 #[derive(Debug, Clone, PartialEq)]
 pub struct MachineData {
     pub info: MachineInfo,
-    pub reg: Vec<u32>,
+    pub reg: Vec<XReg>,
     #[cfg(feature = "zicsr")]
-    pub csr: Vec<(String, u32)>,
-    pub pc: u32,
+    pub csr: Vec<(String, XReg)>,
+    pub pc: XReg,
 }
 
 impl MachineData {
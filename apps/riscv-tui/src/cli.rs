@@ -1,8 +1,9 @@
 use std::env;
 
+use riscv_core::{Isa, IsaError, XReg};
 use thiserror::Error;
 
-const USAGE: &str = "Usage: cargo run <.elf>";
+const USAGE: &str = "Usage: cargo run [--isa <rv32imac_zicsr_zifencei>] [--config <machine.toml>] [--dtb <machine.dtb>] [--image <file>@<addr>]... [--initrd <file>@<addr>] [--bootargs <args>] <.elf>";
 
 #[derive(Error, Debug)]
 pub enum CliError {
@@ -11,28 +12,92 @@ pub enum CliError {
 
     #[error("Too many input file\n{}", USAGE)]
     TooManyArgument,
+
+    #[error("--isa needs an ISA string\n{}", USAGE)]
+    NoIsaString,
+
+    #[error("--config needs a file\n{}", USAGE)]
+    NoConfigFile,
+
+    #[error("--dtb needs a file\n{}", USAGE)]
+    NoDtbFile,
+
+    #[error("{0} needs <file>@<address>\n{usage}", usage = USAGE)]
+    NoImage(String),
+
+    #[error("--bootargs needs a command line\n{}", USAGE)]
+    NoBootargs,
+
+    #[error("Invalid ISA string: {0}")]
+    InvalidIsa(#[from] IsaError),
 }
 
-/// Load CLI argument from `env::args().skip(1)`. Only accept one binary file for now.
+#[derive(Debug)]
+pub struct Args {
+    pub file: String,
+    /// Every compiled-in extension when not given
+    pub isa: Option<Isa>,
+    /// Default memory map when not given
+    pub config: Option<String>,
+    /// Device tree generated from the machine config when not given
+    pub dtb: Option<String>,
+    /// More files loaded after the program, a raw binary goes to its address
+    pub images: Vec<(String, XReg)>,
+    pub initrd: Option<(String, XReg)>,
+    pub bootargs: Option<String>,
+}
+
+// `<file>@<address>`, the address in hex with `0x` or in decimal
+fn image_arg(flag: &str, arg: Option<String>) -> Result<(String, XReg), CliError> {
+    let error = || CliError::NoImage(flag.to_string());
+    let arg = arg.ok_or_else(error)?;
+    let (file, addr) = arg.rsplit_once('@').ok_or_else(error)?;
+    let addr = match addr.strip_prefix("0x") {
+        Some(hex) => XReg::from_str_radix(&hex.replace('_', ""), 16),
+        None => addr.replace('_', "").parse(),
+    };
+    Ok((file.to_string(), addr.map_err(|_| error())?))
+}
+
+/// Load CLI argument from `env::args().skip(1)`. Only accept one binary file for now,
+/// `--isa` picks the extensions out of the compiled-in ones, `--config` reads a machine config file
+/// and `--dtb` hands the program a device tree blob instead of the generated one.
+/// `--image` loads another file, like a kernel after the firmware, and `--initrd` and `--bootargs`
+/// go into the generated device tree.
 /// ## Example
 /// ```bash
 /// # Here is bash
-/// cargo run binary_file
-/// ```
-/// ```rust,no_run
-/// // Rust
-/// # use risc_v_emulator::riscv::loader;
-/// if let Ok(binary_file_name) = loader::load_arg() {
-///     assert_eq!(binary_file_name, String::from("binary_file"));
-/// }
+/// cargo run -- --isa rv32imac_zicsr_zifencei binary_file
+/// cargo run -- --image Image@0x80400000 --initrd rootfs.cpio@0x84000000 fw_jump.elf
 /// ```
-pub fn load_arg() -> Result<String, CliError>{
+pub fn load_arg() -> Result<Args, CliError>{
     let mut args = env::args().skip(1);
+    let mut file = None;
+    let mut isa = None;
+    let mut config = None;
+    let mut dtb = None;
+    let mut images = Vec::new();
+    let mut initrd = None;
+    let mut bootargs = None;
+
+    while let Some(arg) = args.next() {
+        if arg == "--isa" {
+            let isa_str = args.next().ok_or(CliError::NoIsaString)?;
+            isa = Some(isa_str.parse()?);
+        } else if arg == "--config" {
+            config = Some(args.next().ok_or(CliError::NoConfigFile)?);
+        } else if arg == "--dtb" {
+            dtb = Some(args.next().ok_or(CliError::NoDtbFile)?);
+        } else if arg == "--image" {
+            images.push(image_arg(&arg, args.next())?);
+        } else if arg == "--initrd" {
+            initrd = Some(image_arg(&arg, args.next())?);
+        } else if arg == "--bootargs" {
+            bootargs = Some(args.next().ok_or(CliError::NoBootargs)?);
+        } else if file.replace(arg).is_some() {
+            return Err(CliError::TooManyArgument);
+        }
+    }
 
-    Ok(match args.len() {
-        0   => return Err(CliError::NoInputFile),
-        2.. => return Err(CliError::TooManyArgument),
-        // Safe: Here we sure args has the first element 
-        1   => args.next().unwrap(),
-    })
+    Ok(Args { file: file.ok_or(CliError::NoInputFile)?, isa, config, dtb, images, initrd, bootargs })
 }
@@ -0,0 +1,126 @@
+use std::fs;
+
+use riscv_core::{BankKind, MachineConfig, MemoryBank, XReg};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Can not read machine config: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid machine config: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("Flash bank at {0:#x} needs a ctrl_base")]
+    NoFlashCtrl(XReg),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DeviceKind {
+    Clint,
+    Plic,
+    Uart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    #[default]
+    Ram,
+    Rom,
+    Flash,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Dram {
+    base: XReg,
+    size: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Bank {
+    base: XReg,
+    size: usize,
+    #[serde(default)]
+    kind: Kind,
+    /// Flash controller registers
+    ctrl_base: Option<XReg>,
+}
+
+impl TryFrom<&Bank> for MemoryBank {
+    type Error = ConfigError;
+
+    fn try_from(bank: &Bank) -> Result<Self, Self::Error> {
+        let kind = match bank.kind {
+            Kind::Ram   => BankKind::Ram,
+            Kind::Rom   => BankKind::Rom,
+            Kind::Flash => BankKind::Flash { ctrl_base: bank.ctrl_base.ok_or(ConfigError::NoFlashCtrl(bank.base))? },
+        };
+        Ok(MemoryBank { base: bank.base, size: bank.size, kind })
+    }
+}
+
+/// Machine config file, anything left out keeps the default memory map
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    harts: Option<usize>,
+    reset_vector: Option<XReg>,
+    boot_rom: Option<XReg>,
+    dram: Option<Dram>,
+    #[serde(default)]
+    banks: Vec<Bank>,
+    /// Built-in devices that exist, all of them when not given
+    devices: Option<Vec<DeviceKind>>,
+    clint_base: Option<XReg>,
+    plic_base: Option<XReg>,
+    uart_base: Option<XReg>,
+}
+
+impl TryFrom<ConfigFile> for MachineConfig {
+    type Error = ConfigError;
+
+    fn try_from(file: ConfigFile) -> Result<Self, Self::Error> {
+        let default = MachineConfig::default();
+        let exists = |kind| file.devices.as_ref().is_none_or(|devices| devices.contains(&kind));
+        let device = |kind, base: Option<XReg>, default| exists(kind).then(|| base.or(default)).flatten();
+
+        Ok(MachineConfig {
+            harts: file.harts.unwrap_or(default.harts),
+            dram_base: file.dram.as_ref().map_or(default.dram_base, |dram| dram.base),
+            dram_size: file.dram.as_ref().map_or(default.dram_size, |dram| dram.size),
+            banks: file.banks.iter().map(MemoryBank::try_from).collect::<Result<_, _>>()?,
+            reset_vector: file.reset_vector,
+            boot_rom: file.boot_rom,
+            clint_base: device(DeviceKind::Clint, file.clint_base, default.clint_base),
+            plic_base: device(DeviceKind::Plic, file.plic_base, default.plic_base),
+            uart_base: device(DeviceKind::Uart, file.uart_base, default.uart_base),
+        })
+    }
+}
+
+/// Read a TOML machine config.
+/// ## Example
+/// ```toml
+/// boot_rom = 0x1000
+/// devices = ["clint", "uart"]
+/// uart_base = 0x1001_3000
+///
+/// [dram]
+/// base = 0x8000_0000
+/// size = 0x4000
+///
+/// [[banks]]
+/// base = 0x2000_0000
+/// size = 0x20_0000
+/// kind = "flash"
+/// ctrl_base = 0x1002_0000
+/// ```
+pub fn load_config(path: &str) -> Result<MachineConfig, ConfigError> {
+    let file: ConfigFile = toml::from_str(&fs::read_to_string(path)?)?;
+    file.try_into()
+}
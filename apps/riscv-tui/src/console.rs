@@ -0,0 +1,39 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+const CONSOLE_CAPACITY: usize = 16 * 1024;
+
+/// UART output captured for the console panel, so guest prints do not break the terminal
+#[derive(Debug, Clone, Default)]
+pub struct Console(Arc<Mutex<Vec<u8>>>);
+
+impl Console {
+    pub fn text(&self) -> String {
+        self.0.lock()
+            .map(|buf| String::from_utf8_lossy(&buf).replace('\r', ""))
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut buf) = self.0.lock() {
+            buf.clear();
+        }
+    }
+}
+
+impl Write for Console {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if let Ok(mut buf) = self.0.lock() {
+            buf.extend_from_slice(data);
+            if buf.len() > CONSOLE_CAPACITY {
+                let overflow = buf.len() - CONSOLE_CAPACITY;
+                buf.drain(..overflow);
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
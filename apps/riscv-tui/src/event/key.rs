@@ -19,6 +19,7 @@ pub enum NormalKeyControl {
     ChangeView,
     ShowInfo,
     ClosePopup,
+    Console,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -35,6 +36,7 @@ pub enum EditKeyControl {
 pub enum KeyControl {
     Normal(NormalKeyControl),
     Edit(EditKeyControl),
+    Console(EditKeyControl),
 }
 
 pub fn get_normal_key(keycode: KeyCode) -> Option<NormalKeyControl> {
@@ -49,6 +51,7 @@ pub fn get_normal_key(keycode: KeyCode) -> Option<NormalKeyControl> {
             KeyCode::Char('v' | 'V') => SearchBus,
             KeyCode::Char('h' | 'H') => ChangeView,
             KeyCode::Char('i' | 'I') => ShowInfo,
+            KeyCode::Char('t' | 'T') => Console,
             KeyCode::Up              => GoPrev,
             KeyCode::Down            => GoNext,
             KeyCode::Left            => ChangePanel,
@@ -51,10 +51,11 @@ pub fn spawn_event_thread(tx: Sender<EmuEvent>) {
                                             emu_mode.stay();
                                             time = 16;
                                         },
-                                        NormalKeyControl::SearchBus => {
-                                            if emu_mode == EmuMode::Observation {
-                                                input_mode.edit();
-                                            }
+                                        NormalKeyControl::SearchBus if emu_mode == EmuMode::Observation => {
+                                            input_mode.edit();
+                                        },
+                                        NormalKeyControl::Console if matches!(emu_mode, EmuMode::Stay | EmuMode::Running) => {
+                                            input_mode.console();
                                         },
                                         _ => {},
                                     }
@@ -69,6 +70,14 @@ pub fn spawn_event_thread(tx: Sender<EmuEvent>) {
                                     tx.send(EmuEvent::Key(KeyControl::Edit(key)))?;
                                 }  
                             },
+                            InputMode::Console => {
+                                if let Some(key) = get_edit_key(code) {
+                                    if key == EditKeyControl::Exit {
+                                        input_mode = InputMode::Normal;
+                                    }
+                                    tx.send(EmuEvent::Key(KeyControl::Console(key)))?;
+                                }  
+                            },
                         }
                     },
                     Event::Resize(x, y) => tx.send(EmuEvent::Resize(x, y))?,
@@ -1,8 +1,11 @@
+use riscv_core::XReg;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum InputMode {
     #[default]
     Normal,
-    Editting
+    Editting,
+    Console,
 }
 
 impl InputMode {
@@ -13,6 +16,10 @@ impl InputMode {
     pub fn normal(&mut self) {
         *self = InputMode::Normal;
     }
+
+    pub fn console(&mut self) {
+        *self = InputMode::Console;
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -55,8 +62,8 @@ impl EmuInput {
         }
     }
 
-    pub fn submit(&mut self) -> Option<u32> {
-        let res = u32::from_str_radix(&self.chars[2..], 16).ok();
+    pub fn submit(&mut self) -> Option<XReg> {
+        let res = XReg::from_str_radix(&self.chars[2..], 16).ok();
         self.clear();
         res
     } 
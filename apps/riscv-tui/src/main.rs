@@ -1,5 +1,7 @@
 mod app;
 mod cli;
+mod config;
+mod console;
 mod event;
 mod input;
 mod state;
@@ -7,18 +9,31 @@ mod ui;
 
 use anyhow::Result;
 
-use riscv_loader::load;
+use riscv_core::MachineConfig;
+use riscv_loader::{load, load_at, load_dtb};
 
-use crate::app::EmuApp;
+use crate::app::{Boot, EmuApp};
 
 // Main entry for Risc-V emulator. Return any errors.
 fn main() -> Result<()> {
-    let filepath = &cli::load_arg()?;
+    let args = cli::load_arg()?;
 
     // Access file and load instructions into Risc-V's instruction memory
-    let info = load(filepath)?;
+    let info = load(&args.file)?;
 
-    let mut app = EmuApp::new(info)?;
+    let config = match &args.config {
+        Some(path) => config::load_config(path)?,
+        None => MachineConfig::default(),
+    };
+
+    let boot = Boot {
+        images: args.images.iter().map(|(file, addr)| load_at(file, *addr)).collect::<Result<_, _>>()?,
+        initrd: args.initrd.as_ref().map(|(file, addr)| load_at(file, *addr)).transpose()?,
+        bootargs: args.bootargs,
+        dtb: args.dtb.as_ref().map(load_dtb).transpose()?,
+    };
+
+    let mut app = EmuApp::new(info, args.isa, &config, boot)?;
     
     // Go into the TUI app loop
     app.run()?;
@@ -5,6 +5,7 @@ mod snapshot;
 use std::collections::HashSet;
 
 use riscv_core::debug::DebugInterface;
+use riscv_core::XReg;
 
 use snapshot::MachineSnapshot;
 
@@ -24,15 +25,18 @@ pub struct EmuState {
     pub show_search_popup: bool, 
     pub show_info_popup: bool, 
     pub show_bus_popup: bool, 
-    pub temp_bus_view: Option<(u32, Vec<u8>)>,
+    pub temp_bus_view: Option<(XReg, Vec<u8>)>,
 
     pub input: EmuInput,
 
+    pub console: String,
+    pub console_focus: bool,
+
     pub breakpoint_set: HashSet<usize>,
 }
 
 impl EmuState {
-    pub fn new<D: DebugInterface>(mach: &D, ins_list: Vec<(u32, String)>) -> Self {
+    pub fn new<D: DebugInterface>(mach: &D, ins_list: Vec<(XReg, String)>) -> Self {
         let mach_snap = MachineSnapshot::new(mach, ins_list);
 
         let mode = EmuMode::default();
@@ -46,13 +50,16 @@ impl EmuState {
         let temp_bus_view = None;
         let input = EmuInput::default();
 
+        let console = String::new();
+        let console_focus = false;
+
         let breakpoint_set = HashSet::new();
 
         EmuState { 
             mach_snap, 
             mode, selected, mid_selected, data_view,
             show_search_popup, show_bus_popup, show_info_popup, 
-            temp_bus_view, input, breakpoint_set
+            temp_bus_view, input, console, console_focus, breakpoint_set
         }
     }
 
@@ -76,6 +83,10 @@ impl EmuState {
                 Mid::Reg => self.mach_snap.reg
                     .next(self.mach_snap.reg.list.len()),
 
+                #[cfg(feature = "f")]
+                Mid::FReg => self.mach_snap.freg
+                    .next(self.mach_snap.freg.list.len()),
+
                 #[cfg(feature = "zicsr")]
                 Mid::Csr => self.mach_snap.csr.
                     next(self.mach_snap.csr.list.len()),
@@ -91,6 +102,10 @@ impl EmuState {
             Selected::Mid(m) => match m {
                 Mid::Reg => self.mach_snap.reg
                     .prev(self.mach_snap.reg.list.len()),
+
+                #[cfg(feature = "f")]
+                Mid::FReg => self.mach_snap.freg
+                    .prev(self.mach_snap.freg.list.len()),
                     
                 #[cfg(feature = "zicsr")]
                 Mid::Csr => self.mach_snap.csr
@@ -102,8 +117,13 @@ impl EmuState {
     #[cfg(feature = "zicsr")]
     pub fn change_mid(&mut self) {
         self.mid_selected = match self.mid_selected {
-            Mid::Reg => Mid::Csr,
-            Mid::Csr => Mid::Reg,
+            #[cfg(not(feature = "f"))]
+            Mid::Reg  => Mid::Csr,
+            #[cfg(feature = "f")]
+            Mid::Reg  => Mid::FReg,
+            #[cfg(feature = "f")]
+            Mid::FReg => Mid::Csr,
+            Mid::Csr  => Mid::Reg,
         };
         if matches!(self.selected, Selected::Mid(_)) {
             self.selected = Selected::Mid(self.mid_selected)
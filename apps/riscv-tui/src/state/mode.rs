@@ -2,6 +2,7 @@
 pub enum Mid {
     #[default]
     Reg,
+    #[cfg(feature = "f")] FReg,
     #[cfg(feature = "zicsr")] Csr,
 }
 
@@ -1,4 +1,5 @@
-use riscv_core::Exception;
+use riscv_core::Trap;
+use riscv_core::XReg;
 use riscv_core::debug::{DebugInterface, MachineInfo};
 
 use crate::state::list_state::ListStateRecord;
@@ -7,18 +8,22 @@ use crate::state::list_state::ListStateRecord;
 pub struct MachineSnapshot {
     pub info: MachineInfo,
 
-    pub ins: ListStateRecord<(u32, String)>,
-    pub reg: ListStateRecord<u32>,
+    pub ins: ListStateRecord<(XReg, String)>,
+    pub reg: ListStateRecord<XReg>,
+    #[cfg(feature = "f")]
+    pub freg: ListStateRecord<u64>,
     #[cfg(feature = "zicsr")]
-    pub csr: ListStateRecord<(String, u32)>,
-    pub pc: u32,
+    pub csr: ListStateRecord<(String, XReg)>,
+    pub pc: XReg,
     pub except: String,
 }
 
 impl MachineSnapshot {
-    pub fn new<D: DebugInterface>(mach: &D, ins_list: Vec<(u32, String)>) -> Self {
+    pub fn new<D: DebugInterface>(mach: &D, ins_list: Vec<(XReg, String)>) -> Self {
         let ins = ListStateRecord::new(ins_list);
         let reg = ListStateRecord::new(mach.inspect_regs().into_iter().collect());
+        #[cfg(feature = "f")]
+        let freg = ListStateRecord::new(mach.inspect_fregs().into_iter().collect());
         #[cfg(feature = "zicsr")]
         let csr = ListStateRecord::new(mach.inspect_csrs());
 
@@ -28,21 +33,25 @@ impl MachineSnapshot {
         let info = mach.get_info();
 
         MachineSnapshot { info, ins, reg, 
-            #[cfg(feature = "c")]csr, pc, except }
+            #[cfg(feature = "f")]freg,
+            #[cfg(feature = "zicsr")]csr, pc, except }
     }
 
     pub fn update_snapshot<D: DebugInterface>(&mut self, mach: &D) {
         self.info = mach.get_info();
         
         self.reg.list = mach.inspect_regs().into_iter().collect();
+        #[cfg(feature = "f")] {
+        self.freg.list = mach.inspect_fregs().into_iter().collect();
+        }
         #[cfg(feature = "zicsr")] {
         self.csr.list = mach.inspect_csrs();
         }
         self.pc = mach.inspect_pc();
     }
 
-    pub fn update_exception(&mut self, except: Exception) {
-        self.except = except.to_string()
+    pub fn update_exception(&mut self, trap: Trap) {
+        self.except = trap.to_string()
     }
 
     pub fn reset_exception(&mut self) {
@@ -17,7 +17,7 @@ use popup::*;
 
 const HEADER: &str = concat!("RsRisc-V Emulator v", env!("CARGO_PKG_VERSION"));
 const OBSERVATION_HINT_MESSAGE: &str = "(Q) Leave  (TAB) Change mode  (I) Information  (↑/↓) Scroll  (←/→) Change panel  (B) Breakpoint  (V) Bus Search";
-const EMULATE_HINT_MESSAGE: &str = "(Q) Leave  (TAB) Change mode  (I) Information  (↑/↓) Scroll  (S) Single step  (P) Run/Stop  (R) Reset";
+const EMULATE_HINT_MESSAGE: &str = "(Q) Leave  (TAB) Change mode  (I) Information  (↑/↓) Scroll  (S) Single step  (P) Run/Stop  (R) Reset  (T) Console input";
 
 pub(crate) const ANTI_FLASH_WHITE: Color = Color::Rgb(242, 242, 242);
 pub(crate) const BERKELEY_BLUE: Color = Color::Rgb(0, 50, 98);
@@ -75,6 +75,7 @@ fn render_paragraph(f: &mut Frame, area: Rect, message: &str) {
 fn render_content(f: &mut Frame, area: Rect, emu: &mut EmuState) {
     let layout = Layout::vertical([
         Constraint::Min(0),
+        Constraint::Length(8),
         Constraint::Length(3),
     ]).split(area);
     
@@ -86,8 +87,11 @@ fn render_content(f: &mut Frame, area: Rect, emu: &mut EmuState) {
     Instruction::render(f, info_layout[0], emu);
     match emu.mid_selected {
         Mid::Reg => Register::render(f, info_layout[1], emu),
+        #[cfg(feature = "f")]
+        Mid::FReg => FRegister::render(f, info_layout[1], emu),
         #[cfg(feature = "zicsr")]
         Mid::Csr => Csr::render(f, info_layout[1], emu),
     }
-    Exception::render(f, layout[1], emu);
+    Console::render(f, layout[1], emu);
+    Exception::render(f, layout[2], emu);
 }
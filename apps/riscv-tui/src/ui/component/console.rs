@@ -0,0 +1,35 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::widgets::{Block, Paragraph};
+
+use crate::state::EmuState;
+use crate::ui::component::Component;
+use crate::ui::{ANTI_FLASH_WHITE, BERKELEY_BLUE, CALIFORNIA_GOLD};
+
+const CONSOLE_TITLE: &str = "Console";
+const CONSOLE_INPUT_TITLE: &str = "Console (Typing, Esc to leave)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Console;
+
+impl Component for Console {
+    fn render(f: &mut Frame, area: Rect, emu: &mut EmuState) {
+        // Only the tail that fits inside the borders
+        let height = area.height.saturating_sub(2) as usize;
+        let lines: Vec<&str> = emu.console.split('\n').collect();
+        let tail = lines[lines.len().saturating_sub(height)..].join("\n");
+
+        let (title, fg) = if emu.console_focus {
+            (CONSOLE_INPUT_TITLE, ANTI_FLASH_WHITE)
+        } else {
+            (CONSOLE_TITLE, CALIFORNIA_GOLD)
+        };
+
+        let paragraph = Paragraph::new(tail)
+            .block(Block::bordered().title(title))
+            .style(Style::default().bg(BERKELEY_BLUE).fg(fg));
+
+        f.render_widget(paragraph, area);
+    }
+}
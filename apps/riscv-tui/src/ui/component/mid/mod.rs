@@ -1,8 +1,12 @@
 #[cfg(feature = "zicsr")]
 pub mod csr;
+#[cfg(feature = "f")]
+pub mod freg;
 pub mod register;
 
 #[cfg(not(feature = "zicsr"))]
 const MID_TITLE: &str = "Reg (H) Dec/Hex";
-#[cfg(feature = "zicsr")]
+#[cfg(all(feature = "zicsr", not(feature = "f")))]
 const MID_TITLE: &str = "(C) Reg / Csr (H) Dec/Hex ";
+#[cfg(feature = "f")]
+const MID_TITLE: &str = "(C) Reg / FReg / Csr (H) Dec/Hex ";
@@ -1,3 +1,4 @@
+mod console;
 mod instruction;
 mod exception;
 mod mid;
@@ -7,11 +8,14 @@ use ratatui::layout::Rect;
 
 use crate::state::EmuState;
 
+pub use console::Console;
 pub use instruction::Instruction;
 pub use exception::Exception;
 #[cfg(feature = "zicsr")]
 pub use mid::csr::Csr;
 pub use mid::register::Register;
+#[cfg(feature = "f")]
+pub use mid::freg::FRegister;
 
 pub trait Component {
     fn render(f: &mut Frame, area: Rect, emu: &mut EmuState);
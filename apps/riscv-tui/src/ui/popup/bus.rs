@@ -1,3 +1,5 @@
+use riscv_core::XReg;
+
 use crate::state::EmuState;
 use crate::ui::{ANTI_FLASH_WHITE, CALIFORNIA_GOLD};
 
@@ -18,7 +20,7 @@ pub fn render_popup(f: &mut Frame, emu: &EmuState) {
         bus.1.chunks_exact(4).enumerate()
             .map(|(i, raw)| 
                 ListItem::new(format!("{:#010x}: {}", 
-                    start_addr + (i * 4) as u32,
+                    start_addr + (i * 4) as XReg,
                     raw.iter().map(|byte| 
                             format!("{:02x} ", byte)
                         ).collect::<String>()
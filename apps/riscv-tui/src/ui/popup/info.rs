@@ -11,8 +11,9 @@ use ratatui::{
 pub fn render_popup(f: &mut Frame, emu: &EmuState) {
     let items = vec![
         ListItem::new(format!(" Dram Base: {:#010x}", emu.mach_snap.info.dram_base)),
-        ListItem::new(format!(" Dram Size: {} GB", emu.mach_snap.info.dram_size)),
-        ListItem::new(format!(" Page Size: {} KB", emu.mach_snap.info.page_size)),
+        ListItem::new(format!(" Dram Size: {}", format_size(emu.mach_snap.info.dram_size))),
+        ListItem::new(format!(" Page Size: {}", format_size(emu.mach_snap.info.page_size))),
+        ListItem::new(format!(" Misaligned: {}", emu.mach_snap.info.misaligned)),
         #[cfg(feature = "s")]
         ListItem::new(format!(" TLB Hit Rate: {:.2} %", emu.mach_snap.info.hit_rate * 100.0)),
         #[cfg(feature = "s")]
@@ -29,8 +30,19 @@ pub fn render_popup(f: &mut Frame, emu: &EmuState) {
     f.render_widget(list, area);
 }
 
+// Largest unit that keeps a whole number, so 1.5 GB shows as 1536 MB
+fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let (mut size, mut unit) = (bytes, 0);
+    while unit < UNITS.len() - 1 && size >= 1024 && size % 1024 == 0 {
+        size /= 1024;
+        unit += 1;
+    }
+    format!("{} {}", size, UNITS[unit])
+}
+
 fn popup_area(area: Rect) -> Rect {
-    let vertical = Layout::vertical([Constraint::Length(8)]).flex(Flex::End);
+    let vertical = Layout::vertical([Constraint::Length(9)]).flex(Flex::End);
     let horizontal = Layout::horizontal([Constraint::Length(30)]).flex(Flex::End);
     let [area] = vertical.areas(area);
     let [area] = horizontal.areas(area);
@@ -4,43 +4,83 @@ use riscv_decoder::prelude::*;
 
 use riscv_loader::LoadInfo;
 
-use crate::{Exception, Result, RiscVError, StdResult};
+use crate::{Exception, Result, RiscVError, StdResult, Trap};
+#[cfg(feature = "zicsr")]
+use crate::Interrupt;
 #[cfg(feature = "s")]
 use crate::core::Mmu;
 #[cfg(feature = "zicsr")]
-use crate::core::csr::CsrFile;
+use crate::core::csr::{CsrFile, DebugCause, HartIds, HpmEvent};
 #[cfg(feature = "zicsr")]
 use crate::core::privilege::PrivilegeMode;
 use crate::core::access::{Access, AccessType};
-use crate::device::bus::SystemBus;
+use crate::device::bus::SharedBus;
+use crate::device::clint::TimeSource;
+use crate::device::mmio::MmioDevice;
+use crate::device::uart::{UartInput, UartOutput};
+#[cfg(feature = "zicsr")]
+use crate::device::plic::{HART_CONTEXTS, M_CONTEXT};
+#[cfg(feature = "s")]
+use crate::device::plic::S_CONTEXT;
+#[cfg(feature = "c")]
 use crate::device::Device;
+use crate::engine::MisalignedPolicy;
+
 
+use super::{PC, RegisterFile, XReg};
+#[cfg(feature = "f")]
+use super::FRegisterFile;
 
-use super::{PC, RegisterFile};
+/// One hart. Cloning keeps the clone on the same bus
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Cpu {
+    pub(crate) hartid: usize,
     #[cfg(feature = "zicsr")]
     pub(crate) mode: PrivilegeMode,
     pub(crate) regs: RegisterFile,
+    #[cfg(feature = "f")]
+    pub(crate) fregs: FRegisterFile,
     pub(crate) pc: PC,
     #[cfg(feature = "zicsr")]
     pub(crate) csrs: CsrFile,
     #[cfg(feature = "s")]
     pub(crate) mmu: Mmu,
-    pub(crate) bus: SystemBus,
-    #[cfg(feature = "a")]
-    pub(crate) reservation: Option<u32>,
+    pub(crate) bus: SharedBus,
+    // Lives in `misa` when there are CSRs
+    #[cfg(not(feature = "zicsr"))]
+    pub(crate) isa: Isa,
+    pub(crate) misaligned: MisalignedPolicy,
     #[cfg(feature = "c")]
     pub(crate) is_compress: bool,
+    // Stopped by `wfi` until an interrupt is pending
+    #[cfg(feature = "zicsr")]
+    pub(crate) waiting: bool,
 }
 
 impl Cpu {
+    /// Hart `hartid` attached to `bus`
+    pub fn new(hartid: usize, bus: SharedBus) -> Self {
+        #[cfg(feature = "zicsr")]
+        let mut csrs = CsrFile::default();
+        #[cfg(feature = "zicsr")]
+        csrs.set_hartid(hartid as XReg);
+
+        let pc = PC::new(bus.borrow().reset_vector());
+
+        Cpu { hartid, pc, bus, #[cfg(feature = "zicsr")] csrs, ..Default::default() }
+    }
+
     pub fn load_info(&mut self, info: &LoadInfo) -> StdResult<(), RiscVError> {
+        self.load_image(info)?;
+        self.set_pc(info.pc_entry);
+        Ok(())
+    }
+
+    /// Load every segment and leave the PC alone
+    pub fn load_image(&mut self, info: &LoadInfo) -> StdResult<(), RiscVError> {
         for (code, addr) in info.code.iter() {
             self.load(*addr, code)?
         }
-        self.set_pc(info.pc_entry);
-        
         if let Some(data_vec) = &info.data {
             for (data, addr) in data_vec.iter() {
                 self.load(*addr, data)?
@@ -57,43 +97,253 @@ impl Cpu {
         Ok(())
     }
 
-    pub fn load(&mut self, addr: u32, data: &[u8]) -> StdResult<(), RiscVError> {
+    pub fn load(&mut self, addr: XReg, data: &[u8]) -> StdResult<(), RiscVError> {
         let access = Access::new(addr, AccessType::Store);
-        if self.bus.write_bytes(access, data.len(), data).is_err() {
+        if self.bus.borrow_mut().load(access, data).is_err() {
             Err(RiscVError::LoadFailed)
         } else {
             Ok(())
         }
     }
 
-    pub fn set_pc(&mut self, entry: u32) {
+    pub fn set_pc(&mut self, entry: XReg) {
         self.pc.set(entry);
     }
 
-    pub fn set_mem_zero(&mut self, addr: u32, size: usize) -> std::result::Result<(), RiscVError> {
+    pub fn set_mem_zero(&mut self, addr: XReg, size: usize) -> std::result::Result<(), RiscVError> {
         let access = Access::new(addr, AccessType::Store); 
     
-        self.bus.write_bytes(access, size, &vec![0; size]).map_err(|_| RiscVError::BssInitFailed)?;
+        self.bus.borrow_mut().load(access, &vec![0; size]).map_err(|_| RiscVError::BssInitFailed)?;
         Ok(())
     }
 
-    pub fn run(&mut self) -> StdResult<(), RiscVError> {
-        loop { self.step()?; }
+    /// Step a hart that has the bus to itself
+    #[cfg(test)]
+    pub fn step(&mut self) -> StdResult<Option<Trap>, RiscVError> {
+        self.step_hart(true)
     }
- 
-    pub fn step(&mut self) -> StdResult<Option<Exception>, RiscVError> {      
-        #[cfg(feature = "zicsr")]  
-        return Ok(if let Err(execpt) = self.cycle() {      
-            self.trap_handle(execpt);
-            Some(execpt) 
-        } else {
+
+    /// One step of this hart. `idle` lets a waiting hart move devices to their next event,
+    /// which is only right when no other hart is running
+    #[cfg_attr(not(feature = "zicsr"), allow(unused_variables))]
+    pub(crate) fn step_hart(&mut self, idle: bool) -> StdResult<Option<Trap>, RiscVError> {
+        #[cfg(feature = "zicsr")] {
+            // There is no program buffer, a hart in Debug Mode waits for the host
+            if self.csrs.is_halted() {
+                return Ok(None);
+            }
+
+            let stepping = self.csrs.is_stepping();
+            let trap = self.step_running(idle);
+            // A single step may also end in a trap handler or on `wfi`
+            if stepping && !self.csrs.is_halted() {
+                self.waiting = false;
+                self.enter_debug(DebugCause::Step);
+            }
+            Ok(trap)
+        }
+        #[cfg(not(feature = "zicsr"))] {
+            let res = self.cycle();
+            if res.is_ok() {
+                self.bus.borrow_mut().tick();
+            }
+            Ok(res.err().map(Trap::Exception))
+        }
+    }
+
+    #[cfg(feature = "zicsr")]
+    fn step_running(&mut self, idle: bool) -> Option<Trap> {
+        self.sync_interrupts();
+
+        if self.waiting && self.csrs.has_wakeup() {
+            self.waiting = false;
+        }
+
+        // Interrupts are taken at instruction boundaries, before the next fetch. NMIs go first
+        let trap = if self.csrs.step_masks_interrupts() {
             None
-        });
-        #[cfg(not(feature = "zicsr"))] 
-        Ok(self.cycle().err())
+        } else {
+            self.csrs.take_nmi()
+                .or_else(|| self.csrs.pending_interrupt(self.mode).map(Trap::Interrupt))
+        };
+        if let Some(trap) = trap {
+            self.trap_handle(trap);
+            self.csrs.tick_counters(false);
+            return Some(trap);
+        }
+
+        // Nothing to run, move devices straight to their next event
+        if self.waiting {
+            if idle {
+                self.bus.borrow_mut().idle();
+            }
+            self.csrs.tick_counters(false);
+            return None;
+        }
+
+        #[cfg(feature = "s")]
+        let misses = self.mmu.miss_count;
+
+        let res = self.cycle();
+
+        #[cfg(feature = "s")]
+        self.csrs.count_event(HpmEvent::TlbMiss, self.mmu.miss_count.wrapping_sub(misses) as u64);
+
+        let trap = match res {
+            Ok(()) => {
+                self.bus.borrow_mut().tick();
+                None
+            },
+            Err(execpt) => match self.csrs.debug_cause(execpt, self.mode) {
+                Some(cause) => {
+                    self.enter_debug(cause);
+                    None
+                },
+                None => {
+                    let trap = Trap::Exception(execpt);
+                    self.trap_handle(trap);
+                    Some(trap)
+                },
+            },
+        };
+        self.csrs.tick_counters(res.is_ok());
+        trap
+    }
+
+    pub fn set_time_source(&mut self, source: TimeSource) {
+        self.bus.borrow_mut().set_time_source(source);
+    }
+
+    /// Number of implemented `mhpmcounter` CSRs, from 0 to 29. Default is 4
+    #[cfg(feature = "zicsr")]
+    pub fn set_hpm_counters(&mut self, num: usize) {
+        self.csrs.set_hpm_num(num);
+    }
+
+    /// Implemented PMP entries, up to 64, and granularity `G`. Default is 16 entries with `G` = 0
+    #[cfg(feature = "zicsr")]
+    pub fn set_pmp(&mut self, entries: usize, granularity: u32) {
+        self.csrs.set_pmp(entries, granularity);
+    }
+
+    /// Enable Smrnmi with the RNMI interrupt and exception handler addresses. `mnstatus.NMIE`
+    /// resets to 0, which masks every interrupt until firmware sets it
+    #[cfg(feature = "zicsr")]
+    pub fn set_rnmi_handlers(&mut self, irq_handler: XReg, exc_handler: XReg) {
+        self.csrs.set_rnmi_handlers(irq_handler, exc_handler);
+    }
+
+    /// Latch an NMI, taken once `mnstatus.NMIE` is set. Ignored without Smrnmi
+    #[cfg(feature = "zicsr")]
+    pub fn raise_nmi(&mut self, cause: u32) {
+        self.csrs.raise_nmi(cause);
+    }
+
+    /// Halt into Debug Mode as if a debugger asked for it
+    #[cfg(feature = "zicsr")]
+    pub fn halt(&mut self) {
+        if !self.csrs.is_halted() {
+            self.enter_debug(DebugCause::HaltReq);
+        }
+    }
+
+    /// Leave Debug Mode like `dret`, back to `dpc` in the mode from `dcsr.prv`
+    #[cfg(feature = "zicsr")]
+    pub fn resume(&mut self) {
+        if let Ok((mode, pc)) = self.csrs.debug_return() {
+            self.pc.directed_addressing(pc);
+            self.mode = mode;
+        }
+    }
+
+    /// In Debug Mode
+    #[cfg(feature = "zicsr")]
+    pub fn is_halted(&self) -> bool {
+        self.csrs.is_halted()
+    }
+
+    /// Read a CSR like a debugger does through an abstract command, only while halted
+    #[cfg(feature = "zicsr")]
+    pub fn read_csr(&mut self, addr: u16) -> Option<XReg> {
+        self.is_halted().then(|| self.csrs.read(addr, PrivilegeMode::Machine, 0).ok()).flatten()
+    }
+
+    /// Write a CSR like a debugger does through an abstract command, only while halted.
+    /// The next instruction is the one at `dpc`
+    #[cfg(feature = "zicsr")]
+    pub fn write_csr(&mut self, addr: u16, data: XReg) -> bool {
+        let dpc = self.csrs.dpc();
+        self.is_halted() && self.csrs.write_at(addr, data, PrivilegeMode::Machine, 0, dpc).is_ok()
+    }
+
+    /// Values of `mvendorid`, `marchid`, `mimpid` and `mconfigptr`. Default is all 0
+    #[cfg(feature = "zicsr")]
+    pub fn set_hart_ids(&mut self, ids: HartIds) {
+        self.csrs.set_ids(ids);
+    }
+
+    /// Extensions this hart has, out of the compiled-in ones. With Zicsr, `misa` can switch some off
+    pub fn set_isa(&mut self, isa: Isa) {
+        #[cfg(feature = "zicsr")]
+        self.csrs.set_isa(isa);
+        #[cfg(not(feature = "zicsr"))] {
+            self.isa = isa;
+        }
+    }
+
+    /// Extensions the hart has right now
+    pub(crate) fn isa(&self) -> Isa {
+        #[cfg(feature = "zicsr")] {
+            self.csrs.isa()
+        }
+        #[cfg(not(feature = "zicsr"))] {
+            self.isa
+        }
+    }
+
+    pub fn set_misaligned(&mut self, policy: MisalignedPolicy) {
+        self.misaligned = policy;
+    }
+
+    /// Handle for feeding host input into the UART receive FIFO
+    pub fn uart_input(&self) -> UartInput {
+        self.bus.borrow().uart_input()
+    }
+
+    /// Redirect UART transmit, default is the process stdout
+    pub fn set_uart_output(&mut self, output: UartOutput) {
+        self.bus.borrow_mut().set_uart_output(output);
+    }
+
+    /// Map a device on the shared bus, see `SystemBus::attach`
+    pub fn attach_device(&mut self, base: XReg, size: XReg, irq: Option<usize>, device: Box<dyn MmioDevice>) -> StdResult<(), RiscVError> {
+        self.bus.borrow_mut().attach(base, size, irq, device)
+    }
+
+    /// Mirror device interrupt lines into `mip`, and `mtime` into the `time` CSR
+    #[cfg(feature = "zicsr")]
+    fn sync_interrupts(&mut self) {
+        let bus = self.bus.borrow();
+        let clint = bus.clint();
+        let (msip, mtip) = (clint.msip_pending(self.hartid), clint.mtip_pending(self.hartid));
+        self.csrs.set_time(clint.mtime());
+        self.csrs.set_pending(Interrupt::MachineSoftware, msip);
+        self.csrs.set_pending(Interrupt::MachineTimer, mtip);
+
+        let plic = bus.plic();
+        let ctx = self.hartid * HART_CONTEXTS;
+        let meip = plic.irq_pending(ctx + M_CONTEXT);
+        #[cfg(feature = "s")]
+        let seip = plic.irq_pending(ctx + S_CONTEXT);
+        self.csrs.set_pending(Interrupt::MachineExternal, meip);
+        #[cfg(feature = "s")]
+        self.csrs.set_pending(Interrupt::SupervisorExternal, seip);
     }
 
     fn cycle(&mut self) -> Result<()> {
+        #[cfg(feature = "zicsr")]
+        self.csrs.check_triggers(AccessType::Fetch, self.pc.get(), 1, self.mode)?;
+
         #[cfg(feature = "c")]
         let ins = if let Some(c_raw) = self.c_fetch()? {
             self.is_compress = true;
@@ -120,7 +370,7 @@ impl Cpu {
         let pa_access = va_access;
 
         #[cfg(feature = "s")]
-        let pa_access = self.mmu.translate(va_access, self.mode, &self.csrs, &mut self.bus)?;
+        let pa_access = self.mmu.translate(va_access, self.mode, &self.csrs, &mut self.bus.borrow_mut())?;
 
         #[cfg(feature = "zicsr")]
         self.csrs.pmp_check(pa_access, 4, self.mode).map_err(|e| match e {
@@ -128,7 +378,8 @@ impl Cpu {
             _ => e
         })?;
 
-        self.bus.read_u32(pa_access).map_err(|e| match e {
+        let bus = self.bus.borrow();
+        bus.pma_check(pa_access, 4).and_then(|_| bus.read_u32(pa_access)).map_err(|e| match e {
             Exception::InstructionAccessFault(_) => Exception::InstructionAccessFault(va_access.addr),
             _ => e
         })
@@ -142,7 +393,7 @@ impl Cpu {
         let pa_access = va_access;
 
         #[cfg(feature = "s")]
-        let pa_access = self.mmu.translate(va_access, self.mode, &self.csrs, &mut self.bus)?;
+        let pa_access = self.mmu.translate(va_access, self.mode, &self.csrs, &mut self.bus.borrow_mut())?;
 
         #[cfg(feature = "zicsr")]
         self.csrs.pmp_check(pa_access, 2, self.mode).map_err(|e| match e {
@@ -151,7 +402,8 @@ impl Cpu {
         })?;
 
         let mut half_raw = [0; 2];
-        self.bus.read_bytes(pa_access, 2, &mut half_raw).map_err(|e| match e {
+        let bus = self.bus.borrow();
+        bus.pma_check(pa_access, 2).and_then(|_| bus.read_bytes(pa_access, 2, &mut half_raw)).map_err(|e| match e {
             Exception::InstructionAccessFault(_) => Exception::InstructionAccessFault(va_access.addr),
             _ => e
         })?;
@@ -163,14 +415,15 @@ impl Cpu {
         })
     }
 
+    /// Instructions of extensions the hart does not have are illegal
     fn decode(&self, bytes: u32) -> Result<Instruction> {
-        decoder::decode(bytes)
+        decoder::decode_with(bytes, &self.isa())
             .map_err(|_| Exception::IllegalInstruction(bytes))
     }
 
     #[cfg(feature = "c")]
     fn decompress(&self, c_bytes: u16) -> Result<Instruction> {
-        decoder::decompress(c_bytes)
+        decoder::decompress_with(c_bytes, &self.isa())
             .map_err(|_| Exception::IllegalInstruction(c_bytes as u32))
     }
 
@@ -190,7 +443,19 @@ impl Cpu {
             #[cfg(feature = "zicsr")]
             Instruction::Zicsr(op, data, raw) => self.execute_zicsr(op, data, raw)?,
             #[cfg(feature = "zifencei")]
-            Instruction::Zifencei(_, _)  => {},          
+            Instruction::Zifencei(_, _)  => {},
+            #[cfg(feature = "f")]
+            Instruction::F(op, data, raw) => self.execute_f(op, data, raw)?,
+            #[cfg(feature = "d")]
+            Instruction::D(op, data, raw) => self.execute_d(op, data, raw)?,
+            #[cfg(feature = "zba")]
+            Instruction::Zba(op, data) => self.execute_zba(op, data),
+            #[cfg(feature = "zbb")]
+            Instruction::Zbb(op, data) => self.execute_zbb(op, data),
+            #[cfg(feature = "zbc")]
+            Instruction::Zbc(op, data) => self.execute_zbc(op, data),
+            #[cfg(feature = "zbs")]
+            Instruction::Zbs(op, data) => self.execute_zbs(op, data),
         }
         #[cfg(feature = "c")]
         if self.is_compress {
@@ -203,28 +468,44 @@ impl Cpu {
         Ok(())
     }
 
+    /// Debug Mode runs at M-mode privilege
     #[cfg(feature = "zicsr")]
-    fn trap_handle(&mut self, except: Exception) {
-        let (mode, pc) = self.csrs.trap_entry(self.pc.get(), except, self.mode);
+    fn enter_debug(&mut self, cause: DebugCause) {
+        self.csrs.debug_entry(cause, self.pc.get(), self.mode);
+        self.mode = PrivilegeMode::Machine;
+        self.waiting = false;
+    }
+
+    #[cfg(feature = "zicsr")]
+    fn trap_handle(&mut self, trap: Trap) {
+        self.csrs.count_event(HpmEvent::Trap, 1);
+        let (mode, pc) = self.csrs.trap_entry(self.pc.get(), trap, self.mode);
         self.pc.directed_addressing(pc);
         self.mode = mode;
     }
 
+    /// Reset the hart and the bus it is on
     pub fn reset(&mut self) {
+        self.reset_hart();
+        let mut bus = self.bus.borrow_mut();
+        bus.reset_ram();
+        bus.reset_devices();
+    }
+
+    /// Reset architectural state only, the bus is left to the machine
+    pub(crate) fn reset_hart(&mut self) {
         self.regs.reset();
+        #[cfg(feature = "f")]
+        self.fregs.reset();
         self.pc.reset();
-        self.bus.reset_ram();
         #[cfg(feature = "zicsr")] {
             self.mode = PrivilegeMode::default();
             self.csrs.reset();
+            self.waiting = false;
 
              #[cfg(feature = "s")]
             self.mmu.reset();
         }
-
-        #[cfg(feature = "a")] {
-            self.reservation = None;
-        }
         #[cfg(feature = "c")] {
             self.is_compress = false;
         }
@@ -1,39 +1,43 @@
 use crate::debug::{DebugInterface, MachineInfo};
 use crate::core::access::{Access, AccessType};
 use crate::core::cpu::Cpu;
+use crate::core::XReg;
 use crate::device::Device;
 
 impl DebugInterface for Cpu {
-    fn inspect_regs(&self) -> [u32; 32] {
+    fn inspect_regs(&self) -> [XReg; 32] {
         self.regs.inspect()
     }
 
-    fn inspect_pc(&self) -> u32 {
+    #[cfg(feature = "f")]
+    fn inspect_fregs(&self) -> [u64; 32] {
+        self.fregs.inspect()
+    }
+
+    fn inspect_pc(&self) -> XReg {
         self.pc.get()
     }
 
     #[cfg(feature = "zicsr")]
-    fn inspect_csrs(&self) -> Vec<(String, u32)> {
+    fn inspect_csrs(&self) -> Vec<(String, XReg)> {
         self.csrs.inspect()
     }
 
-    fn inspect_bus(&self, addr: u32, len: usize) -> Vec<u8> {
+    fn inspect_bus(&self, addr: XReg, len: usize) -> Vec<u8> {
         let mut bytes: Vec<u8> = vec![0; len]; 
         let access = Access::new(addr, AccessType::Load);
-        let _ = self.bus.read_bytes(access, len, &mut bytes);
+        let _ = self.bus.borrow().peek_bytes(access, len, &mut bytes);
         bytes
     }    
 
     fn get_info(&self) -> MachineInfo {
-        let (dram_size, dram_base, page_size) = self.bus.ram_info();
-        let dram_size = dram_size / 1024 / 1024 / 1024;
-        let page_size = page_size / 1024;
+        let (dram_size, dram_base, page_size) = self.bus.borrow().ram_info();
         #[cfg(feature = "s")]
         let hit = self.mmu.hit_count;
         #[cfg(feature = "s")]
         let miss = self.mmu.miss_count;
 
-        MachineInfo::new(dram_size, dram_base, page_size, 
+        MachineInfo::new(dram_size, dram_base, page_size, self.misaligned,
             #[cfg(feature = "s")]hit, 
             #[cfg(feature = "s")]miss, 
             #[cfg(feature = "s")]self.mode)
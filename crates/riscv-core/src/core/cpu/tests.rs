@@ -1,7 +1,10 @@
 #[cfg(feature = "s")] use riscv_decoder::decoder::decode;
 #[cfg(feature = "s")] use riscv_decoder::instruction::Instruction;
-#[cfg(feature = "s")] use crate::Exception;
+#[cfg(any(feature = "s", feature = "f"))] use crate::Exception;
+#[cfg(feature = "f")] use crate::Trap;
+#[cfg(feature = "f")] use crate::XLEN;
 #[cfg(feature = "zicsr")] use crate::core::privilege::PrivilegeMode;
+#[cfg(feature = "zicsr")] use riscv_decoder::isa::{Extension, Isa};
 use crate::core::access::{Access, AccessType};
 use crate::core::cpu::Cpu;
 use crate::constance::DRAM_BASE_ADDR;
@@ -24,7 +27,7 @@ fn test_load_program_to_memory() {
     cpu.load(DRAM_BASE_ADDR, &code).expect("Load failed");
 
     let access = Access::new(DRAM_BASE_ADDR, AccessType::Load);
-    let val = cpu.bus.read_u32(access.bypass()).expect("Bus read failed");
+    let val = cpu.bus.borrow().read_u32(access.bypass()).expect("Bus read failed");
     
     assert_eq!(val, 0xDEADBEEF, "Memory content mismatch");
 }
@@ -102,12 +105,474 @@ fn test_exception_trap_handling() {
     assert_eq!(mepc, DRAM_BASE_ADDR, "mepc wrong");
 }
 
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_jump_target_misaligned() {
+    let mut cpu = Cpu::default();
+    cpu.set_isa(Isa::default().without(Extension::C));
+
+    let handler_base = DRAM_BASE_ADDR + 0x100;
+    cpu.csrs.write(0x305, handler_base, PrivilegeMode::Machine, 0).unwrap();
+
+    // jalr ra, 2(a0)
+    cpu.load(DRAM_BASE_ADDR, &0x002500e7u32.to_le_bytes()).unwrap();
+    cpu.regs.write(10, DRAM_BASE_ADDR);
+
+    cpu.step().unwrap();
+
+    assert_eq!(cpu.pc.get(), handler_base, "Did not trap to mtvec");
+    assert_eq!(cpu.regs[1], 0, "rd should not be written");
+
+    let mcause = cpu.csrs.read(0x342, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(mcause, 0, "mcause wrong");
+
+    let mtval = cpu.csrs.read(0x343, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(mtval, DRAM_BASE_ADDR + 2, "mtval should hold the target");
+}
+
+#[test]
+#[cfg(all(feature = "zicsr", feature = "m"))]
+fn test_disabled_extension_illegal() {
+    let mut cpu = Cpu::default();
+    cpu.set_isa(Isa::default().without(Extension::M));
+
+    let handler_base = DRAM_BASE_ADDR + 0x100;
+    cpu.csrs.write(0x305, handler_base, PrivilegeMode::Machine, 0).unwrap();
+
+    // mul x5, x6, x7
+    cpu.load(DRAM_BASE_ADDR, &0x027302b3u32.to_le_bytes()).unwrap();
+    cpu.step().unwrap();
+
+    assert_eq!(cpu.pc.get(), handler_base, "Did not trap to mtvec");
+    assert_eq!(cpu.csrs.read(0x342, PrivilegeMode::Machine, 0), Ok(2), "mcause wrong");
+    assert_eq!(cpu.csrs.read(0x343, PrivilegeMode::Machine, 0), Ok(0x027302b3), "mtval wrong");
+}
+
+#[test]
+#[cfg(all(feature = "zicsr", feature = "c", feature = "m"))]
+fn test_misa_switches_extensions() {
+    let mut cpu = Cpu::default();
+    cpu.regs.write(6, 3);
+    cpu.regs.write(7, 4);
+
+    // csrc misa, a0 (clears M); mul x5, x6, x7
+    let code: Vec<u8> = [0x30153073u32, 0x027302b3].iter().flat_map(|ins| ins.to_le_bytes()).collect();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+    cpu.regs.write(10, 1 << 12);
+
+    cpu.step().unwrap();
+    assert!(!cpu.isa().has(Extension::M));
+    let trap = cpu.step().unwrap();
+    assert_eq!(trap, Some(crate::Trap::Exception(crate::Exception::IllegalInstruction(0x027302b3))));
+
+    // Clearing C from a 2-byte aligned pc is ignored, the rest of the write is not
+    let mut cpu = Cpu::default();
+    // c.nop; csrc misa, a0; c.nop
+    let code: Vec<u8> = [0x0001u16, 0x3073, 0x3015, 0x0001].iter().flat_map(|ins| ins.to_le_bytes()).collect();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+    cpu.regs.write(10, (1 << 2) | (1 << 12));
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+    assert!(cpu.isa().has(Extension::C));
+    assert!(!cpu.isa().has(Extension::M));
+    assert_eq!(cpu.step().unwrap(), None, "The c.nop at a 2-byte aligned pc still runs");
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_interrupt_taken_before_fetch() {
+    use crate::{Interrupt, Trap};
+    use crate::device::clint::CLINT_BASE;
+
+    let mut cpu = Cpu::default();
+
+    let handler_base = DRAM_BASE_ADDR + 0x100;
+    cpu.csrs.write(0x305, handler_base, PrivilegeMode::Machine, 0).unwrap();
+    cpu.csrs.write(0x304, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+    cpu.csrs.write(0x300, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+
+    // addi x1, x0, 10
+    let code = 0x00A00093u32.to_le_bytes();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    // Raise msip through the CLINT
+    cpu.bus.borrow_mut().write_u32(Access::new(CLINT_BASE, AccessType::Store), 1).unwrap();
+
+    let trap = cpu.step().unwrap();
+    assert_eq!(trap, Some(Trap::Interrupt(Interrupt::MachineSoftware)));
+    assert_eq!(cpu.pc.get(), handler_base, "Did not trap to mtvec");
+    assert_eq!(cpu.regs[1], 0, "Instruction should not retire");
+
+    let mepc = cpu.csrs.read(0x341, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(mepc, DRAM_BASE_ADDR, "mepc wrong");
+
+    // mstatus.MIE is cleared, so the interrupt is masked in the handler
+    assert_eq!(cpu.csrs.pending_interrupt(cpu.mode), None);
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_rnmi() {
+    use crate::Trap;
+
+    let mut cpu = Cpu::default();
+    let irq_handler = DRAM_BASE_ADDR + 0x100;
+    let exc_handler = DRAM_BASE_ADDR + 0x200;
+    cpu.set_rnmi_handlers(irq_handler, exc_handler);
+
+    // addi x1, x0, 10; illegal
+    cpu.load(DRAM_BASE_ADDR, &[0x00A00093u32.to_le_bytes(), 0xFFFFFFFFu32.to_le_bytes()].concat()).unwrap();
+    // mnret
+    cpu.load(irq_handler, &0x70200073u32.to_le_bytes()).unwrap();
+
+    // NMIE resets to 0, so the NMI waits
+    cpu.raise_nmi(5);
+    assert_eq!(cpu.step().unwrap(), None);
+    assert_eq!(cpu.regs[1], 10);
+
+    // And M-mode exceptions go to the RNMI exception handler
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc.get(), exc_handler);
+    assert_eq!(cpu.csrs.read(0x742, PrivilegeMode::Machine, 0), Ok(2));
+    assert_eq!(cpu.csrs.read(0x741, PrivilegeMode::Machine, 0), Ok(DRAM_BASE_ADDR + 4));
+
+    cpu.csrs.write(0x744, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+    cpu.set_pc(DRAM_BASE_ADDR);
+    assert_eq!(cpu.step().unwrap(), Some(Trap::Nmi(5)));
+    assert_eq!(cpu.pc.get(), irq_handler);
+    assert_eq!(cpu.csrs.read(0x741, PrivilegeMode::Machine, 0), Ok(DRAM_BASE_ADDR));
+    assert_eq!(cpu.csrs.read(0x742, PrivilegeMode::Machine, 0), Ok(Trap::Nmi(5).cause()));
+    assert_eq!(cpu.csrs.read(0x744, PrivilegeMode::Machine, 0), Ok(3 << 11), "NMIE cleared, MNPP is M");
+
+    // mnret resumes with NMIE set
+    assert_eq!(cpu.step().unwrap(), None);
+    assert_eq!(cpu.pc.get(), DRAM_BASE_ADDR);
+    assert_eq!(cpu.csrs.read(0x744, PrivilegeMode::Machine, 0), Ok(1 << 3));
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_ebreak_enters_debug_mode() {
+    let mut cpu = Cpu::default();
+    // addi x1, x0, 10; ebreak
+    cpu.load(DRAM_BASE_ADDR, &[0x00A00093u32.to_le_bytes(), 0x00100073u32.to_le_bytes()].concat()).unwrap();
+
+    // A debugger halts the hart to set dcsr.ebreakm and dcsr.step, prv stays M
+    assert!(!cpu.write_csr(0x7b0, 1 << 15));
+    cpu.halt();
+    assert!(cpu.is_halted());
+    assert!(cpu.write_csr(0x7b0, (1 << 15) | (1 << 2) | 3));
+    cpu.resume();
+
+    // One instruction, then halted again
+    assert_eq!(cpu.step().unwrap(), None);
+    assert_eq!(cpu.regs[1], 10);
+    assert!(cpu.is_halted());
+    assert_eq!(cpu.read_csr(0x7b1), Some(DRAM_BASE_ADDR + 4));
+    assert_eq!(cpu.read_csr(0x7b0).map(|dcsr| (dcsr >> 6) & 0b111), Some(4), "Cause is step");
+
+    // Halted harts do nothing
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc.get(), DRAM_BASE_ADDR + 4);
+
+    cpu.write_csr(0x7b0, (1 << 15) | 3);
+    cpu.resume();
+    assert_eq!(cpu.step().unwrap(), None, "ebreak does not trap");
+    assert!(cpu.is_halted());
+    assert_eq!(cpu.read_csr(0x7b1), Some(DRAM_BASE_ADDR + 4));
+    assert_eq!(cpu.read_csr(0x7b0).map(|dcsr| (dcsr >> 6) & 0b111), Some(1), "Cause is ebreak");
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_trigger_breakpoint() {
+    use crate::{Exception, Trap, XLEN};
+    use crate::debug::DebugInterface;
+
+    let mut cpu = Cpu::default();
+    let handler_base = DRAM_BASE_ADDR + 0x100;
+    let data_addr = DRAM_BASE_ADDR + 0x200;
+    cpu.csrs.write(0x305, handler_base, PrivilegeMode::Machine, 0).unwrap();
+    cpu.set_mem_zero(data_addr, 4).unwrap();
+
+    // addi x1, x0, 10; sw x1, 0(x10); addi x1, x0, 10
+    cpu.load(DRAM_BASE_ADDR, &[0x00A00093u32, 0x00152023, 0x00A00093].map(u32::to_le_bytes).concat()).unwrap();
+    cpu.regs.write(10, data_addr);
+
+    // Store trigger on M-mode, set up by the guest itself
+    cpu.csrs.write(0x7a1, (6 << (XLEN - 4)) | (1 << 6) | (1 << 1), PrivilegeMode::Machine, 0).unwrap();
+    cpu.csrs.write(0x7a2, data_addr, PrivilegeMode::Machine, 0).unwrap();
+
+    cpu.step().unwrap();
+    assert_eq!(cpu.step().unwrap(), Some(Trap::Exception(Exception::Breakpoint)));
+    assert_eq!(cpu.pc.get(), handler_base);
+    assert_eq!(cpu.csrs.read(0x341, PrivilegeMode::Machine, 0), Ok(DRAM_BASE_ADDR + 4));
+    assert_eq!(cpu.csrs.read(0x343, PrivilegeMode::Machine, 0), Ok(data_addr));
+    assert_eq!(cpu.inspect_bus(data_addr, 4), vec![0; 4], "The store must not happen");
+
+    // Execute trigger owned by the debugger enters Debug Mode before the instruction
+    cpu.halt();
+    cpu.write_csr(0x7a0, 1);
+    cpu.write_csr(0x7a1, (6 << (XLEN - 4)) | (1 << (XLEN - 5)) | (1 << 12) | (1 << 6) | (1 << 2));
+    cpu.write_csr(0x7a2, DRAM_BASE_ADDR + 8);
+    cpu.write_csr(0x7b1, DRAM_BASE_ADDR + 8);
+    cpu.resume();
+    assert_eq!(cpu.step().unwrap(), None);
+    assert!(cpu.is_halted());
+    assert_eq!(cpu.read_csr(0x7b0).map(|dcsr| (dcsr >> 6) & 0b111), Some(2), "Cause is trigger");
+    assert_eq!(cpu.read_csr(0x7b1), Some(DRAM_BASE_ADDR + 8));
+
+    // The guest can not touch it
+    cpu.resume();
+    let tdata1 = cpu.csrs.read(0x7a1, PrivilegeMode::Machine, 0).unwrap();
+    cpu.csrs.write(0x7a1, 0, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(cpu.csrs.read(0x7a1, PrivilegeMode::Machine, 0), Ok(tdata1));
+}
+
+#[test]
+#[cfg(all(feature = "zicsr", feature = "a"))]
+fn test_pma_faults() {
+    use crate::{Exception, Trap};
+    use crate::device::bus::UART_BASE;
+
+    let mut cpu = Cpu::default();
+    let handler_base = DRAM_BASE_ADDR + 0x100;
+    cpu.csrs.write(0x305, handler_base, PrivilegeMode::Machine, 0).unwrap();
+
+    // amoadd.w x1, x2, (x10); sw x1, 0(x10); jalr x0, 0(x10)
+    cpu.load(DRAM_BASE_ADDR, &[0x002520AFu32, 0x00152023, 0x00050067].map(u32::to_le_bytes).concat()).unwrap();
+    cpu.regs.write(10, UART_BASE);
+
+    // The UART takes neither AMOs nor word accesses
+    assert_eq!(cpu.step().unwrap(), Some(Trap::Exception(Exception::StoreOrAmoAccessFault(UART_BASE))));
+    assert_eq!(cpu.csrs.read(0x343, PrivilegeMode::Machine, 0), Ok(UART_BASE));
+
+    cpu.pc.set(DRAM_BASE_ADDR + 4);
+    assert_eq!(cpu.step().unwrap(), Some(Trap::Exception(Exception::StoreOrAmoAccessFault(UART_BASE))));
+
+    // Jumping there is fine, fetching from it is not
+    cpu.pc.set(DRAM_BASE_ADDR + 8);
+    cpu.step().unwrap();
+    assert_eq!(cpu.step().unwrap(), Some(Trap::Exception(Exception::InstructionAccessFault(UART_BASE))));
+    assert_eq!(cpu.csrs.read(0x341, PrivilegeMode::Machine, 0), Ok(UART_BASE));
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_clint_timer_interrupt() {
+    use crate::{Interrupt, Trap};
+    use crate::device::clint::CLINT_BASE;
+
+    let mut cpu = Cpu::default();
+
+    let handler_base = DRAM_BASE_ADDR + 0x100;
+    cpu.csrs.write(0x305, handler_base, PrivilegeMode::Machine, 0).unwrap();
+    cpu.csrs.write(0x304, 1 << 7, PrivilegeMode::Machine, 0).unwrap();
+    cpu.csrs.write(0x300, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+
+    // mtimecmp = 2
+    cpu.bus.borrow_mut().write_u32(Access::new(CLINT_BASE + 0x4000, AccessType::Store), 2).unwrap();
+    cpu.bus.borrow_mut().write_u32(Access::new(CLINT_BASE + 0x4004, AccessType::Store), 0).unwrap();
+
+    // addi x1, x1, 1 (x3)
+    let code = [0x00108093u32; 3].iter().flat_map(|ins| ins.to_le_bytes()).collect::<Vec<u8>>();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    assert_eq!(cpu.step().unwrap(), None);
+    assert_eq!(cpu.step().unwrap(), None);
+    assert_eq!(cpu.step().unwrap(), Some(Trap::Interrupt(Interrupt::MachineTimer)));
+    assert_eq!(cpu.regs[1], 2);
+    assert_eq!(cpu.pc.get(), handler_base);
+
+    let mip = cpu.csrs.read(0x344, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(mip & (1 << 7), 1 << 7, "MTIP should be set");
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_plic_uart_external_interrupt() {
+    use crate::{Interrupt, Trap};
+    use crate::device::Device;
+    use crate::device::bus::UART_BASE;
+    use crate::device::plic::PLIC_BASE;
+    use crate::device::uart::UART_IRQ;
+    use crate::XReg;
+
+    let mut cpu = Cpu::default();
+
+    let handler_base = DRAM_BASE_ADDR + 0x100;
+    cpu.csrs.write(0x305, handler_base, PrivilegeMode::Machine, 0).unwrap();
+    cpu.csrs.write(0x304, 1 << 11, PrivilegeMode::Machine, 0).unwrap();
+    cpu.csrs.write(0x300, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+
+    // Source priority 1, enabled for context 0
+    let store = |addr| Access::new(addr, AccessType::Store);
+    cpu.bus.borrow_mut().write_u32(store(PLIC_BASE + 4 * UART_IRQ as XReg), 1).unwrap();
+    cpu.bus.borrow_mut().write_u32(store(PLIC_BASE + 0x2000), 1 << UART_IRQ).unwrap();
+
+    // Enable THRE interrupt in IER
+    cpu.bus.borrow_mut().write_bytes(store(UART_BASE + 1), 1, &[0x02]).unwrap();
+
+    let code = [0x00A00093u32; 2].iter().flat_map(|ins| ins.to_le_bytes()).collect::<Vec<u8>>();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    // Device levels are sampled when an instruction retires
+    assert_eq!(cpu.step().unwrap(), None);
+    assert_eq!(cpu.step().unwrap(), Some(Trap::Interrupt(Interrupt::MachineExternal)));
+    assert_eq!(cpu.pc.get(), handler_base);
+
+    // Claim returns the UART
+    let claim = Access::new(PLIC_BASE + 0x20_0004, AccessType::Load);
+    assert_eq!(cpu.bus.borrow().read_u32(claim).unwrap(), UART_IRQ as u32);
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_wfi_fast_forward() {
+    use crate::{Interrupt, Trap};
+    use crate::device::clint::CLINT_BASE;
+
+    let mut cpu = Cpu::default();
+
+    let handler_base = DRAM_BASE_ADDR + 0x100;
+    cpu.csrs.write(0x305, handler_base, PrivilegeMode::Machine, 0).unwrap();
+    cpu.csrs.write(0x304, 1 << 7, PrivilegeMode::Machine, 0).unwrap();
+    cpu.csrs.write(0x300, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+
+    // mtimecmp = 100_000
+    cpu.bus.borrow_mut().write_u32(Access::new(CLINT_BASE + 0x4000, AccessType::Store), 100_000).unwrap();
+    cpu.bus.borrow_mut().write_u32(Access::new(CLINT_BASE + 0x4004, AccessType::Store), 0).unwrap();
+
+    // wfi
+    let code = 0x10500073u32.to_le_bytes();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    assert_eq!(cpu.step().unwrap(), None);
+    assert!(cpu.waiting);
+    // Idle step jumps mtime to mtimecmp instead of spinning
+    assert_eq!(cpu.step().unwrap(), None);
+    assert_eq!(cpu.step().unwrap(), Some(Trap::Interrupt(Interrupt::MachineTimer)));
+    assert!(!cpu.waiting);
+
+    let mepc = cpu.csrs.read(0x341, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(mepc, DRAM_BASE_ADDR + 4, "Should resume after wfi");
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_rdinstret() {
+    let mut cpu = Cpu::default();
+
+    // addi x1, x0, 1; csrrs x2, instret, x0; csrrs x3, cycle, x0
+    let code: Vec<u8> = [0x00100093u32, 0xc0202173, 0xc00021f3]
+        .iter().flat_map(|ins| ins.to_le_bytes()).collect();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    for _ in 0..3 {
+        assert_eq!(cpu.step().unwrap(), None);
+    }
+    assert_eq!(cpu.regs[2], 1);
+    assert_eq!(cpu.regs[3], 2);
+}
+
+#[test]
+#[cfg(feature = "f")]
+fn test_float_program() {
+    let mut cpu = Cpu::default();
+    cpu.regs.write(1, 7);
+    cpu.regs.write(5, DRAM_BASE_ADDR + 0x100);
+    cpu.fregs.write_s(0, 0);
+    cpu.fregs.write_s(2, 2.0f32.to_bits());
+
+    // fcvt.s.w f1, x1; fdiv.s f3, f1, f2; fcvt.w.s x3, f3, rtz; fdiv.s f4, f1, f0
+    // frflags x4; fsw f3, 0(x5); flw f5, 0(x5)
+    let code: Vec<u8> = [0xd000f0d3u32, 0x1820f1d3, 0xc00191d3, 0x1800f253,
+        0x00102273, 0x0032a027, 0x0002a287]
+        .iter().flat_map(|ins| ins.to_le_bytes()).collect();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    for _ in 0..7 {
+        assert_eq!(cpu.step().unwrap(), None);
+    }
+    assert_eq!(f32::from_bits(cpu.fregs.read_s(3)), 3.5);
+    assert_eq!(cpu.regs[3], 3);
+    assert_eq!(f32::from_bits(cpu.fregs.read_s(4)), f32::INFINITY);
+    // NX from the truncation, DZ from the division by zero
+    assert_eq!(cpu.regs[4], 0b01001);
+    assert_eq!(cpu.fregs.read_s(5), 3.5f32.to_bits());
+
+    // FS is dirty and SD is set
+    let mstatus = cpu.csrs.read(0x300, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!((mstatus >> 13) & 0b11, 0b11);
+    assert_ne!(mstatus & (1 << (XLEN - 1)), 0);
+}
+
+#[test]
+#[cfg(feature = "d")]
+fn test_double_program() {
+    let mut cpu = Cpu::default();
+    cpu.regs.write(1, 1);
+    cpu.regs.write(2, 3);
+    cpu.regs.write(5, DRAM_BASE_ADDR + 0x100);
+
+    // fcvt.d.w f1, x1; fcvt.d.w f2, x2; fdiv.d f3, f1, f2; fsd f3, 0(x5); fld f4, 0(x5)
+    // fcvt.s.d f5, f3; fadd.s f6, f3, f5; feq.d x3, f3, f4
+    let code: Vec<u8> = [0xd20080d3u32, 0xd2010153, 0x1a20f1d3, 0x0032b027, 0x0002b207,
+        0x4011f2d3, 0x0051f353, 0xa241a1d3]
+        .iter().flat_map(|ins| ins.to_le_bytes()).collect();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    for _ in 0..8 {
+        assert_eq!(cpu.step().unwrap(), None);
+    }
+    assert_eq!(cpu.fregs[3], (1.0f64 / 3.0).to_bits());
+    assert_eq!(cpu.fregs[4], cpu.fregs[3]);
+    // Single results are NaN-boxed
+    assert_eq!(cpu.fregs[5], 0xffff_ffff_0000_0000 | (1.0f32 / 3.0).to_bits() as u64);
+    // A double is not a valid single, so it reads as the canonical NaN
+    assert_eq!(cpu.fregs[6], 0xffff_ffff_7fc0_0000);
+    assert_eq!(cpu.regs[3], 1);
+}
+
+#[test]
+#[cfg(feature = "f")]
+fn test_float_fs_off() {
+    let mut cpu = Cpu::default();
+
+    // flw f5, 0(x5), with FS Off
+    cpu.load(DRAM_BASE_ADDR, &0x0002a287u32.to_le_bytes()).unwrap();
+    cpu.csrs.write(0x300, 0, PrivilegeMode::Machine, 0).unwrap();
+
+    assert_eq!(cpu.step().unwrap(), Some(Trap::Exception(Exception::IllegalInstruction(0x0002a287))));
+    assert_eq!(cpu.csrs.read(0x003, PrivilegeMode::Machine, 0x00302573), 
+        Err(Exception::IllegalInstruction(0x00302573)));
+}
+
+#[test]
+#[cfg(feature = "s")]
+fn test_wfi_tw_trap() {
+    let mut cpu = Cpu { mode: PrivilegeMode::Supervisor, ..Default::default() };
+
+    // wfi
+    let ins = decode(0x10500073).unwrap();
+    let Instruction::Privileged(op, data) = ins else {
+        panic!("wfi should decode as privileged");
+    };
+
+    // mstatus.TW
+    cpu.csrs.write(0x300, 1 << 21, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(cpu.execute_privileged(op, data), Err(Exception::IllegalInstruction(0x10500073)));
+    assert!(!cpu.waiting);
+
+    cpu.csrs.write(0x300, 0, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(cpu.execute_privileged(op, data), Ok(false));
+    assert!(cpu.waiting);
+}
+
 #[test]
 #[cfg(feature = "s")]
 fn test_sfence_vma() {
-    let mut cpu = Cpu::default();
-    
-    cpu.mode = PrivilegeMode::Supervisor;
+    let mut cpu = Cpu { mode: PrivilegeMode::Supervisor, ..Default::default() };
     
     // sfence.vma x10, x11
     let raw = 0x12a58073;
@@ -130,7 +595,7 @@ fn test_sfence_vma() {
 
     assert!(res.is_ok());
     let next_pc_manual = res.unwrap();
-    assert_eq!(next_pc_manual, false);
+    assert!(!next_pc_manual);
 
     cpu.mode = PrivilegeMode::User;
     let res_err = if let Instruction::Privileged(op, data) = ins {
@@ -142,4 +607,105 @@ fn test_sfence_vma() {
         Err(Exception::IllegalInstruction(_)) => (),
         _ => panic!(""),
     }
-}
\ No newline at end of file
+}
+
+#[test]
+#[cfg(all(feature = "zba", feature = "zbb", feature = "zbc", feature = "zbs", not(feature = "rv64")))]
+fn test_bit_manipulation_program() {
+    let mut cpu = Cpu::default();
+    cpu.regs.write(1, 0x12345678);
+    cpu.regs.write(2, 3);
+
+    // sh2add x3, x2, x1; rev8 x4, x1; clz x5, x2; rori x6, x1, 4
+    // bexti x7, x1, 4; clmul x8, x2, x2
+    let code: Vec<u8> = [0x201141b3u32, 0x6980d213, 0x60011293, 0x6040d313,
+        0x4840d393, 0x0a211433]
+        .iter().flat_map(|ins| ins.to_le_bytes()).collect();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    for _ in 0..6 {
+        assert_eq!(cpu.step().unwrap(), None);
+    }
+    assert_eq!(cpu.regs[3], 0x12345684);
+    assert_eq!(cpu.regs[4], 0x78563412);
+    assert_eq!(cpu.regs[5], 30);
+    assert_eq!(cpu.regs[6], 0x81234567);
+    assert_eq!(cpu.regs[7], 1);
+    assert_eq!(cpu.regs[8], 0b101);
+}
+
+#[test]
+#[cfg(feature = "rv64")]
+fn test_rv64_program() {
+    let mut cpu = Cpu::default();
+    cpu.regs.write(1, 0x8000_0001_7fff_ffff);
+    cpu.regs.write(2, 1);
+    cpu.regs.write(7, DRAM_BASE_ADDR + 0x100);
+
+    // addiw x3, x1, 1; subw x4, x0, x2; sllw x5, x1, x2; sraiw x6, x4, 1
+    // sd x1, 0(x7); ld x8, 0(x7); lw x9, 4(x7); lwu x10, 4(x7); slli x11, x2, 40
+    let code: Vec<u8> = [0x0010819bu32, 0x4020023b, 0x002092bb, 0x4012531b,
+        0x0013b023, 0x0003b403, 0x0043a483, 0x0043e503, 0x02811593]
+        .iter().flat_map(|ins| ins.to_le_bytes()).collect();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    for _ in 0..9 {
+        assert_eq!(cpu.step().unwrap(), None);
+    }
+    // Word results are sign-extended from bit 31
+    assert_eq!(cpu.regs[3], 0xffff_ffff_8000_0000);
+    assert_eq!(cpu.regs[4], u64::MAX);
+    assert_eq!(cpu.regs[5], 0xffff_ffff_ffff_fffe);
+    assert_eq!(cpu.regs[6], u64::MAX);
+    assert_eq!(cpu.regs[8], 0x8000_0001_7fff_ffff);
+    assert_eq!(cpu.regs[9], 0xffff_ffff_8000_0001);
+    assert_eq!(cpu.regs[10], 0x8000_0001);
+    assert_eq!(cpu.regs[11], 1 << 40);
+}
+
+#[test]
+#[cfg(all(feature = "zbb", feature = "rv64"))]
+fn test_rv64_bit_manipulation_program() {
+    let mut cpu = Cpu::default();
+    cpu.regs.write(1, 0x12345678);
+    cpu.regs.write(2, 3);
+
+    // rev8 x4, x1; clz x5, x2; rori x6, x1, 36
+    let code: Vec<u8> = [0x6b80d213u32, 0x60011293, 0x6240d313]
+        .iter().flat_map(|ins| ins.to_le_bytes()).collect();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    for _ in 0..3 {
+        assert_eq!(cpu.step().unwrap(), None);
+    }
+    assert_eq!(cpu.regs[4], 0x7856_3412_0000_0000);
+    assert_eq!(cpu.regs[5], 62);
+    assert_eq!(cpu.regs[6], 0x0123_4567_8000_0000);
+}
+
+#[test]
+#[cfg(all(feature = "zba", feature = "zbb", feature = "rv64"))]
+fn test_rv64_bit_manipulation_word_program() {
+    let mut cpu = Cpu::default();
+    cpu.regs.write(1, 0xffff_ffff_8765_4321);
+    cpu.regs.write(2, 4);
+
+    // add.uw x3, x1, x2; sh3add.uw x4, x1, x2; slli.uw x5, x1, 40
+    // clzw x6, x2; cpopw x7, x1; rolw x8, x1, x2; roriw x9, x1, 1
+    let code: Vec<u8> = [0x082081bbu32, 0x2020e23b, 0x0a80929b,
+        0x6001131b, 0x6020939b, 0x6020943b, 0x6010d49b]
+        .iter().flat_map(|ins| ins.to_le_bytes()).collect();
+    cpu.load(DRAM_BASE_ADDR, &code).unwrap();
+
+    for _ in 0..7 {
+        assert_eq!(cpu.step().unwrap(), None);
+    }
+    // `.uw` ops zero-extend rs1, word ops sign-extend the result
+    assert_eq!(cpu.regs[3], 0x8765_4325);
+    assert_eq!(cpu.regs[4], 0x4_3b2a_190c);
+    assert_eq!(cpu.regs[5], 0x6543_2100_0000_0000);
+    assert_eq!(cpu.regs[6], 29);
+    assert_eq!(cpu.regs[7], 13);
+    assert_eq!(cpu.regs[8], 0x7654_3218);
+    assert_eq!(cpu.regs[9], 0xffff_ffff_c3b2_a190);
+}
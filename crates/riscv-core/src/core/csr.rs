@@ -1,64 +1,138 @@
 mod addr;
+mod counter;
+mod ids;
+mod misa;
 mod mstatus;
-mod pmpcfg;
+mod pmp;
+mod rnmi;
+mod sdext;
+mod trigger;
 #[cfg(feature = "s")]
 mod satp;
 
-use crate::{Exception, Result};
-use crate::core::access::{Access, Physical};
+use riscv_decoder::isa::Isa;
+#[cfg(any(feature = "f", feature = "s"))]
+use riscv_decoder::isa::Extension;
+
+use crate::{Exception, Interrupt, Result, Trap};
+use crate::core::access::{Access, AccessType, Physical};
+use crate::interrupt::PRIORITY;
 use crate::core::privilege::PrivilegeMode;
+use crate::core::XReg;
 
 use addr::CsrAddr;
+pub use counter::HpmEvent;
+use counter::Counters;
+pub use ids::HartIds;
+use misa::Misa;
 use mstatus::Mstatus;
-use pmpcfg::Pmpcfg;
+use pmp::{Pmp, PMP_MAX_ENTRIES};
+use rnmi::Rnmi;
+pub use sdext::DebugCause;
+use sdext::Sdext;
+pub use trigger::TriggerAction;
+use trigger::Triggers;
 #[cfg(feature = "s")]
 use satp::Satp;
+#[cfg(feature = "s")]
+use crate::core::PagingMode;
 
-pub(super) const PMPCFG_NUM: usize = 4;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct CsrFile {
-    #[cfg(feature = "s")] stvec: u32,
-    #[cfg(feature = "s")] sepc: u32,
-    #[cfg(feature = "s")] scause: u32,
-    #[cfg(feature = "s")] sscratch: u32,
-    #[cfg(feature = "s")] stval: u32,
+    #[cfg(feature = "f")] fcsr: XReg,
+
+    #[cfg(feature = "s")] stvec: XReg,
+    #[cfg(feature = "s")] sepc: XReg,
+    #[cfg(feature = "s")] scause: XReg,
+    #[cfg(feature = "s")] sscratch: XReg,
+    #[cfg(feature = "s")] stval: XReg,
     #[cfg(feature = "s")] satp: Satp,
+    #[cfg(feature = "s")] senvcfg: XReg,
 
     mstatus: Mstatus,
-    #[cfg(feature = "s")] medeleg: u32,
-    #[cfg(feature = "s")] mideleg: u32,
-    mie: u32,
-    mtvec: u32,
-    mscratch: u32,
-    mepc: u32,
-    mcause: u32,
-    mtval: u32,
-    mip: u32,
-
-    pmpcfg: [Pmpcfg; PMPCFG_NUM],
-    pmpaddr: [u32; PMPCFG_NUM * 4],
+    misa: Misa,
+    menvcfg: XReg,
+    #[cfg(feature = "s")] medeleg: XReg,
+    #[cfg(feature = "s")] mideleg: XReg,
+    mie: XReg,
+    mtvec: XReg,
+    mscratch: XReg,
+    mepc: XReg,
+    mcause: XReg,
+    mtval: XReg,
+    mip: XReg,
+    // Lines driven by devices, OR'd into `mip` on read
+    irq_lines: XReg,
+
+    pmp: Pmp,
+
+    pub(crate) counters: Counters,
+
+    rnmi: Rnmi,
+    sdext: Sdext,
+    triggers: Triggers,
+
+    mhartid: XReg,
+    ids: HartIds,
 }
 
 const MODE_MASK: u16 = 3 << 8;
-// const INTERRUPT_MASK: u32 = 1 << 31;
+
+// Only FIOM, the other fields belong to extensions that are not there
+const ENVCFG_WRITE_MASK: XReg = 1;
+
+#[cfg(feature = "f")]
+const FFLAGS_MASK: XReg = 0x1f;
+#[cfg(feature = "f")]
+const FRM_SHIFT: XReg = 5;
+
+#[cfg(feature = "s")]
+const S_INTERRUPT_MASK: XReg = (1 << 1) | (1 << 5) | (1 << 9);
+#[cfg(not(feature = "s"))]
+const S_INTERRUPT_MASK: XReg = 0;
+const M_INTERRUPT_MASK: XReg = (1 << 3) | (1 << 7) | (1 << 11);
+
+// MSIP, MTIP and MEIP are driven by devices, software can only write the S-level bits
+const MIP_WRITE_MASK: XReg = S_INTERRUPT_MASK;
+const MIE_WRITE_MASK: XReg = S_INTERRUPT_MASK | M_INTERRUPT_MASK;
+#[cfg(feature = "s")]
+const SIP_WRITE_MASK: XReg = 1 << 1;
 
 impl CsrFile {
-    pub fn read(&mut self, addr: u16, mode: PrivilegeMode, raw: u32) -> Result<u32> {    
+    pub fn read(&mut self, addr: u16, mode: PrivilegeMode, raw: u32) -> Result<XReg> {    
         if (mode as u16) < ((addr & MODE_MASK) >> 8) {
             Err(Exception::IllegalInstruction(raw))
         } else {
             Ok(match CsrAddr::get_csr(addr, raw)? {
+                #[cfg(feature = "s")]
+                csr if csr.is_supervisor() && !self.misa.has(Extension::S) => {
+                    return Err(Exception::IllegalInstruction(raw));
+                },
+                CsrAddr::Mnscratch | CsrAddr::Mnepc | CsrAddr::Mncause if !self.rnmi.is_enabled() => {
+                    return Err(Exception::IllegalInstruction(raw));
+                },
+                csr if csr.is_debug() && !self.sdext.halted => {
+                    return Err(Exception::IllegalInstruction(raw));
+                },
                 CsrAddr::Ustatus => 0,
+                #[cfg(feature = "f")]
+                CsrAddr::Fflags | CsrAddr::Frm | CsrAddr::Fcsr if self.fs_off() => {
+                    return Err(Exception::IllegalInstruction(raw));
+                },
+                #[cfg(feature = "f")] CsrAddr::Fflags => self.fcsr & FFLAGS_MASK,
+                #[cfg(feature = "f")] CsrAddr::Frm => self.fcsr >> FRM_SHIFT,
+                #[cfg(feature = "f")] CsrAddr::Fcsr => self.fcsr,
 
                 #[cfg(feature = "s")] CsrAddr::Sstatus => self.mstatus.read_s(),
                 #[cfg(feature = "s")] CsrAddr::Sie => self.mie & self.mideleg,
                 #[cfg(feature = "s")] CsrAddr::Stvec => self.stvec,
+                #[cfg(feature = "s")] CsrAddr::Scounteren => self.counters.scounteren as XReg,
+                #[cfg(feature = "s")] CsrAddr::Senvcfg => self.senvcfg,
                 #[cfg(feature = "s")] CsrAddr::Sscratch => self.sscratch,
                 #[cfg(feature = "s")] CsrAddr::Sepc => self.sepc,
                 #[cfg(feature = "s")] CsrAddr::Scause => self.scause,
                 #[cfg(feature = "s")] CsrAddr::Stval => self.stval,
-                #[cfg(feature = "s")] CsrAddr::Sip => self.mip & self.mideleg,
+                #[cfg(feature = "s")] CsrAddr::Sip => self.mip() & self.mideleg,
                 #[cfg(feature = "s")] CsrAddr::Satp => {
                     if mode == PrivilegeMode::Supervisor && self.check_tvm() {
                         return Err(Exception::IllegalInstruction(addr as u32));
@@ -66,73 +140,188 @@ impl CsrFile {
                     self.satp.into()
                 }
                 CsrAddr::Mstatus => self.mstatus.read_m(),
+                CsrAddr::Misa => self.misa.read(),
                 #[cfg(feature = "s")] CsrAddr::Medeleg => self.medeleg,
                 #[cfg(feature = "s")] CsrAddr::Mideleg => self.mideleg,
                 CsrAddr::Mie => self.mie,
                 CsrAddr::Mtvec => self.mtvec,
+                CsrAddr::Mcounteren => self.counters.mcounteren as XReg,
+                CsrAddr::Menvcfg => self.menvcfg,
+                #[cfg(not(feature = "rv64"))]
+                CsrAddr::MenvcfgH => 0,
+                CsrAddr::Mcountinhibit => self.counters.mcountinhibit() as XReg,
+                CsrAddr::Mhpmevent(idx) => self.counters.event(idx) as XReg,
                 CsrAddr::Mscratch => self.mscratch,
                 CsrAddr::Mepc => self.mepc,
                 CsrAddr::Mcause => self.mcause,
                 CsrAddr::Mtval => self.mtval,
-                CsrAddr::Mip => self.mip,
-
-                CsrAddr::Pmpcfg(num) => self.pmpcfg[num].into(),
-                CsrAddr::Pmpaddr(num) => self.pmpaddr[num],
-
-                CsrAddr::Mnstatus => 0,
-                CsrAddr::Mhartid  => 0,
+                CsrAddr::Mip => self.mip(),
+
+                CsrAddr::Pmpcfg(num) => self.pmp.read_cfg(num),
+                CsrAddr::Pmpaddr(num) => self.pmp.read_addr(num),
+                CsrAddr::Mseccfg => self.pmp.read_mseccfg(),
+                #[cfg(not(feature = "rv64"))]
+                CsrAddr::MseccfgH => 0,
+
+                CsrAddr::Counter(idx) if !self.counters.accessible(idx, mode) => {
+                    return Err(Exception::IllegalInstruction(raw));
+                },
+                #[cfg(not(feature = "rv64"))]
+                CsrAddr::CounterH(idx) if !self.counters.accessible(idx, mode) => {
+                    return Err(Exception::IllegalInstruction(raw));
+                },
+                CsrAddr::Counter(idx) => self.counters.read(idx) as XReg,
+                #[cfg(not(feature = "rv64"))]
+                CsrAddr::CounterH(idx) => (self.counters.read(idx) >> 32) as XReg,
+                CsrAddr::Mcounter(idx) => self.counters.read(idx) as XReg,
+                #[cfg(not(feature = "rv64"))]
+                CsrAddr::McounterH(idx) => (self.counters.read(idx) >> 32) as XReg,
+
+                CsrAddr::Mnscratch => self.rnmi.mnscratch,
+                CsrAddr::Mnepc     => self.rnmi.mnepc,
+                CsrAddr::Mncause   => self.rnmi.mncause,
+                CsrAddr::Mnstatus  => self.rnmi.read_mnstatus(),
+
+                CsrAddr::Tselect => self.triggers.tselect(),
+                CsrAddr::Tdata1  => self.triggers.tdata1(),
+                CsrAddr::Tdata2  => self.triggers.tdata2(),
+                CsrAddr::Tdata3  => 0,
+                CsrAddr::Tinfo   => self.triggers.tinfo(),
+                CsrAddr::Dcsr    => self.sdext.read_dcsr(self.rnmi.is_pending()),
+                CsrAddr::Dpc     => self.sdext.dpc,
+                CsrAddr::Dscratch(idx) => self.sdext.dscratch[idx],
+
+                CsrAddr::Mvendorid  => self.ids.mvendorid as XReg,
+                CsrAddr::Marchid    => self.ids.marchid,
+                CsrAddr::Mimpid     => self.ids.mimpid,
+                CsrAddr::Mhartid    => self.mhartid,
+                CsrAddr::Mconfigptr => self.ids.mconfigptr,
             })
         }
     }
 
-    pub fn write(&mut self, addr: u16, data: u32, mode: PrivilegeMode, raw: u32) -> Result<()> {
+    /// `write_at` for an instruction followed by a 4-byte aligned one
+    #[cfg(test)]
+    pub fn write(&mut self, addr: u16, data: XReg, mode: PrivilegeMode, raw: u32) -> Result<()> {
+        self.write_at(addr, data, mode, raw, 0)
+    }
+
+    /// `next_pc` is where the hart goes on after the write, `misa` keeps C when it is not 4-byte aligned
+    #[allow(clippy::unnecessary_cast)]
+    pub fn write_at(&mut self, addr: u16, data: XReg, mode: PrivilegeMode, raw: u32, next_pc: XReg) -> Result<()> {
         if (mode as u16) < ((addr & MODE_MASK) >> 8) {
             Err(Exception::IllegalInstruction(addr as u32))
         } else {
             match CsrAddr::get_csr(addr, raw)? {
+                #[cfg(feature = "s")]
+                csr if csr.is_supervisor() && !self.misa.has(Extension::S) => {
+                    return Err(Exception::IllegalInstruction(raw));
+                },
+                CsrAddr::Mnscratch | CsrAddr::Mnepc | CsrAddr::Mncause if !self.rnmi.is_enabled() => {
+                    return Err(Exception::IllegalInstruction(raw));
+                },
+                csr if csr.is_debug() && !self.sdext.halted => {
+                    return Err(Exception::IllegalInstruction(raw));
+                },
                 CsrAddr::Ustatus => {},
+                #[cfg(feature = "f")]
+                CsrAddr::Fflags | CsrAddr::Frm | CsrAddr::Fcsr if self.fs_off() => {
+                    return Err(Exception::IllegalInstruction(raw));
+                },
+                #[cfg(feature = "f")] CsrAddr::Fflags => self.set_fcsr((self.fcsr & !FFLAGS_MASK) | (data & FFLAGS_MASK)),
+                #[cfg(feature = "f")] CsrAddr::Frm => self.set_fcsr((self.fcsr & FFLAGS_MASK) | (data << FRM_SHIFT)),
+                #[cfg(feature = "f")] CsrAddr::Fcsr => self.set_fcsr(data),
 
                 #[cfg(feature = "s")] CsrAddr::Sstatus => self.mstatus.write_s(data),
                 #[cfg(feature = "s")] CsrAddr::Sie => self.mie = (self.mie & !self.mideleg) | (data & self.mideleg),
                 #[cfg(feature = "s")] CsrAddr::Stvec => self.stvec = data,
+                #[cfg(feature = "s")] CsrAddr::Scounteren => self.counters.scounteren = data as u32 & self.counters.implemented_mask(),
+                #[cfg(feature = "s")] CsrAddr::Senvcfg => self.senvcfg = data & ENVCFG_WRITE_MASK,
                 #[cfg(feature = "s")] CsrAddr::Sscratch => self.sscratch = data,
                 #[cfg(feature = "s")] CsrAddr::Sepc => self.sepc = data,
                 #[cfg(feature = "s")] CsrAddr::Scause => self.scause = data,
                 #[cfg(feature = "s")] CsrAddr::Stval => self.stval = data,
-                #[cfg(feature = "s")] CsrAddr::Sip => self.mip = (self.mip & !self.mideleg) | (data & self.mideleg),
+                #[cfg(feature = "s")] CsrAddr::Sip => {
+                    let mask = self.mideleg & SIP_WRITE_MASK;
+                    self.mip = (self.mip & !mask) | (data & mask)
+                },
                 #[cfg(feature = "s")] CsrAddr::Satp => {
                     if mode == PrivilegeMode::Supervisor && self.check_tvm() {
                         return Err(Exception::IllegalInstruction(addr as u32));
                     }
-                    self.satp = data.into()
+                    let satp = Satp::from(data);
+                    if satp.is_supported() {
+                        self.satp = satp;
+                    }
                 }
                 CsrAddr::Mstatus => self.mstatus.write_m(data),
+                CsrAddr::Misa => self.misa.write(data, next_pc),
                 #[cfg(feature = "s")] CsrAddr::Medeleg => self.medeleg = data,
-                #[cfg(feature = "s")] CsrAddr::Mideleg => self.mideleg = data,
-                CsrAddr::Mie => self.mie = data,
+                #[cfg(feature = "s")] CsrAddr::Mideleg => self.mideleg = data & S_INTERRUPT_MASK,
+                CsrAddr::Mie => self.mie = data & MIE_WRITE_MASK,
                 CsrAddr::Mtvec => self.mtvec = data,
+                CsrAddr::Mcounteren => self.counters.mcounteren = data as u32 & self.counters.implemented_mask(),
+                CsrAddr::Menvcfg => self.menvcfg = data & ENVCFG_WRITE_MASK,
+                #[cfg(not(feature = "rv64"))]
+                CsrAddr::MenvcfgH => {},
+                CsrAddr::Mcountinhibit => self.counters.set_mcountinhibit(data as u32),
+                CsrAddr::Mhpmevent(idx) => self.counters.set_event(idx, data as u32),
                 CsrAddr::Mscratch => self.mscratch = data,
                 CsrAddr::Mepc => self.mepc = data,
                 CsrAddr::Mcause => self.mcause = data,
                 CsrAddr::Mtval => self.mtval = data,
-                CsrAddr::Mip => self.mip = data,
-
-                CsrAddr::Pmpcfg(num) => self.pmpcfg[num] = data.into(),
-                CsrAddr::Pmpaddr(num) => self.pmpaddr[num] = data, 
-
-                CsrAddr::Mnstatus => {},
-                CsrAddr::Mhartid  => return Err(Exception::IllegalInstruction(raw)),
+                CsrAddr::Mip => self.mip = (self.mip & !MIP_WRITE_MASK) | (data & MIP_WRITE_MASK),
+
+                CsrAddr::Pmpcfg(num) => self.pmp.write_cfg(num, data),
+                CsrAddr::Pmpaddr(num) => self.pmp.write_addr(num, data),
+                CsrAddr::Mseccfg => self.pmp.write_mseccfg(data),
+                #[cfg(not(feature = "rv64"))]
+                CsrAddr::MseccfgH => {},
+
+                // User level counters are read-only shadows
+                CsrAddr::Counter(_) => return Err(Exception::IllegalInstruction(raw)),
+                #[cfg(not(feature = "rv64"))]
+                CsrAddr::CounterH(_) => return Err(Exception::IllegalInstruction(raw)),
+                CsrAddr::Mcounter(idx) => self.counters.write(idx, data, false),
+                #[cfg(not(feature = "rv64"))]
+                CsrAddr::McounterH(idx) => self.counters.write(idx, data, true),
+
+                CsrAddr::Mnscratch => self.rnmi.mnscratch = data,
+                CsrAddr::Mnepc     => self.rnmi.mnepc = data & !1,
+                CsrAddr::Mncause   => self.rnmi.mncause = data,
+                CsrAddr::Mnstatus  => self.rnmi.write_mnstatus(data),
+
+                CsrAddr::Tselect => self.triggers.set_tselect(data),
+                CsrAddr::Tdata1  => self.triggers.set_tdata1(data, self.sdext.halted),
+                CsrAddr::Tdata2  => self.triggers.set_tdata2(data, self.sdext.halted),
+                CsrAddr::Tdata3 | CsrAddr::Tinfo => {},
+                CsrAddr::Dcsr    => self.sdext.write_dcsr(data),
+                CsrAddr::Dpc     => self.sdext.dpc = data & !1,
+                CsrAddr::Dscratch(idx) => self.sdext.dscratch[idx] = data,
+
+                // Identification CSRs are read-only
+                CsrAddr::Mvendorid | CsrAddr::Marchid | CsrAddr::Mimpid |
+                CsrAddr::Mhartid | CsrAddr::Mconfigptr => return Err(Exception::IllegalInstruction(raw)),
             };
             Ok(())
         }
     }
 
-    pub fn trap_entry(&mut self, curr_pc: u32, except_code: Exception, mode: PrivilegeMode) -> (PrivilegeMode, u32) {
+    pub fn trap_entry(&mut self, curr_pc: XReg, trap: Trap, mode: PrivilegeMode) -> (PrivilegeMode, XReg) {
+        if let Trap::Nmi(_) = trap {
+            return (PrivilegeMode::Machine, self.rnmi.enter(curr_pc, trap, mode));
+        }
+
         let target_mode = match mode {
             PrivilegeMode::Machine => PrivilegeMode::Machine,
             #[cfg(feature = "s")]
             PrivilegeMode::Supervisor | PrivilegeMode::User => {
-                if self.medeleg & (1 << (u32::from(except_code))) > 0 {
+                let deleg = if trap.is_interrupt() {
+                    self.mideleg
+                } else {
+                    self.medeleg
+                };
+                if deleg & (1 << trap.code()) > 0 {
                     PrivilegeMode::Supervisor
                 } else {
                     PrivilegeMode::Machine
@@ -142,66 +331,137 @@ impl CsrFile {
             PrivilegeMode::User => PrivilegeMode::Machine
         };
 
-        let tval = match except_code {
-            Exception::IllegalInstruction(raw) => raw,
-            Exception::LoadAccessFault(addr) |
-            Exception::StoreOrAmoAccessFault(addr) |
-            Exception::InstructionAccessFault(addr) | 
-            Exception::LoadPageFault(addr) |
-            Exception::StoreOrAmoPageFault(addr) |
-            Exception::InstructionPageFault(addr)
+        let tval = match trap {
+            Trap::Exception(Exception::IllegalInstruction(raw)) => raw as XReg,
+            // Address of the access that fired a trigger, `ebreak` has none
+            Trap::Exception(Exception::Breakpoint) => self.triggers.take_hit().map_or(0, |hit| hit.addr),
+            Trap::Exception(Exception::InstructionAddressMisaligned(addr)) |
+            Trap::Exception(Exception::LoadAddressMisaligned(addr)) |
+            Trap::Exception(Exception::StoreOrAmoAddressMisaligned(addr)) |
+            Trap::Exception(Exception::LoadAccessFault(addr)) |
+            Trap::Exception(Exception::StoreOrAmoAccessFault(addr)) |
+            Trap::Exception(Exception::InstructionAccessFault(addr)) | 
+            Trap::Exception(Exception::LoadPageFault(addr)) |
+            Trap::Exception(Exception::StoreOrAmoPageFault(addr)) |
+            Trap::Exception(Exception::InstructionPageFault(addr))
                 => addr,
             _   => 0,
         };
+
+        // With NMIE clear, M-mode exceptions go to the RNMI exception handler
+        if target_mode == PrivilegeMode::Machine && !self.rnmi.nmie() {
+            return (target_mode, self.rnmi.enter(curr_pc, trap, mode));
+        }
    
         match target_mode {
             PrivilegeMode::Machine => {
                 self.mepc = curr_pc;
-                self.mcause = except_code.into();
+                self.mcause = trap.cause();
                 self.mtval = tval;
                 self.mstatus.set_mpie(self.mstatus.mie());
                 self.mstatus.set_mie(0);
                 self.mstatus.set_mpp(mode as u8);
-                let base_addr = self.mtvec & !0b11;
-                (target_mode,
-                if self.mtvec & 0b11 == 0b01 {
-                    base_addr + 4 * u32::from(except_code)
-                } else {
-                    base_addr
-                })
+                (target_mode, Self::trap_vector(self.mtvec, trap))
             },
             #[cfg(feature = "s")]
             PrivilegeMode::Supervisor => {
                 self.sepc = curr_pc;
-                self.scause = except_code.into();
+                self.scause = trap.cause();
                 self.stval = tval;
                 self.mstatus.set_spie(self.mstatus.sie());
                 self.mstatus.set_sie(0);
                 self.mstatus.set_spp(mode as u8);
-                let base_addr = self.stvec & !0b11;
-                (target_mode,
-                if self.stvec & 0b11 == 0b01 {
-                    base_addr + 4 * u32::from(except_code)
-                } else {
-                    base_addr
-                })
+                (target_mode, Self::trap_vector(self.stvec, trap))
             },
             PrivilegeMode::User => {(PrivilegeMode::User, 0)},
         }
     } 
 
-    pub fn trap_mret(&mut self) -> (PrivilegeMode, u32) {
+    // Only interrupts are vectored, synchronous exceptions always go to BASE
+    fn trap_vector(tvec: XReg, trap: Trap) -> XReg {
+        let base_addr = tvec & !0b11;
+        if tvec & 0b11 == 0b01 && trap.is_interrupt() {
+            base_addr + 4 * trap.code() as XReg
+        } else {
+            base_addr
+        }
+    }
+
+    /// Drive an interrupt line from a device. Software written bits of `mip` are kept
+    pub fn set_pending(&mut self, irq: Interrupt, level: bool) {
+        if level {
+            self.irq_lines |= irq.mask();
+        } else {
+            self.irq_lines &= !irq.mask();
+        }
+    }
+
+    fn mip(&self) -> XReg {
+        self.mip | self.irq_lines
+    }
+
+    /// Highest priority interrupt that is pending, enabled and globally enabled for `mode`
+    pub fn pending_interrupt(&self, mode: PrivilegeMode) -> Option<Interrupt> {
+        let pending = self.mip() & self.mie;
+        if pending == 0 || !self.rnmi.nmie() {
+            return None;
+        }
+
+        #[cfg(feature = "s")]
+        let mideleg = self.mideleg;
+        #[cfg(not(feature = "s"))]
+        let mideleg = 0;
+
+        let m_enabled = mode != PrivilegeMode::Machine || self.mstatus.mie() > 0;
+        let m_pending = if m_enabled { pending & !mideleg } else { 0 };
+
+        #[cfg(feature = "s")]
+        let s_pending = {
+            let s_enabled = match mode {
+                PrivilegeMode::Machine    => false,
+                PrivilegeMode::Supervisor => self.mstatus.sie() > 0,
+                PrivilegeMode::User       => true,
+            };
+            if s_enabled { pending & mideleg } else { 0 }
+        };
+        #[cfg(not(feature = "s"))]
+        let s_pending = 0;
+
+        // M-level interrupts always preempt S-level ones
+        let candidate = if m_pending != 0 { m_pending } else { s_pending };
+
+        PRIORITY.iter()
+            .find(|irq| candidate & irq.mask() != 0)
+            .copied()
+    }
+
+    pub fn trap_mret(&mut self) -> (PrivilegeMode, XReg) {
         let mode = self.mstatus.mpp().into();
         
         self.mstatus.set_mie(self.mstatus.mpie());
         self.mstatus.set_mpie(1);
         self.mstatus.set_mpp(0);
+        if mode != PrivilegeMode::Machine {
+            self.mstatus.set_mprv(0);
+        }
 
         (mode, self.mepc)
     } 
 
+    /// `mnret`, only from M-mode with Smrnmi enabled
+    pub fn trap_mnret(&mut self, curr_mode: PrivilegeMode) -> Result<(PrivilegeMode, XReg)> {
+        if !self.rnmi.is_enabled() || curr_mode != PrivilegeMode::Machine {
+            return Err(Exception::IllegalInstruction(0x70200073));
+        }
+        let (mode, pc) = self.rnmi.leave();
+        if mode != PrivilegeMode::Machine {
+            self.mstatus.set_mprv(0);
+        }
+        Ok((mode, pc))
+    }
+
     #[cfg(feature = "s")]
-    pub fn trap_sret(&mut self, curr_mode: PrivilegeMode) -> Result<(PrivilegeMode, u32)> {
+    pub fn trap_sret(&mut self, curr_mode: PrivilegeMode) -> Result<(PrivilegeMode, XReg)> {
         if self.mstatus.tsr() > 0 && curr_mode == PrivilegeMode::Supervisor {
             return Err(Exception::IllegalInstruction(0x10200073));
         };
@@ -214,20 +474,46 @@ impl CsrFile {
         self.mstatus.set_sie(self.mstatus.spie());
         self.mstatus.set_spie(1);
         self.mstatus.set_spp(0);
+        self.mstatus.set_mprv(0);
 
         Ok((mode, self.sepc))
     } 
 
     #[cfg(feature = "s")]
-    pub fn check_satp(&self, mode: PrivilegeMode) -> Result<Option<(u16, u32)>> {
+    pub fn check_satp(&self, mode: PrivilegeMode) -> Result<Option<(PagingMode, u16, XReg)>> {
         if mode == PrivilegeMode::Supervisor && self.check_tvm() {
             return Err(Exception::IllegalInstruction(0x180));
         }
-        Ok(if self.satp.mode() > 0  {
-            Some((self.satp.asid(), self.satp.ppn()))
+        Ok(PagingMode::from_satp(self.satp.mode())
+            .map(|paging| (paging, self.satp.asid(), self.satp.ppn() as XReg)))
+    }
+
+    /// Privilege of loads and stores, which is `MPP` when `mstatus.MPRV` is set in M-mode
+    pub fn data_mode(&self, mode: PrivilegeMode) -> PrivilegeMode {
+        if mode == PrivilegeMode::Machine && self.mstatus.mprv() > 0 {
+            self.mstatus.mpp().into()
         } else {
-            None
-        })
+            mode
+        }
+    }
+
+    pub fn check_tw(&self) -> bool {
+        self.mstatus.tw() > 0
+    }
+
+    /// Any locally enabled interrupt is pending, ignoring the global enable bits
+    pub fn has_wakeup(&self) -> bool {
+        self.mip() & self.mie != 0 || self.rnmi.is_pending()
+    }
+
+    /// Pending NMI, if `mnstatus.NMIE` lets it in
+    pub fn take_nmi(&mut self) -> Option<Trap> {
+        self.rnmi.take()
+    }
+
+    /// Latch an NMI with an implementation-defined `cause`. Ignored without Smrnmi
+    pub fn raise_nmi(&mut self, cause: u32) {
+        self.rnmi.raise(cause);
     }
 
     #[cfg(feature = "s")]
@@ -246,96 +532,206 @@ impl CsrFile {
     }
 
     pub fn pmp_check(&self, access: Access<Physical>, size: usize, mode: PrivilegeMode) -> Result<()> {
-        use pmpcfg::MatchingMode::*;
-        let mut is_match = None;
-        for i in 0..PMPCFG_NUM * 4 {
-            if match self.pmpcfg[i / 4][i % 4].mode() {
-                Off   => continue,
-                Tor   => self.top_of_range(i, access.addr, size),
-                Na4   => self.na4(i, access.addr, size),
-                Napot => self.napot(i, access.addr, size),
-            } {
-                is_match = Some(i);
-                break;
-            }
+        self.pmp.check(access, size, mode)
+    }
+
+    pub fn reset(&mut self) {
+        let hpm_num = self.counters.hpm_num();
+        *self = Self { mhartid: self.mhartid, ids: self.ids, misa: self.misa, rnmi: self.rnmi, pmp: self.pmp, ..Self::default() };
+        self.counters.set_hpm_num(hpm_num);
+        self.misa.reset();
+        self.rnmi.reset();
+        self.pmp.reset();
+    }
+
+    /// Extensions `misa` starts with and can switch back on
+    pub fn set_isa(&mut self, isa: Isa) {
+        self.misa = Misa::new(isa);
+    }
+
+    /// Extensions the hart has right now
+    pub fn isa(&self) -> Isa {
+        self.misa.isa()
+    }
+
+    pub fn set_hartid(&mut self, id: XReg) {
+        self.mhartid = id;
+    }
+
+    /// Raise a breakpoint if a trigger matches the access. Nothing fires in Debug Mode
+    pub fn check_triggers(&self, kind: AccessType, addr: XReg, size: usize, mode: PrivilegeMode) -> Result<()> {
+        if !self.sdext.halted && self.triggers.check(kind, addr, size, mode) {
+            Err(Exception::Breakpoint)
+        } else {
+            Ok(())
         }
-        match is_match {
-            Some(idx) => {
-                let pmpcfg = &self.pmpcfg[idx / 4][idx % 4];
-                if pmpcfg.mode_check(mode) {
-                    return Ok(());
-                }
-                if !pmpcfg.access_check(access) {
-                    return Err(access.into_access_exception());
-                }
-                Ok(())
+    }
+
+    /// Breakpoints enter Debug Mode for triggers that say so and for `ebreak` when `dcsr` asks for it
+    pub fn debug_cause(&self, except: Exception, mode: PrivilegeMode) -> Option<DebugCause> {
+        if except != Exception::Breakpoint {
+            return None;
+        }
+        match self.triggers.peek_hit() {
+            Some(hit) if hit.action == TriggerAction::DebugMode => {
+                self.triggers.take_hit();
+                Some(DebugCause::Trigger)
             },
-            None => match mode {
-                PrivilegeMode::Machine => Ok(()),
-                _                      => Err(access.into_access_exception()),
-            }
+            Some(_) => None,
+            None    => self.sdext.ebreak_halts(mode).then_some(DebugCause::Ebreak),
         }
     }
 
-    fn top_of_range(&self, idx: usize, addr: u32, size: usize) -> bool {
-        let lower = match idx.checked_sub(1) {
-            Some(i) => self.pmpaddr[i] << 2,
-            None      => 0,
-        };
-        let upper = self.pmpaddr[idx] << 2;
+    pub fn debug_entry(&mut self, cause: DebugCause, curr_pc: XReg, mode: PrivilegeMode) {
+        self.sdext.enter(cause, curr_pc, mode);
+    }
+
+    /// `dret` and resuming from the host, only in Debug Mode
+    pub fn debug_return(&mut self) -> Result<(PrivilegeMode, XReg)> {
+        if !self.sdext.halted {
+            return Err(Exception::IllegalInstruction(0x7b200073));
+        }
+        let (mode, pc) = self.sdext.leave();
+        if mode != PrivilegeMode::Machine {
+            self.mstatus.set_mprv(0);
+        }
+        Ok((mode, pc))
+    }
 
-        lower <= addr && addr + (size as u32) < upper
+    pub fn is_halted(&self) -> bool {
+        self.sdext.halted
     }
 
-    fn na4(&self, idx: usize, addr: u32, size: usize) -> bool {
-        let base = self.pmpaddr[idx] << 2;
+    /// Where the hart goes on when it leaves Debug Mode
+    pub fn dpc(&self) -> XReg {
+        self.sdext.dpc
+    }
 
-        base <= addr && addr + (size as u32) < base + 4
+    /// `dcsr.step` is set, so the hart halts again after one instruction
+    pub fn is_stepping(&self) -> bool {
+        !self.sdext.halted && self.sdext.dcsr.step() > 0
     }
 
-    fn napot(&self, idx: usize, addr: u32, size: usize) -> bool {
-        let mask_bit = self.pmpaddr[idx].trailing_ones();
-        let chunck_size = 1 << (3 + mask_bit);
-        let base = (self.pmpaddr[idx] & !((1 << mask_bit) - 1)) << 2;
+    /// Interrupts are off while stepping unless `dcsr.stepie` is set
+    pub fn step_masks_interrupts(&self) -> bool {
+        self.is_stepping() && self.sdext.dcsr.stepie() == 0
+    }
 
-        base <= addr && (addr as usize) + size < (base as usize)  + chunck_size
+    /// Enable Smrnmi with the RNMI interrupt and exception handler addresses
+    pub fn set_rnmi_handlers(&mut self, irq_handler: XReg, exc_handler: XReg) {
+        self.rnmi.enable(irq_handler, exc_handler);
     }
 
-    pub fn reset(&mut self) {
-        *self = Self::default()
+    /// Number of implemented PMP entries, up to 64, and the granularity `G`
+    pub fn set_pmp(&mut self, entries: usize, granularity: u32) {
+        self.pmp.set_config(entries, granularity);
+    }
+
+    /// `mvendorid`, `marchid`, `mimpid` and `mconfigptr`
+    pub fn set_ids(&mut self, ids: HartIds) {
+        self.ids = ids.aligned();
+    }
+
+    /// Number of implemented `mhpmcounter`, up to 29
+    pub fn set_hpm_num(&mut self, num: usize) {
+        self.counters.set_hpm_num(num);
     }
 
-    pub fn inspect(&self) -> Vec<(String, u32)> {
-        let pmp_list = self.pmpcfg.iter().enumerate()
-            .map(|(i, cfg)| (format!("pmpcfg{}", i), (*cfg).into()))
-            .chain(self.pmpaddr.iter().enumerate()
-                .map(|(i, addr)| (format!("pmpaddr{}", i), *addr))
-            );
+    pub fn count_event(&mut self, event: HpmEvent, num: u64) {
+        self.counters.count_event(event, num);
+    }
 
-        let mut csr_list: Vec<(String, u32)> = vec![
+    pub fn set_time(&mut self, time: u64) {
+        self.counters.set_time(time);
+    }
+
+    /// Advance `mcycle` and, if the instruction retired, `minstret`
+    pub fn tick_counters(&mut self, retired: bool) {
+        self.counters.tick(retired);
+    }
+
+    /// `mstatus.FS` is Off or F is switched off, every floating-point instruction and CSR is illegal
+    #[cfg(feature = "f")]
+    pub fn fs_off(&self) -> bool {
+        self.mstatus.fs() == 0 || !self.misa.has(Extension::F)
+    }
+
+    #[cfg(feature = "f")]
+    pub fn set_fs_dirty(&mut self) {
+        self.mstatus.set_fs_dirty();
+    }
+
+    /// Dynamic rounding mode
+    #[cfg(feature = "f")]
+    pub fn frm(&self) -> u8 {
+        (self.fcsr >> FRM_SHIFT) as u8
+    }
+
+    /// Exception flags are sticky, they are only cleared by writing `fflags`
+    #[cfg(feature = "f")]
+    pub fn accrue_fflags(&mut self, flags: u8) {
+        if flags != 0 {
+            self.fcsr |= flags as XReg & FFLAGS_MASK;
+            self.mstatus.set_fs_dirty();
+        }
+    }
+
+    #[cfg(feature = "f")]
+    fn set_fcsr(&mut self, data: XReg) {
+        self.fcsr = data & 0xff;
+        self.mstatus.set_fs_dirty();
+    }
+
+    pub fn inspect(&self) -> Vec<(String, XReg)> {
+        let mut csr_list: Vec<(String, XReg)> = vec![
             ("ustatus".to_string(), 0),
+            #[cfg(feature = "f")] ("fcsr".to_string(), self.fcsr),
             ("sstatus".to_string(), self.mstatus.read_s()),
             #[cfg(feature = "s")] ("sie".to_string(), self.mie & self.mideleg),
             #[cfg(feature = "s")] ("stvec".to_string(), self.stvec),
+            #[cfg(feature = "s")] ("senvcfg".to_string(), self.senvcfg),
             #[cfg(feature = "s")] ("sscratch".to_string(), self.sscratch),
             #[cfg(feature = "s")] ("sepc".to_string(), self.sepc),
             #[cfg(feature = "s")] ("scause".to_string(), self.scause),
             #[cfg(feature = "s")] ("stval".to_string(), self.stval),
-            #[cfg(feature = "s")] ("sip".to_string(), self.mip & self.mideleg),
+            #[cfg(feature = "s")] ("sip".to_string(), self.mip() & self.mideleg),
             #[cfg(feature = "s")] ("stap".to_string(), self.satp.into()),
             ("mstatus".to_string(), self.mstatus.read_m()),
+            ("misa".to_string(), self.misa.read()),
             #[cfg(feature = "s")] ("medeleg".to_string(), self.medeleg),
             #[cfg(feature = "s")] ("mideleg".to_string(), self.mideleg),
             ("mie".to_string(), self.mie),
             ("mtvec".to_string(), self.mtvec),
+            ("mcounteren".to_string(), self.counters.mcounteren as XReg),
+            ("menvcfg".to_string(), self.menvcfg),
+            #[cfg(feature = "s")] ("scounteren".to_string(), self.counters.scounteren as XReg),
+            ("mcountinhibit".to_string(), self.counters.mcountinhibit() as XReg),
             ("mscratch".to_string(), self.mscratch),
             ("mepc".to_string(), self.mepc),
             ("mcause".to_string(), self.mcause),
-            ("mip".to_string(), self.mip),
+            ("mip".to_string(), self.mip()),
         ];
-        csr_list.extend(pmp_list);
-        csr_list.extend(vec![("mnstatus".to_string(), 0),
-            ("mhartid".to_string(), 0)]);
+        csr_list.extend(self.pmp.inspect());
+        csr_list.extend([(0, "mcycle"), (2, "minstret")].into_iter()
+            .map(|(idx, name)| (name.to_string(), self.counters.read(idx) as XReg)));
+        if self.rnmi.is_enabled() {
+            csr_list.extend(vec![("mnscratch".to_string(), self.rnmi.mnscratch),
+                ("mnepc".to_string(), self.rnmi.mnepc),
+                ("mncause".to_string(), self.rnmi.mncause)]);
+        }
+        csr_list.extend(vec![("mnstatus".to_string(), self.rnmi.read_mnstatus()),
+            ("mvendorid".to_string(), self.ids.mvendorid as XReg),
+            ("marchid".to_string(), self.ids.marchid),
+            ("mimpid".to_string(), self.ids.mimpid),
+            ("mhartid".to_string(), self.mhartid),
+            ("mconfigptr".to_string(), self.ids.mconfigptr)]);
+        csr_list.extend(vec![("tselect".to_string(), self.triggers.tselect()),
+            ("tdata1".to_string(), self.triggers.tdata1()),
+            ("tdata2".to_string(), self.triggers.tdata2()),
+            ("dcsr".to_string(), self.sdext.read_dcsr(self.rnmi.is_pending())),
+            ("dpc".to_string(), self.sdext.dpc),
+            ("dscratch0".to_string(), self.sdext.dscratch[0]),
+            ("dscratch1".to_string(), self.sdext.dscratch[1])]);
 
         csr_list
     }
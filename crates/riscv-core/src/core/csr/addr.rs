@@ -2,18 +2,23 @@ use crate::{Exception, Result};
 
 use CsrAddr::*;
 
-use super::PMPCFG_NUM;
+use super::PMP_MAX_ENTRIES;
 
-const PMPCFG_END: u16 = 0x3a0 + PMPCFG_NUM as u16;
-const PMPADDR_END: u16 = 0x3b0 + (PMPCFG_NUM * 4) as u16;
+const PMPCFG_END: u16 = 0x3a0 + (PMP_MAX_ENTRIES / 4) as u16 - 1;
+const PMPADDR_END: u16 = 0x3b0 + PMP_MAX_ENTRIES as u16 - 1;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CsrAddr {
     Ustatus,
+    #[cfg(feature = "f")] Fflags,
+    #[cfg(feature = "f")] Frm,
+    #[cfg(feature = "f")] Fcsr,
 
     #[cfg(feature = "s")] Sstatus,
     #[cfg(feature = "s")] Sie,
     #[cfg(feature = "s")] Stvec,
+    #[cfg(feature = "s")] Scounteren,
+    #[cfg(feature = "s")] Senvcfg,
     #[cfg(feature = "s")] Sscratch,
     #[cfg(feature = "s")] Sepc,
     #[cfg(feature = "s")] Scause,
@@ -22,10 +27,17 @@ pub enum CsrAddr {
     #[cfg(feature = "s")] Satp,
 
     Mstatus,
+    Misa,
     #[cfg(feature = "s")] Medeleg,
     #[cfg(feature = "s")] Mideleg,
     Mie,
     Mtvec,
+    Mcounteren,
+    Menvcfg,
+    #[cfg(not(feature = "rv64"))]
+    MenvcfgH,
+    Mcountinhibit,
+    Mhpmevent(usize),
     Mscratch,
     Mepc,
     Mcause,
@@ -33,19 +45,52 @@ pub enum CsrAddr {
     Mip,
     Pmpcfg(usize),
     Pmpaddr(usize),
+    Mseccfg,
+    #[cfg(not(feature = "rv64"))]
+    MseccfgH,
 
+    /// Index 0 is `cycle`, 1 is `time`, 2 is `instret`, 3.. are `hpmcounter`
+    Counter(usize),
+    #[cfg(not(feature = "rv64"))]
+    CounterH(usize),
+    Mcounter(usize),
+    #[cfg(not(feature = "rv64"))]
+    McounterH(usize),
+
+    Mnscratch,
+    Mnepc,
+    Mncause,
     Mnstatus,
+
+    Tselect,
+    Tdata1,
+    Tdata2,
+    Tdata3,
+    Tinfo,
+    Dcsr,
+    Dpc,
+    Dscratch(usize),
+
+    Mvendorid,
+    Marchid,
+    Mimpid,
     Mhartid,
+    Mconfigptr,
 }
 
 impl CsrAddr {
     pub fn get_csr(addr: u16, raw: u32) -> Result<Self> {
         Ok(match addr {
             0x000 => Ustatus,
+            #[cfg(feature = "f")] 0x001 => Fflags,
+            #[cfg(feature = "f")] 0x002 => Frm,
+            #[cfg(feature = "f")] 0x003 => Fcsr,
 
             #[cfg(feature = "s")] 0x100 => Sstatus,
             #[cfg(feature = "s")] 0x104 => Sie,
             #[cfg(feature = "s")] 0x105 => Stvec,
+            #[cfg(feature = "s")] 0x106 => Scounteren,
+            #[cfg(feature = "s")] 0x10a => Senvcfg,
             #[cfg(feature = "s")] 0x140 => Sscratch,
             #[cfg(feature = "s")] 0x141 => Sepc,
             #[cfg(feature = "s")] 0x142 => Scause,
@@ -54,22 +99,72 @@ impl CsrAddr {
             #[cfg(feature = "s")] 0x180 => Satp,
             
             0x300 => Mstatus,
+            0x301 => Misa,
             #[cfg(feature = "s")] 0x302 => Medeleg,
             #[cfg(feature = "s")] 0x303 => Mideleg,
             0x304 => Mie,
             0x305 => Mtvec,
+            0x306 => Mcounteren,
+            0x30a => Menvcfg,
+            #[cfg(not(feature = "rv64"))]
+            0x31a => MenvcfgH,
+            0x320 => Mcountinhibit,
+            num @ 0x323..=0x33f => Mhpmevent((num - 0x320) as usize),
             0x340 => Mscratch,
             0x341 => Mepc,
             0x342 => Mcause,
             0x343 => Mtval,
             0x344 => Mip,
+            // Odd `pmpcfg` do not exist under RV64
+            #[cfg(feature = "rv64")]
+            0x3a0..=PMPCFG_END if addr & 1 == 1 => return Err(Exception::IllegalInstruction(raw)),
             num @ 0x3a0..=PMPCFG_END => Pmpcfg((num - 0x3a0) as usize),
             num @ 0x3b0..=PMPADDR_END => Pmpaddr((num - 0x3b0) as usize),
+
+            // There is no `mtime` CSR, so 0xb01 and 0xb81 do not exist
+            num @ (0xb00 | 0xb02..=0xb1f) => Mcounter((num - 0xb00) as usize),
+            #[cfg(not(feature = "rv64"))]
+            num @ (0xb80 | 0xb82..=0xb9f) => McounterH((num - 0xb80) as usize),
+            num @ 0xc00..=0xc1f => Counter((num - 0xc00) as usize),
+            #[cfg(not(feature = "rv64"))]
+            num @ 0xc80..=0xc9f => CounterH((num - 0xc80) as usize),
             
+            0x740 => Mnscratch,
+            0x741 => Mnepc,
+            0x742 => Mncause,
             0x744 => Mnstatus,
-            0xf14 => Mhartid, 
+            0x747 => Mseccfg,
+            #[cfg(not(feature = "rv64"))]
+            0x757 => MseccfgH,
+
+            0x7a0 => Tselect,
+            0x7a1 => Tdata1,
+            0x7a2 => Tdata2,
+            0x7a3 => Tdata3,
+            0x7a4 => Tinfo,
+            0x7b0 => Dcsr,
+            0x7b1 => Dpc,
+            num @ 0x7b2..=0x7b3 => Dscratch((num - 0x7b2) as usize),
+
+            0xf11 => Mvendorid,
+            0xf12 => Marchid,
+            0xf13 => Mimpid,
+            0xf14 => Mhartid,
+            0xf15 => Mconfigptr,
 
             _     => return Err(Exception::IllegalInstruction(raw)),
         })
     }
+
+    /// Only exists while the hart has S
+    #[cfg(feature = "s")]
+    pub fn is_supervisor(&self) -> bool {
+        matches!(self, Sstatus | Sie | Stvec | Scounteren | Senvcfg | Sscratch | Sepc | Scause | Stval | Sip | Satp
+            | Medeleg | Mideleg)
+    }
+
+    /// Only accessible in Debug Mode
+    pub fn is_debug(&self) -> bool {
+        matches!(self, Dcsr | Dpc | Dscratch(_))
+    }
 }
@@ -0,0 +1,176 @@
+use crate::core::privilege::PrivilegeMode;
+use crate::core::XReg;
+
+/// `mhpmcounter3` to `mhpmcounter31`
+pub const HPM_MAX: usize = 29;
+pub const DEFAULT_HPM_NUM: usize = 4;
+
+const CY: usize = 0;
+const TM: usize = 1;
+const IR: usize = 2;
+const HPM_BASE: usize = 3;
+
+/// Events that `mhpmevent` can select. The value is what software writes into `mhpmevent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HpmEvent {
+    #[cfg(feature = "s")]
+    TlbMiss     = 1,
+    Trap        = 2,
+    Load        = 3,
+    Store       = 4,
+    BranchTaken = 5,
+}
+
+impl HpmEvent {
+    const MAX: u32 = HpmEvent::BranchTaken as u32;
+}
+
+/// Zicntr and Zihpm counters. Index 0 is `cycle`, 1 is `time`, 2 is `instret`, 3.. are `hpmcounter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counters {
+    mcycle: u64,
+    minstret: u64,
+    // Mirror of CLINT `mtime`, updated by the hart every step
+    time: u64,
+    hpm: [u64; HPM_MAX],
+    event: [u32; HPM_MAX],
+    hpm_num: usize,
+
+    pub mcounteren: u32,
+    #[cfg(feature = "s")]
+    pub scounteren: u32,
+    mcountinhibit: u32,
+
+    // Counters written by the current instruction, so they skip this step's increment
+    written: u32,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Counters {
+            mcycle: 0,
+            minstret: 0,
+            time: 0,
+            hpm: [0; HPM_MAX],
+            event: [0; HPM_MAX],
+            hpm_num: DEFAULT_HPM_NUM,
+            mcounteren: 0,
+            #[cfg(feature = "s")]
+            scounteren: 0,
+            mcountinhibit: 0,
+            written: 0,
+        }
+    }
+}
+
+impl Counters {
+    /// Number of implemented `mhpmcounter`, the rest are read-only zero
+    pub fn set_hpm_num(&mut self, num: usize) {
+        self.hpm_num = num.min(HPM_MAX);
+        self.hpm[self.hpm_num..].fill(0);
+        self.event[self.hpm_num..].fill(0);
+        self.mcountinhibit &= self.implemented_mask();
+    }
+
+    pub fn hpm_num(&self) -> usize {
+        self.hpm_num
+    }
+
+    pub fn set_time(&mut self, time: u64) {
+        self.time = time;
+    }
+
+    pub fn read(&self, idx: usize) -> u64 {
+        match idx {
+            CY => self.mcycle,
+            TM => self.time,
+            IR => self.minstret,
+            _  => self.hpm.get(idx - HPM_BASE).copied().unwrap_or(0),
+        }
+    }
+
+    /// Write the low or high half of a machine counter. `time` has no machine counter.
+    /// Under RV64 the low half is the whole counter
+    #[allow(clippy::unnecessary_cast)]
+    pub fn write(&mut self, idx: usize, data: XReg, high: bool) {
+        if !self.is_implemented(idx) || idx == TM {
+            return;
+        }
+        let old = self.read(idx);
+        let new = if high {
+            (old & 0xffff_ffff) | ((data as u64) << 32)
+        } else {
+            (old & !(XReg::MAX as u64)) | data as u64
+        };
+
+        match idx {
+            CY => self.mcycle = new,
+            IR => self.minstret = new,
+            _  => self.hpm[idx - HPM_BASE] = new,
+        }
+        self.written |= 1 << idx;
+    }
+
+    /// `mcounteren` and `scounteren` gate the user level shadows
+    pub fn accessible(&self, idx: usize, mode: PrivilegeMode) -> bool {
+        let bit = 1 << idx;
+        match mode {
+            PrivilegeMode::Machine    => true,
+            #[cfg(feature = "s")]
+            PrivilegeMode::Supervisor => self.mcounteren & bit != 0,
+            #[cfg(feature = "s")]
+            PrivilegeMode::User       => self.mcounteren & self.scounteren & bit != 0,
+            #[cfg(not(feature = "s"))]
+            PrivilegeMode::User       => self.mcounteren & bit != 0,
+        }
+    }
+
+    pub fn mcountinhibit(&self) -> u32 {
+        self.mcountinhibit
+    }
+
+    pub fn set_mcountinhibit(&mut self, data: u32) {
+        // Bit 1 would be `time`, which can not be inhibited
+        self.mcountinhibit = data & self.implemented_mask() & !(1 << TM);
+    }
+
+    pub fn event(&self, idx: usize) -> u32 {
+        self.event.get(idx - HPM_BASE).copied().unwrap_or(0)
+    }
+
+    pub fn set_event(&mut self, idx: usize, data: u32) {
+        if self.is_implemented(idx) {
+            // WARL: unknown events read back as 0
+            self.event[idx - HPM_BASE] = if data <= HpmEvent::MAX { data } else { 0 };
+        }
+    }
+
+    pub fn count_event(&mut self, event: HpmEvent, num: u64) {
+        for i in 0..self.hpm_num {
+            if self.event[i] == event as u32 && self.mcountinhibit & (1 << (i + HPM_BASE)) == 0 {
+                self.hpm[i] = self.hpm[i].wrapping_add(num);
+            }
+        }
+    }
+
+    /// End of a step. `cycle` always counts, `instret` only when an instruction retired
+    pub fn tick(&mut self, retired: bool) {
+        let active = !self.mcountinhibit & !self.written;
+
+        if active & (1 << CY) != 0 {
+            self.mcycle = self.mcycle.wrapping_add(1);
+        }
+        if retired && active & (1 << IR) != 0 {
+            self.minstret = self.minstret.wrapping_add(1);
+        }
+        self.written = 0;
+    }
+
+    pub fn is_implemented(&self, idx: usize) -> bool {
+        idx < HPM_BASE + self.hpm_num
+    }
+
+    pub fn implemented_mask(&self) -> u32 {
+        ((1u64 << (HPM_BASE + self.hpm_num)) - 1) as u32
+    }
+}
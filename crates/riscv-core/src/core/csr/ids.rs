@@ -0,0 +1,19 @@
+use crate::core::{XLEN, XReg};
+
+/// Values of the read-only identification CSRs. 0 means not implemented, which is the default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HartIds {
+    /// JEDEC bank and offset of the vendor
+    pub mvendorid: u32,
+    pub marchid: XReg,
+    pub mimpid: XReg,
+    /// Address of the configuration data structure
+    pub mconfigptr: XReg,
+}
+
+impl HartIds {
+    /// `mconfigptr` can only point at `XLEN` aligned addresses
+    pub(super) fn aligned(self) -> Self {
+        HartIds { mconfigptr: self.mconfigptr & !(XLEN as XReg / 8 - 1), ..self }
+    }
+}
@@ -0,0 +1,62 @@
+use riscv_decoder::isa::Isa;
+#[cfg(any(feature = "f", feature = "s"))]
+use riscv_decoder::isa::Extension;
+
+use crate::core::{XLEN, XReg};
+
+// MXL is 1 for RV32 and 2 for RV64
+const MXL: XReg = (XLEN as XReg / 32) << (XLEN - 2);
+// User mode comes with Zicsr
+const U_BIT: XReg = 1 << 20;
+
+/// Only these extensions can be switched off and on again by software
+const WRITABLE: u32 = (1 << 0) | (1 << 2) | (1 << 3) | (1 << 5) | (1 << 12);
+const C_BIT: u32 = 1 << 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Misa {
+    // Configured ISA, and what is left after software switched some off
+    max: Isa,
+    curr: Isa,
+}
+
+impl Default for Misa {
+    fn default() -> Self {
+        Misa::new(Isa::default())
+    }
+}
+
+impl Misa {
+    pub fn new(isa: Isa) -> Self {
+        Misa { max: isa, curr: isa }
+    }
+
+    pub fn read(&self) -> XReg {
+        MXL | U_BIT | self.curr.misa() as XReg
+    }
+
+    /// WARL, extensions that were not configured stay off and D goes with F. Clearing C is
+    /// ignored while the instruction at `next_pc` is not 4-byte aligned
+    #[allow(clippy::unnecessary_cast)]
+    pub fn write(&mut self, data: XReg, next_pc: XReg) {
+        let max = self.max.misa();
+        let mut data = data as u32 & WRITABLE;
+        if next_pc & 0b10 != 0 {
+            data |= self.curr.misa() & C_BIT;
+        }
+        self.curr = self.max.with_misa(data | (max & !WRITABLE));
+    }
+
+    pub fn isa(&self) -> Isa {
+        self.curr
+    }
+
+    #[cfg(any(feature = "f", feature = "s"))]
+    pub fn has(&self, ext: Extension) -> bool {
+        self.curr.has(ext)
+    }
+
+    pub fn reset(&mut self) {
+        self.curr = self.max;
+    }
+}
@@ -1,5 +1,8 @@
 use modular_bitfield::prelude::*;
 
+use crate::core::XReg;
+
+#[cfg(not(feature = "rv64"))]
 #[bitfield]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Mstatus {
@@ -26,32 +29,73 @@ pub struct Mstatus {
     pub sd: B1,
 }
 
-const M_MODE_WRITE_MASK: u32 = 
+#[cfg(feature = "rv64")]
+#[bitfield]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mstatus {
+    #[skip] __: B1, // uie: need N extension
+    pub sie: B1,
+    #[skip] __: B1, // Reserved
+    pub mie: B1, 
+    #[skip] __: B1, // upie: need N extension
+    pub spie: B1, 
+    #[skip] __: B1, // User Big Endian: Always 0
+    pub mpie: B1,
+    pub spp: B1,
+    #[skip] __: B2, // Vector Status: need V extension
+    pub mpp: B2,
+    pub fs: B2, 
+    pub xs: B2, 
+    pub mprv: B1,
+    pub sum: B1,
+    pub mxr: B1,
+    pub tvm: B1,
+    pub tw: B1,
+    pub tsr: B1,
+    #[skip] __: B9, // WPRI: Reserved
+    pub uxl: B2,
+    pub sxl: B2,
+    #[skip] __: B27, // Endianness and hypervisor bits: Always 0
+    pub sd: B1,
+}
+
+const M_MODE_WRITE_MASK: XReg = 
     (1 << 1)  | (1 << 3)  | (1 << 5)  | (1 << 7)  | (1 << 8)  | (3 << 11) | (3 << 13) | (3 << 15) | 
     (1 << 16) | (1 << 17) | (1 << 18) | (1 << 16) | (1 << 17) | (1 << 18) | (1 << 19) | (1 << 20) | (1 << 21);
 
-const S_MODE_WRITE_MASK: u32 = 
+const S_MODE_WRITE_MASK: XReg = 
     (1 << 1) | (1 << 5) | (1 << 8) | 
     (3 << 13) | (3 << 15) | (1 << 18) | (1 << 19);
 
-const S_MODE_READ_MASK: u32 = S_MODE_WRITE_MASK | (1 << 31);
+#[cfg(not(feature = "rv64"))]
+const S_MODE_READ_MASK: XReg = S_MODE_WRITE_MASK | (1 << 31);
+// `UXL` is visible through `sstatus` but read-only
+#[cfg(feature = "rv64")]
+const S_MODE_READ_MASK: XReg = S_MODE_WRITE_MASK | (3 << 32) | (1 << 63);
 
 impl Mstatus {
-    pub fn read_m(&self) -> u32{
-        u32::from(*self)
+    pub fn read_m(&self) -> XReg {
+        XReg::from(*self)
     }
 
-    pub fn read_s(&self) -> u32{
-        u32::from(*self) & S_MODE_READ_MASK
+    pub fn read_s(&self) -> XReg {
+        XReg::from(*self) & S_MODE_READ_MASK
+    }
+
+    pub fn write_m(&mut self, data: XReg) {
+        *self = ((XReg::from(*self) & !M_MODE_WRITE_MASK) | (data & M_MODE_WRITE_MASK)).into();
+        self.check_update_sd();
     }
 
-    pub fn write_m(&mut self, data: u32) {
-        *self = ((u32::from(*self) & !M_MODE_WRITE_MASK) | (data & M_MODE_WRITE_MASK)).into();
+    pub fn write_s(&mut self, data: XReg) {
+        *self = ((XReg::from(*self) & !S_MODE_WRITE_MASK) | (data & S_MODE_WRITE_MASK)).into();
         self.check_update_sd();
     }
 
-    pub fn write_s(&mut self, data: u32) {
-        *self = ((u32::from(*self) & !S_MODE_WRITE_MASK) | (data & S_MODE_WRITE_MASK)).into();
+    /// FP state was modified, `FS` becomes Dirty
+    #[cfg(feature = "f")]
+    pub fn set_fs_dirty(&mut self) {
+        self.set_fs(0b11);
         self.check_update_sd();
     }
 
@@ -64,20 +108,29 @@ impl Mstatus {
     }
 }
 
-impl From<Mstatus> for u32 {
+impl From<Mstatus> for XReg {
     fn from(value: Mstatus) -> Self {
         Self::from_le_bytes(value.into_bytes())
     }
 }
 
-impl From<u32> for Mstatus {
-    fn from(value: u32) -> Self {
+impl From<XReg> for Mstatus {
+    fn from(value: XReg) -> Self {
         Self::from_bytes(value.to_le_bytes())
     }
 }
 
 impl Default for Mstatus {
     fn default() -> Self {
-        Self::new()
+        let mstatus = Self::new();
+        // FS starts as Initial, so bare-metal code can use the FPU without enabling it first
+        #[cfg(feature = "f")]
+        let mstatus = mstatus.with_fs(0b01);
+        // `UXL` and `SXL` are hardwired to 64-bit
+        #[cfg(feature = "rv64")]
+        let mstatus = mstatus.with_uxl(2);
+        #[cfg(all(feature = "rv64", feature = "s"))]
+        let mstatus = mstatus.with_sxl(2);
+        mstatus
     }
 }
\ No newline at end of file
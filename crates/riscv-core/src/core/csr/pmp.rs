@@ -0,0 +1,302 @@
+use modular_bitfield::prelude::*;
+
+use crate::Result;
+use crate::core::access::{Access, AccessType, Physical};
+use crate::core::{PrivilegeMode, XLEN, XReg};
+
+use MatchingMode::*;
+
+/// Most entries a hart can have, `pmpcfg0..15` and `pmpaddr0..63`
+pub const PMP_MAX_ENTRIES: usize = 64;
+const DEFAULT_ENTRIES: usize = 16;
+
+// `pmpaddr` holds bits 55:2 of the address under RV64
+#[cfg(not(feature = "rv64"))]
+const PMPADDR_MASK: XReg = XReg::MAX;
+#[cfg(feature = "rv64")]
+const PMPADDR_MASK: XReg = (1 << 54) - 1;
+
+// Bits 5 and 6 are reserved
+const PMPCFG_WRITE_MASK: u8 = 0x9f;
+
+// Smepmp `mseccfg`
+const MML: XReg = 1 << 0;
+const MMWP: XReg = 1 << 1;
+const RLB: XReg = 1 << 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchingMode {
+    #[default]
+    Off   = 0b00,
+    Tor   = 0b01,
+    Na4   = 0b10,
+    Napot = 0b11,
+}
+
+#[bitfield]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PmpEntry {
+    r: B1,
+    w: B1,
+    x: B1,
+    a: B2,
+    #[skip] __: B2,
+    l: B1,
+}
+
+// What a matching entry lets a mode do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Perms {
+    r: bool,
+    w: bool,
+    x: bool,
+}
+
+impl Perms {
+    const NONE: Perms = Perms { r: false, w: false, x: false };
+    const R: Perms = Perms { r: true, w: false, x: false };
+    const X: Perms = Perms { r: false, w: false, x: true };
+    const RW: Perms = Perms { r: true, w: true, x: false };
+    const RX: Perms = Perms { r: true, w: false, x: true };
+    const RWX: Perms = Perms { r: true, w: true, x: true };
+
+    fn allows(&self, kind: AccessType) -> bool {
+        match kind {
+            AccessType::Load  => self.r,
+            AccessType::Store => self.w,
+            AccessType::Fetch => self.x,
+            #[cfg(feature = "a")]
+            AccessType::Amo   => self.r && self.w,
+        }
+    }
+}
+
+impl PmpEntry {
+    pub fn mode(&self) -> MatchingMode {
+        match self.a() {
+            0b00 => Off,
+            0b01 => Tor,
+            0b10 => Na4,
+            0b11 => Napot,
+            _    => unreachable!("pmpcfg's 'a' only has 2 bits"),
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.l() > 0
+    }
+
+    fn own_perms(&self) -> Perms {
+        Perms { r: self.r() > 0, w: self.w() > 0, x: self.x() > 0 }
+    }
+
+    // R = 0 and W = 1 is a shared region under MML
+    fn is_shared(&self) -> bool {
+        self.r() == 0 && self.w() > 0
+    }
+
+    fn perms(&self, mode: PrivilegeMode, mml: bool) -> Perms {
+        let machine = mode == PrivilegeMode::Machine;
+        if !mml {
+            // Unlocked entries do not apply to M-mode
+            return if machine && !self.is_locked() { Perms::RWX } else { self.own_perms() };
+        }
+
+        let (locked, x) = (self.is_locked(), self.x() > 0);
+        match (self.is_shared(), locked, machine) {
+            (true, false, true)  => Perms::RW,
+            (true, false, false) => if x { Perms::RW } else { Perms::R },
+            (true, true, true)   => if x { Perms::RX } else { Perms::X },
+            (true, true, false)  => Perms::X,
+            // Locked shared data region
+            (false, true, _) if self.own_perms() == Perms::RWX => Perms::R,
+            (false, true, true) | (false, false, false) => self.own_perms(),
+            (false, _, _) => Perms::NONE,
+        }
+    }
+
+    // With MML and no RLB, M-mode executable and locked shared code regions can not be added
+    fn is_m_executable(&self) -> bool {
+        self.is_locked() && self.own_perms() != Perms::RWX && (self.x() > 0 || self.is_shared())
+    }
+}
+
+impl From<PmpEntry> for u8 {
+    fn from(value: PmpEntry) -> Self {
+        value.into_bytes()[0]
+    }
+}
+
+impl From<u8> for PmpEntry {
+    fn from(value: u8) -> Self {
+        PmpEntry::from_bytes([value; 1])
+    }
+}
+
+/// PMP entries and Smepmp `mseccfg`. Entries past the implemented ones are read-only zero
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pmp {
+    cfg: [PmpEntry; PMP_MAX_ENTRIES],
+    addr: [XReg; PMP_MAX_ENTRIES],
+    entries: usize,
+    // Regions are at least 2^(G + 2) bytes
+    granularity: u32,
+    mseccfg: XReg,
+}
+
+impl Default for Pmp {
+    fn default() -> Self {
+        Pmp {
+            cfg: [PmpEntry::default(); PMP_MAX_ENTRIES],
+            addr: [0; PMP_MAX_ENTRIES],
+            entries: DEFAULT_ENTRIES,
+            granularity: 0,
+            mseccfg: 0,
+        }
+    }
+}
+
+impl Pmp {
+    /// Implemented entries and granularity `G`, which survive a reset
+    pub fn set_config(&mut self, entries: usize, granularity: u32) {
+        *self = Pmp {
+            entries: entries.min(PMP_MAX_ENTRIES),
+            granularity: granularity.min(XLEN - 1),
+            ..Pmp::default()
+        };
+    }
+
+    pub fn reset(&mut self) {
+        self.set_config(self.entries, self.granularity);
+    }
+
+    fn entry(&self, idx: usize) -> PmpEntry {
+        if idx < self.entries { self.cfg[idx] } else { PmpEntry::default() }
+    }
+
+    fn mml(&self) -> bool {
+        self.mseccfg & MML != 0
+    }
+
+    fn rlb(&self) -> bool {
+        self.mseccfg & RLB != 0
+    }
+
+    /// `pmpcfg<num>`, four entries per register or eight under RV64
+    pub fn read_cfg(&self, num: usize) -> XReg {
+        (0..size_of::<XReg>())
+            .map(|i| (u8::from(self.entry(num * 4 + i)) as XReg) << (8 * i))
+            .fold(0, |cfg, byte| cfg | byte)
+    }
+
+    pub fn write_cfg(&mut self, num: usize, data: XReg) {
+        for i in 0..size_of::<XReg>() {
+            self.write_entry(num * 4 + i, (data >> (8 * i)) as u8);
+        }
+    }
+
+    /// Locked entries are read-only until `mseccfg.RLB` is set
+    fn write_entry(&mut self, idx: usize, data: u8) {
+        if idx >= self.entries || (self.cfg[idx].is_locked() && !self.rlb()) {
+            return;
+        }
+        let mut entry = PmpEntry::from(data & PMPCFG_WRITE_MASK);
+        if self.mml() && !self.rlb() && entry.is_m_executable() {
+            return;
+        }
+        // NA4 is not selectable with G >= 1
+        if self.granularity >= 1 && entry.mode() == Na4 {
+            entry.set_a(Off as u8);
+        }
+        self.cfg[idx] = entry;
+    }
+
+    /// Low bits read as ones for NAPOT and zeros otherwise when `G` is larger than a word
+    pub fn read_addr(&self, idx: usize) -> XReg {
+        if idx >= self.entries {
+            return 0;
+        }
+        let addr = self.addr[idx];
+        let g = self.granularity;
+        match self.cfg[idx].mode() {
+            Napot if g >= 2 => addr | ((1 << (g - 1)) - 1),
+            Off | Tor if g >= 1 => addr & !((1 << g) - 1),
+            _ => addr,
+        }
+    }
+
+    /// A locked entry also locks the address below it when it is TOR
+    pub fn write_addr(&mut self, idx: usize, data: XReg) {
+        if idx >= self.entries {
+            return;
+        }
+        let next = self.entry(idx + 1);
+        let locked = self.cfg[idx].is_locked() || (next.is_locked() && next.mode() == Tor);
+        if !locked || self.rlb() {
+            self.addr[idx] = data & PMPADDR_MASK;
+        }
+    }
+
+    pub fn read_mseccfg(&self) -> XReg {
+        self.mseccfg
+    }
+
+    /// MML and MMWP are sticky, RLB can not be set while any entry is locked
+    pub fn write_mseccfg(&mut self, data: XReg) {
+        let locked = self.cfg[..self.entries].iter().any(PmpEntry::is_locked);
+        let rlb = if self.rlb() || !locked { data & RLB } else { 0 };
+        self.mseccfg |= data & (MML | MMWP);
+        self.mseccfg = (self.mseccfg & !RLB) | rlb;
+    }
+
+    // Bytes `[lo, hi)` the entry covers, wide enough for any `pmpaddr`
+    fn range(&self, idx: usize) -> Option<(u128, u128)> {
+        let addr = self.read_addr(idx) as u128;
+        match self.cfg[idx].mode() {
+            Off   => None,
+            Tor   => {
+                let lo = idx.checked_sub(1).map_or(0, |i| (self.read_addr(i) as u128) << 2);
+                (lo < addr << 2).then_some((lo, addr << 2))
+            },
+            Na4   => Some((addr << 2, (addr << 2) + 4)),
+            Napot => {
+                let ones = addr.trailing_ones();
+                let lo = (addr & !((1 << ones) - 1)) << 2;
+                Some((lo, lo + (1 << (ones + 3))))
+            },
+        }
+    }
+
+    /// The lowest entry that covers any byte decides, and it has to cover all of them
+    pub fn check(&self, access: Access<Physical>, size: usize, mode: PrivilegeMode) -> Result<()> {
+        let start = access.addr as u128;
+        let end = start + size.max(1) as u128;
+
+        let matched = (0..self.entries)
+            .filter_map(|idx| self.range(idx).map(|range| (idx, range)))
+            .find(|&(_, (lo, hi))| start < hi && lo < end);
+
+        let allowed = match matched {
+            Some((_, (lo, hi))) if start < lo || hi < end => false,
+            Some((idx, _)) => self.cfg[idx].perms(mode, self.mml()).allows(access.kind),
+            None => match mode {
+                // Under MML M-mode only executes from regions set up for it
+                PrivilegeMode::Machine => {
+                    self.mseccfg & MMWP == 0 && !(self.mml() && access.kind == AccessType::Fetch)
+                },
+                _ => self.entries == 0,
+            },
+        };
+        if allowed { Ok(()) } else { Err(access.into_access_exception()) }
+    }
+
+    /// Names and values of the implemented `pmpcfg` and `pmpaddr`
+    pub fn inspect(&self) -> Vec<(String, XReg)> {
+        let cfg_step = size_of::<XReg>() / 4;
+        (0..self.entries.div_ceil(4)).step_by(cfg_step)
+            .map(|num| (format!("pmpcfg{}", num), self.read_cfg(num)))
+            .chain((0..self.entries).map(|idx| (format!("pmpaddr{}", idx), self.read_addr(idx))))
+            .chain([("mseccfg".to_string(), self.mseccfg)])
+            .collect()
+    }
+}
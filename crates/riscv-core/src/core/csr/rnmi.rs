@@ -0,0 +1,98 @@
+use crate::Trap;
+use crate::core::XReg;
+use crate::core::privilege::PrivilegeMode;
+
+const NMIE_BIT: XReg = 1 << 3;
+// MNPV is read-only zero: without the H extension there is no virtualization mode to save
+const MNPV_BIT: XReg = 1 << 7;
+const MNPP_SHIFT: usize = 11;
+
+/// Resumable NMI state (Smrnmi). Off until the host gives it handler addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rnmi {
+    // Interrupt and exception handler addresses
+    handlers: Option<(XReg, XReg)>,
+    pending: Option<u32>,
+
+    pub mnscratch: XReg,
+    pub mnepc: XReg,
+    pub mncause: XReg,
+    nmie: bool,
+    mnpp: PrivilegeMode,
+}
+
+impl Rnmi {
+    pub fn enable(&mut self, irq_handler: XReg, exc_handler: XReg) {
+        self.handlers = Some((irq_handler & !0b11, exc_handler & !0b11));
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.handlers.is_some()
+    }
+
+    /// `NMIE` masks every interrupt, NMIs included
+    pub fn nmie(&self) -> bool {
+        !self.is_enabled() || self.nmie
+    }
+
+    pub fn read_mnstatus(&self) -> XReg {
+        if !self.is_enabled() {
+            return 0;
+        }
+        (if self.nmie { NMIE_BIT } else { 0 }) | (self.mnpp as XReg) << MNPP_SHIFT
+    }
+
+    /// `NMIE` can be set but not cleared, `MNPP` is WARL (a reserved or missing mode becomes M)
+    /// and `MNPV` ignores writes
+    pub fn write_mnstatus(&mut self, data: XReg) {
+        if !self.is_enabled() {
+            return;
+        }
+        let data = data & !MNPV_BIT;
+        self.nmie |= data & NMIE_BIT != 0;
+        self.mnpp = PrivilegeMode::from((data >> MNPP_SHIFT) as u8 & 0b11);
+    }
+
+    /// Latch an NMI, it is taken once `NMIE` is set
+    pub fn raise(&mut self, cause: u32) {
+        if self.is_enabled() {
+            self.pending = Some(cause);
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn take(&mut self) -> Option<Trap> {
+        if self.nmie {
+            self.pending.take().map(Trap::Nmi)
+        } else {
+            None
+        }
+    }
+
+    /// Save the interrupted state and return the handler address. Only NMIs and
+    /// M-mode exceptions while `NMIE` is clear come here
+    pub fn enter(&mut self, curr_pc: XReg, trap: Trap, mode: PrivilegeMode) -> XReg {
+        let (irq_handler, exc_handler) = self.handlers.unwrap_or_default();
+        self.mnepc = curr_pc;
+        self.mncause = trap.cause();
+        self.mnpp = mode;
+        self.nmie = false;
+        if matches!(trap, Trap::Nmi(_)) { irq_handler } else { exc_handler }
+    }
+
+    /// `mnret` sets `NMIE` again and resets `MNPP` to the least privileged mode
+    pub fn leave(&mut self) -> (PrivilegeMode, XReg) {
+        let mode = self.mnpp;
+        self.mnpp = PrivilegeMode::User;
+        self.nmie = true;
+        (mode, self.mnepc)
+    }
+
+    /// The handlers are configuration, everything else starts over with `NMIE` clear
+    pub fn reset(&mut self) {
+        *self = Rnmi { handlers: self.handlers, ..Rnmi::default() };
+    }
+}
@@ -1,5 +1,8 @@
 use modular_bitfield::prelude::*;
 
+use crate::core::{PagingMode, XReg};
+
+#[cfg(not(feature = "rv64"))]
 #[bitfield]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Satp {
@@ -8,14 +11,30 @@ pub struct Satp {
     pub mode: B1,
 }
 
-impl From<Satp> for u32 {
+#[cfg(feature = "rv64")]
+#[bitfield]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Satp {
+    pub ppn: B44,
+    pub asid: B16,
+    pub mode: B4,
+}
+
+impl Satp {
+    /// Writing an unsupported `MODE` has no effect on the whole register
+    pub fn is_supported(&self) -> bool {
+        self.mode() == 0 || PagingMode::from_satp(self.mode()).is_some()
+    }
+}
+
+impl From<Satp> for XReg {
     fn from(value: Satp) -> Self {
         Self::from_le_bytes(value.into_bytes())
     }
 }
 
-impl From<u32> for Satp {
-    fn from(value: u32) -> Self {
+impl From<XReg> for Satp {
+    fn from(value: XReg) -> Self {
         Self::from_bytes(value.to_le_bytes())
     }
 }
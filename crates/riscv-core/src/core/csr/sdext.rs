@@ -0,0 +1,90 @@
+use modular_bitfield::prelude::*;
+
+use crate::core::XReg;
+use crate::core::privilege::PrivilegeMode;
+
+/// Why the hart entered Debug Mode, the value of `dcsr.cause`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCause {
+    Ebreak  = 1,
+    Trigger = 2,
+    HaltReq = 3,
+    Step    = 4,
+}
+
+#[bitfield]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dcsr {
+    pub prv: B2,
+    pub step: B1,
+    pub nmip: B1,
+    #[skip] __: B2, // mprven and v: Always 0
+    pub cause: B3,
+    #[skip] __: B2, // stoptime and stopcount: Always 0
+    pub stepie: B1,
+    pub ebreaku: B1,
+    pub ebreaks: B1,
+    #[skip] __: B1, // Reserved
+    pub ebreakm: B1,
+    #[skip] __: B12, // Reserved
+    pub debugver: B4,
+}
+
+#[cfg(feature = "s")]
+const DCSR_WRITE_MASK: u32 = 0b111 | (1 << 11) | (1 << 12) | (1 << 13) | (1 << 15);
+#[cfg(not(feature = "s"))]
+const DCSR_WRITE_MASK: u32 = 0b111 | (1 << 11) | (1 << 12) | (1 << 15);
+
+impl Default for Dcsr {
+    fn default() -> Self {
+        // External debug support as in the 1.0 spec, the hart was in M-mode
+        Dcsr::new().with_debugver(4).with_prv(PrivilegeMode::Machine as u8)
+    }
+}
+
+/// Debug Mode (Sdext). There is no program buffer, a hart in Debug Mode stays halted
+/// until the host resumes it or it executes `dret`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sdext {
+    pub dcsr: Dcsr,
+    pub dpc: XReg,
+    pub dscratch: [XReg; 2],
+    pub halted: bool,
+}
+
+impl Sdext {
+    /// `nmip` shows an NMI that waits for the hart
+    pub fn read_dcsr(&self, nmi_pending: bool) -> XReg {
+        u32::from_le_bytes(self.dcsr.with_nmip(nmi_pending as u8).into_bytes()) as XReg
+    }
+
+    /// `prv` only takes implemented modes
+    #[allow(clippy::unnecessary_cast)]
+    pub fn write_dcsr(&mut self, data: XReg) {
+        let old = u32::from_le_bytes(self.dcsr.into_bytes());
+        let dcsr = Dcsr::from_bytes(((old & !DCSR_WRITE_MASK) | (data as u32 & DCSR_WRITE_MASK)).to_le_bytes());
+        self.dcsr = dcsr.with_prv(PrivilegeMode::from(dcsr.prv()) as u8);
+    }
+
+    /// `ebreak` in `mode` enters Debug Mode instead of raising a breakpoint
+    pub fn ebreak_halts(&self, mode: PrivilegeMode) -> bool {
+        match mode {
+            PrivilegeMode::Machine    => self.dcsr.ebreakm() > 0,
+            #[cfg(feature = "s")]
+            PrivilegeMode::Supervisor => self.dcsr.ebreaks() > 0,
+            PrivilegeMode::User       => self.dcsr.ebreaku() > 0,
+        }
+    }
+
+    pub fn enter(&mut self, cause: DebugCause, curr_pc: XReg, mode: PrivilegeMode) {
+        self.dpc = curr_pc;
+        self.dcsr.set_cause(cause as u8);
+        self.dcsr.set_prv(mode as u8);
+        self.halted = true;
+    }
+
+    pub fn leave(&mut self) -> (PrivilegeMode, XReg) {
+        self.halted = false;
+        (self.dcsr.prv().into(), self.dpc)
+    }
+}
@@ -1,6 +1,8 @@
-use crate::core::CsrFile;
+use crate::core::{CsrFile, XLEN, XReg};
+use crate::core::csr::{DebugCause, HpmEvent};
 use crate::core::privilege::PrivilegeMode;
 use crate::exception::Exception;
+use crate::interrupt::Interrupt;
 
 #[test]
 fn test_csr_rw_permission() {
@@ -28,10 +30,220 @@ fn test_mstatus_behavior() {
     
     #[cfg(feature = "s")] {
         let sstatus = csr.read(0x100, PrivilegeMode::Supervisor, 0).unwrap();
+        #[cfg(not(feature = "rv64"))]
         assert_eq!(sstatus, 0);
+        // UXL is read-only 64-bit
+        #[cfg(feature = "rv64")]
+        assert_eq!(sstatus, 2 << 32);
     }
 }
 
+#[test]
+fn test_misa_warl() {
+    use riscv_decoder::isa::Isa;
+
+    let mut csr = CsrFile::default();
+    let mxl = (XLEN as XReg / 32) << (XLEN - 2);
+    let misa = csr.read(0x301, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(misa & !((1 << 26) - 1), mxl, "MXL should match XLEN");
+    assert_eq!(misa & (1 << 8), 1 << 8, "I is always there");
+    assert_eq!(misa & (1 << 20), 1 << 20, "U comes with Zicsr");
+    assert_eq!(misa & ((1 << 26) - 1) & !(1 << 20), Isa::default().misa() as XReg);
+
+    // Writing 0 only clears the extensions software may switch off
+    csr.write(0x301, 0, PrivilegeMode::Machine, 0).unwrap();
+    let cleared = csr.read(0x301, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(cleared & ((1 << 0) | (1 << 2) | (1 << 3) | (1 << 5) | (1 << 12)), 0);
+    assert_eq!(cleared & (1 << 8), 1 << 8);
+
+    // And they come back, but never more than configured
+    csr.write(0x301, XReg::MAX, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x301, PrivilegeMode::Machine, 0), Ok(misa));
+
+    assert!(csr.write(0x301, 0, PrivilegeMode::User, 0).is_err());
+}
+
+#[test]
+#[cfg(feature = "c")]
+fn test_misa_c_needs_aligned_next_pc() {
+    use riscv_decoder::isa::Extension;
+
+    let mut csr = CsrFile::default();
+    let misa = csr.read(0x301, PrivilegeMode::Machine, 0).unwrap();
+
+    // Only the C change is dropped, M still goes
+    csr.write_at(0x301, misa & !((1 << 2) | (1 << 12)), PrivilegeMode::Machine, 0, 0x8000_0002).unwrap();
+    assert!(csr.isa().has(Extension::C), "C must stay while the next pc is 2-byte aligned");
+    assert!(!csr.isa().has(Extension::M));
+
+    csr.write_at(0x301, misa & !(1 << 2), PrivilegeMode::Machine, 0, 0x8000_0004).unwrap();
+    assert!(!csr.isa().has(Extension::C));
+
+    // Setting C back is fine from anywhere
+    csr.write_at(0x301, misa, PrivilegeMode::Machine, 0, 0x8000_0006).unwrap();
+    assert_eq!(csr.read(0x301, PrivilegeMode::Machine, 0), Ok(misa));
+}
+
+#[test]
+#[cfg(feature = "d")]
+fn test_misa_d_needs_f() {
+    use riscv_decoder::isa::Extension;
+
+    let mut csr = CsrFile::default();
+    let misa = csr.read(0x301, PrivilegeMode::Machine, 0).unwrap();
+    csr.write(0x301, misa & !(1 << 5), PrivilegeMode::Machine, 0).unwrap();
+
+    assert!(!csr.isa().has(Extension::D), "D should go with F");
+    assert_eq!(csr.read(0x301, PrivilegeMode::Machine, 0).unwrap() & (1 << 3), 0);
+    // FP CSRs are gone with F
+    assert_eq!(csr.read(0x003, PrivilegeMode::Machine, 7), Err(Exception::IllegalInstruction(7)));
+
+    csr.reset();
+    assert_eq!(csr.read(0x301, PrivilegeMode::Machine, 0), Ok(misa));
+}
+
+#[test]
+#[cfg(feature = "s")]
+fn test_supervisor_csrs_need_s() {
+    use riscv_decoder::isa::{Extension, Isa};
+
+    let mut csr = CsrFile::default();
+    csr.set_isa(Isa::default().without(Extension::S));
+
+    assert_eq!(csr.read(0x100, PrivilegeMode::Machine, 7), Err(Exception::IllegalInstruction(7)));
+    assert_eq!(csr.write(0x302, 0, PrivilegeMode::Machine, 7), Err(Exception::IllegalInstruction(7)));
+    assert_eq!(csr.read(0x301, PrivilegeMode::Machine, 0).unwrap() & (1 << 18), 0);
+}
+
+#[test]
+fn test_machine_ids() {
+    use crate::core::csr::HartIds;
+
+    let mut csr = CsrFile::default();
+    for addr in 0xf11..=0xf15 {
+        if addr != 0xf14 {
+            assert_eq!(csr.read(addr, PrivilegeMode::Machine, 0), Ok(0), "{:#x} should default to 0", addr);
+        }
+        assert_eq!(csr.write(addr, 1, PrivilegeMode::Machine, 7), Err(Exception::IllegalInstruction(7)));
+    }
+
+    csr.set_ids(HartIds { mvendorid: 0x489, marchid: 5, mimpid: 0x100, mconfigptr: 0x1003 });
+    assert_eq!(csr.read(0xf11, PrivilegeMode::Machine, 0), Ok(0x489));
+    assert_eq!(csr.read(0xf12, PrivilegeMode::Machine, 0), Ok(5));
+    assert_eq!(csr.read(0xf13, PrivilegeMode::Machine, 0), Ok(0x100));
+    assert_eq!(csr.read(0xf15, PrivilegeMode::Machine, 0), Ok(0x1000), "mconfigptr is XLEN aligned");
+
+    // Configuration survives a reset
+    csr.reset();
+    assert_eq!(csr.read(0xf11, PrivilegeMode::Machine, 0), Ok(0x489));
+    assert!(csr.read(0xf11, PrivilegeMode::User, 0).is_err());
+}
+
+#[test]
+fn test_envcfg_warl() {
+    let mut csr = CsrFile::default();
+    // Only FIOM sticks
+    csr.write(0x30a, XReg::MAX, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x30a, PrivilegeMode::Machine, 0), Ok(1));
+    #[cfg(not(feature = "rv64"))] {
+        csr.write(0x31a, XReg::MAX, PrivilegeMode::Machine, 0).unwrap();
+        assert_eq!(csr.read(0x31a, PrivilegeMode::Machine, 0), Ok(0));
+    }
+    #[cfg(feature = "s")] {
+        csr.write(0x10a, XReg::MAX, PrivilegeMode::Supervisor, 0).unwrap();
+        assert_eq!(csr.read(0x10a, PrivilegeMode::Supervisor, 0), Ok(1));
+        assert!(csr.read(0x30a, PrivilegeMode::Supervisor, 0).is_err());
+    }
+}
+
+#[test]
+fn test_rnmi_csrs() {
+    let mut csr = CsrFile::default();
+    // Without Smrnmi only the mnstatus stub is there
+    assert_eq!(csr.read(0x740, PrivilegeMode::Machine, 7), Err(Exception::IllegalInstruction(7)));
+    csr.write(0x744, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x744, PrivilegeMode::Machine, 0), Ok(0));
+    assert_eq!(csr.trap_mnret(PrivilegeMode::Machine), Err(Exception::IllegalInstruction(0x70200073)));
+
+    csr.set_rnmi_handlers(0x100, 0x200);
+    csr.write(0x740, 0x1234, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x740, PrivilegeMode::Machine, 0), Ok(0x1234));
+
+    // Every interrupt is masked until NMIE is set
+    csr.write(0x300, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+    csr.write(0x304, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+    csr.set_pending(Interrupt::MachineSoftware, true);
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Machine), None);
+    csr.write(0x744, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Machine), Some(Interrupt::MachineSoftware));
+
+    // NMIE can not be cleared by software
+    csr.write(0x744, 0, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x744, PrivilegeMode::Machine, 0), Ok(1 << 3));
+    assert!(csr.trap_mnret(PrivilegeMode::User).is_err());
+
+    // MNPV stays zero, MNPP only holds modes that exist
+    csr.write(0x744, (1 << 7) | (2 << 11), PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x744, PrivilegeMode::Machine, 0), Ok((1 << 3) | (3 << 11)));
+    csr.write(0x744, 1 << 7, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x744, PrivilegeMode::Machine, 0), Ok(1 << 3));
+}
+
+#[test]
+fn test_trigger_csrs() {
+    use crate::core::access::AccessType;
+
+    let mut csr = CsrFile::default();
+    let mcontrol6 = 6 << (XLEN - 4);
+    let dmode = 1 << (XLEN - 5);
+    assert_eq!(csr.read(0x7a1, PrivilegeMode::Machine, 0), Ok(15 << (XLEN - 4)), "Triggers start disabled");
+    assert_eq!(csr.read(0x7a4, PrivilegeMode::Machine, 0).unwrap() & (1 << 6), 1 << 6, "mcontrol6 is supported");
+
+    // tselect is WARL
+    csr.write(0x7a0, 1, PrivilegeMode::Machine, 0).unwrap();
+    csr.write(0x7a0, 100, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x7a0, PrivilegeMode::Machine, 0), Ok(1));
+
+    // Outside Debug Mode dmode stays clear and the action falls back to a breakpoint
+    csr.write(0x7a1, mcontrol6 | dmode | (1 << 12) | (1 << 6) | (1 << 1), PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x7a1, PrivilegeMode::Machine, 0), Ok(mcontrol6 | (1 << 6) | (1 << 1)));
+    csr.write(0x7a2, 0x8000_0100, PrivilegeMode::Machine, 0).unwrap();
+
+    // Store to any byte of 0x8000_0100 as M-mode
+    assert_eq!(csr.check_triggers(AccessType::Load, 0x8000_0100, 4, PrivilegeMode::Machine), Ok(()));
+    assert_eq!(csr.check_triggers(AccessType::Store, 0x8000_0104, 4, PrivilegeMode::Machine), Ok(()));
+    assert_eq!(csr.check_triggers(AccessType::Store, 0x8000_00fe, 4, PrivilegeMode::Machine), Err(Exception::Breakpoint));
+    assert_eq!(csr.debug_cause(Exception::Breakpoint, PrivilegeMode::Machine), None);
+    csr.write(0x305, 0x100, PrivilegeMode::Machine, 0).unwrap();
+    csr.trap_entry(0x8000_0000, Exception::Breakpoint.into(), PrivilegeMode::Machine);
+    assert_eq!(csr.read(0x343, PrivilegeMode::Machine, 0), Ok(0x8000_00fe), "mtval holds the access address");
+
+    // Unsupported types disable the trigger
+    csr.write(0x7a1, 2 << (XLEN - 4), PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x7a1, PrivilegeMode::Machine, 0), Ok(15 << (XLEN - 4)));
+    assert!(csr.read(0x7a0, PrivilegeMode::User, 0).is_err());
+}
+
+#[test]
+fn test_debug_csrs_need_debug_mode() {
+    let mut csr = CsrFile::default();
+    for addr in 0x7b0..=0x7b3 {
+        assert_eq!(csr.read(addr, PrivilegeMode::Machine, 7), Err(Exception::IllegalInstruction(7)));
+    }
+    assert!(csr.debug_return().is_err(), "dret is illegal outside Debug Mode");
+
+    csr.debug_entry(DebugCause::HaltReq, 0x8000_0010, PrivilegeMode::User);
+    assert_eq!(csr.read(0x7b1, PrivilegeMode::Machine, 0), Ok(0x8000_0010));
+    let dcsr = csr.read(0x7b0, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(dcsr >> 28, 4, "debugver");
+    assert_eq!((dcsr >> 6) & 0b111, DebugCause::HaltReq as XReg);
+    assert_eq!(dcsr & 0b11, 0, "prv");
+
+    csr.write(0x7b0, (1 << 15) | 3, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.debug_cause(Exception::Breakpoint, PrivilegeMode::Machine), Some(DebugCause::Ebreak));
+    assert_eq!(csr.debug_return(), Ok((PrivilegeMode::Machine, 0x8000_0010)), "prv was written to M");
+}
+
 #[test]
 fn test_trap_entry() {
     let mut csr = CsrFile::default();
@@ -44,14 +256,14 @@ fn test_trap_entry() {
     let handler_base = 0x8000_0004;
     csr.write(0x305, handler_base, PrivilegeMode::Machine, 0).unwrap();
 
-    let (next_mode, next_pc) = csr.trap_entry(fault_pc, cause, PrivilegeMode::Machine);
+    let (next_mode, next_pc) = csr.trap_entry(fault_pc, cause.into(), PrivilegeMode::Machine);
 
     assert_eq!(next_mode, PrivilegeMode::Machine);
     
     assert_eq!(next_pc, handler_base);
 
     assert_eq!(csr.mepc, fault_pc);
-    assert_eq!(csr.mcause, u32::from(cause));
+    assert_eq!(csr.mcause, u32::from(cause) as XReg);
 
     let mstatus_new = csr.read(0x300, PrivilegeMode::Machine, 0).unwrap();
     assert_eq!(mstatus_new & (1 << 3), 0);
@@ -78,6 +290,23 @@ fn test_trap_return_mret() {
     assert_eq!(mstatus_after & (3 << 11), 0);
 }
 
+#[test]
+fn test_mret_clears_mprv() {
+    let mut csr = CsrFile::default();
+    let mprv = 1 << 17;
+
+    // Back to M-mode keeps MPRV
+    csr.write(0x300, mprv | (3 << 11), PrivilegeMode::Machine, 0).unwrap();
+    csr.trap_mret();
+    assert_eq!(csr.read(0x300, PrivilegeMode::Machine, 0).unwrap() & mprv, mprv);
+
+    // Any lower mode clears it
+    csr.write(0x300, mprv, PrivilegeMode::Machine, 0).unwrap();
+    let (ret_mode, _) = csr.trap_mret();
+    assert_eq!(ret_mode, PrivilegeMode::User);
+    assert_eq!(csr.read(0x300, PrivilegeMode::Machine, 0).unwrap() & mprv, 0);
+}
+
 #[test]
 #[cfg(feature = "s")]
 fn test_exception_delegation() {
@@ -90,30 +319,232 @@ fn test_exception_delegation() {
     let s_handler = 0x8000_4000;
     csr.write(0x105, s_handler, PrivilegeMode::Supervisor, 0).unwrap();
 
-    let (next_mode, next_pc) = csr.trap_entry(fault_pc, cause, PrivilegeMode::User);
+    let (next_mode, next_pc) = csr.trap_entry(fault_pc, cause.into(), PrivilegeMode::User);
 
     assert_eq!(next_mode, PrivilegeMode::Supervisor);
     assert_eq!(next_pc, s_handler);
     
     assert_eq!(csr.sepc, fault_pc);
-    assert_eq!(csr.scause, u32::from(cause));
+    assert_eq!(csr.scause, u32::from(cause) as XReg);
 
     assert_eq!(csr.mcause, 0); 
 }
 
+#[test]
+fn test_interrupt_pending_and_enable() {
+    let mut csr = CsrFile::default();
+    csr.set_pending(Interrupt::MachineTimer, true);
+
+    // Not enabled in mie
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Machine), None);
+
+    csr.write(0x304, 1 << 7, PrivilegeMode::Machine, 0).unwrap();
+    // mstatus.MIE still clear
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Machine), None);
+    // Lower privilege always takes M-level interrupts
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::User), Some(Interrupt::MachineTimer));
+
+    csr.write(0x300, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Machine), Some(Interrupt::MachineTimer));
+
+    csr.set_pending(Interrupt::MachineTimer, false);
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Machine), None);
+}
+
+#[test]
+fn test_interrupt_priority() {
+    let mut csr = CsrFile::default();
+    csr.write(0x300, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+    csr.write(0x304, XReg::MAX, PrivilegeMode::Machine, 0).unwrap();
+
+    csr.set_pending(Interrupt::MachineTimer, true);
+    csr.set_pending(Interrupt::MachineSoftware, true);
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Machine), Some(Interrupt::MachineSoftware));
+
+    csr.set_pending(Interrupt::MachineExternal, true);
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Machine), Some(Interrupt::MachineExternal));
+}
+
+#[test]
+fn test_mip_device_bits_read_only() {
+    let mut csr = CsrFile::default();
+
+    csr.write(0x344, 1 << 7, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x344, PrivilegeMode::Machine, 0), Ok(0));
+
+    csr.set_pending(Interrupt::MachineTimer, true);
+    csr.write(0x344, 0, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x344, PrivilegeMode::Machine, 0), Ok(1 << 7));
+}
+
+#[test]
+fn test_interrupt_trap_entry_vectored() {
+    let mut csr = CsrFile::default();
+    let pc = 0x8000_1000;
+    let base = 0x8000_0100;
+
+    // mtvec MODE = Vectored
+    csr.write(0x305, base | 1, PrivilegeMode::Machine, 0).unwrap();
+
+    let (next_mode, next_pc) = csr.trap_entry(pc, Interrupt::MachineTimer.into(), PrivilegeMode::Machine);
+    assert_eq!(next_mode, PrivilegeMode::Machine);
+    assert_eq!(next_pc, base + 4 * 7);
+    assert_eq!(csr.mcause, (1 << (XLEN - 1)) | 7);
+    assert_eq!(csr.mepc, pc);
+    assert_eq!(csr.mtval, 0);
+
+    // Synchronous exceptions still go to BASE
+    let (_, next_pc) = csr.trap_entry(pc, Exception::Breakpoint.into(), PrivilegeMode::Machine);
+    assert_eq!(next_pc, base);
+}
+
+#[test]
+#[cfg(feature = "s")]
+fn test_interrupt_delegation() {
+    let mut csr = CsrFile::default();
+    let s_handler = 0x8000_4000;
+
+    // Delegate STIP, enable it in sie and sstatus.SIE
+    csr.write(0x303, 1 << 5, PrivilegeMode::Machine, 0).unwrap();
+    csr.write(0x104, 1 << 5, PrivilegeMode::Supervisor, 0).unwrap();
+    csr.write(0x100, 1 << 1, PrivilegeMode::Supervisor, 0).unwrap();
+    csr.write(0x105, s_handler, PrivilegeMode::Supervisor, 0).unwrap();
+
+    csr.set_pending(Interrupt::SupervisorTimer, true);
+
+    // Delegated interrupts are never taken in M-mode
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Machine), None);
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Supervisor), Some(Interrupt::SupervisorTimer));
+
+    let (next_mode, next_pc) = csr.trap_entry(0x8000_3000, Interrupt::SupervisorTimer.into(), PrivilegeMode::Supervisor);
+    assert_eq!(next_mode, PrivilegeMode::Supervisor);
+    assert_eq!(next_pc, s_handler);
+    assert_eq!(csr.scause, (1 << (XLEN - 1)) | 5);
+    assert_eq!(csr.mcause, 0);
+
+    // sstatus.SIE was cleared on entry
+    assert_eq!(csr.pending_interrupt(PrivilegeMode::Supervisor), None);
+}
+
+#[test]
+fn test_counter_gating() {
+    let mut csr = CsrFile::default();
+
+    // cycle is always readable from M-mode, never writable
+    assert!(csr.read(0xc00, PrivilegeMode::Machine, 0).is_ok());
+    assert!(csr.write(0xc00, 1, PrivilegeMode::Machine, 0).is_err());
+    assert!(csr.read(0xc00, PrivilegeMode::User, 0).is_err());
+
+    csr.write(0x306, 1 << 0, PrivilegeMode::Machine, 0).unwrap();
+    #[cfg(feature = "s")] {
+        assert!(csr.read(0xc00, PrivilegeMode::Supervisor, 0).is_ok());
+        assert!(csr.read(0xc00, PrivilegeMode::User, 0).is_err());
+        csr.write(0x106, 1 << 0, PrivilegeMode::Supervisor, 0).unwrap();
+    }
+    assert!(csr.read(0xc00, PrivilegeMode::User, 0).is_ok());
+    // instret is still gated
+    assert!(csr.read(0xc02, PrivilegeMode::User, 0).is_err());
+
+    // There is no mtime CSR
+    assert!(csr.read(0xb01, PrivilegeMode::Machine, 0).is_err());
+}
+
+#[test]
+fn test_counter_write_and_inhibit() {
+    let mut csr = CsrFile::default();
+
+    csr.write(0xb00, 10, PrivilegeMode::Machine, 0).unwrap();
+    #[cfg(not(feature = "rv64"))]
+    csr.write(0xb80, 1, PrivilegeMode::Machine, 0).unwrap();
+    // The written value wins over the increment of the writing instruction
+    csr.tick_counters(true);
+    assert_eq!(csr.read(0xc00, PrivilegeMode::Machine, 0), Ok(10));
+    #[cfg(not(feature = "rv64"))]
+    assert_eq!(csr.read(0xc80, PrivilegeMode::Machine, 0), Ok(1));
+
+    csr.tick_counters(false);
+    assert_eq!(csr.read(0xb00, PrivilegeMode::Machine, 0), Ok(11));
+    assert_eq!(csr.read(0xb02, PrivilegeMode::Machine, 0), Ok(1));
+
+    // Inhibit cycle, the time bit is read-only zero
+    csr.write(0x320, 0b011, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x320, PrivilegeMode::Machine, 0), Ok(0b001));
+    csr.tick_counters(true);
+    assert_eq!(csr.read(0xb00, PrivilegeMode::Machine, 0), Ok(11));
+    assert_eq!(csr.read(0xb02, PrivilegeMode::Machine, 0), Ok(2));
+}
+
+#[test]
+fn test_hpm_event() {
+    let mut csr = CsrFile::default();
+
+    csr.write(0x323, HpmEvent::Load as XReg, PrivilegeMode::Machine, 0).unwrap();
+    csr.count_event(HpmEvent::Load, 2);
+    csr.count_event(HpmEvent::Store, 1);
+    assert_eq!(csr.read(0xb03, PrivilegeMode::Machine, 0), Ok(2));
+
+    // Unknown events read back as 0
+    csr.write(0x324, 0xff, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x324, PrivilegeMode::Machine, 0), Ok(0));
+
+    // Counters past the implemented bank are read-only zero
+    csr.set_hpm_num(1);
+    csr.write(0xb04, 5, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0xb04, PrivilegeMode::Machine, 0), Ok(0));
+    csr.write(0x306, XReg::MAX, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x306, PrivilegeMode::Machine, 0), Ok(0b1111));
+}
+
+#[test]
+#[cfg(feature = "rv64")]
+fn test_rv64_csrs() {
+    let mut csr = CsrFile::default();
+
+    // Machine counters are a single 64-bit CSR, the `h` halves do not exist
+    csr.write(0xb00, 1 << 40, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0xb00, PrivilegeMode::Machine, 0), Ok(1 << 40));
+    assert!(csr.read(0xb80, PrivilegeMode::Machine, 0).is_err());
+    assert!(csr.read(0xc80, PrivilegeMode::Machine, 0).is_err());
+
+    // pmpcfg0 holds eight entries, odd pmpcfg do not exist
+    csr.write(0x3a0, 0x0f << 32, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x3a0, PrivilegeMode::Machine, 0), Ok(0x0f << 32));
+    assert!(csr.read(0x3a1, PrivilegeMode::Machine, 0).is_err());
+
+    // UXL is 64-bit and read-only
+    let mstatus = csr.read(0x300, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!((mstatus >> 32) & 0b11, 2);
+    csr.write(0x300, 0, PrivilegeMode::Machine, 0).unwrap();
+    let mstatus = csr.read(0x300, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!((mstatus >> 32) & 0b11, 2);
+}
+
+#[test]
+#[cfg(all(feature = "s", feature = "rv64"))]
+fn test_rv64_satp_unsupported_mode() {
+    let mut csr = CsrFile::default();
+
+    // Writing an unsupported MODE leaves satp unchanged
+    csr.write(0x180, (15 << 60) | 0x1234, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x180, PrivilegeMode::Machine, 0), Ok(0));
+
+    csr.write(0x180, 0x1234, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(csr.read(0x180, PrivilegeMode::Machine, 0), Ok(0x1234));
+}
+
 mod pmp {
-    use crate::core::CsrFile;
+    use crate::core::{CsrFile, XReg};
     use crate::core::access::{Access, AccessType};
     use crate::core::privilege::PrivilegeMode;
     use crate::exception::Exception;
 
-    fn set_pmp_entry(csr: &mut CsrFile, idx: usize, cfg: u8, addr: u32) {
+    fn set_pmp_entry(csr: &mut CsrFile, idx: usize, cfg: u8, addr: XReg) {
         let shift = (idx % 4) * 8;
         
         let mut curr_cfg = csr.read(0x3a0, PrivilegeMode::Machine, 0).unwrap();
         curr_cfg &= !(0xff << shift);
 
-        curr_cfg |= (cfg as u32) << shift;
+        curr_cfg |= (cfg as XReg) << shift;
         csr.write(0x3a0, curr_cfg, PrivilegeMode::Machine, 0).unwrap();
 
         let csr_addr = 0x3b0 + idx as u16;
@@ -141,7 +572,7 @@ mod pmp {
         let addr = 0x8000_0000;
 
         let cfg = (1 << 3) | (1 << 0); // A = 01, R = 1
-        set_pmp_entry(&mut csr, 0, cfg, addr + 1000 >> 2);
+        set_pmp_entry(&mut csr, 0, cfg, (addr + 1000) >> 2);
 
         let mut access = Access::new(addr, AccessType::Load);
         let mode = PrivilegeMode::User;
@@ -183,7 +614,7 @@ mod pmp {
         let mut csr = CsrFile::default();
         let addr = 0x8000_0000;
 
-        let cfg = (3 << 3) | (3 << 0); // A = 11, R = 1, W = 1
+        let cfg = (3 << 3) | 3; // A = 11, R = 1, W = 1
         let pmpaddr = (0x8000_0000 >> 2) | 0x3FF;
         set_pmp_entry(&mut csr, 0, cfg, pmpaddr);
 
@@ -207,7 +638,7 @@ mod pmp {
         // pmp0: A = 01
         set_pmp_entry(&mut csr, 0, 1 << 3, 0x8000_1000 >> 2);
         // pmp1: A = 01, R = 1, W = 1, X = 1
-        set_pmp_entry(&mut csr, 1, (1 << 3) | (7 << 0), 0x8000_2000 >> 2);
+        set_pmp_entry(&mut csr, 1, (1 << 3) | 7, 0x8000_2000 >> 2);
 
         let access0 = Access::new(0x8000_0050, AccessType::Load);
         assert!(csr.pmp_check(access0, 4, mode).is_err());
@@ -231,4 +662,99 @@ mod pmp {
         assert_eq!(csr.pmp_check(access, 4, PrivilegeMode::Machine), 
             Err(Exception::StoreOrAmoAccessFault(0x8000_0050)));
     }
+
+    #[test]
+    fn test_locked_entry_read_only() {
+        let mut csr = CsrFile::default();
+        let mode = PrivilegeMode::Machine;
+
+        // pmp1: L = 1, A = 01, R = 1, top of range above pmp0
+        set_pmp_entry(&mut csr, 0, 0, 0x8000_1000 >> 2);
+        set_pmp_entry(&mut csr, 1, (1 << 3) | 1, 0x8000_2000 >> 2);
+        set_pmp_entry(&mut csr, 1, (1 << 7) | (1 << 3) | 1, 0x8000_2000 >> 2);
+
+        let cfg = csr.read(0x3a0, mode, 0).unwrap();
+        csr.write(0x3a0, 0, mode, 0).unwrap();
+        assert_eq!(csr.read(0x3a0, mode, 0), Ok(cfg), "Locked pmpcfg can not change");
+        csr.write(0x3b1, 0, mode, 0).unwrap();
+        assert_eq!(csr.read(0x3b1, mode, 0), Ok(0x8000_2000 >> 2));
+        csr.write(0x3b0, 0, mode, 0).unwrap();
+        assert_eq!(csr.read(0x3b0, mode, 0), Ok(0x8000_1000 >> 2), "TOR locks the entry below");
+    }
+
+    #[test]
+    fn test_partial_match() {
+        let mut csr = CsrFile::default();
+        let addr = 0x8000_0000;
+
+        // pmp0: TOR, R = 1, up to addr + 8
+        set_pmp_entry(&mut csr, 0, (1 << 3) | 1, (addr + 8) >> 2);
+
+        // The last bytes of a region are part of it
+        let access = Access::new(addr + 4, AccessType::Load);
+        assert!(csr.pmp_check(access, 4, PrivilegeMode::User).is_ok());
+
+        let access = Access::new(addr + 6, AccessType::Load);
+        assert_eq!(csr.pmp_check(access, 4, PrivilegeMode::User), Err(Exception::LoadAccessFault(addr + 6)));
+        assert_eq!(csr.pmp_check(access, 4, PrivilegeMode::Machine), Err(Exception::LoadAccessFault(addr + 6)),
+            "A partial match fails in M-mode too");
+    }
+
+    #[test]
+    fn test_entries_and_granularity() {
+        let mut csr = CsrFile::default();
+        let mode = PrivilegeMode::Machine;
+
+        csr.set_pmp(64, 2);
+        csr.write(0x3ef, 0x1234, mode, 0).unwrap();
+        assert_eq!(csr.read(0x3ef, mode, 0), Ok(0x1234 & !0b11), "OFF hides the bits below G");
+
+        // NAPOT shows the low bits as ones, NA4 can not be selected
+        set_pmp_entry(&mut csr, 0, 3 << 3, 0x8000_0000 >> 2);
+        assert_eq!(csr.read(0x3b0, mode, 0), Ok((0x8000_0000 >> 2) | 1));
+        set_pmp_entry(&mut csr, 0, 2 << 3, 0x8000_0000 >> 2);
+        assert_eq!(csr.read(0x3a0, mode, 0), Ok(0));
+
+        // Without entries nothing is checked
+        csr.set_pmp(0, 0);
+        csr.write(0x3b0, 0x1234, mode, 0).unwrap();
+        assert_eq!(csr.read(0x3b0, mode, 0), Ok(0));
+        let access = Access::new(0x8000_0000, AccessType::Load);
+        assert!(csr.pmp_check(access, 4, PrivilegeMode::User).is_ok());
+    }
+
+    #[test]
+    fn test_smepmp() {
+        let mut csr = CsrFile::default();
+        let mode = PrivilegeMode::Machine;
+        let addr = 0x8000_0000;
+
+        csr.write(0x747, 0b001, mode, 0).unwrap();
+        csr.write(0x747, 0, mode, 0).unwrap();
+        assert_eq!(csr.read(0x747, mode, 0), Ok(0b001), "MML is sticky");
+
+        // No rule: M-mode can read but not execute
+        assert!(csr.pmp_check(Access::new(addr, AccessType::Load), 4, mode).is_ok());
+        assert_eq!(csr.pmp_check(Access::new(addr, AccessType::Fetch), 4, mode),
+            Err(Exception::InstructionAccessFault(addr)));
+
+        // A locked M-mode code rule needs RLB
+        set_pmp_entry(&mut csr, 0, (1 << 7) | (3 << 3) | 0b101, (addr >> 2) | 0x3ff);
+        assert_eq!(csr.read(0x3a0, mode, 0), Ok(0));
+        csr.write(0x747, 0b100, mode, 0).unwrap();
+        set_pmp_entry(&mut csr, 0, (1 << 7) | (3 << 3) | 0b101, (addr >> 2) | 0x3ff);
+        assert!(csr.pmp_check(Access::new(addr, AccessType::Fetch), 4, mode).is_ok());
+        assert!(csr.pmp_check(Access::new(addr, AccessType::Fetch), 4, PrivilegeMode::User).is_err(),
+            "Locked rules are M-mode only");
+
+        // RLB can not come back once something is locked
+        csr.write(0x747, 0, mode, 0).unwrap();
+        csr.write(0x747, 0b100, mode, 0).unwrap();
+        assert_eq!(csr.read(0x747, mode, 0), Ok(0b001));
+
+        // MMWP denies M-mode accesses that match nothing
+        csr.write(0x747, 0b010, mode, 0).unwrap();
+        assert_eq!(csr.pmp_check(Access::new(0x1000, AccessType::Load), 4, mode),
+            Err(Exception::LoadAccessFault(0x1000)));
+    }
 }
\ No newline at end of file
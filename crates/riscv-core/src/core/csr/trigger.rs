@@ -0,0 +1,192 @@
+use std::cell::Cell;
+
+use crate::core::{XLEN, XReg};
+use crate::core::access::AccessType;
+use crate::core::privilege::PrivilegeMode;
+
+/// Number of triggers behind `tselect`
+pub const TRIGGER_NUM: usize = 4;
+
+const TYPE_SHIFT: u32 = XLEN - 4;
+const DMODE_BIT: XReg = 1 << (XLEN - 5);
+const MCONTROL6: XReg = 6;
+const DISABLED: XReg = 15;
+
+const LOAD_BIT: XReg = 1 << 0;
+const STORE_BIT: XReg = 1 << 1;
+const EXECUTE_BIT: XReg = 1 << 2;
+const U_BIT: XReg = 1 << 3;
+#[cfg(feature = "s")]
+const S_BIT: XReg = 1 << 4;
+const M_BIT: XReg = 1 << 6;
+const MATCH_SHIFT: usize = 7;
+const ACTION_SHIFT: usize = 12;
+
+#[cfg(feature = "s")]
+const MODE_MASK: XReg = U_BIT | S_BIT | M_BIT;
+#[cfg(not(feature = "s"))]
+const MODE_MASK: XReg = U_BIT | M_BIT;
+const MCONTROL6_WRITE_MASK: XReg = LOAD_BIT | STORE_BIT | EXECUTE_BIT | MODE_MASK;
+
+// `mcontrol6` and the disabled type, Sdtrig version 1
+const TINFO: XReg = (1 << 24) | (1 << MCONTROL6) | (1 << DISABLED);
+
+/// What a matching trigger does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerAction {
+    Breakpoint,
+    DebugMode,
+}
+
+/// A trigger that fired on the current instruction, it has not been handled yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriggerHit {
+    pub addr: XReg,
+    pub action: TriggerAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Trigger {
+    tdata1: XReg,
+    tdata2: XReg,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Trigger { tdata1: DISABLED << TYPE_SHIFT, tdata2: 0 }
+    }
+}
+
+impl Trigger {
+    fn is_dmode(&self) -> bool {
+        self.tdata1 & DMODE_BIT != 0
+    }
+
+    /// Only `mcontrol6` with `size` 0 and no chaining. Anything else disables the trigger
+    fn write_tdata1(&mut self, data: XReg, debug_mode: bool) {
+        if data >> TYPE_SHIFT != MCONTROL6 {
+            self.tdata1 = DISABLED << TYPE_SHIFT;
+            return;
+        }
+        let dmode = if debug_mode { data & DMODE_BIT } else { 0 };
+        let kind = match (data >> MATCH_SHIFT) & 0xf {
+            kind @ 0..=3 => kind,
+            _            => 0,
+        };
+        // Entering Debug Mode is only for triggers that belong to the debugger
+        let action = match (data >> ACTION_SHIFT) & 0xf {
+            1 if dmode != 0 => 1,
+            _               => 0,
+        };
+        self.tdata1 = (MCONTROL6 << TYPE_SHIFT) | dmode | (action << ACTION_SHIFT) |
+            (kind << MATCH_SHIFT) | (data & MCONTROL6_WRITE_MASK);
+    }
+
+    fn hit(&self, kind: AccessType, addr: XReg, size: usize, mode: PrivilegeMode) -> Option<TriggerHit> {
+        if self.tdata1 >> TYPE_SHIFT != MCONTROL6 {
+            return None;
+        }
+        let kind_bit = match kind {
+            AccessType::Load  => LOAD_BIT,
+            AccessType::Store => STORE_BIT,
+            AccessType::Fetch => EXECUTE_BIT,
+            #[cfg(feature = "a")]
+            AccessType::Amo   => LOAD_BIT | STORE_BIT,
+        };
+        let mode_bit = match mode {
+            PrivilegeMode::Machine    => M_BIT,
+            #[cfg(feature = "s")]
+            PrivilegeMode::Supervisor => S_BIT,
+            PrivilegeMode::User       => U_BIT,
+        };
+        if self.tdata1 & kind_bit == 0 || self.tdata1 & mode_bit == 0 {
+            return None;
+        }
+
+        // Equal matches any accessed byte, the others compare the lowest address
+        let last = addr.wrapping_add(size.max(1) as XReg - 1);
+        let is_match = match (self.tdata1 >> MATCH_SHIFT) & 0xf {
+            0 => addr <= self.tdata2 && self.tdata2 <= last,
+            1 => {
+                let mask = !(self.tdata2 ^ self.tdata2.wrapping_add(1));
+                addr & mask == self.tdata2 & mask
+            },
+            2 => addr >= self.tdata2,
+            _ => addr < self.tdata2,
+        };
+        let action = if (self.tdata1 >> ACTION_SHIFT) & 0xf == 1 {
+            TriggerAction::DebugMode
+        } else {
+            TriggerAction::Breakpoint
+        };
+        is_match.then_some(TriggerHit { addr, action })
+    }
+}
+
+/// Sdtrig trigger module with `mcontrol6` address match triggers
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Triggers {
+    select: usize,
+    triggers: [Trigger; TRIGGER_NUM],
+    // Set during a load or store, which only sees the CSRs through a shared reference
+    hit: Cell<Option<TriggerHit>>,
+}
+
+impl Triggers {
+    pub fn tselect(&self) -> XReg {
+        self.select as XReg
+    }
+
+    /// WARL, out of range indexes are ignored
+    pub fn set_tselect(&mut self, data: XReg) {
+        if (data as usize) < TRIGGER_NUM {
+            self.select = data as usize;
+        }
+    }
+
+    pub fn tdata1(&self) -> XReg {
+        self.triggers[self.select].tdata1
+    }
+
+    pub fn tdata2(&self) -> XReg {
+        self.triggers[self.select].tdata2
+    }
+
+    pub fn tinfo(&self) -> XReg {
+        TINFO
+    }
+
+    /// Triggers with `dmode` set can only be changed from Debug Mode
+    pub fn set_tdata1(&mut self, data: XReg, debug_mode: bool) {
+        let trigger = &mut self.triggers[self.select];
+        if debug_mode || !trigger.is_dmode() {
+            trigger.write_tdata1(data, debug_mode);
+        }
+    }
+
+    pub fn set_tdata2(&mut self, data: XReg, debug_mode: bool) {
+        let trigger = &mut self.triggers[self.select];
+        if debug_mode || !trigger.is_dmode() {
+            trigger.tdata2 = data;
+        }
+    }
+
+    /// Remember the first matching trigger, the access must not happen
+    pub fn check(&self, kind: AccessType, addr: XReg, size: usize, mode: PrivilegeMode) -> bool {
+        match self.triggers.iter().find_map(|trigger| trigger.hit(kind, addr, size, mode)) {
+            Some(hit) => {
+                self.hit.set(Some(hit));
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn peek_hit(&self) -> Option<TriggerHit> {
+        self.hit.get()
+    }
+
+    pub fn take_hit(&self) -> Option<TriggerHit> {
+        self.hit.take()
+    }
+}
@@ -3,70 +3,111 @@ use riscv_decoder::instruction::AOp::{self, *};
 
 use crate::Result;
 use crate::engine::{Alu, Lsu};
+use crate::device::pma::AmoClass::{self, Arithmetic, Logical, Swap};
 use crate::core::cpu::Cpu;
+use crate::core::XReg;
+#[cfg(feature = "rv64")]
+use crate::core::sext_w;
+#[cfg(feature = "zicsr")]
+use crate::core::csr::HpmEvent;
 
 impl Cpu {
     pub(crate) fn execute_a(&mut self, op: AOp, data: AmoInsData) -> Result<()> {
         let rs1_data = self.regs[data.rs1];
         let rs2_data = self.regs[data.rs2];
+
+        #[cfg(not(feature = "rv64"))]
+        let num = 4;
+        #[cfg(feature = "rv64")]
+        let num = if op.is_doubleword() { 8 } else { 4 };
+        // Word AMOs compare and combine sign-extended values, memory only keeps the low half
+        #[cfg(feature = "rv64")]
+        let rs2_data = if num == 4 { sext_w(rs2_data as u32) } else { rs2_data };
         
         let rd_data = match op {
-            LrW      => self.load_reserved(rs1_data)?,
-            ScW      => self.store_conditional(rs1_data, rs2_data)?,
-            AmoSwapW => self.atomic_operate(rs1_data, rs2_data, direct_out)?,
-            AmoAddW  => self.atomic_operate(rs1_data, rs2_data, Alu::add)?,
-            AmoXorW  => self.atomic_operate(rs1_data, rs2_data, Alu::xor)?,
-            AmoAndW  => self.atomic_operate(rs1_data, rs2_data, Alu::and)?,
-            AmoOrW   => self.atomic_operate(rs1_data, rs2_data, Alu::or)?,
-            AmoMinW  => self.atomic_operate(rs1_data, rs2_data, Alu::min)?,
-            AmoMaxW  => self.atomic_operate(rs1_data, rs2_data, Alu::max)?,
-            AmoMaxuW => self.atomic_operate(rs1_data, rs2_data, Alu::max_unsigned)?,
-            AmoMinuW => self.atomic_operate(rs1_data, rs2_data, Alu::min_unsigned)?,    
+            LrW      => self.load_reserved(rs1_data, num)?,
+            ScW      => self.store_conditional(rs1_data, rs2_data, num)?,
+            AmoSwapW => self.atomic_operate(rs1_data, rs2_data, num, Swap, direct_out)?,
+            AmoAddW  => self.atomic_operate(rs1_data, rs2_data, num, Arithmetic, Alu::add)?,
+            AmoXorW  => self.atomic_operate(rs1_data, rs2_data, num, Logical, Alu::xor)?,
+            AmoAndW  => self.atomic_operate(rs1_data, rs2_data, num, Logical, Alu::and)?,
+            AmoOrW   => self.atomic_operate(rs1_data, rs2_data, num, Logical, Alu::or)?,
+            AmoMinW  => self.atomic_operate(rs1_data, rs2_data, num, Arithmetic, Alu::min)?,
+            AmoMaxW  => self.atomic_operate(rs1_data, rs2_data, num, Arithmetic, Alu::max)?,
+            AmoMaxuW => self.atomic_operate(rs1_data, rs2_data, num, Arithmetic, Alu::max_unsigned)?,
+            AmoMinuW => self.atomic_operate(rs1_data, rs2_data, num, Arithmetic, Alu::min_unsigned)?,    
+            #[cfg(feature = "rv64")]
+            LrD      => self.load_reserved(rs1_data, num)?,
+            #[cfg(feature = "rv64")]
+            ScD      => self.store_conditional(rs1_data, rs2_data, num)?,
+            #[cfg(feature = "rv64")]
+            AmoSwapD => self.atomic_operate(rs1_data, rs2_data, num, Swap, direct_out)?,
+            #[cfg(feature = "rv64")]
+            AmoAddD  => self.atomic_operate(rs1_data, rs2_data, num, Arithmetic, Alu::add)?,
+            #[cfg(feature = "rv64")]
+            AmoXorD  => self.atomic_operate(rs1_data, rs2_data, num, Logical, Alu::xor)?,
+            #[cfg(feature = "rv64")]
+            AmoAndD  => self.atomic_operate(rs1_data, rs2_data, num, Logical, Alu::and)?,
+            #[cfg(feature = "rv64")]
+            AmoOrD   => self.atomic_operate(rs1_data, rs2_data, num, Logical, Alu::or)?,
+            #[cfg(feature = "rv64")]
+            AmoMinD  => self.atomic_operate(rs1_data, rs2_data, num, Arithmetic, Alu::min)?,
+            #[cfg(feature = "rv64")]
+            AmoMaxD  => self.atomic_operate(rs1_data, rs2_data, num, Arithmetic, Alu::max)?,
+            #[cfg(feature = "rv64")]
+            AmoMaxuD => self.atomic_operate(rs1_data, rs2_data, num, Arithmetic, Alu::max_unsigned)?,
+            #[cfg(feature = "rv64")]
+            AmoMinuD => self.atomic_operate(rs1_data, rs2_data, num, Arithmetic, Alu::min_unsigned)?,
         };
 
+        // AMOs and SC count as stores, LR as a load
+        #[cfg(feature = "zicsr")]
+        self.csrs.count_event(if op.is_load() { HpmEvent::Load } else { HpmEvent::Store }, 1);
+
         self.regs.write(data.rd, rd_data);
         Ok(())
     }   
 
-    fn load_reserved(&mut self, src: u32) -> Result<u32> {
+    fn load_reserved(&mut self, src: XReg, num: usize) -> Result<XReg> {
         let mut lsu = Lsu::new(
-            &mut self.bus, 
+            &self.bus,
+            self.misaligned,
             #[cfg(feature = "s")] &mut self.mmu, 
             #[cfg(feature = "zicsr")] &self.csrs, 
             #[cfg(feature = "zicsr")] self.mode
         );
-        let (res_data, addr) = lsu.atomic_load(src)?;
-        self.reservation = Some(addr);
-        Ok(res_data)
+        lsu.atomic_load(src, num, self.hartid)
     }
 
-    fn store_conditional(&mut self, des: u32, src: u32) -> Result<u32> {
+    fn store_conditional(&mut self, des: XReg, src: XReg, num: usize) -> Result<XReg> {
         let mut lsu = Lsu::new(
-            &mut self.bus, 
+            &self.bus,
+            self.misaligned,
             #[cfg(feature = "s")] &mut self.mmu, 
             #[cfg(feature = "zicsr")] &self.csrs, 
             #[cfg(feature = "zicsr")] self.mode
         );
-        Ok(if lsu.atomic_store(des, src, &mut self.reservation)? {
+        Ok(if lsu.atomic_store(des, src, num, self.hartid)? {
             0
         } else {
             1
         }) 
     }
 
-    fn atomic_operate<F>(&mut self, des: u32, data: u32, ope: F) -> Result<u32> 
-        where F: Fn(u32, u32) -> u32
+    fn atomic_operate<F>(&mut self, des: XReg, data: XReg, num: usize, class: AmoClass, ope: F) -> Result<XReg> 
+        where F: Fn(XReg, XReg) -> XReg
     {
         let mut lsu = Lsu::new(
-            &mut self.bus, 
+            &self.bus,
+            self.misaligned,
             #[cfg(feature = "s")] &mut self.mmu, 
             #[cfg(feature = "zicsr")] &self.csrs, 
             #[cfg(feature = "zicsr")] self.mode
         );
-        lsu.atomic_operate(des, data, ope, &mut self.reservation)
+        lsu.atomic_operate(des, data, num, class, ope)
     }
 }
 
-fn direct_out(_: u32, data: u32) -> u32 {
+fn direct_out(_: XReg, data: XReg) -> XReg {
     data
 }
\ No newline at end of file
@@ -0,0 +1,119 @@
+use riscv_decoder::instruction::FpInsData;
+use riscv_decoder::instruction::DOp::{self, *};
+
+use crate::{Exception, Result};
+use crate::engine::{Fpu, Lsu};
+use crate::engine::fpu::{F32, F64, RoundingMode};
+use crate::core::cpu::Cpu;
+use crate::core::XReg;
+#[cfg(feature = "rv64")]
+use crate::core::sext_w;
+use crate::core::csr::HpmEvent;
+
+impl Cpu {
+    pub(crate) fn execute_d(&mut self, op: DOp, data: FpInsData, raw: u32) -> Result<()> {
+        if self.csrs.fs_off() {
+            return Err(Exception::IllegalInstruction(raw));
+        }
+
+        if op.is_load() {
+            let res = Lsu::new(
+                &self.bus,
+                self.misaligned,
+                #[cfg(feature = "s")] &mut self.mmu, 
+                &self.csrs, 
+                self.mode
+            ).load_double(self.regs[data.rs1], data.imm)?;
+            self.fregs.write(data.rd, res);
+            self.csrs.set_fs_dirty();
+            self.csrs.count_event(HpmEvent::Load, 1);
+            return Ok(());
+        }
+
+        if op.is_store() {
+            Lsu::new(
+                &self.bus,
+                self.misaligned,
+                #[cfg(feature = "s")] &mut self.mmu, 
+                &self.csrs, 
+                self.mode
+            ).store_double(self.regs[data.rs1], self.fregs[data.rs2], data.imm)?;
+            self.csrs.count_event(HpmEvent::Store, 1);
+            return Ok(());
+        }
+
+        let rm = if op.has_rm() {
+            self.rounding_mode(data.rm, raw)?
+        } else {
+            RoundingMode::default()
+        };
+        let mut fpu = Fpu::new(rm);
+
+        let a = self.fregs[data.rs1];
+        let b = self.fregs[data.rs2];
+        let c = self.fregs[data.rs3];
+        let x: XReg = self.regs[data.rs1];
+
+        let res = match op {
+            FmaddD  => fpu.fma(F64, a, b, c),
+            FmsubD  => fpu.fma(F64, a, b, neg(c)),
+            FnmsubD => fpu.fma(F64, neg(a), b, c),
+            FnmaddD => fpu.fma(F64, neg(a), b, neg(c)),
+            FaddD   => fpu.add(F64, a, b),
+            FsubD   => fpu.sub(F64, a, b),
+            FmulD   => fpu.mul(F64, a, b),
+            FdivD   => fpu.div(F64, a, b),
+            FsqrtD  => fpu.sqrt(F64, a),
+            FsgnjD  => Fpu::sgnj(F64, a, b),
+            FsgnjnD => Fpu::sgnjn(F64, a, b),
+            FsgnjxD => Fpu::sgnjx(F64, a, b),
+            FminD   => fpu.min(F64, a, b),
+            FmaxD   => fpu.max(F64, a, b),
+            FcvtSD  => {
+                let res = fpu.convert(F64, F32, a) as u32;
+                self.csrs.accrue_fflags(fpu.flags());
+                self.fregs.write_s(data.rd, res);
+                self.csrs.set_fs_dirty();
+                return Ok(());
+            },
+            FcvtDS  => fpu.convert(F32, F64, self.fregs.read_s(data.rs1) as u64),
+            FcvtWD  => fpu.float_to_int(F64, a, true, 32),
+            FcvtWuD => fpu.float_to_int(F64, a, false, 32),
+            FcvtDW  => fpu.int_to_float(F64, x as i32 as i64 as u64, true),
+            FcvtDWu => fpu.int_to_float(F64, x as u64 & 0xffff_ffff, false),
+            FeqD    => fpu.equal(F64, a, b) as u64,
+            FltD    => fpu.less(F64, a, b) as u64,
+            FleD    => fpu.less_equal(F64, a, b) as u64,
+            FclassD => Fpu::classify(F64, a) as u64,
+            #[cfg(feature = "rv64")]
+            FcvtLD  => fpu.float_to_int(F64, a, true, 64),
+            #[cfg(feature = "rv64")]
+            FcvtLuD => fpu.float_to_int(F64, a, false, 64),
+            #[cfg(feature = "rv64")]
+            FcvtDL  => fpu.int_to_float(F64, x as u64, true),
+            #[cfg(feature = "rv64")]
+            FcvtDLu => fpu.int_to_float(F64, x as u64, false),
+            #[cfg(feature = "rv64")]
+            FmvXD   => a,
+            #[cfg(feature = "rv64")]
+            FmvDX   => x as u64,
+            Fld | Fsd => unreachable!("Handled as load and store"),
+        };
+
+        self.csrs.accrue_fflags(fpu.flags());
+        if op.is_rd_int() {
+            // Word results, `fcvt.wu.d` included, are sign-extended
+            #[cfg(feature = "rv64")]
+            let res = if matches!(op, FcvtLD | FcvtLuD | FmvXD) { res } else { sext_w(res as u32) };
+            self.regs.write(data.rd, res as XReg);
+        } else {
+            self.fregs.write(data.rd, res);
+            self.csrs.set_fs_dirty();
+        }
+        Ok(())
+    }
+}
+
+fn neg(a: u64) -> u64 {
+    Fpu::sgnjn(F64, a, a)
+}
@@ -0,0 +1,119 @@
+use riscv_decoder::instruction::FpInsData;
+use riscv_decoder::instruction::FOp::{self, *};
+
+use crate::{Exception, Result};
+use crate::engine::{Fpu, Lsu};
+use crate::engine::fpu::{F32, RoundingMode};
+use crate::core::cpu::Cpu;
+use crate::core::XReg;
+#[cfg(feature = "rv64")]
+use crate::core::sext_w;
+use crate::core::csr::HpmEvent;
+
+/// `rm` value that selects the rounding mode in `frm`
+const DYN_RM: u8 = 0b111;
+
+impl Cpu {
+    pub(crate) fn execute_f(&mut self, op: FOp, data: FpInsData, raw: u32) -> Result<()> {
+        if self.csrs.fs_off() {
+            return Err(Exception::IllegalInstruction(raw));
+        }
+
+        if op.is_load() {
+            let res: XReg = Lsu::new(
+                &self.bus,
+                self.misaligned,
+                #[cfg(feature = "s")] &mut self.mmu, 
+                &self.csrs, 
+                self.mode
+            ).load(self.regs[data.rs1], data.imm, 4)?;
+            self.fregs.write_s(data.rd, res as u32);
+            self.csrs.set_fs_dirty();
+            self.csrs.count_event(HpmEvent::Load, 1);
+            return Ok(());
+        }
+
+        if op.is_store() {
+            Lsu::new(
+                &self.bus,
+                self.misaligned,
+                #[cfg(feature = "s")] &mut self.mmu, 
+                &self.csrs, 
+                self.mode
+            ).store(self.regs[data.rs1], self.fregs.read_raw_s(data.rs2) as XReg, data.imm, 4)?;
+            self.csrs.count_event(HpmEvent::Store, 1);
+            return Ok(());
+        }
+
+        let rm = if op.has_rm() {
+            self.rounding_mode(data.rm, raw)?
+        } else {
+            RoundingMode::default()
+        };
+        let mut fpu = Fpu::new(rm);
+
+        let a = self.fregs.read_s(data.rs1) as u64;
+        let b = self.fregs.read_s(data.rs2) as u64;
+        let c = self.fregs.read_s(data.rs3) as u64;
+        let x: XReg = self.regs[data.rs1];
+
+        let res = match op {
+            FmaddS  => fpu.fma(F32, a, b, c),
+            FmsubS  => fpu.fma(F32, a, b, neg(c)),
+            FnmsubS => fpu.fma(F32, neg(a), b, c),
+            FnmaddS => fpu.fma(F32, neg(a), b, neg(c)),
+            FaddS   => fpu.add(F32, a, b),
+            FsubS   => fpu.sub(F32, a, b),
+            FmulS   => fpu.mul(F32, a, b),
+            FdivS   => fpu.div(F32, a, b),
+            FsqrtS  => fpu.sqrt(F32, a),
+            FsgnjS  => Fpu::sgnj(F32, a, b),
+            FsgnjnS => Fpu::sgnjn(F32, a, b),
+            FsgnjxS => Fpu::sgnjx(F32, a, b),
+            FminS   => fpu.min(F32, a, b),
+            FmaxS   => fpu.max(F32, a, b),
+            FcvtWS  => fpu.float_to_int(F32, a, true, 32),
+            FcvtWuS => fpu.float_to_int(F32, a, false, 32),
+            FcvtSW  => fpu.int_to_float(F32, x as i32 as i64 as u64, true),
+            FcvtSWu => fpu.int_to_float(F32, x as u64 & 0xffff_ffff, false),
+            // Moves out ignore NaN-boxing
+            FmvXW   => self.fregs.read_raw_s(data.rs1) as u64,
+            FmvWX   => x as u64,
+            FeqS    => fpu.equal(F32, a, b) as u64,
+            FltS    => fpu.less(F32, a, b) as u64,
+            FleS    => fpu.less_equal(F32, a, b) as u64,
+            FclassS => Fpu::classify(F32, a) as u64,
+            #[cfg(feature = "rv64")]
+            FcvtLS  => fpu.float_to_int(F32, a, true, 64),
+            #[cfg(feature = "rv64")]
+            FcvtLuS => fpu.float_to_int(F32, a, false, 64),
+            #[cfg(feature = "rv64")]
+            FcvtSL  => fpu.int_to_float(F32, x as u64, true),
+            #[cfg(feature = "rv64")]
+            FcvtSLu => fpu.int_to_float(F32, x as u64, false),
+            Flw | Fsw => unreachable!("Handled as load and store"),
+        };
+
+        self.csrs.accrue_fflags(fpu.flags());
+        if op.is_rd_int() {
+            // Word results, `fcvt.wu.s` included, are sign-extended
+            #[cfg(feature = "rv64")]
+            let res = if matches!(op, FcvtLS | FcvtLuS) { res } else { sext_w(res as u32) };
+            self.regs.write(data.rd, res as XReg);
+        } else {
+            self.fregs.write_s(data.rd, res as u32);
+            self.csrs.set_fs_dirty();
+        }
+        Ok(())
+    }
+
+    /// Static `rm` from the instruction, or `frm` when it is dynamic. Reserved values are illegal
+    pub(super) fn rounding_mode(&self, rm: u8, raw: u32) -> Result<RoundingMode> {
+        let rm = if rm == DYN_RM { self.csrs.frm() } else { rm };
+        RoundingMode::try_from(rm).map_err(|_| Exception::IllegalInstruction(raw))
+    }
+}
+
+fn neg(a: u64) -> u64 {
+    Fpu::sgnjn(F32, a, a)
+}
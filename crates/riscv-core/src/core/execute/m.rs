@@ -19,6 +19,16 @@ impl Cpu {
                 Divu   => Alu::div_unsigned(rs1_data, rs2_data),
                 Rem    => Alu::rem(rs1_data, rs2_data),
                 Remu   => Alu::rem_unsigned(rs1_data, rs2_data),
+                #[cfg(feature = "rv64")]
+                Mulw   => Alu::mul_word(rs1_data, rs2_data),
+                #[cfg(feature = "rv64")]
+                Divw   => Alu::div_word(rs1_data, rs2_data),
+                #[cfg(feature = "rv64")]
+                Divuw  => Alu::div_unsigned_word(rs1_data, rs2_data),
+                #[cfg(feature = "rv64")]
+                Remw   => Alu::rem_word(rs1_data, rs2_data),
+                #[cfg(feature = "rv64")]
+                Remuw  => Alu::rem_unsigned_word(rs1_data, rs2_data),
             }
         )
     }   
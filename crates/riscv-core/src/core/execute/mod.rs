@@ -6,4 +6,16 @@ pub mod a;
 #[cfg(feature = "zicsr")]
 pub mod zicsr;
 #[cfg(feature = "zicsr")]
-pub mod privilege;
\ No newline at end of file
+pub mod privilege;
+#[cfg(feature = "f")]
+pub mod f;
+#[cfg(feature = "d")]
+pub mod d;
+#[cfg(feature = "zba")]
+pub mod zba;
+#[cfg(feature = "zbb")]
+pub mod zbb;
+#[cfg(feature = "zbc")]
+pub mod zbc;
+#[cfg(feature = "zbs")]
+pub mod zbs;
@@ -11,8 +11,22 @@ impl Cpu {
     pub(crate) fn execute_privileged(&mut self, op: PrivilegeOp, data: InstructionData) -> Result<bool> {
         let (mode, pc) = match op {
             Mret           => self.csrs.trap_mret(),
+            Mnret          => self.csrs.trap_mnret(self.mode)?,
+            Dret           => self.csrs.debug_return()?,
             #[cfg(feature = "s")]
             Sret           => self.csrs.trap_sret(self.mode)?,
+            Wfi(raw)       => {
+                // There is no bounded wait, so lower privilege traps right away
+                #[cfg(feature = "s")]
+                if self.mode == PrivilegeMode::User {
+                    return Err(Exception::IllegalInstruction(raw));
+                }
+                if self.mode != PrivilegeMode::Machine && self.csrs.check_tw() {
+                    return Err(Exception::IllegalInstruction(raw));
+                }
+                self.waiting = true;
+                return Ok(false);
+            }
             #[cfg(feature = "s")]
             SfenceVma(raw) => {
                 if self.mode == PrivilegeMode::User {
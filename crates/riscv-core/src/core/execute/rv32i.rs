@@ -1,9 +1,13 @@
 use riscv_decoder::instruction::InstructionData;
+use riscv_decoder::isa::Extension;
 use riscv_decoder::instruction::Rv32iOp::{self, *};
 
 use crate::{Exception, Result};
 use crate::engine::{Alu, Branch, Lsu};
 use crate::core::cpu::Cpu;
+use crate::core::{SXReg, XReg};
+#[cfg(feature = "zicsr")]
+use crate::core::csr::HpmEvent;
 
 impl Cpu {
     pub(crate) fn execute_rv32i(&mut self, op: Rv32iOp, data: InstructionData) -> Result<bool> {
@@ -22,17 +26,24 @@ impl Cpu {
 
         if let Some(res) = self.lsu_load(op, rs1_data, data.imm) {
             self.regs.write(data.rd, res?);
+            #[cfg(feature = "zicsr")]
+            self.csrs.count_event(HpmEvent::Load, 1);
             return Ok(false);
         }
 
         if let Some(res) = self.lsu_store(op, rs1_data, rs2_data, data.imm) {
             res?;
+            #[cfg(feature = "zicsr")]
+            self.csrs.count_event(HpmEvent::Store, 1);
             return Ok(false);
         }
 
         if let Some(res) = Self::branch(op, rs1_data, rs2_data) {
             if res {
-                self.pc.related_addressing(data.imm)
+                self.check_target(self.pc.get().wrapping_add_signed(data.imm as SXReg))?;
+                self.pc.related_addressing(data.imm);
+                #[cfg(feature = "zicsr")]
+                self.csrs.count_event(HpmEvent::BranchTaken, 1);
             }
             return Ok(res);
         }
@@ -47,11 +58,14 @@ impl Cpu {
             #[cfg(not(feature = "c"))]
             let next_ins_addr = 4;
             
+            let target = match res {
+                true  => rs1_data.wrapping_add_signed(data.imm as SXReg) & !1,
+                false => self.pc.get().wrapping_add_signed(data.imm as SXReg),
+            };
+            self.check_target(target)?;
+
             self.regs.write(data.rd, self.pc.get() + next_ins_addr);
-            match res {
-                true  => self.pc.directed_addressing(rs1_data.wrapping_add_signed(data.imm)),
-                false => self.pc.related_addressing(data.imm),
-            }
+            self.pc.directed_addressing(target);
             return Ok(true);
         }
 
@@ -61,52 +75,83 @@ impl Cpu {
         Ok(false)
     }
 
-    fn alu_imm(op: Rv32iOp, data: u32, imm: i32, pc: u32) -> Option<u32> {
+    /// Without C every instruction sits on a 4-byte boundary
+    fn check_target(&self, target: XReg) -> Result<()> {
+        if target & 0b10 != 0 && !self.isa().has(Extension::C) {
+            return Err(Exception::InstructionAddressMisaligned(target));
+        }
+        Ok(())
+    }
+
+    fn alu_imm(op: Rv32iOp, data: XReg, imm: i32, pc: XReg) -> Option<XReg> {
         Some(match op {
             Addi  => Alu::add_signed(data, imm),
-            Slli  => Alu::shl_logic(data, imm as u32),
-            Slti  => Alu::set_less_than(data as i32, imm),
-            Sltiu => Alu::set_less_than_unsigned(data, imm as u32),
-            Xori  => Alu::xor(data, imm as u32),
-            Srli  => Alu::shr_logic(data, imm as u32),
-            Srai  => Alu::shr_ar(data as i32, imm as u32),
-            Ori   => Alu::or(data, imm as u32),
-            Andi  => Alu::and(data, imm as u32),
-
-            Lui   => imm as u32,
-            Auipc => Alu::add(pc, imm as u32),
+            Slli  => Alu::shl_logic(data, imm as XReg),
+            Slti  => Alu::set_less_than(data as SXReg, imm as SXReg),
+            Sltiu => Alu::set_less_than_unsigned(data, imm as XReg),
+            Xori  => Alu::xor(data, imm as XReg),
+            Srli  => Alu::shr_logic(data, imm as XReg),
+            Srai  => Alu::shr_ar(data as SXReg, imm as XReg),
+            Ori   => Alu::or(data, imm as XReg),
+            Andi  => Alu::and(data, imm as XReg),
+            #[cfg(feature = "rv64")]
+            Addiw => Alu::add_signed_word(data, imm),
+            #[cfg(feature = "rv64")]
+            Slliw => Alu::shl_logic_word(data, imm as XReg),
+            #[cfg(feature = "rv64")]
+            Srliw => Alu::shr_logic_word(data, imm as XReg),
+            #[cfg(feature = "rv64")]
+            Sraiw => Alu::shr_ar_word(data, imm as XReg),
+
+            Lui   => imm as XReg,
+            Auipc => Alu::add(pc, imm as XReg),
             _     => return None,
         })
     }
 
-    fn alu_reg(op: Rv32iOp, data1: u32, data2: u32) -> Option<u32> {
+    fn alu_reg(op: Rv32iOp, data1: XReg, data2: XReg) -> Option<XReg> {
         Some(match op {
             Add  => Alu::add(data1, data2),
             Sub  => Alu::sub(data1, data2),
             Sll  => Alu::shl_logic(data1, data2),
-            Slt  => Alu::set_less_than(data1 as i32, data2 as i32),
+            Slt  => Alu::set_less_than(data1 as SXReg, data2 as SXReg),
             Sltu => Alu::set_less_than_unsigned(data1, data2),
             Xor  => Alu::xor(data1, data2),
             Srl  => Alu::shr_logic(data1, data2),
-            Sra  => Alu::shr_ar(data1 as i32, data2),
+            Sra  => Alu::shr_ar(data1 as SXReg, data2),
             Or   => Alu::or(data1, data2),
             And  => Alu::and(data1, data2),
+            #[cfg(feature = "rv64")]
+            Addw => Alu::add_word(data1, data2),
+            #[cfg(feature = "rv64")]
+            Subw => Alu::sub_word(data1, data2),
+            #[cfg(feature = "rv64")]
+            Sllw => Alu::shl_logic_word(data1, data2),
+            #[cfg(feature = "rv64")]
+            Srlw => Alu::shr_logic_word(data1, data2),
+            #[cfg(feature = "rv64")]
+            Sraw => Alu::shr_ar_word(data1, data2),
             _    => return None,
         })
     }
 
-    fn lsu_load(&mut self, op: Rv32iOp, src: u32, offset: i32) -> Option<Result<u32>> {
+    fn lsu_load(&mut self, op: Rv32iOp, src: XReg, offset: i32) -> Option<Result<XReg>> {
         let (is_signed, byte_num) = match op {
             Lb  => (true, 1),
             Lh  => (true, 2),
-            Lw  => (false, 4),
+            Lw  => (true, 4),
             Lbu => (false, 1),
             Lhu => (false, 2),
+            #[cfg(feature = "rv64")]
+            Lwu => (false, 4),
+            #[cfg(feature = "rv64")]
+            Ld  => (false, 8),
             _   => return None,
         };
 
         let mut lsu = Lsu::new(
-            &mut self.bus, 
+            &self.bus,
+            self.misaligned,
             #[cfg(feature = "s")] &mut self.mmu, 
             #[cfg(feature = "zicsr")] &self.csrs, 
             #[cfg(feature = "zicsr")] self.mode
@@ -120,30 +165,32 @@ impl Cpu {
         )
     }
 
-    fn lsu_store(&mut self, op: Rv32iOp, des: u32, src: u32, offset: i32) -> Option<Result<()>> {
+    fn lsu_store(&mut self, op: Rv32iOp, des: XReg, src: XReg, offset: i32) -> Option<Result<()>> {
         let byte_num = match op {
             Sb => 1,
             Sh => 2,
             Sw => 4,
+            #[cfg(feature = "rv64")]
+            Sd => 8,
             _  => return None,
         };
         let mut lsu = Lsu::new(
-            &mut self.bus,
+            &self.bus,
+            self.misaligned,
             #[cfg(feature = "s")] &mut self.mmu, 
             #[cfg(feature = "zicsr")] &self.csrs, 
             #[cfg(feature = "zicsr")] self.mode
         );
 
-        Some(lsu.store(des, src, offset, byte_num, 
-            #[cfg(feature = "a")] &mut self.reservation))
+        Some(lsu.store(des, src, offset, byte_num))
     }
 
-    fn branch(op: Rv32iOp, data1: u32, data2: u32) -> Option<bool> {
+    fn branch(op: Rv32iOp, data1: XReg, data2: XReg) -> Option<bool> {
         Some(match op {
             Beq  => Branch::equal(data1, data2),
             Bne  => Branch::not_equal(data1, data2),
-            Blt  => Branch::less(data1 as i32, data2 as i32),
-            Bge  => Branch::greater_eqaul(data1 as i32, data2 as i32),
+            Blt  => Branch::less(data1 as SXReg, data2 as SXReg),
+            Bge  => Branch::greater_eqaul(data1 as SXReg, data2 as SXReg),
             Bltu => Branch::less_unsigned(data1, data2),
             Bgeu => Branch::greater_eqaul_unsigned(data1, data2),
             _    => return None,
@@ -0,0 +1,21 @@
+use riscv_decoder::instruction::InstructionData;
+use riscv_decoder::instruction::ZbaOp;
+
+use crate::engine::Alu;
+use crate::core::cpu::Cpu;
+use crate::core::XReg;
+
+impl Cpu {
+    pub(crate) fn execute_zba(&mut self, op: ZbaOp, data: InstructionData) {
+        let rs1_data = self.regs[data.rs1];
+        let rs2_data = self.regs[data.rs2];
+        #[cfg(feature = "rv64")]
+        let rs1_data = if op.is_unsigned_word() { Alu::zero_extend_word(rs1_data) } else { rs1_data };
+
+        if op.is_imm() {
+            self.regs.write(data.rd, Alu::shl_logic(rs1_data, data.imm as XReg))
+        } else {
+            self.regs.write(data.rd, Alu::shift_add(rs1_data, rs2_data, op.shift()))
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use riscv_decoder::instruction::InstructionData;
+use riscv_decoder::instruction::ZbbOp::{self, *};
+
+use crate::engine::Alu;
+use crate::core::cpu::Cpu;
+use crate::core::XReg;
+
+impl Cpu {
+    pub(crate) fn execute_zbb(&mut self, op: ZbbOp, data: InstructionData) {
+        let rs1_data = self.regs[data.rs1];
+        let rs2_data = self.regs[data.rs2];
+
+        self.regs.write(data.rd,
+            match op {
+                Andn  => Alu::and_not(rs1_data, rs2_data),
+                Orn   => Alu::or_not(rs1_data, rs2_data),
+                Xnor  => Alu::xnor(rs1_data, rs2_data),
+                Min   => Alu::min(rs1_data, rs2_data),
+                Minu  => Alu::min_unsigned(rs1_data, rs2_data),
+                Max   => Alu::max(rs1_data, rs2_data),
+                Maxu  => Alu::max_unsigned(rs1_data, rs2_data),
+                Rol   => Alu::rotate_left(rs1_data, rs2_data),
+                Ror   => Alu::rotate_right(rs1_data, rs2_data),
+                Rori  => Alu::rotate_right(rs1_data, data.imm as XReg),
+                ZextH => Alu::zero_extend_half(rs1_data),
+                Clz   => Alu::count_leading_zeros(rs1_data),
+                Ctz   => Alu::count_trailing_zeros(rs1_data),
+                Cpop  => Alu::count_ones(rs1_data),
+                SextB => Alu::sign_extend_byte(rs1_data),
+                SextH => Alu::sign_extend_half(rs1_data),
+                OrcB  => Alu::or_combine_byte(rs1_data),
+                Rev8  => Alu::reverse_bytes(rs1_data),
+                #[cfg(feature = "rv64")] Rolw  => Alu::rotate_left_word(rs1_data, rs2_data),
+                #[cfg(feature = "rv64")] Rorw  => Alu::rotate_right_word(rs1_data, rs2_data),
+                #[cfg(feature = "rv64")] Roriw => Alu::rotate_right_word(rs1_data, data.imm as XReg),
+                #[cfg(feature = "rv64")] Clzw  => Alu::count_leading_zeros_word(rs1_data),
+                #[cfg(feature = "rv64")] Ctzw  => Alu::count_trailing_zeros_word(rs1_data),
+                #[cfg(feature = "rv64")] Cpopw => Alu::count_ones_word(rs1_data),
+            }
+        )
+    }
+}
@@ -0,0 +1,20 @@
+use riscv_decoder::instruction::InstructionData;
+use riscv_decoder::instruction::ZbcOp::{self, *};
+
+use crate::engine::Alu;
+use crate::core::cpu::Cpu;
+
+impl Cpu {
+    pub(crate) fn execute_zbc(&mut self, op: ZbcOp, data: InstructionData) {
+        let rs1_data = self.regs[data.rs1];
+        let rs2_data = self.regs[data.rs2];
+
+        self.regs.write(data.rd,
+            match op {
+                Clmul  => Alu::clmul(rs1_data, rs2_data),
+                Clmulr => Alu::clmulr(rs1_data, rs2_data),
+                Clmulh => Alu::clmulh(rs1_data, rs2_data),
+            }
+        )
+    }
+}
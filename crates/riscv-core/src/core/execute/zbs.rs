@@ -0,0 +1,26 @@
+use riscv_decoder::instruction::InstructionData;
+use riscv_decoder::instruction::ZbsOp::{self, *};
+
+use crate::engine::Alu;
+use crate::core::cpu::Cpu;
+use crate::core::XReg;
+
+impl Cpu {
+    pub(crate) fn execute_zbs(&mut self, op: ZbsOp, data: InstructionData) {
+        let rs1_data = self.regs[data.rs1];
+        let index = if op.is_imm() {
+            data.imm as XReg
+        } else {
+            self.regs[data.rs2]
+        };
+
+        self.regs.write(data.rd,
+            match op {
+                Bclr | Bclri => Alu::bit_clear(rs1_data, index),
+                Bext | Bexti => Alu::bit_extract(rs1_data, index),
+                Binv | Binvi => Alu::bit_invert(rs1_data, index),
+                Bset | Bseti => Alu::bit_set(rs1_data, index),
+            }
+        )
+    }
+}
@@ -3,12 +3,14 @@ use riscv_decoder::instruction::ZicsrOp;
 
 use crate::Result;
 use crate::core::cpu::Cpu;
+use crate::core::XReg;
 
 impl Cpu {
     pub(crate) fn execute_zicsr(&mut self, op: ZicsrOp, data: InstructionData, raw: u32) -> Result<()> {
         let addr = (data.imm & 0xfff) as u16;
+        let next_pc = self.pc.get().wrapping_add(4);
         let (val, check_val) = if op.is_imm() {
-            (data.rs1 as u32, data.rs1)
+            (data.rs1 as XReg, data.rs1)
         } else {
             (self.regs[data.rs1], data.rs1)
         };
@@ -16,10 +18,10 @@ impl Cpu {
         if op.is_rw() {
             if data.rd != 0 {
                 let csr_data = self.csrs.read(addr, self.mode, raw)?;
-                self.csrs.write(addr, val, self.mode, raw)?;
+                self.csrs.write_at(addr, val, self.mode, raw, next_pc)?;
                 self.regs.write(data.rd, csr_data);
             } else {
-                self.csrs.write(addr, val, self.mode, raw)?;
+                self.csrs.write_at(addr, val, self.mode, raw, next_pc)?;
             }
         } else {
             let csr_data = self.csrs.read(addr, self.mode, raw)?;
@@ -29,7 +31,7 @@ impl Cpu {
                 } else { // is_rc
                     (!val) & csr_data
                 };
-                self.csrs.write(addr, write_val, self.mode, raw)?;
+                self.csrs.write_at(addr, write_val, self.mode, raw, next_pc)?;
             }
             self.regs.write(data.rd, csr_data);
         }
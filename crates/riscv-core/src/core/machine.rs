@@ -0,0 +1,308 @@
+mod config;
+mod fdt;
+
+use std::cell::RefCell;
+
+use riscv_decoder::isa::Isa;
+use riscv_loader::LoadInfo;
+
+use crate::{RiscVError, StdResult, Trap};
+use crate::debug::{DebugInterface, MachineInfo};
+use crate::device::bus::{SharedBus, SystemBus};
+use crate::device::clint::TimeSource;
+use crate::device::mmio::MmioDevice;
+use crate::device::uart::{UartInput, UartOutput};
+use crate::device::MAX_HARTS;
+use crate::engine::MisalignedPolicy;
+
+use super::XReg;
+#[cfg(feature = "zicsr")]
+use super::HartIds;
+use super::cpu::Cpu;
+
+pub use config::{BankKind, MachineConfig, MemoryBank};
+
+/// Instructions a hart runs before the next one is scheduled
+pub const DEFAULT_QUANTUM: usize = 100;
+
+/// Harts sharing one bus, scheduled round-robin with a fixed quantum.
+/// The schedule only depends on retired instructions, so every run interleaves the same way
+#[derive(Debug)]
+pub struct Machine {
+    harts: Vec<Cpu>,
+    quantum: usize,
+    // Hart that runs next and how much of its quantum is used
+    current: usize,
+    used: usize,
+    // Device tree blob and where it goes, placed again on reset
+    dtb: Option<(XReg, Vec<u8>)>,
+    // `/chosen` of the generated device tree
+    bootargs: Option<String>,
+    initrd: Option<(XReg, XReg)>,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Machine {
+    /// Machine with `num` harts and the default memory map. Panics unless `num` is from 1 to `MAX_HARTS`
+    pub fn new(num: usize) -> Self {
+        assert!((1..=MAX_HARTS).contains(&num), "{}", RiscVError::HartCount);
+        Self::with_bus(num, SystemBus::default())
+    }
+
+    /// Start from the default memory map, see `MachineConfig`
+    pub fn builder() -> MachineConfig {
+        MachineConfig::default()
+    }
+
+    fn with_bus(num: usize, bus: SystemBus) -> Self {
+        let bus = SharedBus::new(RefCell::new(bus));
+        let harts = (0..num)
+            .map(|id| Cpu::new(id, bus.clone()))
+            .collect();
+
+        Machine { harts, quantum: DEFAULT_QUANTUM, current: 0, used: 0, dtb: None, bootargs: None, initrd: None }
+    }
+
+    pub fn hart_num(&self) -> usize {
+        self.harts.len()
+    }
+
+    /// Hart that the next `step` runs
+    pub fn current_hart(&self) -> usize {
+        self.current
+    }
+
+    /// Debug view of hart `id`, the machine itself shows hart 0
+    pub fn hart(&self, id: usize) -> Option<&impl DebugInterface> {
+        self.harts.get(id)
+    }
+
+    /// Instructions per scheduling slot, at least 1
+    pub fn set_quantum(&mut self, quantum: usize) {
+        self.quantum = quantum.max(1);
+    }
+
+    /// Load into the shared memory, every hart starts at the entry point.
+    /// With a boot ROM the harts start there instead, and it jumps to the entry point
+    pub fn load_info(&mut self, info: &LoadInfo) -> StdResult<(), RiscVError> {
+        self.harts[0].load_info(info)?;
+        let start = {
+            let mut bus = self.harts[0].bus.borrow_mut();
+            if bus.has_boot_rom() {
+                bus.set_boot_entry(info.pc_entry);
+                bus.reset_vector()
+            } else {
+                info.pc_entry
+            }
+        };
+        self.set_pc(start);
+        Ok(())
+    }
+
+    /// Load another image, like a kernel or initramfs next to the firmware. The PC stays where it is
+    pub fn load_image(&mut self, info: &LoadInfo) -> StdResult<(), RiscVError> {
+        self.harts[0].load_image(info)
+    }
+
+    /// Kernel command line, `bootargs` in the generated device tree
+    pub fn set_bootargs(&mut self, bootargs: &str) {
+        self.bootargs = Some(bootargs.to_string());
+    }
+
+    /// Where the initramfs was loaded, `linux,initrd-start` and `linux,initrd-end` in the generated device tree
+    pub fn set_initrd(&mut self, start: XReg, size: usize) -> StdResult<(), RiscVError> {
+        let end = XReg::try_from(size).ok().and_then(|size| start.checked_add(size)).ok_or(RiscVError::InitrdOutOfRange)?;
+        self.initrd = Some((start, end));
+        Ok(())
+    }
+
+    /// Device tree address the boot ROM hands over in `a1`, 0 by default
+    pub fn set_boot_dtb(&mut self, dtb: XReg) {
+        self.harts[0].bus.borrow_mut().set_boot_dtb(dtb);
+    }
+
+    pub fn load(&mut self, addr: XReg, data: &[u8]) -> StdResult<(), RiscVError> {
+        self.harts[0].load(addr, data)
+    }
+
+    pub fn set_pc(&mut self, entry: XReg) {
+        self.harts.iter_mut().for_each(|hart| hart.set_pc(entry));
+    }
+
+    pub fn set_mem_zero(&mut self, addr: XReg, size: usize) -> StdResult<(), RiscVError> {
+        self.harts[0].set_mem_zero(addr, size)
+    }
+
+    pub fn run(&mut self) -> StdResult<(), RiscVError> {
+        loop { self.step()?; }
+    }
+
+    /// Step the current hart, a waiting hart gives up the rest of its quantum
+    pub fn step(&mut self) -> StdResult<Option<Trap>, RiscVError> {
+        // Time only skips ahead when nobody else is running
+        #[cfg(feature = "zicsr")]
+        let idle = self.harts.iter()
+            .enumerate()
+            .all(|(id, hart)| id == self.current || hart.waiting || hart.is_halted());
+        #[cfg(not(feature = "zicsr"))]
+        let idle = true;
+
+        let hart = &mut self.harts[self.current];
+        let trap = hart.step_hart(idle)?;
+
+        self.used += 1;
+        #[cfg(feature = "zicsr")]
+        let yielded = hart.waiting || hart.is_halted();
+        #[cfg(not(feature = "zicsr"))]
+        let yielded = false;
+
+        if self.used >= self.quantum || yielded {
+            self.current = (self.current + 1) % self.harts.len();
+            self.used = 0;
+        }
+        Ok(trap)
+    }
+
+    pub fn set_time_source(&mut self, source: TimeSource) {
+        self.harts[0].set_time_source(source);
+    }
+
+    /// Number of implemented `mhpmcounter` CSRs, from 0 to 29. Default is 4
+    #[cfg(feature = "zicsr")]
+    pub fn set_hpm_counters(&mut self, num: usize) {
+        self.harts.iter_mut().for_each(|hart| hart.set_hpm_counters(num));
+    }
+
+    /// Implemented PMP entries on every hart, usually 0, 16 or 64, and the granularity `G`:
+    /// regions are at least 2^(G + 2) bytes. Default is 16 entries with `G` = 0
+    #[cfg(feature = "zicsr")]
+    pub fn set_pmp(&mut self, entries: usize, granularity: u32) {
+        self.harts.iter_mut().for_each(|hart| hart.set_pmp(entries, granularity));
+    }
+
+    /// Enable Smrnmi on every hart with the RNMI interrupt and exception handler addresses.
+    /// Off by default, since `mnstatus.NMIE` resets to 0 and masks every interrupt until firmware sets it
+    #[cfg(feature = "zicsr")]
+    pub fn set_rnmi_handlers(&mut self, irq_handler: XReg, exc_handler: XReg) {
+        self.harts.iter_mut().for_each(|hart| hart.set_rnmi_handlers(irq_handler, exc_handler));
+    }
+
+    /// Inject an NMI into `hart` with an implementation-defined `cause`, for example a watchdog.
+    /// It stays pending until `mnstatus.NMIE` is set and is ignored without Smrnmi
+    #[cfg(feature = "zicsr")]
+    pub fn raise_nmi(&mut self, hart: usize, cause: u32) {
+        if let Some(hart) = self.harts.get_mut(hart) {
+            hart.raise_nmi(cause);
+        }
+    }
+
+    /// Halt `hart` into Debug Mode, a halted hart gives its slots away until it is resumed
+    #[cfg(feature = "zicsr")]
+    pub fn halt(&mut self, hart: usize) {
+        if let Some(hart) = self.harts.get_mut(hart) {
+            hart.halt();
+        }
+    }
+
+    /// Let `hart` leave Debug Mode like `dret` does
+    #[cfg(feature = "zicsr")]
+    pub fn resume(&mut self, hart: usize) {
+        if let Some(hart) = self.harts.get_mut(hart) {
+            hart.resume();
+        }
+    }
+
+    #[cfg(feature = "zicsr")]
+    pub fn is_halted(&self, hart: usize) -> bool {
+        self.harts.get(hart).is_some_and(|hart| hart.is_halted())
+    }
+
+    /// CSR of a halted `hart`, like a debugger's abstract command. This is how `dcsr` is set up
+    #[cfg(feature = "zicsr")]
+    pub fn read_csr(&mut self, hart: usize, addr: u16) -> Option<XReg> {
+        self.harts.get_mut(hart).and_then(|hart| hart.read_csr(addr))
+    }
+
+    /// Write a CSR of a halted `hart`, false when it is running or the write is illegal
+    #[cfg(feature = "zicsr")]
+    pub fn write_csr(&mut self, hart: usize, addr: u16, data: XReg) -> bool {
+        self.harts.get_mut(hart).is_some_and(|hart| hart.write_csr(addr, data))
+    }
+
+    /// Values of `mvendorid`, `marchid`, `mimpid` and `mconfigptr` on every hart. Default is all 0
+    #[cfg(feature = "zicsr")]
+    pub fn set_hart_ids(&mut self, ids: HartIds) {
+        self.harts.iter_mut().for_each(|hart| hart.set_hart_ids(ids));
+    }
+
+    /// Extensions every hart has, out of the compiled-in ones. Default is all of them
+    pub fn set_isa(&mut self, isa: Isa) {
+        self.harts.iter_mut().for_each(|hart| hart.set_isa(isa));
+    }
+
+    /// How every hart handles loads and stores that are not naturally aligned
+    pub fn set_misaligned(&mut self, policy: MisalignedPolicy) {
+        self.harts.iter_mut().for_each(|hart| hart.set_misaligned(policy));
+    }
+
+    /// Handle for feeding host input into the UART receive FIFO
+    pub fn uart_input(&self) -> UartInput {
+        self.harts[0].uart_input()
+    }
+
+    /// Redirect UART transmit, default is the process stdout
+    pub fn set_uart_output(&mut self, output: UartOutput) {
+        self.harts[0].set_uart_output(output);
+    }
+
+    /// Map `device` at `base..base + size`, shared by every hart. With `irq` its interrupt line
+    /// drives that PLIC source (1 to 31, 10 is the UART). Fails if the range is empty or overlaps
+    /// memory, the CLINT, PLIC, UART or another device. There is no `irq` without a PLIC
+    pub fn attach_device(&mut self, base: XReg, size: XReg, irq: Option<usize>, device: impl MmioDevice + 'static) -> StdResult<(), RiscVError> {
+        self.harts[0].attach_device(base, size, irq, Box::new(device))
+    }
+
+    pub fn reset(&mut self) {
+        self.harts[0].reset();
+        self.harts[1..].iter_mut().for_each(Cpu::reset_hart);
+        self.current = 0;
+        self.used = 0;
+        self.place_dtb();
+    }
+}
+
+impl DebugInterface for Machine {
+    fn inspect_regs(&self) -> [XReg; 32] {
+        self.harts[0].inspect_regs()
+    }
+
+    #[cfg(feature = "f")]
+    fn inspect_fregs(&self) -> [u64; 32] {
+        self.harts[0].inspect_fregs()
+    }
+
+    fn inspect_pc(&self) -> XReg {
+        self.harts[0].inspect_pc()
+    }
+
+    #[cfg(feature = "zicsr")]
+    fn inspect_csrs(&self) -> Vec<(String, XReg)> {
+        self.harts[0].inspect_csrs()
+    }
+
+    fn inspect_bus(&self, start: XReg, len: usize) -> Vec<u8> {
+        self.harts[0].inspect_bus(start, len)
+    }
+
+    fn get_info(&self) -> MachineInfo {
+        self.harts[0].get_info()
+    }
+}
+
+#[cfg(test)]
+mod tests;
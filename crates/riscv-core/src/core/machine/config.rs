@@ -0,0 +1,128 @@
+use crate::{RiscVError, StdResult};
+use crate::device::bus::{SystemBus, DRAM_BASE_ADDR, UART_BASE};
+use crate::device::clint::CLINT_BASE;
+use crate::device::memory::DEFAULT_DRAM_SIZE;
+use crate::device::plic::PLIC_BASE;
+use crate::device::MAX_HARTS;
+use crate::core::XReg;
+
+use super::Machine;
+
+/// What a memory bank is made of. Images can be loaded into any of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BankKind {
+    #[default]
+    Ram,
+    /// Stores from harts raise an access fault
+    Rom,
+    /// NOR flash, programmed through a controller register block at `ctrl_base`
+    Flash { ctrl_base: XReg },
+}
+
+/// Extra memory besides DRAM, like the SRAM and flash of a microcontroller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBank {
+    pub base: XReg,
+    /// Bytes, rounded up to a whole page
+    pub size: usize,
+    pub kind: BankKind,
+}
+
+/// Memory map and devices of a machine. `None` leaves a device out of the map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachineConfig {
+    pub harts: usize,
+    pub dram_base: XReg,
+    /// Bytes, rounded up to a whole page
+    pub dram_size: usize,
+    pub banks: Vec<MemoryBank>,
+    /// Where every hart starts after a reset. When not given it is the boot ROM if there is one,
+    /// or else the DRAM base
+    pub reset_vector: Option<XReg>,
+    /// Base of a one page boot ROM that jumps to the loaded image with `a0 = mhartid` and `a1` the device tree
+    pub boot_rom: Option<XReg>,
+    pub clint_base: Option<XReg>,
+    pub plic_base: Option<XReg>,
+    pub uart_base: Option<XReg>,
+}
+
+impl Default for MachineConfig {
+    fn default() -> Self {
+        MachineConfig {
+            harts: 1,
+            dram_base: DRAM_BASE_ADDR,
+            dram_size: DEFAULT_DRAM_SIZE,
+            banks: Vec::new(),
+            reset_vector: None,
+            boot_rom: None,
+            clint_base: Some(CLINT_BASE),
+            plic_base: Some(PLIC_BASE),
+            uart_base: Some(UART_BASE),
+        }
+    }
+}
+
+impl MachineConfig {
+    /// Number of harts, from 1 to `MAX_HARTS`
+    pub fn harts(mut self, num: usize) -> Self {
+        self.harts = num;
+        self
+    }
+
+    pub fn dram(mut self, base: XReg, size: usize) -> Self {
+        self.dram_base = base;
+        self.dram_size = size;
+        self
+    }
+
+    pub fn ram_bank(mut self, base: XReg, size: usize) -> Self {
+        self.banks.push(MemoryBank { base, size, kind: BankKind::Ram });
+        self
+    }
+
+    pub fn rom_bank(mut self, base: XReg, size: usize) -> Self {
+        self.banks.push(MemoryBank { base, size, kind: BankKind::Rom });
+        self
+    }
+
+    /// Flash at `base` and its controller registers at `ctrl_base`
+    pub fn flash_bank(mut self, base: XReg, size: usize, ctrl_base: XReg) -> Self {
+        self.banks.push(MemoryBank { base, size, kind: BankKind::Flash { ctrl_base } });
+        self
+    }
+
+    pub fn reset_vector(mut self, addr: XReg) -> Self {
+        self.reset_vector = Some(addr);
+        self
+    }
+
+    pub fn boot_rom(mut self, base: XReg) -> Self {
+        self.boot_rom = Some(base);
+        self
+    }
+
+    pub fn clint(mut self, base: Option<XReg>) -> Self {
+        self.clint_base = base;
+        self
+    }
+
+    pub fn plic(mut self, base: Option<XReg>) -> Self {
+        self.plic_base = base;
+        self
+    }
+
+    pub fn uart(mut self, base: Option<XReg>) -> Self {
+        self.uart_base = base;
+        self
+    }
+
+    /// Fails if there are 0 or more than `MAX_HARTS` harts, or if a memory or device region is empty,
+    /// does not fit the address space or overlaps another
+    pub fn build(&self) -> StdResult<Machine, RiscVError> {
+        if !(1..=MAX_HARTS).contains(&self.harts) {
+            return Err(RiscVError::HartCount);
+        }
+        let bus = SystemBus::new(self)?;
+        Ok(Machine::with_bus(self.harts, bus))
+    }
+}
@@ -0,0 +1,108 @@
+use riscv_decoder::isa::Extension;
+
+use crate::{RiscVError, StdResult};
+use crate::device::clint::WALL_CLOCK_FREQ;
+use crate::device::fdt::Fdt;
+use crate::device::memory::PAGE_SIZE;
+use crate::core::XReg;
+
+use super::Machine;
+
+impl Machine {
+    /// Flattened device tree of this machine: memory, harts, the built-in and attached devices
+    pub fn fdt(&self) -> Vec<u8> {
+        let bus = self.harts[0].bus.borrow();
+        let mut fdt = Fdt::default();
+
+        fdt.begin_node("");
+        fdt.prop_u32("#address-cells", 2);
+        fdt.prop_u32("#size-cells", 2);
+        fdt.prop_str("compatible", "rsriscv,emu");
+        fdt.prop_str("model", "rsriscv-emu");
+
+        fdt.begin_node("chosen");
+        if let Some(stdout) = bus.fdt_stdout() {
+            fdt.prop_str("stdout-path", &stdout);
+        }
+        if let Some(bootargs) = &self.bootargs {
+            fdt.prop_str("bootargs", bootargs);
+        }
+        if let Some((start, end)) = self.initrd {
+            fdt.prop_addr("linux,initrd-start", start);
+            fdt.prop_addr("linux,initrd-end", end);
+        }
+        fdt.end_node();
+
+        bus.fdt_memory(&mut fdt);
+
+        fdt.begin_node("cpus");
+        fdt.prop_u32("#address-cells", 1);
+        fdt.prop_u32("#size-cells", 0);
+        fdt.prop_u32("timebase-frequency", WALL_CLOCK_FREQ as u32);
+        // Phandle of each hart's interrupt controller is its id plus one
+        let intc: Vec<u32> = (1..=self.harts.len() as u32).collect();
+        for (id, hart) in self.harts.iter().enumerate() {
+            fdt.begin_node(&format!("cpu@{id}"));
+            fdt.prop_str("device_type", "cpu");
+            fdt.prop_u32("reg", id as u32);
+            fdt.prop_str("status", "okay");
+            fdt.prop_str("compatible", "riscv");
+            // Supervisor mode is told by `mmu-type`, not the ISA string
+            fdt.prop_str("riscv,isa", &hart.isa().without(Extension::S).to_string());
+            #[cfg(feature = "s")]
+            if hart.isa().has(Extension::S) {
+                fdt.prop_str("mmu-type", if cfg!(feature = "rv64") { "riscv,sv48" } else { "riscv,sv32" });
+            }
+            fdt.begin_node("interrupt-controller");
+            fdt.prop_u32("#interrupt-cells", 1);
+            fdt.prop_empty("interrupt-controller");
+            fdt.prop_str("compatible", "riscv,cpu-intc");
+            fdt.prop_u32("phandle", intc[id]);
+            fdt.end_node();
+            fdt.end_node();
+        }
+        fdt.end_node();
+
+        bus.fdt_soc(&mut fdt, &intc, intc.len() as u32 + 1);
+        fdt.end_node();
+        fdt.finish()
+    }
+
+    /// Place a device tree blob at the top of DRAM, page aligned, and hand it to the harts:
+    /// through the boot ROM if there is one, or else in `a1` with `a0 = mhartid`.
+    /// It is placed again on every reset. Returns its address
+    pub fn load_dtb(&mut self, dtb: Vec<u8>) -> StdResult<XReg, RiscVError> {
+        let (dram_size, dram_base, _) = self.harts[0].bus.borrow().ram_info();
+        let size = dtb.len().next_multiple_of(PAGE_SIZE);
+        if size > dram_size {
+            return Err(RiscVError::LoadFailed);
+        }
+        let addr = dram_base + (dram_size - size) as XReg;
+        self.dtb = Some((addr, dtb));
+        self.place_dtb();
+        Ok(addr)
+    }
+
+    /// Generate the device tree with `fdt` and load it like `load_dtb`
+    pub fn load_fdt(&mut self) -> StdResult<XReg, RiscVError> {
+        let dtb = self.fdt();
+        self.load_dtb(dtb)
+    }
+
+    pub(super) fn place_dtb(&mut self) {
+        let Some((addr, dtb)) = self.dtb.take() else {
+            return;
+        };
+        // Fits, checked by `load_dtb`
+        let _ = self.harts[0].load(addr, &dtb);
+        if self.harts[0].bus.borrow().has_boot_rom() {
+            self.set_boot_dtb(addr);
+        } else {
+            for (id, hart) in self.harts.iter_mut().enumerate() {
+                hart.regs.write(10, id as XReg);
+                hart.regs.write(11, addr);
+            }
+        }
+        self.dtb = Some((addr, dtb));
+    }
+}
@@ -0,0 +1,480 @@
+#[cfg(feature = "zicsr")] use crate::core::privilege::PrivilegeMode;
+use crate::debug::DebugInterface;
+use crate::constance::DRAM_BASE_ADDR;
+use crate::core::machine::Machine;
+
+fn program(code: &[u32]) -> Vec<u8> {
+    code.iter().flat_map(|ins| ins.to_le_bytes()).collect()
+}
+
+#[test]
+fn test_round_robin_quantum() {
+    let mut mach = Machine::new(3);
+    mach.set_quantum(2);
+
+    // addi a1, a1, 1 (x8)
+    mach.load(DRAM_BASE_ADDR, &program(&[0x00158593; 8])).unwrap();
+
+    let order: Vec<usize> = (0..8).map(|_| {
+        let hart = mach.current_hart();
+        mach.step().unwrap();
+        hart
+    }).collect();
+    assert_eq!(order, [0, 0, 1, 1, 2, 2, 0, 0]);
+
+    assert_eq!(mach.harts[0].regs[11], 4);
+    assert_eq!(mach.harts[1].regs[11], 2);
+    assert_eq!(mach.harts[2].regs[11], 2);
+}
+
+#[test]
+fn test_shared_memory() {
+    let mut mach = Machine::new(2);
+    mach.set_quantum(1);
+
+    // sw t2, 0(a0)
+    mach.load(DRAM_BASE_ADDR, &program(&[0x00752023])).unwrap();
+    let addr = DRAM_BASE_ADDR + 0x1000;
+    mach.harts[0].regs.write(10, addr);
+    mach.harts[0].regs.write(7, 0x1234);
+
+    mach.step().unwrap();
+    let word = mach.hart(1).unwrap().inspect_bus(addr, 4);
+    assert_eq!(u32::from_le_bytes(word.try_into().unwrap()), 0x1234);
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_mhartid_per_hart() {
+    let mut mach = Machine::new(4);
+    mach.set_quantum(1);
+
+    // csrr a0, mhartid
+    mach.load(DRAM_BASE_ADDR, &program(&[0xf1402573])).unwrap();
+    for _ in 0..4 {
+        mach.step().unwrap();
+    }
+
+    for id in 0..4 {
+        assert_eq!(mach.harts[id].regs[10], id as crate::XReg);
+    }
+}
+
+#[test]
+#[cfg(feature = "a")]
+fn test_store_breaks_other_hart_reservation() {
+    let mut mach = Machine::new(2);
+    mach.set_quantum(1);
+
+    // Hart 0: lr.w t0, (a0); sc.w t1, t2, (a0)
+    mach.load(DRAM_BASE_ADDR, &program(&[0x100522af, 0x1875232f])).unwrap();
+    // Hart 1: sw t2, 0(a0)
+    let store_code = DRAM_BASE_ADDR + 0x100;
+    mach.load(store_code, &program(&[0x00752023])).unwrap();
+    mach.harts[1].set_pc(store_code);
+
+    let addr = DRAM_BASE_ADDR + 0x1000;
+    mach.set_mem_zero(addr, 4).unwrap();
+    for (id, hart) in mach.harts.iter_mut().enumerate() {
+        hart.regs.write(10, addr);
+        hart.regs.write(7, 0x10 + id as crate::XReg);
+    }
+
+    // lr, store from hart 1, then sc
+    for _ in 0..3 {
+        mach.step().unwrap();
+    }
+    assert_eq!(mach.harts[0].regs[6], 1, "sc should fail after another hart stored");
+
+    let word = mach.inspect_bus(addr, 4);
+    assert_eq!(u32::from_le_bytes(word.try_into().unwrap()), 0x11);
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_software_ipi() {
+    use crate::{Interrupt, Trap};
+    use crate::device::clint::CLINT_BASE;
+
+    let mut mach = Machine::new(2);
+    mach.set_quantum(1);
+
+    // Hart 0: addi a1, a1, 1; sw t2, 0(a0)
+    mach.load(DRAM_BASE_ADDR, &program(&[0x00158593, 0x00752023])).unwrap();
+    mach.harts[0].regs.write(10, CLINT_BASE + 4);
+    mach.harts[0].regs.write(7, 1);
+
+    // Hart 1: wfi, with MSIE and MIE set
+    let wfi_code = DRAM_BASE_ADDR + 0x100;
+    let handler_base = DRAM_BASE_ADDR + 0x200;
+    mach.load(wfi_code, &program(&[0x10500073])).unwrap();
+    let hart = &mut mach.harts[1];
+    hart.set_pc(wfi_code);
+    hart.csrs.write(0x305, handler_base, PrivilegeMode::Machine, 0).unwrap();
+    hart.csrs.write(0x304, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+    hart.csrs.write(0x300, 1 << 3, PrivilegeMode::Machine, 0).unwrap();
+
+    mach.step().unwrap();
+    mach.step().unwrap();
+    assert!(mach.harts[1].waiting);
+
+    // Hart 0 raises msip of hart 1
+    mach.step().unwrap();
+    let trap = mach.step().unwrap();
+    assert_eq!(trap, Some(Trap::Interrupt(Interrupt::MachineSoftware)));
+    assert_eq!(mach.harts[1].pc.get(), handler_base);
+    assert!(!mach.harts[1].waiting);
+
+    // Hart 0 never sees it
+    let mip = mach.harts[0].csrs.read(0x344, PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(mip & (1 << 3), 0);
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_waiting_hart_yields() {
+    let mut mach = Machine::new(2);
+    mach.set_quantum(4);
+
+    // Hart 0: wfi, hart 1: addi a1, a1, 1 (x4)
+    mach.load(DRAM_BASE_ADDR, &program(&[0x10500073])).unwrap();
+    let add_code = DRAM_BASE_ADDR + 0x100;
+    mach.load(add_code, &program(&[0x00158593; 4])).unwrap();
+    mach.harts[1].set_pc(add_code);
+
+    mach.step().unwrap();
+    assert!(mach.harts[0].waiting);
+    assert_eq!(mach.current_hart(), 1, "Waiting hart should give up its slot");
+
+    for _ in 0..4 {
+        mach.step().unwrap();
+    }
+    assert_eq!(mach.harts[1].regs[11], 4);
+    // No timer was set and hart 1 was running, so time only moved with retired instructions
+    assert_eq!(mach.harts[1].bus.borrow().clint().mtime(), 5);
+}
+
+#[test]
+fn test_attach_device() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use crate::{RiscVError, XReg};
+    use crate::device::bus::UART_BASE;
+    use crate::device::plic::PLIC_BASE;
+    use crate::mmio::{MmioDevice, MmioFault};
+
+    // One write-only register at 0, reading 4 gives it back doubled
+    struct Doubler(Rc<Cell<u32>>);
+
+    impl MmioDevice for Doubler {
+        fn read(&mut self, offset: XReg, data: &mut [u8]) -> Result<(), MmioFault> {
+            if offset != 4 {
+                return Err(MmioFault);
+            }
+            data.copy_from_slice(&(self.0.get() * 2).to_le_bytes());
+            Ok(())
+        }
+
+        fn write(&mut self, offset: XReg, data: &[u8]) -> Result<(), MmioFault> {
+            if offset != 0 {
+                return Err(MmioFault);
+            }
+            self.0.set(u32::from_le_bytes(data.try_into().unwrap()));
+            Ok(())
+        }
+
+        fn irq_pending(&self) -> bool {
+            self.0.get() != 0
+        }
+    }
+
+    let base = 0x2000_0000;
+    let reg = Rc::new(Cell::new(0));
+    let mut mach = Machine::default();
+    assert_eq!(mach.attach_device(UART_BASE + 0x80, 0x100, None, Doubler(reg.clone())), Err(RiscVError::DeviceOverlap));
+    assert_eq!(mach.attach_device(base, 0, None, Doubler(reg.clone())), Err(RiscVError::DeviceOverlap));
+    assert_eq!(mach.attach_device(base, 0x1000, Some(10), Doubler(reg.clone())), Err(RiscVError::IrqUnavailable));
+    mach.attach_device(base, 0x1000, Some(3), Doubler(reg.clone())).unwrap();
+    assert_eq!(mach.attach_device(base + 0xff0, 0x10, None, Doubler(reg.clone())), Err(RiscVError::DeviceOverlap));
+
+    // sw t2, 0(a0); lw a1, 4(a0)
+    mach.load(DRAM_BASE_ADDR, &program(&[0x00752023, 0x00452583])).unwrap();
+    mach.harts[0].regs.write(10, base);
+    mach.harts[0].regs.write(7, 21);
+    mach.step().unwrap();
+    mach.step().unwrap();
+
+    assert_eq!(reg.get(), 21);
+    assert_eq!(mach.harts[0].regs[11], 42);
+    // Without `peek` the debugger does not read the device
+    assert_eq!(mach.inspect_bus(base + 4, 4), [0; 4]);
+    // Pending bit of source 3
+    assert_eq!(mach.inspect_bus(PLIC_BASE + 0x1000, 1)[0] & (1 << 3), 1 << 3);
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_attach_device_fault_mtval() {
+    use crate::{Exception, Trap, XReg};
+    use crate::core::access::{Access, AccessType};
+    use crate::device::Device;
+    use crate::mmio::{MmioDevice, MmioFault};
+
+    // Refuses every access
+    struct Refuser;
+
+    impl MmioDevice for Refuser {
+        fn read(&mut self, _: XReg, _: &mut [u8]) -> Result<(), MmioFault> {
+            Err(MmioFault)
+        }
+
+        fn write(&mut self, _: XReg, _: &[u8]) -> Result<(), MmioFault> {
+            Err(MmioFault)
+        }
+    }
+
+    let base = 0x2000_0000;
+    let mut mach = Machine::default();
+    mach.attach_device(base, 0x1000, None, Refuser).unwrap();
+
+    // The bus reports the physical address, not the offset into the device
+    let mut data = [0; 4];
+    let access = Access::new(base + 8, AccessType::Load);
+    assert_eq!(mach.harts[0].bus.borrow().read_bytes(access, 4, &mut data), Err(Exception::LoadAccessFault(base + 8)));
+
+    // lw a1, 8(a0); sw t2, 12(a0)
+    mach.load(DRAM_BASE_ADDR, &program(&[0x00852583, 0x00752623])).unwrap();
+    let hart = &mut mach.harts[0];
+    hart.csrs.write(0x305, DRAM_BASE_ADDR + 4, PrivilegeMode::Machine, 0).unwrap();
+    hart.regs.write(10, base);
+
+    assert_eq!(mach.step().unwrap(), Some(Trap::Exception(Exception::LoadAccessFault(base + 8))));
+    assert_eq!(mach.harts[0].csrs.read(0x343, PrivilegeMode::Machine, 0), Ok(base + 8), "mtval of the load");
+    assert_eq!(mach.step().unwrap(), Some(Trap::Exception(Exception::StoreOrAmoAccessFault(base + 12))));
+    assert_eq!(mach.harts[0].csrs.read(0x343, PrivilegeMode::Machine, 0), Ok(base + 12), "mtval of the store");
+}
+
+#[test]
+fn test_machine_config() {
+    use crate::{RiscVError, XReg};
+    use crate::device::MAX_HARTS;
+    use crate::device::bus::UART_BASE;
+    use crate::mmio::{MmioDevice, MmioFault};
+
+    struct Dummy;
+
+    impl MmioDevice for Dummy {
+        fn read(&mut self, _: XReg, _: &mut [u8]) -> Result<(), MmioFault> {
+            Ok(())
+        }
+
+        fn write(&mut self, _: XReg, _: &[u8]) -> Result<(), MmioFault> {
+            Ok(())
+        }
+    }
+
+    assert_eq!(Machine::builder().dram(UART_BASE - 0x1000, 0x2000).build().unwrap_err(), RiscVError::DeviceOverlap);
+    assert_eq!(Machine::builder().rom_bank(DRAM_BASE_ADDR + 0x1000, 0x1000).build().unwrap_err(), RiscVError::DeviceOverlap);
+    assert_eq!(Machine::builder().ram_bank(0x1000, 0).build().unwrap_err(), RiscVError::DeviceOverlap);
+    assert_eq!(Machine::builder().harts(0).build().unwrap_err(), RiscVError::HartCount);
+    assert_eq!(Machine::builder().harts(MAX_HARTS + 1).build().unwrap_err(), RiscVError::HartCount);
+    assert_eq!(Machine::builder().harts(MAX_HARTS).build().unwrap().hart_num(), MAX_HARTS);
+
+    let sram = 0x2000_0000;
+    let mut mach = Machine::builder()
+        .harts(2)
+        .dram(sram, 0x3000)
+        .rom_bank(0x0, 0x1000)
+        .reset_vector(0x0)
+        .uart(None)
+        .plic(None)
+        .build()
+        .unwrap();
+    assert_eq!(mach.hart_num(), 2);
+    assert_eq!(mach.inspect_pc(), 0x0);
+    let info = mach.get_info();
+    assert_eq!((info.dram_base, info.dram_size), (sram, 0x3000));
+
+    // The old UART and DRAM addresses are free now, but a device can not drive an interrupt
+    assert_eq!(mach.attach_device(UART_BASE, 0x100, Some(3), Dummy), Err(RiscVError::IrqUnavailable));
+    mach.attach_device(UART_BASE, 0x100, None, Dummy).unwrap();
+    assert!(mach.load(DRAM_BASE_ADDR, &[0; 4]).is_err());
+
+    // sw t2, 0(a0); sw t2, 0(a1)
+    mach.load(0x0, &program(&[0x00752023, 0x0075a023])).unwrap();
+    mach.harts[0].regs.write(10, sram + 0x2ffc);
+    mach.harts[0].regs.write(11, 0x100);
+    mach.harts[0].regs.write(7, 0x55);
+    mach.step().unwrap();
+    assert_eq!(mach.inspect_bus(sram + 0x2ffc, 1), [0x55]);
+
+    // The ROM is read-only to the harts
+    #[cfg(feature = "zicsr")] {
+        use crate::{Exception, Trap};
+        assert_eq!(mach.step().unwrap(), Some(Trap::Exception(Exception::StoreOrAmoAccessFault(0x100))));
+        assert_eq!(mach.inspect_bus(0x100, 1), [0]);
+    }
+
+    mach.reset();
+    assert_eq!(mach.inspect_pc(), 0x0);
+}
+
+#[test]
+fn test_boot_rom() {
+    use riscv_loader::LoadInfo;
+
+    let rom = 0x1000;
+    let dtb = DRAM_BASE_ADDR + 0x20_0000;
+    let mut mach = Machine::builder().harts(2).boot_rom(rom).build().unwrap();
+    mach.set_quantum(5);
+
+    let entry = DRAM_BASE_ADDR + 0x100;
+    // nop
+    let info = LoadInfo { pc_entry: entry, code: vec![(program(&[0x00000013]), entry)], ..Default::default() };
+    mach.load_info(&info).unwrap();
+    mach.set_boot_dtb(dtb);
+    assert_eq!(mach.inspect_pc(), rom);
+
+    for _ in 0..10 {
+        mach.step().unwrap();
+    }
+    for (id, hart) in mach.harts.iter().enumerate() {
+        assert_eq!(hart.pc.get(), entry);
+        assert_eq!(hart.regs[11], dtb);
+        #[cfg(feature = "zicsr")]
+        assert_eq!(hart.regs[10], id as crate::XReg);
+        #[cfg(not(feature = "zicsr"))]
+        assert_eq!(hart.regs[10], 0, "Hart {} has no mhartid to read", id);
+    }
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_access_straddling_regions() {
+    use crate::{Exception, Trap, XReg};
+    use crate::core::access::{Access, AccessType};
+    use crate::mmio::{MmioDevice, MmioFault};
+
+    struct Dummy;
+
+    impl MmioDevice for Dummy {
+        fn read(&mut self, _: XReg, _: &mut [u8]) -> Result<(), MmioFault> {
+            Ok(())
+        }
+
+        fn write(&mut self, _: XReg, _: &[u8]) -> Result<(), MmioFault> {
+            Ok(())
+        }
+    }
+
+    // A ROM bank right after DRAM
+    let rom = DRAM_BASE_ADDR + 0x2000;
+    let mut mach = Machine::builder().dram(DRAM_BASE_ADDR, 0x2000).rom_bank(rom, 0x1000).build().unwrap();
+
+    let bus = mach.harts[0].bus.borrow();
+    assert!(bus.pma_check(Access::new(rom - 4, AccessType::Load), 4).is_ok());
+    assert_eq!(bus.pma_check(Access::new(rom - 2, AccessType::Load), 4), Err(Exception::LoadAccessFault(rom - 2)));
+    assert_eq!(bus.pma_check(Access::new(rom - 2, AccessType::Store), 4), Err(Exception::StoreOrAmoAccessFault(rom - 2)));
+    drop(bus);
+
+    // Two 2-byte devices side by side, an aligned word covers both
+    let base = 0x2000_0000;
+    mach.attach_device(base, 2, None, Dummy).unwrap();
+    mach.attach_device(base + 2, 2, None, Dummy).unwrap();
+
+    // lw a1, 0(a0); sw a1, 0(a0)
+    mach.load(DRAM_BASE_ADDR, &program(&[0x00052583, 0x00b52023])).unwrap();
+    let hart = &mut mach.harts[0];
+    hart.csrs.write(0x305, DRAM_BASE_ADDR + 4, PrivilegeMode::Machine, 0).unwrap();
+    hart.regs.write(10, base);
+
+    assert_eq!(mach.step().unwrap(), Some(Trap::Exception(Exception::LoadAccessFault(base))));
+    assert_eq!(mach.step().unwrap(), Some(Trap::Exception(Exception::StoreOrAmoAccessFault(base))));
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_flash_bank() {
+    use crate::{Exception, Trap};
+
+    let (flash, ctrl) = (0x2000_0000, 0x1002_0000);
+    let mut mach = Machine::builder().flash_bank(flash, 0x1000, ctrl).build().unwrap();
+    assert_eq!(mach.inspect_bus(flash, 4), [0xff; 4]);
+
+    // sw t2, 0(a0); sw t1, 0(a1); sw t2, 0(a0), the fault goes on to the next instruction
+    mach.load(DRAM_BASE_ADDR, &program(&[0x00752023, 0x0065a023, 0x00752023])).unwrap();
+    let hart = &mut mach.harts[0];
+    hart.csrs.write(0x305, DRAM_BASE_ADDR + 4, PrivilegeMode::Machine, 0).unwrap();
+    hart.regs.write(10, flash);
+    hart.regs.write(11, ctrl);
+    hart.regs.write(7, 0x1234_5678);
+    hart.regs.write(6, 1);
+
+    assert_eq!(mach.step().unwrap(), Some(Trap::Exception(Exception::StoreOrAmoAccessFault(flash))));
+    assert_eq!(mach.inspect_bus(ctrl + 8, 1), [1], "Error bit of the controller");
+    // Set PG, then program
+    mach.step().unwrap();
+    mach.step().unwrap();
+    assert_eq!(mach.inspect_bus(flash, 4), 0x1234_5678u32.to_le_bytes());
+
+    // The array survives a reset, PG does not
+    mach.reset();
+    assert_eq!(mach.inspect_bus(flash, 4), 0x1234_5678u32.to_le_bytes());
+    assert_eq!(mach.inspect_bus(ctrl, 4), [0; 4]);
+}
+
+#[test]
+fn test_device_tree() {
+    use crate::device::memory::PAGE_SIZE;
+
+    let contains = |blob: &[u8], text: &[u8]| blob.windows(text.len()).any(|window| window == text);
+    let dram_size = 0x10_0000;
+    let mut mach = Machine::builder().harts(2).dram(DRAM_BASE_ADDR, dram_size).build().unwrap();
+
+    let blob = mach.fdt();
+    assert_eq!(blob[..4], [0xd0, 0x0d, 0xfe, 0xed]);
+    assert_eq!(u32::from_be_bytes(blob[4..8].try_into().unwrap()) as usize, blob.len());
+    assert!(contains(&blob, b"memory@80000000\0"));
+    assert!(contains(&blob, b"cpu@1\0"));
+    assert!(contains(&blob, b"serial@10000000\0"));
+    assert!(contains(&blob, mach.harts[0].isa().without(riscv_decoder::isa::Extension::S).to_string().as_bytes()));
+
+    // Top of DRAM, and `a1` points at it on every hart
+    let addr = mach.load_fdt().unwrap();
+    assert_eq!(addr, DRAM_BASE_ADDR + (dram_size - blob.len().next_multiple_of(PAGE_SIZE)) as crate::XReg);
+    mach.reset();
+    assert_eq!(mach.inspect_bus(addr, blob.len()), blob);
+    for (id, hart) in mach.harts.iter().enumerate() {
+        assert_eq!(hart.regs[10], id as crate::XReg);
+        assert_eq!(hart.regs[11], addr);
+    }
+
+    assert!(mach.load_dtb(vec![0; dram_size + 1]).is_err());
+}
+
+#[test]
+fn test_load_image() {
+    use riscv_loader::LoadInfo;
+    use crate::{RiscVError, XReg};
+
+    let mut mach = Machine::new(1);
+    let (kernel, initrd) = (DRAM_BASE_ADDR + 0x40_0000, DRAM_BASE_ADDR + 0x80_0000);
+    let image = |addr| LoadInfo { pc_entry: addr, code: vec![(vec![0xaa; 16], addr)], ..Default::default() };
+    mach.load_info(&image(DRAM_BASE_ADDR)).unwrap();
+    mach.load_image(&image(kernel)).unwrap();
+    mach.load_image(&image(initrd)).unwrap();
+    assert_eq!(mach.inspect_pc(), DRAM_BASE_ADDR);
+    assert_eq!(mach.inspect_bus(kernel, 16), [0xaa; 16]);
+
+    mach.set_bootargs("console=ttyS0");
+    mach.set_initrd(initrd, 16).unwrap();
+    let blob = mach.fdt();
+    let contains = |text: &[u8]| blob.windows(text.len()).any(|window| window == text);
+    assert!(contains(b"console=ttyS0\0"));
+    assert!(contains(b"linux,initrd-end\0"));
+    // Two cells, 0x8080_0010
+    assert!(contains(&[0, 0, 0, 0, 0x80, 0x80, 0x00, 0x10]));
+
+    assert_eq!(mach.set_initrd(XReg::MAX - 8, 16), Err(RiscVError::InitrdOutOfRange));
+    mach.set_initrd(XReg::MAX - 16, 16).unwrap();
+}
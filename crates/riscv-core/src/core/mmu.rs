@@ -1,15 +1,65 @@
-mod sv32;
+mod pte;
 mod tlb;
 
 use crate::Result;
-use crate::core::CsrFile;
+use crate::core::{CsrFile, SXReg, XLEN, XReg};
 use crate::core::privilege::PrivilegeMode;
 use crate::device::bus::SystemBus;
 use crate::core::access::{Access, AccessType, Physical, Virtual};
 
-use sv32::{Sv32Pte, Sv32Vpn};
+use pte::Pte;
 use tlb::{Tlb, TlbResult};
 
+#[cfg(not(feature = "rv64"))]
+const VPN_BITS: u32 = 10;
+#[cfg(feature = "rv64")]
+const VPN_BITS: u32 = 9;
+
+const PTE_SIZE: usize = size_of::<XReg>();
+
+/// Translation scheme selected by `satp.MODE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagingMode {
+    #[cfg(not(feature = "rv64"))]
+    Sv32,
+    #[cfg(feature = "rv64")]
+    Sv39,
+    #[cfg(feature = "rv64")]
+    Sv48,
+}
+
+impl PagingMode {
+    /// `None` is Bare or an unsupported mode
+    pub fn from_satp(mode: u8) -> Option<Self> {
+        match mode {
+            #[cfg(not(feature = "rv64"))]
+            1 => Some(PagingMode::Sv32),
+            #[cfg(feature = "rv64")]
+            8 => Some(PagingMode::Sv39),
+            #[cfg(feature = "rv64")]
+            9 => Some(PagingMode::Sv48),
+            _ => None,
+        }
+    }
+
+    fn levels(&self) -> usize {
+        match self {
+            #[cfg(not(feature = "rv64"))]
+            PagingMode::Sv32 => 2,
+            #[cfg(feature = "rv64")]
+            PagingMode::Sv39 => 3,
+            #[cfg(feature = "rv64")]
+            PagingMode::Sv48 => 4,
+        }
+    }
+
+    /// Bits above the virtual address width must all equal its top bit
+    fn is_canonical(&self, v_addr: XReg) -> bool {
+        let shift = XLEN - 12 - self.levels() as u32 * VPN_BITS;
+        (((v_addr << shift) as SXReg) >> shift) as XReg == v_addr
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct Mmu {
     tlb: Tlb,
@@ -27,21 +77,32 @@ impl Mmu {
     ) -> Result<Access<Physical>> {
         let v_addr = access.addr; 
 
+        // MPRV only moves loads and stores, fetches stay at the current privilege
+        let mode = if access.kind == AccessType::Fetch {
+            mode
+        } else {
+            csrs.data_mode(mode)
+        };
+
         if mode == PrivilegeMode::Machine {
             return Ok(access.bypass());
         }
 
-        let (asid, root_ppn) = match csrs.check_satp(mode)? {
-            Some((asid, ppn)) => (asid, ppn),
+        let (paging, asid, root_ppn) = match csrs.check_satp(mode)? {
+            Some(satp) => satp,
             None => return Ok(access.bypass()),
         };
 
+        if !paging.is_canonical(v_addr) {
+            return Err(access.into_page_exception());
+        }
+
         let tlb_res = self.tlb.lookup(csrs, v_addr, asid, access.kind, mode);
 
         match tlb_res {
-            TlbResult::Hit(is_mega, ppn) => {
+            TlbResult::Hit(level, ppn) => {
                 self.hit_count += 1;
-                let p_addr = Self::get_physical(v_addr, ppn, is_mega);
+                let p_addr = Self::get_physical(v_addr, ppn, level);
                 return Ok(access.into_physical(p_addr));
             },
             TlbResult::PageFault => {
@@ -52,25 +113,7 @@ impl Mmu {
             TlbResult::Miss => self.miss_count += 1,
         }
 
-        let vpn = Sv32Vpn::from(v_addr);
-
-        let (mut pte, pte_addr, is_mega) = {
-            let (pte1, addr1, is_leaf) = Self::pte_walk(
-                vpn.vpn_1(), root_ppn, &access, bus
-            )?;
-
-            if is_leaf {
-                (pte1, addr1, true)
-            } else {
-                let (pte0, addr0, is_leaf) = Self::pte_walk(
-                    vpn.vpn_0(), pte1.ppn(), &access, bus
-                )?;
-                if !is_leaf {
-                    return Err(access.into_page_exception());
-                }
-                (pte0, addr0, false)
-            }
-        };
+        let (mut pte, pte_addr, level) = Self::pte_walk(paging, v_addr, root_ppn, &access, bus)?;
 
         Self::access_check(&pte, &access, mode, csrs)?;
 
@@ -84,35 +127,57 @@ impl Mmu {
         }
         if update_pte {
             let pte_access = Access::new(pte_addr, access.kind);
-            bus.write_u32(pte_access, pte.into())?;
+            bus.write_xreg_bytes(pte_access, pte.into(), PTE_SIZE)?;
         }
 
-        if is_mega && (pte.ppn() & 0x3ff) != 0 {
-            return Err(access.into_page_exception());
-        }
+        self.tlb.fill(v_addr, pte, asid, level);
 
-        self.tlb.fill(v_addr, pte, asid, is_mega);
-
-        let p_addr = Self::get_physical(v_addr, pte.ppn(), is_mega);
+        let p_addr = Self::get_physical(v_addr, pte.ppn() as XReg, level);
 
         Ok(access.into_physical(p_addr))
     }
 
-    fn pte_walk(vpn: u16, ppn: u32, access: &Access<Virtual>, bus: &mut SystemBus) -> Result<(Sv32Pte, u32, bool)> {
-        let pte_addr = (ppn << 12) + (vpn * 4) as u32;
+    /// Walk from the root table down to the leaf. Returns the leaf, its address and its level
+    fn pte_walk(
+        paging: PagingMode,
+        v_addr: XReg,
+        root_ppn: XReg,
+        access: &Access<Virtual>,
+        bus: &mut SystemBus
+    ) -> Result<(Pte, XReg, usize)> {
+        let mut ppn = root_ppn;
+
+        for level in (0..paging.levels()).rev() {
+            let shift = 12 + level as u32 * VPN_BITS;
+            let vpn = (v_addr >> shift) & ((1 << VPN_BITS) - 1);
+            let pte_addr = (ppn << 12) + vpn * PTE_SIZE as XReg;
 
-        let pte_access = Access::new(pte_addr, AccessType::Load);
+            let pte_access = Access::new(pte_addr, AccessType::Load);
 
-        let pte = Sv32Pte::from(bus.read_u32(pte_access)?);
+            let pte = Pte::from(bus.read_xreg_bytes(pte_access, PTE_SIZE, false)?);
 
-        if !pte.is_valid() || (!pte.can_read() && pte.can_write()) {
-            return  Err(access.into_page_exception())
-        } 
+            if !pte.is_valid() || (!pte.can_read() && pte.can_write()) {
+                return Err(access.into_page_exception());
+            }
+            #[cfg(feature = "rv64")]
+            if pte.has_reserved() {
+                return Err(access.into_page_exception());
+            }
+
+            if pte.is_leaf() {
+                // Superpages must be aligned to their size
+                if pte.ppn() as XReg & ((1 << (shift - 12)) - 1) != 0 {
+                    return Err(access.into_page_exception());
+                }
+                return Ok((pte, pte_addr, level));
+            }
+            ppn = pte.ppn() as XReg;
+        }
 
-        Ok((pte, pte_addr, pte.is_leaf()))
+        Err(access.into_page_exception())
     }
 
-    fn access_check(pte: &Sv32Pte, access: &Access<Virtual>, mode: PrivilegeMode, csrs: &CsrFile) -> Result<()> {
+    fn access_check(pte: &Pte, access: &Access<Virtual>, mode: PrivilegeMode, csrs: &CsrFile) -> Result<()> {
         let can_access = match access.kind {
             AccessType::Load  => pte.can_read() || (pte.can_execute() && csrs.check_mxr()),
             AccessType::Store => pte.can_write(),
@@ -132,19 +197,13 @@ impl Mmu {
         }
     }
 
-    fn get_physical(v_addr: u32, ppn: u32, is_mega: bool) -> u32 {
-        let level_size = if is_mega {
-                1 << 22
-            } else {
-                1 << 12
-            };
-        let offset_mask = level_size - 1;
+    fn get_physical(v_addr: XReg, ppn: XReg, level: usize) -> XReg {
+        let offset_mask = (1 << (12 + level as u32 * VPN_BITS)) - 1;
         (ppn << 12) | (v_addr & offset_mask)
     }
 
-    pub fn flush_tlb(&mut self, v_addr: u32, asid: u32) {
-        let vpn = v_addr >> 12; 
-        self.tlb.flush(vpn, asid as u16);
+    pub fn flush_tlb(&mut self, v_addr: XReg, asid: XReg) {
+        self.tlb.flush(Tlb::vpn(v_addr), asid as u16);
     }
 
     pub fn reset(&mut self) {
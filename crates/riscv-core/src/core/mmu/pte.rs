@@ -1,28 +1,29 @@
 use modular_bitfield::prelude::*;
 
+use crate::core::XReg;
+
+/// Sv32 page table entry
+#[cfg(not(feature = "rv64"))]
 #[bitfield]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Sv32Vpn {
-    pub offset: B12,
-    pub vpn_0: B10,
-    pub vpn_1: B10,
-}
-
-impl From<u32> for Sv32Vpn {
-    fn from(value: u32) -> Self {
-        Self::from_bytes(value.to_le_bytes())
-    }
-}
-
-impl From<Sv32Vpn> for u32 {
-    fn from(value: Sv32Vpn) -> Self {
-        Self::from_le_bytes(value.into_bytes())
-    }
+pub struct Pte {
+    v: B1,
+    r: B1,
+    w: B1,
+    x: B1,
+    u: B1,
+    g: B1,
+    a: B1,
+    d: B1,
+    #[skip] __: B2,
+    pub ppn: B22,
 }
 
+/// Sv39 and Sv48 share the same page table entry
+#[cfg(feature = "rv64")]
 #[bitfield]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Sv32Pte {
+pub struct Pte {
     v: B1,
     r: B1,
     w: B1,
@@ -32,22 +33,25 @@ pub struct Sv32Pte {
     a: B1,
     d: B1,
     #[skip] __: B2,
-    pub ppn: B22,
+    pub ppn: B44,
+    reserved: B7,
+    pbmt: B2,
+    n: B1,
 }
 
-impl From<u32> for Sv32Pte {
-    fn from(value: u32) -> Self {
+impl From<XReg> for Pte {
+    fn from(value: XReg) -> Self {
         Self::from_bytes(value.to_le_bytes())
     }
 }
 
-impl From<Sv32Pte> for u32 {
-    fn from(value: Sv32Pte) -> Self {
+impl From<Pte> for XReg {
+    fn from(value: Pte) -> Self {
         Self::from_le_bytes(value.into_bytes())
     }
 }
 
-impl Sv32Pte {
+impl Pte {
     pub fn is_valid(&self) -> bool {
         self.v() > 0
     }
@@ -101,4 +105,10 @@ impl Sv32Pte {
     pub fn is_dirty(&self) -> bool {
         self.d() > 0
     }
+
+    /// Svpbmt and Svnapot are not implemented, so their bits are reserved as well
+    #[cfg(feature = "rv64")]
+    pub fn has_reserved(&self) -> bool {
+        self.reserved() != 0 || self.pbmt() != 0 || self.n() != 0
+    }
 }
\ No newline at end of file
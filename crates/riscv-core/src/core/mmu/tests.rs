@@ -1,12 +1,14 @@
 use crate::Exception;
 use crate::core::{CsrFile, Mmu};
-use crate::core::mmu::{Sv32Pte};
+use crate::core::XReg;
+use crate::core::mmu::{Pte, PTE_SIZE};
 use crate::core::privilege::PrivilegeMode;
 use crate::core::access::{Access, AccessType};
 use crate::device::bus::SystemBus;
 use crate::core::mmu::tlb::TlbResult;
 
-fn make_pte(ppn: u32, 
+#[allow(clippy::too_many_arguments)]
+fn make_pte(ppn: XReg, 
     v: bool, 
     r: bool, 
     w: bool, 
@@ -15,7 +17,7 @@ fn make_pte(ppn: u32,
     g: bool,
     a: bool, 
     d: bool
-) -> u32 {
+) -> XReg {
     let mut pte = 0;
     if v { pte |= 1 << 0; }
     if r { pte |= 1 << 1; }
@@ -25,18 +27,18 @@ fn make_pte(ppn: u32,
     if g { pte |= 1 << 5; }
     if a { pte |= 1 << 6; }
     if d { pte |= 1 << 7; }
-    pte |= (ppn & 0x3FFFFF) << 10;
+    pte |= ppn << 10;
     pte
 }
 
-fn write_pte(bus: &mut SystemBus, addr: u32, pte: u32) {
+fn write_pte(bus: &mut SystemBus, addr: XReg, pte: XReg) {
     let access = Access::new(addr, AccessType::Store);
-    bus.write_u32(access.into_physical(addr), pte).expect("Setup PTE failed");
+    bus.write_xreg_bytes(access.into_physical(addr), pte, PTE_SIZE).expect("Setup PTE failed");
 }
 
-fn read_ram_u32(bus: &mut SystemBus, addr: u32) -> u32 {
+fn read_pte(bus: &mut SystemBus, addr: XReg) -> XReg {
     let access = Access::new(addr, AccessType::Load);
-    bus.read_u32(access.into_physical(addr)).expect("Read RAM failed")
+    bus.read_xreg_bytes(access.into_physical(addr), PTE_SIZE, false).expect("Read RAM failed")
 }
 
 #[test]
@@ -58,6 +60,7 @@ fn test_bypass_mode() {
 }
 
 #[test]
+#[cfg(not(feature = "rv64"))]
 fn test_sv32_4k_page_translation_and_accessed_bit() {
     let mut mmu = Mmu::default();
     let mut csrs = CsrFile::default();
@@ -92,11 +95,12 @@ fn test_sv32_4k_page_translation_and_accessed_bit() {
     let pa = res.unwrap().addr;
     assert_eq!(pa, target_ppn << 12, "Physical address mismatch");
 
-    let updated_leaf_pte = read_ram_u32(&mut bus, leaf_pte_addr);
+    let updated_leaf_pte = read_pte(&mut bus, leaf_pte_addr);
     assert_eq!(updated_leaf_pte & (1 << 6), (1 << 6), "Accessed bit should be set by MMU");
 }
 
 #[test]
+#[cfg(not(feature = "rv64"))]
 fn test_sv32_megapage_translation() {
     let mut mmu = Mmu::default();
     let mut csrs = CsrFile::default();
@@ -122,12 +126,13 @@ fn test_sv32_megapage_translation() {
     let expected_pa = (target_megapage_ppn << 12) | (va & 0x3FFFFF);
     assert_eq!(pa, expected_pa);
 
-    let updated_pte = read_ram_u32(&mut bus, root_pte_addr);
+    let updated_pte = read_pte(&mut bus, root_pte_addr);
     assert_eq!(updated_pte & (1 << 6), (1 << 6), "A bit missing");
     assert_eq!(updated_pte & (1 << 7), (1 << 7), "D bit missing");
 }
 
 #[test]
+#[cfg(not(feature = "rv64"))]
 fn test_page_fault_read_only() {
     let mut mmu = Mmu::default();
     let mut csrs = CsrFile::default();
@@ -156,6 +161,7 @@ fn test_page_fault_read_only() {
 }
 
 #[test]
+#[cfg(not(feature = "rv64"))]
 fn test_page_fault_invalid() {
     let mut mmu = Mmu::default();
     let mut csrs = CsrFile::default();
@@ -180,6 +186,7 @@ fn test_page_fault_invalid() {
 }
 
 #[test]
+#[cfg(not(feature = "rv64"))]
 fn test_tlb() {
     let mut mmu = Mmu::default();
     let mut csrs = CsrFile::default();
@@ -217,6 +224,75 @@ fn test_tlb() {
     assert_eq!(mmu.miss_count, 1, "Miss count should not increase");
 }
 
+#[test]
+#[cfg(not(feature = "rv64"))]
+fn test_mprv_translates_machine_data_access() {
+    let mut mmu = Mmu::default();
+    let mut csrs = CsrFile::default();
+    let mut bus = SystemBus::default();
+    let root_ppn = 0x80001;
+    let target_ppn = 0x80400;
+    let va = 0x0040_0123;
+
+    let vpn1 = (va >> 22) & 0x3FF;
+    let root_pte_addr = (root_ppn << 12) + vpn1 * 4;
+    write_pte(&mut bus, root_pte_addr, make_pte(target_ppn, true, true, true, true, false, false, true, true));
+    csrs.write(0x180, root_ppn | (1 << 31), PrivilegeMode::Machine, 0).unwrap();
+
+    let load = Access::new(va, AccessType::Load);
+    let fetch = Access::new(va, AccessType::Fetch);
+    assert_eq!(mmu.translate(load, PrivilegeMode::Machine, &csrs, &mut bus).unwrap().addr, va);
+
+    // MPRV with MPP = S
+    csrs.write(0x300, (1 << 17) | (1 << 11), PrivilegeMode::Machine, 0).unwrap();
+    let pa = mmu.translate(load, PrivilegeMode::Machine, &csrs, &mut bus).unwrap().addr;
+    assert_eq!(pa, (target_ppn << 12) | (va & 0x3FFFFF));
+    assert_eq!(mmu.translate(fetch, PrivilegeMode::Machine, &csrs, &mut bus).unwrap().addr, va, 
+        "Fetch should ignore MPRV");
+
+    // MPRV with MPP = M changes nothing
+    csrs.write(0x300, (1 << 17) | (3 << 11), PrivilegeMode::Machine, 0).unwrap();
+    assert_eq!(mmu.translate(load, PrivilegeMode::Machine, &csrs, &mut bus).unwrap().addr, va);
+}
+
+#[test]
+#[cfg(not(feature = "rv64"))]
+fn test_mprv_with_sum_and_mxr() {
+    let mut mmu = Mmu::default();
+    let mut csrs = CsrFile::default();
+    let mut bus = SystemBus::default();
+    let root_ppn = 0x80001;
+    let target_ppn = 0x80400;
+    let va = 0x0040_0000;
+
+    // Execute-only user page
+    let vpn1 = (va >> 22) & 0x3FF;
+    let root_pte_addr = (root_ppn << 12) + vpn1 * 4;
+    write_pte(&mut bus, root_pte_addr, make_pte(target_ppn, true, false, false, true, true, false, true, true));
+    csrs.write(0x180, root_ppn | (1 << 31), PrivilegeMode::Machine, 0).unwrap();
+
+    let load = Access::new(va, AccessType::Load);
+    let mprv_s = (1 << 17) | (1 << 11);
+
+    csrs.write(0x300, mprv_s, PrivilegeMode::Machine, 0).unwrap();
+    match mmu.translate(load, PrivilegeMode::Machine, &csrs, &mut bus) {
+        Err(Exception::LoadPageFault(addr)) => assert_eq!(addr, va),
+        res => panic!("S-mode data access to a user page needs SUM, got {:?}", res),
+    }
+
+    csrs.write(0x300, mprv_s | (1 << 18), PrivilegeMode::Machine, 0).unwrap();
+    assert!(mmu.translate(load, PrivilegeMode::Machine, &csrs, &mut bus).is_err(), 
+        "Execute-only page needs MXR to be read");
+
+    csrs.write(0x300, mprv_s | (1 << 18) | (1 << 19), PrivilegeMode::Machine, 0).unwrap();
+    let pa = mmu.translate(load, PrivilegeMode::Machine, &csrs, &mut bus).unwrap().addr;
+    assert_eq!(pa, target_ppn << 12);
+
+    // MPP = U reaches the user page without SUM
+    csrs.write(0x300, (1 << 17) | (1 << 19), PrivilegeMode::Machine, 0).unwrap();
+    assert!(mmu.translate(load, PrivilegeMode::Machine, &csrs, &mut bus).is_ok());
+}
+
 #[test]
 fn test_flush_tlb() {
     let mut mmu = Mmu::default();
@@ -230,15 +306,15 @@ fn test_flush_tlb() {
 
     // Entry 1
     let pte_a = make_pte(ppn_a, true, true, true, false, true, false, true, true); // V, R, W, U
-    mmu.tlb.fill(0x10 << 12, Sv32Pte::from(pte_a), 1, false);
+    mmu.tlb.fill(0x10 << 12, Pte::from(pte_a), 1, 0);
 
     // Entry 2
     let pte_b = make_pte(ppn_b, true, true, false, false, false, true, true, true); 
-    mmu.tlb.fill(0x20 << 12, Sv32Pte::from(pte_b), 1, false);
+    mmu.tlb.fill(0x20 << 12, Pte::from(pte_b), 1, 0);
 
     // Entry 3
     let pte_c = make_pte(ppn_c, true, true, true, false, true, false, true, true);
-    mmu.tlb.fill(0x10 << 12, Sv32Pte::from(pte_c), 2, false);
+    mmu.tlb.fill(0x10 << 12, Pte::from(pte_c), 2, 0);
 
     mmu.flush_tlb(0x10 << 12, 1);
 
@@ -255,7 +331,7 @@ fn test_flush_tlb() {
     assert!(matches!(res3, TlbResult::Hit(_, _)));
 
     // --- Refill Entry 1 for next test ---
-    mmu.tlb.fill(0x10 << 12, Sv32Pte::from(pte_a), 1, false);
+    mmu.tlb.fill(0x10 << 12, Pte::from(pte_a), 1, 0);
 
     mmu.flush_tlb(0, 1); // vaddr=0 triggers ASID flush
 
@@ -265,7 +341,7 @@ fn test_flush_tlb() {
     let res2 = mmu.tlb.lookup(&csrs, 0x20 << 12, 1, AccessType::Load, PrivilegeMode::Supervisor);
     assert!(matches!(res2, TlbResult::Hit(_, _)));
 
-    mmu.tlb.fill(0x10 << 12, Sv32Pte::from(pte_a), 1, false); // Refill A
+    mmu.tlb.fill(0x10 << 12, Pte::from(pte_a), 1, 0); // Refill A
     mmu.flush_tlb(0x10 << 12, 0); // asid=0 triggers VAddr flush
 
     let res1 = mmu.tlb.lookup(&csrs, 0x10 << 12, 1, AccessType::Load, PrivilegeMode::User);
@@ -275,9 +351,121 @@ fn test_flush_tlb() {
 
 
     // Flush All
-    mmu.tlb.fill(0x10 << 12, Sv32Pte::from(pte_a), 1, false);
+    mmu.tlb.fill(0x10 << 12, Pte::from(pte_a), 1, 0);
     mmu.flush_tlb(0, 0);
 
     let res2 = mmu.tlb.lookup(&csrs, 0x20 << 12, 1, AccessType::Load, PrivilegeMode::Supervisor);
     assert!(matches!(res2, TlbResult::Miss));
-}
\ No newline at end of file
+}
+#[cfg(feature = "rv64")]
+const SV39: XReg = 8 << 60;
+#[cfg(feature = "rv64")]
+const SV48: XReg = 9 << 60;
+
+#[test]
+#[cfg(feature = "rv64")]
+fn test_sv39_4k_page_translation() {
+    let mut mmu = Mmu::default();
+    let mut csrs = CsrFile::default();
+    let mut bus = SystemBus::default();
+
+    let root_ppn = 0x80001;
+    let mid_pt_ppn = 0x80002;
+    let leaf_pt_ppn = 0x80003;
+    let target_ppn = 0x80004;
+
+    let va = 0x20_2030_0abc;
+    let vpn2 = (va >> 30) & 0x1ff;
+    let vpn1 = (va >> 21) & 0x1ff;
+    let vpn0 = (va >> 12) & 0x1ff;
+
+    write_pte(&mut bus, (root_ppn << 12) + vpn2 * 8, make_pte(mid_pt_ppn, true, false, false, false, false, false, false, false));
+    write_pte(&mut bus, (mid_pt_ppn << 12) + vpn1 * 8, make_pte(leaf_pt_ppn, true, false, false, false, false, false, false, false));
+    let leaf_pte_addr = (leaf_pt_ppn << 12) + vpn0 * 8;
+    write_pte(&mut bus, leaf_pte_addr, make_pte(target_ppn, true, true, true, false, false, false, false, false));
+
+    csrs.write(0x180, SV39 | root_ppn, PrivilegeMode::Machine, 0).unwrap();
+    let access = Access::new(va, AccessType::Store);
+    let res = mmu.translate(access, PrivilegeMode::Supervisor, &csrs, &mut bus);
+
+    assert_eq!(res.map(|a| a.addr), Ok((target_ppn << 12) | 0xabc));
+    let updated_pte = read_pte(&mut bus, leaf_pte_addr);
+    assert_eq!(updated_pte & (3 << 6), 3 << 6, "A and D bits should be set");
+}
+
+#[test]
+#[cfg(feature = "rv64")]
+fn test_sv39_gigapage_translation() {
+    let mut mmu = Mmu::default();
+    let mut csrs = CsrFile::default();
+    let mut bus = SystemBus::default();
+
+    let root_ppn = 0x80001;
+    let va = 0x1_2345_6789;
+    let vpn2 = (va >> 30) & 0x1ff;
+    let giga_ppn = 0x80000;
+
+    write_pte(&mut bus, (root_ppn << 12) + vpn2 * 8, make_pte(giga_ppn, true, true, false, false, false, false, true, false));
+
+    csrs.write(0x180, SV39 | root_ppn, PrivilegeMode::Machine, 0).unwrap();
+    let access = Access::new(va, AccessType::Load);
+    let res = mmu.translate(access, PrivilegeMode::Supervisor, &csrs, &mut bus);
+    assert_eq!(res.map(|a| a.addr), Ok((giga_ppn << 12) | (va & 0x3fff_ffff)));
+
+    // A gigapage whose PPN is not 1 GiB aligned is a page fault
+    let mut mmu = Mmu::default();
+    write_pte(&mut bus, (root_ppn << 12) + vpn2 * 8, make_pte(giga_ppn | 0x200, true, true, false, false, false, false, true, false));
+    let res = mmu.translate(access, PrivilegeMode::Supervisor, &csrs, &mut bus);
+    assert_eq!(res, Err(Exception::LoadPageFault(va)));
+}
+
+#[test]
+#[cfg(feature = "rv64")]
+fn test_sv48_4k_page_translation() {
+    let mut mmu = Mmu::default();
+    let mut csrs = CsrFile::default();
+    let mut bus = SystemBus::default();
+
+    let table_ppn = [0x80001, 0x80002, 0x80003, 0x80004];
+    let target_ppn = 0x80005;
+    let va = 0x7f12_3456_7000;
+
+    for (i, ppn) in table_ppn.iter().enumerate() {
+        let level = 3 - i as u32;
+        let vpn = (va >> (12 + level * 9)) & 0x1ff;
+        let next = table_ppn.get(i + 1).copied().unwrap_or(target_ppn);
+        let is_leaf = level == 0;
+        write_pte(&mut bus, (ppn << 12) + vpn * 8, make_pte(next, true, is_leaf, false, false, false, false, true, false));
+    }
+
+    csrs.write(0x180, SV48 | table_ppn[0], PrivilegeMode::Machine, 0).unwrap();
+    let access = Access::new(va, AccessType::Load);
+    let res = mmu.translate(access, PrivilegeMode::Supervisor, &csrs, &mut bus);
+    assert_eq!(res.map(|a| a.addr), Ok(target_ppn << 12));
+}
+
+#[test]
+#[cfg(feature = "rv64")]
+fn test_sv39_non_canonical_and_reserved() {
+    let mut mmu = Mmu::default();
+    let mut csrs = CsrFile::default();
+    let mut bus = SystemBus::default();
+    let root_ppn = 0x80001;
+
+    csrs.write(0x180, SV39 | root_ppn, PrivilegeMode::Machine, 0).unwrap();
+
+    // Bits 63:39 differ from bit 38
+    let va = 0x0000_0080_0000_0000;
+    let access = Access::new(va, AccessType::Fetch);
+    let res = mmu.translate(access, PrivilegeMode::Supervisor, &csrs, &mut bus);
+    assert_eq!(res, Err(Exception::InstructionPageFault(va)));
+
+    // The top half is canonical, but a PTE with reserved bits set faults
+    let va = 0xffff_ffc0_0000_0000;
+    let vpn2 = (va >> 30) & 0x1ff;
+    let pte = make_pte(0x80000, true, true, false, false, false, false, true, false) | (1 << 54);
+    write_pte(&mut bus, (root_ppn << 12) + vpn2 * 8, pte);
+    let access = Access::new(va, AccessType::Load);
+    let res = mmu.translate(access, PrivilegeMode::Supervisor, &csrs, &mut bus);
+    assert_eq!(res, Err(Exception::LoadPageFault(va)));
+}
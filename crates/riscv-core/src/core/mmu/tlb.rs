@@ -5,11 +5,11 @@ mod set;
 
 use std::ops::IndexMut;
 
-use crate::core::{CsrFile, PrivilegeMode}; 
+use crate::core::{CsrFile, PrivilegeMode, XReg}; 
 use crate::core::access::AccessType;
-use crate::core::mmu::sv32::Sv32Pte;
+use crate::core::mmu::pte::Pte;
 
-use entry::TlbEntry;
+use entry::{Tag, TlbEntry};
 use set::TlbSet;
 
 pub use result::TlbResult;
@@ -18,6 +18,12 @@ const TLB_WAY_NUM: usize = 4;
 const TLB_SET_SHIFT: usize = 6;
 const TLB_SET_NUM: usize = 1 << 6;
 
+// Sv48 has the widest VPN
+#[cfg(not(feature = "rv64"))]
+const VPN_MASK: XReg = (1 << 20) - 1;
+#[cfg(feature = "rv64")]
+const VPN_MASK: XReg = (1 << 36) - 1;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Tlb {
     sets: [TlbSet; TLB_SET_NUM],
@@ -27,13 +33,13 @@ impl Tlb {
     pub fn lookup(
         &mut self, 
         csrs: &CsrFile,
-        v_addr: u32,
+        v_addr: XReg,
         asid: u16, 
         kind: AccessType, 
         mode: PrivilegeMode) -> TlbResult {
-        let vpn = v_addr >> 12;
+        let vpn = Self::vpn(v_addr);
         let set_idx = Self::get_set_idx(vpn);
-        let tag = (vpn >> TLB_SET_SHIFT) as u16;
+        let tag = (vpn >> TLB_SET_SHIFT) as Tag;
         
         let target = self.find(set_idx, tag, asid);
         
@@ -44,7 +50,7 @@ impl Tlb {
 
                 if entry.access_check(kind, mode, csrs) {
                     if entry.ad_check(kind) {
-                        TlbResult::Hit(entry.is_mega_page(), entry.ppn())
+                        TlbResult::Hit(entry.level() as usize, entry.ppn() as XReg)
                     } else {
                         TlbResult::UpdateAD
                     }
@@ -56,10 +62,10 @@ impl Tlb {
         }
     }
 
-    pub fn fill(&mut self, v_addr: u32, pte: Sv32Pte, asid: u16, is_mega: bool) {
-        let vpn = v_addr >> 12;
+    pub fn fill(&mut self, v_addr: XReg, pte: Pte, asid: u16, level: usize) {
+        let vpn = Self::vpn(v_addr);
         let set_idx = Self::get_set_idx(vpn);
-        let tag = (vpn >> TLB_SET_SHIFT) as u16;
+        let tag = (vpn >> TLB_SET_SHIFT) as Tag;
 
         let in_tlb = self.find(set_idx, tag, asid);
 
@@ -73,12 +79,12 @@ impl Tlb {
         victim.set_flags(pte);
         victim.set_ppn(pte.ppn());
         victim.set_asid(asid);
-        victim.set_page_size(is_mega as u8);
+        victim.set_level(level as u8);
         
         self.sets[set_idx].plru.update(idx);
     }
 
-    fn find(&self, set_idx: usize, tag: u16, asid: u16) -> Option<usize> {
+    fn find(&self, set_idx: usize, tag: Tag, asid: u16) -> Option<usize> {
         for (i, entry) in self.sets[set_idx].entries.iter().enumerate() {
             if entry.is_valid() 
                 && entry.tag() == tag
@@ -89,11 +95,15 @@ impl Tlb {
         None
     }
 
-    fn get_set_idx(vpn: u32) -> usize {
+    pub fn vpn(v_addr: XReg) -> XReg {
+        (v_addr >> 12) & VPN_MASK
+    }
+
+    fn get_set_idx(vpn: XReg) -> usize {
         vpn as usize & (TLB_SET_NUM - 1)
     }
 
-    pub fn flush(&mut self, vpn: u32, asid: u16) {
+    pub fn flush(&mut self, vpn: XReg, asid: u16) {
         match (vpn, asid) {
             (0, 0) => self.flush_all(),
             (0, a) => self.flush_by_asid(a),
@@ -118,9 +128,9 @@ impl Tlb {
         });
     }
 
-    fn flush_by_address(&mut self, vpn: u32) {
+    fn flush_by_address(&mut self, vpn: XReg) {
         let set_index = Self::get_set_idx(vpn);
-        let tag = (vpn >> TLB_SET_SHIFT) as u16;
+        let tag = (vpn >> TLB_SET_SHIFT) as Tag;
 
         self.sets[set_index].entries.iter_mut()
             .for_each(|entry| {
@@ -130,9 +140,9 @@ impl Tlb {
             }); 
     }
 
-    fn flush_by_both(&mut self, vpn: u32, asid: u16) {
+    fn flush_by_both(&mut self, vpn: XReg, asid: u16) {
         let set_index = Self::get_set_idx(vpn);
-        let tag = (vpn >> TLB_SET_SHIFT) as u16;
+        let tag = (vpn >> TLB_SET_SHIFT) as Tag;
 
         self.sets[set_index].entries.iter_mut()
             .for_each(|entry| {
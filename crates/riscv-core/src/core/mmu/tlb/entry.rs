@@ -2,8 +2,14 @@ use modular_bitfield::prelude::*;
 
 use crate::core::{CsrFile, PrivilegeMode}; 
 use crate::core::access::AccessType;
-use crate::core::mmu::sv32::Sv32Pte;
+use crate::core::mmu::pte::Pte;
 
+#[cfg(not(feature = "rv64"))]
+pub type Tag = u16;
+#[cfg(feature = "rv64")]
+pub type Tag = u32;
+
+#[cfg(not(feature = "rv64"))]
 #[bitfield]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TlbEntry {
@@ -18,10 +24,30 @@ pub struct TlbEntry {
     pub u: B1,
     pub a: B1,
     pub d: B1,
-    pub page_size: B1,
+    pub level: B1,
     #[skip] __: B10,
 }
 
+// Sv48 has a 36-bit VPN and a 44-bit PPN
+#[cfg(feature = "rv64")]
+#[bitfield]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlbEntry {
+    pub valid: B1,
+    pub global: B1,
+    pub tag: B30,
+    pub asid: B16,
+    pub ppn: B44,
+    pub r: B1,
+    pub w: B1,
+    pub x: B1,
+    pub u: B1,
+    pub a: B1,
+    pub d: B1,
+    pub level: B2,
+    #[skip] __: B4,
+}
+
 impl TlbEntry {
     pub fn is_valid(&self) -> bool {
         self.valid() > 0
@@ -31,9 +57,6 @@ impl TlbEntry {
         self.global() > 0
     }
 
-    pub fn is_mega_page(&self) -> bool {
-        self.page_size() > 0
-    }
 
     pub fn can_read(&self) -> bool {
         self.r() > 0
@@ -59,7 +82,7 @@ impl TlbEntry {
         self.d() > 0
     }
 
-    pub fn set_flags(&mut self, pte: Sv32Pte) {
+    pub fn set_flags(&mut self, pte: Pte) {
         self.set_valid(pte.is_valid() as u8);
         self.set_r(pte.can_read() as u8);
         self.set_w(pte.can_write() as u8);
@@ -1,6 +1,8 @@
+use crate::core::XReg;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TlbResult {
-    Hit(bool, u32),
+    Hit(usize, XReg),
     Miss,
     PageFault,
     UpdateAD,
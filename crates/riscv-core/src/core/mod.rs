@@ -2,6 +2,7 @@ mod cpu;
 #[cfg(feature = "zicsr")]
 mod csr;
 mod execute;
+mod machine;
 #[cfg(feature = "s")]
 mod mmu;
 mod pc;
@@ -13,13 +14,18 @@ pub(crate) mod access;
 
 use pc::PC;
 use register::RegisterFile;
+#[cfg(feature = "f")]
+use register::FRegisterFile;
 
 #[cfg(feature = "zicsr")]
 pub(crate) use csr::CsrFile;
 #[cfg(feature = "zicsr")]
+pub use csr::HartIds;
+#[cfg(feature = "zicsr")]
 pub(crate) use privilege::PrivilegeMode;
 #[cfg(feature = "s")]
-pub(crate) use mmu::Mmu;
+pub(crate) use mmu::{Mmu, PagingMode};
 
 
-pub use cpu::Cpu as RiscV;
+pub use machine::{Machine as RiscV, BankKind, MachineConfig, MemoryBank, DEFAULT_QUANTUM};
+pub use register::{sext_w, SXReg, XLEN, XReg};
@@ -1,11 +1,17 @@
 use crate::constance::DRAM_BASE_ADDR;
+use crate::core::register::{SXReg, XReg};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PC {
-    pointer: u32,
+    pointer: XReg,
+    reset_vector: XReg,
 }
 
 impl PC {
+    pub fn new(reset_vector: XReg) -> Self {
+        PC { pointer: reset_vector, reset_vector }
+    }
+
     pub fn step(&mut self) {
         self.pointer += 4;
     }
@@ -15,29 +21,29 @@ impl PC {
         self.pointer += 2;
     }
 
-    pub fn get(&self) -> u32 {
+    pub fn get(&self) -> XReg {
         self.pointer
     }
 
-    pub fn set(&mut self, pointer: u32) {
+    pub fn set(&mut self, pointer: XReg) {
         self.pointer = pointer;
     }
 
     pub fn reset(&mut self) {
-        self.pointer = DRAM_BASE_ADDR;
+        self.pointer = self.reset_vector;
     }
 
     pub fn related_addressing(&mut self, offset: i32) {
-        self.pointer = self.pointer.wrapping_add_signed(offset);
+        self.pointer = self.pointer.wrapping_add_signed(offset as SXReg);
     }
 
-    pub fn directed_addressing(&mut self, address: u32) {
+    pub fn directed_addressing(&mut self, address: XReg) {
         self.pointer = address & !1;
     }
 }
 
 impl Default for PC {
     fn default() -> Self {
-        PC { pointer: DRAM_BASE_ADDR }
+        PC::new(DRAM_BASE_ADDR)
     }
 }
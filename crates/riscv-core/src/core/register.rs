@@ -1,10 +1,27 @@
+/// Integer register width, `XLEN`
+#[cfg(not(feature = "rv64"))]
+pub type XReg = u32;
+#[cfg(feature = "rv64")]
+pub type XReg = u64;
+#[cfg(not(feature = "rv64"))]
+pub type SXReg = i32;
+#[cfg(feature = "rv64")]
+pub type SXReg = i64;
+
+pub const XLEN: u32 = XReg::BITS;
+
+/// Sign-extends a 32 bits result to `XLEN`, as the `*w` instructions do
+pub fn sext_w(data: u32) -> XReg {
+    data as i32 as SXReg as XReg
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct RegisterFile {
-    regs: [u32; 32],
+    regs: [XReg; 32],
 }
 
 impl RegisterFile {
-    pub fn write(&mut self, id: u8, data: u32) {
+    pub fn write(&mut self, id: u8, data: XReg) {
         if id == 0 {
             return;
         }
@@ -15,14 +32,86 @@ impl RegisterFile {
         self.regs.fill(0);
     }
 
-    pub fn inspect(&self) -> [u32; 32] {
+    pub fn inspect(&self) -> [XReg; 32] {
         self.regs
     }
 }
 
 impl std::ops::Index<u8> for RegisterFile {
-    type Output = u32;
+    type Output = XReg;
+    fn index(&self, index: u8) -> &Self::Output {
+        &self.regs[index as usize]
+    }
+}
+
+/// Raw register width, `FLEN`
+#[cfg(all(feature = "f", not(feature = "d")))]
+pub type FReg = u32;
+#[cfg(feature = "d")]
+pub type FReg = u64;
+
+/// Upper half of a single-precision value boxed in a 64 bits register
+#[cfg(feature = "d")]
+const NAN_BOX: u64 = 0xffff_ffff_0000_0000;
+#[cfg(feature = "d")]
+const CANONICAL_NAN_S: u32 = 0x7fc0_0000;
+
+/// Floating-point registers. Unlike `x0`, `f0` is an ordinary register
+#[cfg(feature = "f")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FRegisterFile {
+    regs: [FReg; 32],
+}
+
+#[cfg(feature = "f")]
+impl FRegisterFile {
+    pub fn write(&mut self, id: u8, data: FReg) {
+        self.regs[id as usize] = data;
+    }
+
+    /// Single-precision operand. A value that is not NaN-boxed reads as the canonical NaN
+    pub fn read_s(&self, id: u8) -> u32 {
+        let data = self.regs[id as usize];
+        #[cfg(feature = "d")]
+        if data & NAN_BOX != NAN_BOX {
+            return CANONICAL_NAN_S;
+        }
+        low_word(data)
+    }
+
+    /// Low 32 bits as they are, for `fsw` and `fmv.x.w`
+    pub fn read_raw_s(&self, id: u8) -> u32 {
+        low_word(self.regs[id as usize])
+    }
+
+    /// Single-precision result, NaN-boxed when registers are wider
+    pub fn write_s(&mut self, id: u8, data: u32) {
+        #[cfg(feature = "d")]
+        self.write(id, NAN_BOX | data as u64);
+        #[cfg(not(feature = "d"))]
+        self.write(id, data);
+    }
+
+    pub fn reset(&mut self) {
+        self.regs.fill(0);
+    }
+
+    #[allow(clippy::unnecessary_cast)]
+    pub fn inspect(&self) -> [u64; 32] {
+        self.regs.map(|reg| reg as u64)
+    }
+}
+
+#[cfg(feature = "f")]
+#[allow(clippy::unnecessary_cast)]
+fn low_word(data: FReg) -> u32 {
+    data as u32
+}
+
+#[cfg(feature = "f")]
+impl std::ops::Index<u8> for FRegisterFile {
+    type Output = FReg;
     fn index(&self, index: u8) -> &Self::Output {
         &self.regs[index as usize]
     }
-}
\ No newline at end of file
+}
@@ -1,11 +1,16 @@
+use crate::core::XReg;
+use crate::engine::MisalignedPolicy;
 #[cfg(feature = "s")]
 use crate::core::PrivilegeMode;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MachineInfo {
+    /// Bytes
     pub dram_size: usize,
-    pub dram_base: u32,
+    pub dram_base: XReg,
+    /// Bytes
     pub page_size: usize,
+    pub misaligned: MisalignedPolicy,
     #[cfg(feature = "s")]
     pub hit_rate: f32,
     #[cfg(feature = "s")]
@@ -13,7 +18,7 @@ pub struct MachineInfo {
 }
 
 impl MachineInfo {
-    pub fn new(dram_size: usize, dram_base: u32 , page_size: usize, 
+    pub fn new(dram_size: usize, dram_base: XReg, page_size: usize, misaligned: MisalignedPolicy,
         #[cfg(feature = "s")] hit: usize, 
         #[cfg(feature = "s")] miss: usize, 
         #[cfg(feature = "s")] mode: PrivilegeMode) -> Self {
@@ -31,21 +36,25 @@ impl MachineInfo {
             PrivilegeMode::User       => "User"
         }.to_string();
 
-        MachineInfo { dram_size, dram_base, page_size, 
+        MachineInfo { dram_size, dram_base, page_size, misaligned,
             #[cfg(feature = "s")] hit_rate, 
             #[cfg(feature = "s")] curr_mode}
     }
 }
 
 pub trait DebugInterface {
-    fn inspect_regs(&self) -> [u32; 32];
+    fn inspect_regs(&self) -> [XReg; 32];
 
-    fn inspect_pc(&self) -> u32;
+    /// Raw bits, single-precision values are NaN-boxed when D is enabled
+    #[cfg(feature = "f")]
+    fn inspect_fregs(&self) -> [u64; 32];
+
+    fn inspect_pc(&self) -> XReg;
 
     #[cfg(feature = "zicsr")]
-    fn inspect_csrs(&self) -> Vec<(String, u32)>;
+    fn inspect_csrs(&self) -> Vec<(String, XReg)>;
 
-    fn inspect_bus(&self, start: u32, len: usize) -> Vec<u8>;
+    fn inspect_bus(&self, start: XReg, len: usize) -> Vec<u8>;
 
     fn get_info(&self) -> MachineInfo;
 }
\ No newline at end of file
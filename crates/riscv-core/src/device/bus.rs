@@ -1,115 +1,608 @@
-use crate::Result;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{RiscVError, Result, StdResult};
 use crate::core::access::{Access, Physical};
+use crate::core::{BankKind, MachineConfig, XReg};
 use super::Device;
+#[cfg(feature = "a")]
+use super::MAX_HARTS;
+use super::clint::{Clint, CLINT_SIZE, TimeSource};
+use super::fdt::Fdt;
 use super::memory::{Memory, PAGE_SIZE};
-use super::uart::Uart;
+use super::mmio::{MmioDevice, MmioRegion};
+use super::plic::{Plic, PLIC_SIZE, PLIC_SOURCES};
+use super::pma::{AmoClass, Pma, WIDTH_B, WIDTH_D, WIDTH_W};
+use super::rom::{Flash, Rom, BOOT_DTB, BOOT_ENTRY, BOOT_ROM_SIZE, FLASH_CTRL_SIZE};
+use super::uart::{Uart, UartInput, UartOutput, UART_CLOCK_FREQ, UART_IRQ};
 
 use MappedDevice::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum MappedDevice {
+    Clint,
+    Plic,
     Uart,
     Ram,
+    Bank(usize),
+    FlashCtrl(usize),
+    Mmio(usize),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+// One entry of the memory map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Region {
+    base: XReg,
+    // Last address, inclusive
+    end: XReg,
+    device: MappedDevice,
+    pma: Pma,
+}
+
+impl Region {
+    fn contains(&self, addr: XReg) -> bool {
+        (self.base..=self.end).contains(&addr)
+    }
+}
+
+// Memory without AMOs or LR/SC, flash turns stores away by itself unless it is being programmed
+const FLASH: Pma = Pma { amo: AmoClass::None, lrsc: false, ..Pma::MAIN_MEMORY };
+const ROM: Pma = Pma { writable: false, ..FLASH };
+
+// Memory besides DRAM
+#[derive(Debug, PartialEq, Eq)]
+enum Bank {
+    Ram(Memory),
+    Rom(Rom),
+    Flash(Flash),
+}
+
+impl Bank {
+    // Images get into every kind of memory
+    fn load(&mut self, access: Access<Physical>, data: &[u8]) -> Result<()> {
+        match self {
+            Bank::Ram(ram)     => ram.write_bytes(access, data.len(), data),
+            Bank::Rom(rom)     => rom.load(access, data),
+            Bank::Flash(flash) => flash.load(access, data),
+        }
+    }
+
+    fn device(&self) -> &dyn Device {
+        match self {
+            Bank::Ram(ram)     => ram,
+            Bank::Rom(rom)     => rom,
+            Bank::Flash(flash) => flash,
+        }
+    }
+
+    fn device_mut(&mut self) -> &mut dyn Device {
+        match self {
+            Bank::Ram(ram)     => ram,
+            Bank::Rom(rom)     => rom,
+            Bank::Flash(flash) => flash,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct SystemBus {
+    clint: Clint,
+    plic: Plic,
     uart: Uart,
     ram: Memory,
+    banks: Vec<Bank>,
+    // Index of the boot ROM in `banks`
+    boot: Option<usize>,
+    devices: Vec<MmioRegion>,
+    // DRAM first, then the other memory, the built-in devices and attached ones
+    regions: Vec<Region>,
+    reset_vector: XReg,
+    // LR/SC reservation of every hart, kept here so stores from any hart can see them
+    #[cfg(feature = "a")]
+    reservations: [Option<XReg>; MAX_HARTS],
+}
+
+/// One bus and its memory, shared by every hart of a machine
+pub type SharedBus = Rc<RefCell<SystemBus>>;
+
+pub const UART_BASE: XReg = 0x1000_0000;
+pub const UART_SIZE: XReg = 0x100;
+pub const DRAM_BASE_ADDR: XReg = 0x8000_0000;
+
+/// Bytes covered by one reservation, wide enough for `lr.d`
+#[cfg(feature = "a")]
+const RESERVATION_GRANULE: XReg = 8;
+
+// Whole pages that memory of `size` bytes takes in the address space
+fn memory_size(size: usize) -> StdResult<XReg, RiscVError> {
+    if size == 0 {
+        return Err(RiscVError::DeviceOverlap);
+    }
+    XReg::try_from(size.next_multiple_of(PAGE_SIZE)).map_err(|_| RiscVError::DeviceOverlap)
 }
 
-pub const UART_BASE: u32 = 0x1000_0000;
-pub const UART_END: u32 = 0x1000_00FF;
-pub const DRAM_BASE_ADDR: u32 = 0x8000_0000;
+impl Default for SystemBus {
+    fn default() -> Self {
+        SystemBus::new(&MachineConfig::default()).expect("The default memory map has no overlap")
+    }
+}
 
 impl SystemBus {
-    fn mapping(&self, access: &mut Access<Physical>) -> Result<MappedDevice> {
-        let addr = access.addr;
-        Ok(match addr {
-            UART_BASE..=UART_END => {
-               access.addr = addr - UART_BASE;
-               Uart
+    /// Bus with the memory map of `config`, fails if any two regions overlap
+    pub fn new(config: &MachineConfig) -> StdResult<Self, RiscVError> {
+        let ram_size = memory_size(config.dram_size)?;
+        let mut bus = SystemBus {
+            clint: Clint::default(),
+            plic: Plic::default(),
+            uart: Uart::default(),
+            ram: Memory::new(config.dram_size),
+            banks: Vec::new(),
+            boot: None,
+            devices: Vec::new(),
+            regions: Vec::new(),
+            reset_vector: config.reset_vector.or(config.boot_rom).unwrap_or(config.dram_base),
+            #[cfg(feature = "a")]
+            reservations: [None; MAX_HARTS],
+        };
+
+        bus.map(config.dram_base, ram_size, Ram, Pma::MAIN_MEMORY)?;
+        for bank in config.banks.iter() {
+            let (idx, size) = (bus.banks.len(), memory_size(bank.size)?);
+            match bank.kind {
+                BankKind::Ram => {
+                    bus.map(bank.base, size, Bank(idx), Pma::MAIN_MEMORY)?;
+                    bus.banks.push(Bank::Ram(Memory::new(bank.size)));
+                },
+                BankKind::Rom => {
+                    bus.map(bank.base, size, Bank(idx), ROM)?;
+                    bus.banks.push(Bank::Rom(Rom::new(bank.size)));
+                },
+                BankKind::Flash { ctrl_base } => {
+                    bus.map(bank.base, size, Bank(idx), FLASH)?;
+                    bus.map(ctrl_base, FLASH_CTRL_SIZE, FlashCtrl(idx), Pma::io(WIDTH_W))?;
+                    bus.banks.push(Bank::Flash(Flash::new(bank.size)));
+                },
             }
-            DRAM_BASE_ADDR.. => {
-                let ram_addr = addr - DRAM_BASE_ADDR;
-                if ram_addr as usize >= self.ram.size {
-                    return Err(access.into_access_exception())
-                } else {
-                    access.addr = ram_addr;
-                    Ram
-                }
+        }
+        if let Some(base) = config.boot_rom {
+            let idx = bus.banks.len();
+            bus.map(base, BOOT_ROM_SIZE as XReg, Bank(idx), ROM)?;
+            bus.banks.push(Bank::Rom(Rom::boot(config.dram_base)));
+            bus.boot = Some(idx);
+        }
+        if let Some(base) = config.clint_base {
+            bus.map(base, CLINT_SIZE, Clint, Pma::io(WIDTH_W | WIDTH_D))?;
+        }
+        if let Some(base) = config.plic_base {
+            bus.map(base, PLIC_SIZE, Plic, Pma::io(WIDTH_W))?;
+        }
+        if let Some(base) = config.uart_base {
+            bus.map(base, UART_SIZE, Uart, Pma::io(WIDTH_B))?;
+        }
+        Ok(bus)
+    }
+
+    // Last address of `base..base + size` if nothing is mapped there yet
+    fn free_range(&self, base: XReg, size: XReg) -> StdResult<XReg, RiscVError> {
+        let end = size.checked_sub(1)
+            .and_then(|last| base.checked_add(last))
+            .ok_or(RiscVError::DeviceOverlap)?;
+        if self.regions.iter().any(|region| base <= region.end && region.base <= end) {
+            return Err(RiscVError::DeviceOverlap);
+        }
+        Ok(end)
+    }
+
+    fn map(&mut self, base: XReg, size: XReg, device: MappedDevice, pma: Pma) -> StdResult<(), RiscVError> {
+        let end = self.free_range(base, size)?;
+        self.regions.push(Region { base, end, device, pma });
+        Ok(())
+    }
+
+    fn region(&self, addr: XReg) -> Option<&Region> {
+        self.regions.iter().find(|region| region.contains(addr))
+    }
+
+    fn mapping(&self, access: &mut Access<Physical>) -> Result<MappedDevice> {
+        match self.region(access.addr) {
+            Some(region) => {
+                access.addr -= region.base;
+                Ok(region.device)
             },
-            _ => return Err(access.into_access_exception()),
-        })
+            None => Err(access.into_access_exception()),
+        }
     }
 
-    pub fn read_u32(&self, access: Access<Physical>) -> Result<u32> { 
-        self.read_u32_bytes(access, 4, false)
+    /// Map `device` at `base..base + size`, driving PLIC source `irq` if given.
+    /// It must not overlap memory, a built-in device or another attached one
+    pub fn attach(&mut self, base: XReg, size: XReg, irq: Option<usize>, device: Box<dyn MmioDevice>) -> StdResult<(), RiscVError> {
+        let end = self.free_range(base, size)?;
+        let has_plic = self.regions.iter().any(|region| region.device == Plic);
+        if let Some(irq) = irq && (!has_plic || irq == 0 || irq >= PLIC_SOURCES || irq == UART_IRQ ||
+            self.devices.iter().any(|region| region.irq == Some(irq))) {
+            return Err(RiscVError::IrqUnavailable);
+        }
+
+        self.regions.push(Region { base, end, device: Mmio(self.devices.len()), pma: device.pma() });
+        self.devices.push(MmioRegion::new(base, end, irq, device));
+        Ok(())
+    }
+
+    /// Attributes of the region `addr` is in
+    #[cfg(feature = "a")]
+    pub fn pma(&self, addr: XReg) -> Pma {
+        self.region(addr).map_or(Pma::default(), |region| region.pma)
     }
 
-    pub fn read_u32_bytes(&self, mut access: Access<Physical>, len: usize, is_signed: bool) -> Result<u32> {
+    /// Checked after PMP, a fault carries the physical address. An access that runs past the end
+    /// of its first byte's region faults, whatever the next region allows
+    pub fn pma_check(&self, access: Access<Physical>, size: usize) -> Result<()> {
+        let last = access.addr.checked_add(size.max(1) as XReg - 1);
+        self.region(access.addr)
+            .filter(|region| last.is_some_and(|last| region.contains(last)))
+            .map_or(Pma::default(), |region| region.pma)
+            .check(access, size)
+    }
+
+    /// Instruction fetch and page table walks stay 32 bits wide on every `XLEN`
+    pub fn read_u32(&self, access: Access<Physical>) -> Result<u32> { 
         let mut four_bytes = [0; 4];
+        self.read_bytes(access, 4, &mut four_bytes)?;
+        Ok(u32::from_le_bytes(four_bytes))
+    }
+
+    /// Up to `XLEN / 8` bytes, zero or sign extended to a whole register
+    #[cfg(any(feature = "a", feature = "s"))]
+    pub fn read_xreg_bytes(&self, mut access: Access<Physical>, len: usize, is_signed: bool) -> Result<XReg> {
+        let mut bytes = [0; size_of::<XReg>()];
 
         match self.mapping(&mut access)? {
-            Uart => self.uart.read_bytes(access, len, &mut four_bytes[..len])?,
-            Ram  => self.ram.read_bytes(access, len, &mut four_bytes[..len])?,
+            Clint => self.clint.read_bytes(access, len, &mut bytes[..len])?,
+            Plic  => self.plic.read_bytes(access, len, &mut bytes[..len])?,
+            Uart  => self.uart.read_bytes(access, len, &mut bytes[..len])?,
+            Ram  => self.ram.read_bytes(access, len, &mut bytes[..len])?,
+            Bank(idx) => self.banks[idx].device().read_bytes(access, len, &mut bytes[..len])?,
+            FlashCtrl(idx) => self.flash(idx).read_ctrl(access, len, &mut bytes[..len])?,
+            Mmio(idx) => self.devices[idx].read_bytes(access, len, &mut bytes[..len])?,
         }
         
-        if is_signed && (four_bytes[len - 1] & 0x80 != 0) {
-            four_bytes[len..].fill(0xff);
+        if is_signed && (bytes[len - 1] & 0x80 != 0) {
+            bytes[len..].fill(0xff);
         }
 
-        Ok(u32::from_le_bytes(four_bytes))
+        Ok(XReg::from_le_bytes(bytes))
     }
 
     #[allow(unused)]
     pub fn write_u32(&mut self, access: Access<Physical>, data: u32) -> Result<()> {
-        self.write_u32_bytes(access, data, 4)
+        self.write_bytes(access, 4, &data.to_le_bytes())
     }
 
-    pub fn write_u32_bytes(&mut self, mut access: Access<Physical>, data: u32, len: usize) -> Result<()> {
+    #[cfg(any(feature = "a", feature = "s"))]
+    pub fn write_xreg_bytes(&mut self, mut access: Access<Physical>, data: XReg, len: usize) -> Result<()> {
+        #[cfg(feature = "a")]
+        self.snoop(access.addr, len);
         match self.mapping(&mut access)? {
-            Uart => self.uart.write_bytes(access, len, &data.to_le_bytes())?,
+            Clint => self.clint.write_bytes(access, len, &data.to_le_bytes())?,
+            Plic  => self.plic.write_bytes(access, len, &data.to_le_bytes())?,
+            Uart  => self.uart.write_bytes(access, len, &data.to_le_bytes())?,
             Ram  => self.ram.write_bytes(access, len, &data.to_le_bytes())?,
+            Bank(idx) => self.banks[idx].device_mut().write_bytes(access, len, &data.to_le_bytes())?,
+            FlashCtrl(idx) => self.flash_mut(idx).write_ctrl(access, len, &data.to_le_bytes())?,
+            Mmio(idx) => self.devices[idx].write_bytes(access, len, &data.to_le_bytes())?,
         }
         Ok(())
     }
 
-    pub fn ram_info(&self) -> (usize, u32, usize) {
-        (self.ram.size, DRAM_BASE_ADDR, PAGE_SIZE)
+    /// Record a load-reserved of `hart` on `addr`
+    #[cfg(feature = "a")]
+    pub fn reserve(&mut self, hart: usize, addr: XReg) {
+        self.reservations[hart] = Some(addr);
+    }
+
+    /// Reservation of `hart`, which a store-conditional always consumes
+    #[cfg(feature = "a")]
+    pub fn take_reservation(&mut self, hart: usize) -> Option<XReg> {
+        self.reservations[hart].take()
+    }
+
+    /// A write that touches a reserved granule breaks the reservation, whichever hart made it
+    #[cfg(feature = "a")]
+    fn snoop(&mut self, addr: XReg, len: usize) {
+        let end = addr.saturating_add(len as XReg);
+        for reservation in self.reservations.iter_mut() {
+            if let Some(reserved) = *reservation {
+                let granule = reserved & !(RESERVATION_GRANULE - 1);
+                if addr < granule + RESERVATION_GRANULE && granule < end {
+                    *reservation = None;
+                }
+            }
+        }
+    }
+
+    pub fn ram_info(&self) -> (usize, XReg, usize) {
+        let dram_base = self.regions.iter().find(|region| region.device == Ram).map_or(0, |region| region.base);
+        (self.ram.size, dram_base, PAGE_SIZE)
     }
 
+    /// Where harts start after a reset
+    pub fn reset_vector(&self) -> XReg {
+        self.reset_vector
+    }
+
+    pub fn has_boot_rom(&self) -> bool {
+        self.boot.is_some()
+    }
+
+    /// Payload entry the boot ROM jumps to, the DRAM base at first
+    pub fn set_boot_entry(&mut self, entry: XReg) {
+        self.set_boot_word(BOOT_ENTRY, entry);
+    }
+
+    /// Device tree address the boot ROM passes in `a1`
+    pub fn set_boot_dtb(&mut self, dtb: XReg) {
+        self.set_boot_word(BOOT_DTB, dtb);
+    }
+
+    fn set_boot_word(&mut self, offset: XReg, data: XReg) {
+        if let Some(idx) = self.boot && let Bank::Rom(rom) = &mut self.banks[idx] {
+            rom.set_word(offset, data);
+        }
+    }
+
+    /// Write an image, into ROM and flash as well
+    pub fn load(&mut self, access: Access<Physical>, data: &[u8]) -> Result<()> {
+        let mut mapped = access;
+        match self.mapping(&mut mapped)? {
+            Bank(idx) => self.banks[idx].load(mapped, data),
+            _ => self.write_bytes(access, data.len(), data),
+        }
+    }
+
+    /// ROM and flash keep their contents
     pub fn reset_ram(&mut self) {
         self.ram.reset();
+        for bank in self.banks.iter_mut() {
+            if let Bank::Ram(ram) = bank {
+                ram.reset();
+            }
+        }
+    }
+
+    #[cfg(feature = "zicsr")]
+    pub fn clint(&self) -> &Clint {
+        &self.clint
+    }
+
+    #[cfg(feature = "zicsr")]
+    pub fn plic(&self) -> &Plic {
+        &self.plic
+    }
+
+    /// Feed device interrupt levels into the PLIC gateways
+    fn update_irq_sources(&mut self) {
+        self.plic.set_level(UART_IRQ, self.uart.irq_pending());
+        for region in &self.devices {
+            if let Some(irq) = region.irq {
+                self.plic.set_level(irq, region.irq_pending());
+            }
+        }
+    }
+
+    pub fn set_time_source(&mut self, source: TimeSource) {
+        self.clint.set_time_source(source);
+    }
+
+    pub fn uart_input(&self) -> UartInput {
+        self.uart.input()
+    }
+
+    pub fn set_uart_output(&mut self, output: UartOutput) {
+        self.uart.set_output(output);
+    }
+
+    /// Advance devices by one retired instruction
+    pub fn tick(&mut self) {
+        self.clint.tick();
+        self.uart.tick();
+        self.devices.iter_mut().for_each(MmioRegion::tick);
+        self.update_irq_sources();
+    }
+
+    /// Advance devices while every hart is waiting for an interrupt
+    #[cfg(feature = "zicsr")]
+    pub fn idle(&mut self) {
+        self.clint.idle();
+        self.uart.tick();
+        self.devices.iter_mut().for_each(MmioRegion::tick);
+        self.update_irq_sources();
+    }
+
+    pub fn reset_devices(&mut self) {
+        self.clint.reset();
+        self.plic.reset();
+        self.uart.reset();
+        self.devices.iter_mut().for_each(MmioRegion::reset);
+        for bank in self.banks.iter_mut() {
+            if let Bank::Flash(flash) = bank {
+                flash.reset();
+            }
+        }
+        #[cfg(feature = "a")]
+        self.reservations.fill(None);
+    }
+
+    fn is_ram(&self, device: MappedDevice) -> bool {
+        match device {
+            Ram => true,
+            Bank(idx) => matches!(self.banks[idx], Bank::Ram(_)),
+            _ => false,
+        }
+    }
+
+    /// A `memory` node for DRAM and every RAM bank
+    pub fn fdt_memory(&self, fdt: &mut Fdt) {
+        for region in self.regions.iter().filter(|region| self.is_ram(region.device)) {
+            fdt.begin_node(&format!("memory@{:x}", region.base));
+            fdt.prop_str("device_type", "memory");
+            fdt.prop_reg(region.base, region.end - region.base + 1);
+            fdt.end_node();
+        }
+    }
+
+    /// The `soc` node with the CLINT, PLIC, UART and attached devices. `intc` has the phandle
+    /// of every hart's interrupt controller, and `plic` is the one the PLIC gets
+    pub fn fdt_soc(&self, fdt: &mut Fdt, intc: &[u32], plic: u32) {
+        let has_plic = self.regions.iter().any(|region| region.device == Plic);
+        let irq = |fdt: &mut Fdt, irq: usize| if has_plic {
+            fdt.prop_u32("interrupt-parent", plic);
+            fdt.prop_u32("interrupts", irq as u32);
+        };
+
+        fdt.begin_node("soc");
+        fdt.prop_u32("#address-cells", 2);
+        fdt.prop_u32("#size-cells", 2);
+        fdt.prop_str("compatible", "simple-bus");
+        fdt.prop_empty("ranges");
+        for region in &self.regions {
+            let (base, size) = (region.base, region.end - region.base + 1);
+            match region.device {
+                Clint => {
+                    fdt.begin_node(&format!("clint@{:x}", base));
+                    fdt.prop_strs("compatible", &["sifive,clint0", "riscv,clint0"]);
+                    fdt.prop_reg(base, size);
+                    // Machine software and timer interrupts
+                    let lines: Vec<u32> = intc.iter().flat_map(|&hart| [hart, 3, hart, 7]).collect();
+                    fdt.prop_cells("interrupts-extended", &lines);
+                    fdt.end_node();
+                },
+                Plic => {
+                    fdt.begin_node(&format!("plic@{:x}", base));
+                    fdt.prop_strs("compatible", &["sifive,plic-1.0.0", "riscv,plic0"]);
+                    fdt.prop_reg(base, size);
+                    fdt.prop_u32("#address-cells", 0);
+                    fdt.prop_u32("#interrupt-cells", 1);
+                    fdt.prop_empty("interrupt-controller");
+                    fdt.prop_u32("riscv,ndev", PLIC_SOURCES as u32 - 1);
+                    // Machine then supervisor external interrupt context of each hart
+                    let lines: Vec<u32> = intc.iter().flat_map(|&hart| [hart, 11, hart, 9]).collect();
+                    fdt.prop_cells("interrupts-extended", &lines);
+                    fdt.prop_u32("phandle", plic);
+                    fdt.end_node();
+                },
+                Uart => {
+                    fdt.begin_node(&format!("serial@{:x}", base));
+                    fdt.prop_str("compatible", "ns16550a");
+                    fdt.prop_reg(base, size);
+                    fdt.prop_u32("clock-frequency", UART_CLOCK_FREQ);
+                    irq(fdt, UART_IRQ);
+                    fdt.end_node();
+                },
+                Mmio(idx) => {
+                    let device = &self.devices[idx];
+                    fdt.begin_node(&format!("mmio@{:x}", base));
+                    if let Some(compatible) = &device.compatible {
+                        fdt.prop_str("compatible", compatible);
+                    }
+                    fdt.prop_reg(base, size);
+                    if let Some(line) = device.irq {
+                        irq(fdt, line);
+                    }
+                    fdt.end_node();
+                },
+                _ => {},
+            }
+        }
+        fdt.end_node();
+    }
+
+    /// Path of the UART node for `/chosen/stdout-path`
+    pub fn fdt_stdout(&self) -> Option<String> {
+        self.regions.iter()
+            .find(|region| region.device == Uart)
+            .map(|region| format!("/soc/serial@{:x}", region.base))
+    }
+
+    fn flash(&self, idx: usize) -> &Flash {
+        match &self.banks[idx] {
+            Bank::Flash(flash) => flash,
+            _ => unreachable!("Only flash banks have a controller"),
+        }
+    }
+
+    fn flash_mut(&mut self, idx: usize) -> &mut Flash {
+        match &mut self.banks[idx] {
+            Bank::Flash(flash) => flash,
+            _ => unreachable!("Only flash banks have a controller"),
+        }
     }
 }
 
 impl Device for SystemBus {
     fn read_byte(&self, mut access: Access<Physical>) -> Result<u8> {
         match self.mapping(&mut access)? {
-            Uart => self.uart.read_byte(access),
+            Clint => self.clint.read_byte(access),
+            Plic  => self.plic.read_byte(access),
+            Uart  => self.uart.read_byte(access),
             Ram  => self.ram.read_byte(access),
+            Bank(idx) => self.banks[idx].device().read_byte(access),
+            FlashCtrl(idx) => {
+                let mut byte = [0; 1];
+                self.flash(idx).read_ctrl(access, 1, &mut byte).map(|_| byte[0])
+            },
+            Mmio(idx) => self.devices[idx].read_byte(access),
         }
     }
 
     fn write_byte(&mut self, mut access: Access<Physical>, data: u8) -> Result<()> {
+        #[cfg(feature = "a")]
+        self.snoop(access.addr, 1);
         match self.mapping(&mut access)? {
-            Uart => self.uart.write_byte(access, data),
+            Clint => self.clint.write_byte(access, data),
+            Plic  => self.plic.write_byte(access, data),
+            Uart  => self.uart.write_byte(access, data),
             Ram  => self.ram.write_byte(access, data),
+            Bank(idx) => self.banks[idx].device_mut().write_byte(access, data),
+            FlashCtrl(idx) => self.flash_mut(idx).write_ctrl(access, 1, &[data]),
+            Mmio(idx) => self.devices[idx].write_byte(access, data),
         }
     }
 
     fn read_bytes(&self, mut access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
         match self.mapping(&mut access)? {
-            Uart => self.uart.read_bytes(access, size, des),
+            Clint => self.clint.read_bytes(access, size, des),
+            Plic  => self.plic.read_bytes(access, size, des),
+            Uart  => self.uart.read_bytes(access, size, des),
             Ram  => self.ram.read_bytes(access, size, des),
+            Bank(idx) => self.banks[idx].device().read_bytes(access, size, des),
+            FlashCtrl(idx) => self.flash(idx).read_ctrl(access, size, des),
+            Mmio(idx) => self.devices[idx].read_bytes(access, size, des),
         }
     }
 
     fn write_bytes(&mut self, mut access: Access<Physical>, size: usize, src: &[u8]) -> Result<()> {
+        #[cfg(feature = "a")]
+        self.snoop(access.addr, size);
         match self.mapping(&mut access)? {
-            Uart => self.uart.write_bytes(access, size, src),
+            Clint => self.clint.write_bytes(access, size, src),
+            Plic  => self.plic.write_bytes(access, size, src),
+            Uart  => self.uart.write_bytes(access, size, src),
             Ram  => self.ram.write_bytes(access, size, src),
+            Bank(idx) => self.banks[idx].device_mut().write_bytes(access, size, src),
+            FlashCtrl(idx) => self.flash_mut(idx).write_ctrl(access, size, src),
+            Mmio(idx) => self.devices[idx].write_bytes(access, size, src),
+        }
+    }
+
+    fn peek_bytes(&self, mut access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
+        match self.mapping(&mut access)? {
+            Clint => self.clint.peek_bytes(access, size, des),
+            Plic  => self.plic.peek_bytes(access, size, des),
+            Uart  => self.uart.peek_bytes(access, size, des),
+            Ram  => self.ram.peek_bytes(access, size, des),
+            Bank(idx) => self.banks[idx].device().peek_bytes(access, size, des),
+            FlashCtrl(idx) => self.flash(idx).read_ctrl(access, size, des),
+            Mmio(idx) => self.devices[idx].peek_bytes(access, size, des),
         }
     }
 }
@@ -0,0 +1,265 @@
+//! Core Local Interruptor (CLINT), SiFive compatible layout
+
+use std::time::{Duration, Instant};
+
+use crate::Result;
+use crate::core::access::{Access, Physical};
+use crate::core::XReg;
+use super::{Device, MAX_HARTS};
+
+pub const CLINT_BASE: XReg = 0x0200_0000;
+pub const CLINT_SIZE: XReg = 0x1_0000;
+
+const MSIP: XReg = 0x0000;
+const MSIP_END: XReg = MSIP + 4 * MAX_HARTS as XReg;
+const MTIMECMP: XReg = 0x4000;
+const MTIMECMP_END: XReg = MTIMECMP + 8 * MAX_HARTS as XReg;
+const MTIME: XReg = 0xBFF8;
+
+/// `mtime` frequency when driven by host wall-clock (10 MHz)
+pub const WALL_CLOCK_FREQ: u64 = 10_000_000;
+
+const NANOS_PER_TICK: u64 = 1_000_000_000 / WALL_CLOCK_FREQ;
+// Upper bound of one idle sleep, so host input is still polled
+#[cfg(feature = "zicsr")]
+const MAX_IDLE_NANOS: u64 = 1_000_000;
+
+/// Where `mtime` comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeSource {
+    /// One tick per retired instruction. Deterministic
+    #[default]
+    Instret,
+    /// Host wall-clock at `WALL_CLOCK_FREQ`
+    WallClock,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clint {
+    // One `msip` word and one `mtimecmp` per hart, `mtime` is shared
+    msip: [bool; MAX_HARTS],
+    mtimecmp: [u64; MAX_HARTS],
+    mtime: u64,
+    source: TimeSource,
+    // Host instant that `mtime` was last synced at, only used by `WallClock`
+    epoch: Instant,
+}
+
+impl Default for Clint {
+    fn default() -> Self {
+        Clint {
+            msip: [false; MAX_HARTS],
+            mtimecmp: [u64::MAX; MAX_HARTS],
+            mtime: 0,
+            source: TimeSource::default(),
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl Clint {
+    pub fn set_time_source(&mut self, source: TimeSource) {
+        self.sync();
+        self.source = source;
+        self.epoch = Instant::now();
+    }
+
+    /// Advance `mtime` by one retired instruction
+    pub fn tick(&mut self) {
+        match self.source {
+            TimeSource::Instret   => self.mtime = self.mtime.wrapping_add(1),
+            TimeSource::WallClock => self.sync(),
+        }
+    }
+
+    #[cfg(feature = "zicsr")]
+    pub fn mtime(&self) -> u64 {
+        self.mtime
+    }
+
+    #[cfg(feature = "zicsr")]
+    pub fn msip_pending(&self, hart: usize) -> bool {
+        self.msip[hart]
+    }
+
+    #[cfg(feature = "zicsr")]
+    pub fn mtip_pending(&self, hart: usize) -> bool {
+        self.mtime >= self.mtimecmp[hart]
+    }
+
+    /// Skip forward to the next timer event while every hart is waiting
+    #[cfg(feature = "zicsr")]
+    pub fn idle(&mut self) {
+        let next = self.mtimecmp.iter()
+            .copied()
+            .filter(|cmp| *cmp > self.mtime)
+            .min()
+            .unwrap_or(u64::MAX);
+
+        match self.source {
+            TimeSource::Instret => {
+                // `u64::MAX` is the usual way to say "no timer"
+                if next != u64::MAX {
+                    self.mtime = next;
+                } else {
+                    self.mtime = self.mtime.wrapping_add(1);
+                }
+            },
+            TimeSource::WallClock => {
+                let remain = next.saturating_sub(self.mtime);
+                let nanos = remain.saturating_mul(NANOS_PER_TICK).min(MAX_IDLE_NANOS);
+                std::thread::sleep(Duration::from_nanos(nanos));
+                self.sync();
+            },
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Clint { source: self.source, ..Default::default() };
+    }
+
+    fn sync(&mut self) {
+        if self.source == TimeSource::WallClock {
+            let now = Instant::now();
+            let elapsed = now.duration_since(self.epoch).as_nanos();
+            let ticks = (elapsed * WALL_CLOCK_FREQ as u128 / 1_000_000_000) as u64;
+            if ticks > 0 {
+                self.mtime = self.mtime.wrapping_add(ticks);
+                // Keep the remainder so slow polling does not lose time
+                self.epoch += Duration::from_nanos(ticks * NANOS_PER_TICK);
+            }
+        }
+    }
+}
+
+fn get_byte(reg: u64, offset: XReg) -> u8 {
+    (reg >> (offset * 8)) as u8
+}
+
+fn set_byte(reg: &mut u64, offset: XReg, data: u8) {
+    let shift = offset * 8;
+    *reg = (*reg & !(0xff << shift)) | ((data as u64) << shift);
+}
+
+impl Device for Clint {
+    fn read_byte(&self, access: Access<Physical>) -> Result<u8> {
+        let addr = access.addr;
+        Ok(match addr {
+            // Only bit 0 of each word is implemented
+            MSIP..MSIP_END if (addr - MSIP).is_multiple_of(4) => self.msip[((addr - MSIP) / 4) as usize] as u8,
+            MTIMECMP..MTIMECMP_END => {
+                let hart = ((addr - MTIMECMP) / 8) as usize;
+                get_byte(self.mtimecmp[hart], (addr - MTIMECMP) % 8)
+            },
+            MTIME..=0xBFFF => get_byte(self.mtime, addr - MTIME),
+            _ => 0,
+        })
+    }
+
+    fn write_byte(&mut self, access: Access<Physical>, data: u8) -> Result<()> {
+        let addr = access.addr;
+        match addr {
+            MSIP..MSIP_END if (addr - MSIP).is_multiple_of(4) => self.msip[((addr - MSIP) / 4) as usize] = data & 1 != 0,
+            MTIMECMP..MTIMECMP_END => {
+                let hart = ((addr - MTIMECMP) / 8) as usize;
+                set_byte(&mut self.mtimecmp[hart], (addr - MTIMECMP) % 8, data);
+            },
+            MTIME..=0xBFFF => {
+                self.sync();
+                set_byte(&mut self.mtime, addr - MTIME, data);
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn read_bytes(&self, mut access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
+        for byte in des.iter_mut().take(size) {
+            *byte = self.read_byte(access)?;
+            access.addr += 1;
+        }
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, mut access: Access<Physical>, size: usize, src: &[u8]) -> Result<()> {
+        for byte in src.iter().take(size) {
+            self.write_byte(access, *byte)?;
+            access.addr += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::access::AccessType;
+    use super::*;
+
+    fn access(addr: XReg) -> Access<Physical> {
+        Access::new(addr, AccessType::Load)
+    }
+
+    #[test]
+    fn test_mtime_instret() {
+        let mut clint = Clint::default();
+        clint.tick();
+        clint.tick();
+
+        let mut buf = [0; 4];
+        clint.read_bytes(access(MTIME), 4, &mut buf).unwrap();
+        assert_eq!(u32::from_le_bytes(buf), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "zicsr")]
+    fn test_mtimecmp_raises_mtip() {
+        let mut clint = Clint::default();
+        assert!(!clint.mtip_pending(0));
+
+        clint.write_bytes(access(MTIMECMP), 4, &3u32.to_le_bytes()).unwrap();
+        clint.write_bytes(access(MTIMECMP + 4), 4, &0u32.to_le_bytes()).unwrap();
+        assert_eq!(clint.mtimecmp[0], 3);
+
+        clint.tick();
+        clint.tick();
+        assert!(!clint.mtip_pending(0));
+        clint.tick();
+        assert!(clint.mtip_pending(0));
+
+        // Writing a new compare value clears the line
+        clint.write_bytes(access(MTIMECMP), 4, &100u32.to_le_bytes()).unwrap();
+        assert!(!clint.mtip_pending(0));
+    }
+
+    #[test]
+    #[cfg(feature = "zicsr")]
+    fn test_msip() {
+        let mut clint = Clint::default();
+        clint.write_bytes(access(MSIP), 4, &1u32.to_le_bytes()).unwrap();
+        assert!(clint.msip_pending(0));
+
+        clint.write_bytes(access(MSIP), 4, &0u32.to_le_bytes()).unwrap();
+        assert!(!clint.msip_pending(0));
+    }
+
+    #[test]
+    #[cfg(feature = "zicsr")]
+    fn test_per_hart_registers() {
+        let mut clint = Clint::default();
+        clint.write_bytes(access(MSIP + 4), 4, &1u32.to_le_bytes()).unwrap();
+        assert!(!clint.msip_pending(0));
+        assert!(clint.msip_pending(1));
+
+        clint.write_bytes(access(MTIMECMP + 8), 8, &2u64.to_le_bytes()).unwrap();
+        clint.tick();
+        clint.tick();
+        assert!(!clint.mtip_pending(0));
+        assert!(clint.mtip_pending(1));
+
+        // Idle jumps to the earliest timer of any hart
+        clint.write_bytes(access(MTIMECMP + 16), 8, &50u64.to_le_bytes()).unwrap();
+        clint.write_bytes(access(MTIMECMP), 8, &20u64.to_le_bytes()).unwrap();
+        clint.idle();
+        assert_eq!(clint.mtime(), 20);
+    }
+}
@@ -0,0 +1,170 @@
+//! Flattened device tree (FDT) blob writer, version 17
+
+use crate::core::XReg;
+
+const FDT_MAGIC: u32 = 0xd00d_feed;
+const FDT_VERSION: u32 = 17;
+const FDT_LAST_COMP_VERSION: u32 = 16;
+
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_END: u32 = 0x9;
+
+// Header is 10 words, followed by an empty memory reservation map
+const HEADER_SIZE: usize = 40;
+const RESERVE_MAP_SIZE: usize = 16;
+
+/// Builds the structure and strings blocks node by node
+#[derive(Debug, Clone, Default)]
+pub struct Fdt {
+    structs: Vec<u8>,
+    strings: Vec<u8>,
+    // Name and offset of every property name in `strings`
+    names: Vec<(String, u32)>,
+}
+
+impl Fdt {
+    fn word(&mut self, data: u32) {
+        self.structs.extend_from_slice(&data.to_be_bytes());
+    }
+
+    // Every token starts on a 4-byte boundary
+    fn bytes(&mut self, data: &[u8]) {
+        self.structs.extend_from_slice(data);
+        self.structs.resize(self.structs.len().next_multiple_of(4), 0);
+    }
+
+    fn name_offset(&mut self, name: &str) -> u32 {
+        if let Some(&(_, offset)) = self.names.iter().find(|(known, _)| known == name) {
+            return offset;
+        }
+        let offset = self.strings.len() as u32;
+        self.strings.extend_from_slice(name.as_bytes());
+        self.strings.push(0);
+        self.names.push((name.to_string(), offset));
+        offset
+    }
+
+    /// The root node has an empty name
+    pub fn begin_node(&mut self, name: &str) {
+        self.word(FDT_BEGIN_NODE);
+        let mut name = name.as_bytes().to_vec();
+        name.push(0);
+        self.bytes(&name);
+    }
+
+    pub fn end_node(&mut self) {
+        self.word(FDT_END_NODE);
+    }
+
+    pub fn prop(&mut self, name: &str, value: &[u8]) {
+        let offset = self.name_offset(name);
+        self.word(FDT_PROP);
+        self.word(value.len() as u32);
+        self.word(offset);
+        self.bytes(value);
+    }
+
+    pub fn prop_empty(&mut self, name: &str) {
+        self.prop(name, &[]);
+    }
+
+    pub fn prop_u32(&mut self, name: &str, value: u32) {
+        self.prop_cells(name, &[value]);
+    }
+
+    pub fn prop_cells(&mut self, name: &str, cells: &[u32]) {
+        let value: Vec<u8> = cells.iter().flat_map(|cell| cell.to_be_bytes()).collect();
+        self.prop(name, &value);
+    }
+
+    pub fn prop_str(&mut self, name: &str, value: &str) {
+        self.prop_strs(name, &[value]);
+    }
+
+    /// A string list, each one NUL terminated
+    pub fn prop_strs(&mut self, name: &str, values: &[&str]) {
+        let value: Vec<u8> = values.iter().flat_map(|value| value.bytes().chain([0])).collect();
+        self.prop(name, &value);
+    }
+
+    /// An address as two cells, whatever `XLEN` is
+    pub fn prop_addr(&mut self, name: &str, addr: XReg) {
+        self.prop_cells(name, &split(addr));
+    }
+
+    /// `reg` of a region with two address and two size cells
+    pub fn prop_reg(&mut self, base: XReg, size: XReg) {
+        let [base_hi, base_lo] = split(base);
+        let [size_hi, size_lo] = split(size);
+        self.prop_cells("reg", &[base_hi, base_lo, size_hi, size_lo]);
+    }
+
+    /// Header, empty reservation map, structure block and strings block
+    pub fn finish(mut self) -> Vec<u8> {
+        self.word(FDT_END);
+        let off_structs = HEADER_SIZE + RESERVE_MAP_SIZE;
+        let off_strings = off_structs + self.structs.len();
+        let total = off_strings + self.strings.len();
+
+        let header = [
+            FDT_MAGIC,
+            total as u32,
+            off_structs as u32,
+            off_strings as u32,
+            HEADER_SIZE as u32,
+            FDT_VERSION,
+            FDT_LAST_COMP_VERSION,
+            0, // boot_cpuid_phys
+            self.strings.len() as u32,
+            self.structs.len() as u32,
+        ];
+        let mut blob: Vec<u8> = header.iter().flat_map(|word| word.to_be_bytes()).collect();
+        blob.resize(off_structs, 0);
+        blob.extend_from_slice(&self.structs);
+        blob.extend_from_slice(&self.strings);
+        blob
+    }
+}
+
+#[allow(clippy::unnecessary_cast)]
+fn split(value: XReg) -> [u32; 2] {
+    [(value as u64 >> 32) as u32, value as u32]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(blob: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes(blob[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_blob_layout() {
+        let mut fdt = Fdt::default();
+        fdt.begin_node("");
+        fdt.prop_u32("#size-cells", 2);
+        fdt.begin_node("cpus");
+        fdt.prop_u32("#size-cells", 0);
+        fdt.end_node();
+        fdt.end_node();
+        let blob = fdt.finish();
+
+        assert_eq!(word(&blob, 0), FDT_MAGIC);
+        assert_eq!(word(&blob, 4) as usize, blob.len());
+        let off_structs = word(&blob, 8) as usize;
+        let off_strings = word(&blob, 12) as usize;
+        // The same property name is stored once
+        assert_eq!(&blob[off_strings..], b"#size-cells\0");
+
+        // Root node with an empty name, then its property
+        assert_eq!(word(&blob, off_structs), FDT_BEGIN_NODE);
+        assert_eq!(word(&blob, off_structs + 4), 0);
+        assert_eq!(word(&blob, off_structs + 8), FDT_PROP);
+        assert_eq!(word(&blob, off_structs + 12), 4);
+        assert_eq!(word(&blob, off_structs + 20), 2);
+        assert_eq!(word(&blob, off_strings - 4), FDT_END);
+    }
+}
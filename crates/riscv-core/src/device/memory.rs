@@ -4,6 +4,7 @@ mod page;
 
 use crate::Result;
 use crate::core::access::{Access, Physical};
+use crate::core::XReg;
 use super::Device;
 
 use page::Page;
@@ -45,11 +46,12 @@ impl Memory {
     }
 }
 
-const _2GB: usize = 2 * 1024 * 1024 * 1024;
+/// DRAM of the default memory map, 2 GB
+pub const DEFAULT_DRAM_SIZE: usize = 2 * 1024 * 1024 * 1024;
 
 impl Default for Memory {
     fn default() -> Self {
-        Self::new(_2GB)
+        Self::new(DEFAULT_DRAM_SIZE)
     }
 }
 
@@ -91,7 +93,7 @@ impl Device for Memory {
             }
 
             start += len;
-            access.addr += len as u32;
+            access.addr += len as XReg;
         }
         Ok(())
     }
@@ -114,7 +116,7 @@ impl Device for Memory {
             }
             
             start += len;
-            access.addr += len as u32;
+            access.addr += len as XReg;
         }
         Ok(())
     }
@@ -158,15 +160,16 @@ mod tests {
     use crate::Exception;
     use crate::core::access::{Access, AccessType};
     use crate::device::Device;
-    use crate::device::memory::{Memory, _2GB};
+    use crate::device::memory::{Memory, DEFAULT_DRAM_SIZE};
     use crate::device::memory::page::PAGE_SIZE;
+    use crate::XReg;
     
     #[test]
     fn test_initalization() {
-        let mem = Memory::new(_2GB);
+        let mem = Memory::new(DEFAULT_DRAM_SIZE);
 
-        assert_eq!(mem.size, _2GB);
-        assert_eq!(mem.pages.len(), _2GB / PAGE_SIZE);
+        assert_eq!(mem.size, DEFAULT_DRAM_SIZE);
+        assert_eq!(mem.pages.len(), DEFAULT_DRAM_SIZE / PAGE_SIZE);
         assert!(mem.pages.iter().all(|p| p.is_none()));
     }
 
@@ -194,7 +197,7 @@ mod tests {
         let addr = PAGE_SIZE - 2;
         let data = [0x11, 0x22, 0x33, 0x44];
         
-        let access = Access::new(addr as u32, AccessType::Store);
+        let access = Access::new(addr as XReg, AccessType::Store);
         
         assert!(mem.write_bytes(access, 4, &data).is_ok());
         
@@ -0,0 +1,127 @@
+//! User-defined memory mapped devices
+
+use std::cell::RefCell;
+
+use crate::{Exception, Result};
+use crate::core::access::{Access, Physical};
+use crate::core::XReg;
+use super::Device;
+use super::pma::{Pma, WIDTH_W};
+
+/// The device refused an access, the hart gets an access fault
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MmioFault;
+
+/// A peripheral attached with `RiscV::attach_device`. Offsets are relative to its base address.
+/// Loads and stores of the harts already passed the region's PMA check
+pub trait MmioDevice {
+    fn read(&mut self, offset: XReg, data: &mut [u8]) -> std::result::Result<(), MmioFault>;
+
+    fn write(&mut self, offset: XReg, data: &[u8]) -> std::result::Result<(), MmioFault>;
+
+    /// Read for a debugger, which must not change the device. Default refuses, so it shows zeros
+    fn peek(&self, _offset: XReg, _data: &mut [u8]) -> std::result::Result<(), MmioFault> {
+        Err(MmioFault)
+    }
+
+    /// Attributes of the whole region, read once when it is attached. Default is 4-byte registers
+    fn pma(&self) -> Pma {
+        Pma::io(WIDTH_W)
+    }
+
+    /// `compatible` of its device tree node, read once when it is attached
+    fn compatible(&self) -> Option<&str> {
+        None
+    }
+
+    /// Advance by one retired instruction, or one idle step while every hart waits
+    fn tick(&mut self) {}
+
+    /// Level of the interrupt line, if the device was attached with a PLIC source
+    fn irq_pending(&self) -> bool {
+        false
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// An attached device and where it lives
+pub struct MmioRegion {
+    pub base: XReg,
+    /// Last address, inclusive
+    pub end: XReg,
+    pub irq: Option<usize>,
+    pub compatible: Option<String>,
+    // Reads can have side effects, but the bus reads through `&self`
+    device: RefCell<Box<dyn MmioDevice>>,
+}
+
+impl MmioRegion {
+    pub fn new(base: XReg, end: XReg, irq: Option<usize>, device: Box<dyn MmioDevice>) -> Self {
+        let compatible = device.compatible().map(str::to_string);
+        MmioRegion { base, end, irq, compatible, device: RefCell::new(device) }
+    }
+
+    pub fn tick(&mut self) {
+        self.device.get_mut().tick();
+    }
+
+    pub fn irq_pending(&self) -> bool {
+        self.device.borrow().irq_pending()
+    }
+
+    pub fn reset(&mut self) {
+        self.device.get_mut().reset();
+    }
+
+    /// The bus hands over offsets, a fault carries the physical address again
+    fn fault(&self, access: Access<Physical>) -> Exception {
+        Access::<Physical>::new(self.base + access.addr, access.kind).into_access_exception()
+    }
+}
+
+impl std::fmt::Debug for MmioRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MmioRegion")
+            .field("base", &self.base)
+            .field("end", &self.end)
+            .field("irq", &self.irq)
+            .finish_non_exhaustive()
+    }
+}
+
+// Two regions are the same when they sit at the same place, whatever the device does
+impl PartialEq for MmioRegion {
+    fn eq(&self, other: &Self) -> bool {
+        (self.base, self.end, self.irq) == (other.base, other.end, other.irq)
+    }
+}
+
+impl Eq for MmioRegion {}
+
+impl Device for MmioRegion {
+    fn read_byte(&self, access: Access<Physical>) -> Result<u8> {
+        let mut byte = [0; 1];
+        self.read_bytes(access, 1, &mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn write_byte(&mut self, access: Access<Physical>, data: u8) -> Result<()> {
+        self.write_bytes(access, 1, &[data])
+    }
+
+    fn read_bytes(&self, access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
+        self.device.borrow_mut().read(access.addr, &mut des[..size])
+            .map_err(|_| self.fault(access))
+    }
+
+    fn write_bytes(&mut self, access: Access<Physical>, size: usize, src: &[u8]) -> Result<()> {
+        self.device.get_mut().write(access.addr, &src[..size])
+            .map_err(|_| self.fault(access))
+    }
+
+    fn peek_bytes(&self, access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
+        self.device.borrow().peek(access.addr, &mut des[..size])
+            .map_err(|_| self.fault(access))
+    }
+}
@@ -1,10 +1,19 @@
 pub mod bus;
+pub mod clint;
+pub mod fdt;
 pub mod memory;
+pub mod mmio;
+pub mod plic;
+pub mod pma;
+pub mod rom;
 pub mod uart;
 
 use crate::core::access::{Access, Physical};
 use crate::Exception;
 
+/// Harts a machine can have, sized by the CLINT and PLIC register blocks
+pub const MAX_HARTS: usize = 8;
+
 pub trait Device {
     fn read_byte(&self, access: Access<Physical>) -> Result<u8, Exception>;
 
@@ -13,4 +22,10 @@ pub trait Device {
     fn read_bytes(&self, access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<(), Exception>;
 
     fn write_bytes(&mut self, access: Access<Physical>, size: usize, src: &[u8]) -> Result<(), Exception>;
+
+    /// Read for a debugger, without the side effects a hart's read has. Default is `read_bytes`,
+    /// for devices whose reads have none
+    fn peek_bytes(&self, access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<(), Exception> {
+        self.read_bytes(access, size, des)
+    }
 }
@@ -0,0 +1,318 @@
+//! Platform-Level Interrupt Controller (PLIC), SiFive / QEMU virt compatible layout
+
+use std::cell::Cell;
+
+use crate::Result;
+use crate::core::access::{Access, Physical};
+use crate::core::XReg;
+use super::{Device, MAX_HARTS};
+
+pub const PLIC_BASE: XReg = 0x0C00_0000;
+pub const PLIC_SIZE: XReg = 0x400_0000;
+
+/// Source 0 is reserved, so 1..PLIC_SOURCES are usable
+pub const PLIC_SOURCES: usize = 32;
+/// Each hart has two contexts, context `2n` is hart n M-mode, context `2n + 1` is hart n S-mode
+pub const HART_CONTEXTS: usize = 2;
+pub const PLIC_CONTEXTS: usize = HART_CONTEXTS * MAX_HARTS;
+
+#[cfg(feature = "zicsr")]
+pub const M_CONTEXT: usize = 0;
+#[cfg(feature = "s")]
+pub const S_CONTEXT: usize = 1;
+
+const PRIORITY_BASE: XReg = 0x00_0000;
+const PENDING_BASE: XReg = 0x00_1000;
+const ENABLE_BASE: XReg = 0x00_2000;
+const ENABLE_STRIDE: XReg = 0x80;
+const CONTEXT_BASE: XReg = 0x20_0000;
+const CONTEXT_STRIDE: XReg = 0x1000;
+
+const PRIORITY_MASK: u32 = 0x7;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Plic {
+    priority: [u32; PLIC_SOURCES],
+    // Claim is a side effect of a read, so the gateway state lives in `Cell`
+    pending: Cell<u32>,
+    claimed: Cell<u32>,
+    levels: u32,
+    enable: [u32; PLIC_CONTEXTS],
+    threshold: [u32; PLIC_CONTEXTS],
+}
+
+enum PlicReg {
+    Priority(usize),
+    Pending,
+    Enable(usize),
+    Threshold(usize),
+    Claim(usize),
+    Reserved,
+}
+
+impl Plic {
+    /// Level of interrupt source `src`. A high level becomes pending unless it is already claimed
+    pub fn set_level(&mut self, src: usize, level: bool) {
+        if src == 0 || src >= PLIC_SOURCES {
+            return;
+        }
+        let mask = 1 << src;
+
+        if level {
+            self.levels |= mask;
+        } else {
+            self.levels &= !mask;
+        }
+        self.update_gateway(mask);
+    }
+
+    /// Whether `ctx` has an enabled pending source above its threshold
+    #[cfg(feature = "zicsr")]
+    pub fn irq_pending(&self, ctx: usize) -> bool {
+        self.best_source(ctx).is_some()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default()
+    }
+
+    fn update_gateway(&mut self, mask: u32) {
+        let pending = self.pending.get();
+        if self.levels & mask != 0 && self.claimed.get() & mask == 0 {
+            self.pending.set(pending | mask);
+        } else if self.levels & mask == 0 {
+            self.pending.set(pending & !mask);
+        }
+    }
+
+    fn best_source(&self, ctx: usize) -> Option<usize> {
+        let candidate = self.pending.get() & self.enable[ctx];
+
+        // Ties go to the lowest source id
+        (1..PLIC_SOURCES)
+            .filter(|src| candidate & (1 << src) != 0)
+            .filter(|src| self.priority[*src] > self.threshold[ctx])
+            .fold(None, |best: Option<usize>, src| match best {
+                Some(b) if self.priority[b] >= self.priority[src] => Some(b),
+                _ => Some(src),
+            })
+    }
+
+    fn claim(&self, ctx: usize) -> u32 {
+        if let Some(src) = self.best_source(ctx) {
+            let mask = 1 << src;
+            self.pending.set(self.pending.get() & !mask);
+            self.claimed.set(self.claimed.get() | mask);
+            src as u32
+        } else {
+            0
+        }
+    }
+
+    fn complete(&mut self, ctx: usize, src: u32) {
+        let src = src as usize;
+        if src == 0 || src >= PLIC_SOURCES || self.enable[ctx] & (1 << src) == 0 {
+            return;
+        }
+        let mask = 1 << src;
+        self.claimed.set(self.claimed.get() & !mask);
+        self.update_gateway(mask);
+    }
+
+    fn decode(offset: XReg) -> PlicReg {
+        match offset {
+            PRIORITY_BASE..PENDING_BASE => {
+                let src = (offset / 4) as usize;
+                if src < PLIC_SOURCES { PlicReg::Priority(src) } else { PlicReg::Reserved }
+            },
+            PENDING_BASE => PlicReg::Pending,
+            ENABLE_BASE..CONTEXT_BASE => {
+                let ctx = ((offset - ENABLE_BASE) / ENABLE_STRIDE) as usize;
+                if ctx < PLIC_CONTEXTS && (offset - ENABLE_BASE).is_multiple_of(ENABLE_STRIDE) {
+                    PlicReg::Enable(ctx)
+                } else {
+                    PlicReg::Reserved
+                }
+            },
+            CONTEXT_BASE.. => {
+                let ctx = ((offset - CONTEXT_BASE) / CONTEXT_STRIDE) as usize;
+                match ((offset - CONTEXT_BASE) % CONTEXT_STRIDE, ctx < PLIC_CONTEXTS) {
+                    (0, true) => PlicReg::Threshold(ctx),
+                    (4, true) => PlicReg::Claim(ctx),
+                    _         => PlicReg::Reserved,
+                }
+            },
+            _ => PlicReg::Reserved,
+        }
+    }
+
+    /// Register value without the claim side effect, claim/complete shows what a claim would return
+    fn peek_reg(&self, offset: XReg) -> u32 {
+        match Self::decode(offset) {
+            PlicReg::Priority(src)  => self.priority[src],
+            PlicReg::Pending        => self.pending.get(),
+            PlicReg::Enable(ctx)    => self.enable[ctx],
+            PlicReg::Threshold(ctx) => self.threshold[ctx],
+            PlicReg::Claim(ctx)     => self.best_source(ctx).unwrap_or(0) as u32,
+            PlicReg::Reserved       => 0,
+        }
+    }
+
+    fn read_reg(&self, offset: XReg) -> u32 {
+        match Self::decode(offset) {
+            PlicReg::Claim(ctx) => self.claim(ctx),
+            _ => self.peek_reg(offset),
+        }
+    }
+
+    fn write_reg(&mut self, offset: XReg, data: u32) {
+        match Self::decode(offset) {
+            PlicReg::Priority(src) if src != 0 => self.priority[src] = data & PRIORITY_MASK,
+            // Bit 0 is source 0, which does not exist
+            PlicReg::Enable(ctx)    => self.enable[ctx] = data & !1,
+            PlicReg::Threshold(ctx) => self.threshold[ctx] = data & PRIORITY_MASK,
+            PlicReg::Claim(ctx)     => self.complete(ctx, data),
+            _ => {},
+        }
+    }
+}
+
+impl Device for Plic {
+    fn read_byte(&self, access: Access<Physical>) -> Result<u8> {
+        let mut byte = [0];
+        self.read_bytes(access, 1, &mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn write_byte(&mut self, access: Access<Physical>, data: u8) -> Result<()> {
+        self.write_bytes(access, 1, &[data])
+    }
+
+    fn read_bytes(&self, access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
+        let offset = access.addr & 0x3;
+        let reg = self.read_reg(access.addr & !0x3).to_le_bytes();
+
+        for (i, byte) in des.iter_mut().take(size).enumerate() {
+            *byte = reg.get(offset as usize + i).copied().unwrap_or(0);
+        }
+        Ok(())
+    }
+
+    // A debugger may look at several registers at once
+    fn peek_bytes(&self, access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
+        for (i, byte) in des.iter_mut().take(size).enumerate() {
+            let addr = access.addr + i as XReg;
+            *byte = self.peek_reg(addr & !0x3).to_le_bytes()[(addr & 0x3) as usize];
+        }
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, access: Access<Physical>, size: usize, src: &[u8]) -> Result<()> {
+        let offset = (access.addr & 0x3) as usize;
+        let reg_addr = access.addr & !0x3;
+        let mut reg = self.peek_reg(reg_addr).to_le_bytes();
+
+        for (i, byte) in src.iter().take(size).enumerate() {
+            if let Some(b) = reg.get_mut(offset + i) {
+                *b = *byte;
+            }
+        }
+        self.write_reg(reg_addr, u32::from_le_bytes(reg));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::access::AccessType;
+    use super::*;
+
+    const SRC: usize = 10;
+
+    fn read(plic: &Plic, offset: XReg) -> u32 {
+        let mut buf = [0; 4];
+        plic.read_bytes(Access::new(offset, AccessType::Load), 4, &mut buf).unwrap();
+        u32::from_le_bytes(buf)
+    }
+
+    fn write(plic: &mut Plic, offset: XReg, data: u32) {
+        plic.write_bytes(Access::new(offset, AccessType::Store), 4, &data.to_le_bytes()).unwrap();
+    }
+
+    fn setup() -> Plic {
+        let mut plic = Plic::default();
+        write(&mut plic, 4 * SRC as XReg, 1);
+        write(&mut plic, ENABLE_BASE, 1 << SRC);
+        plic
+    }
+
+    #[test]
+    fn test_claim_complete() {
+        let mut plic = setup();
+        plic.set_level(SRC, true);
+        assert_eq!(read(&plic, PENDING_BASE), 1 << SRC);
+
+        let claim = CONTEXT_BASE + 4;
+        assert_eq!(read(&plic, claim), SRC as u32);
+        // Claimed source is no longer pending and can not be claimed twice
+        assert_eq!(read(&plic, PENDING_BASE), 0);
+        assert_eq!(read(&plic, claim), 0);
+
+        // Level still high on complete, so it becomes pending again
+        write(&mut plic, claim, SRC as u32);
+        assert_eq!(read(&plic, PENDING_BASE), 1 << SRC);
+
+        plic.set_level(SRC, false);
+        assert_eq!(read(&plic, PENDING_BASE), 0);
+    }
+
+    #[test]
+    fn test_peek_does_not_claim() {
+        let mut plic = setup();
+        plic.set_level(SRC, true);
+
+        let claim = CONTEXT_BASE + 4;
+        let mut regs = [0; 8];
+        plic.peek_bytes(Access::new(CONTEXT_BASE, AccessType::Load), 8, &mut regs).unwrap();
+        assert_eq!(regs[4..], (SRC as u32).to_le_bytes());
+
+        // Still pending, and the guest claims it
+        assert_eq!(read(&plic, PENDING_BASE), 1 << SRC);
+        assert_eq!(read(&plic, claim), SRC as u32);
+    }
+
+    #[test]
+    fn test_threshold_and_priority() {
+        let mut plic = setup();
+        plic.set_level(SRC, true);
+
+        // Priority must be strictly greater than threshold
+        write(&mut plic, CONTEXT_BASE, 1);
+        assert_eq!(read(&plic, CONTEXT_BASE + 4), 0);
+
+        write(&mut plic, CONTEXT_BASE, 0);
+        write(&mut plic, 4 * 3, 5);
+        write(&mut plic, ENABLE_BASE, (1 << SRC) | (1 << 3));
+        plic.set_level(3, true);
+        assert_eq!(read(&plic, CONTEXT_BASE + 4), 3);
+        assert_eq!(read(&plic, CONTEXT_BASE + 4), SRC as u32);
+    }
+
+    #[test]
+    #[cfg(feature = "zicsr")]
+    fn test_context_enable() {
+        let mut plic = setup();
+        plic.set_level(SRC, true);
+
+        assert!(plic.irq_pending(0));
+        assert!(!plic.irq_pending(1));
+
+        write(&mut plic, ENABLE_BASE, 0);
+        assert!(!plic.irq_pending(0));
+
+        // Hart 1 M-mode
+        let ctx = HART_CONTEXTS + M_CONTEXT;
+        write(&mut plic, ENABLE_BASE + ENABLE_STRIDE * ctx as XReg, 1 << SRC);
+        assert!(plic.irq_pending(ctx));
+    }
+}
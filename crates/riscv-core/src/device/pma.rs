@@ -0,0 +1,138 @@
+//! Physical memory attributes of the mapped regions
+
+use crate::Result;
+use crate::core::access::{Access, AccessType, Physical};
+use crate::core::XReg;
+
+/// Widest kind of AMO a region supports, each class includes the ones before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(not(feature = "a"), allow(dead_code))]
+pub enum AmoClass {
+    #[default]
+    None,
+    /// `amoswap` only
+    Swap,
+    /// Swap plus `amoand`, `amoor` and `amoxor`
+    Logical,
+    /// Every AMO, including `amoadd` and the min / max ones
+    Arithmetic,
+}
+
+/// Access sizes in bytes as a bit mask, bit `n` allows `1 << n` bytes
+pub const WIDTH_ANY: u8 = 0b1111;
+pub const WIDTH_B: u8 = 1 << 0;
+pub const WIDTH_H: u8 = 1 << 1;
+pub const WIDTH_W: u8 = 1 << 2;
+pub const WIDTH_D: u8 = 1 << 3;
+
+/// What one region of the physical address space supports. The default is an unmapped hole
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pma {
+    pub executable: bool,
+    pub readable: bool,
+    pub writable: bool,
+    pub amo: AmoClass,
+    pub lrsc: bool,
+    pub widths: u8,
+    /// Reads and writes have no side effects, so an access may be split or repeated
+    pub idempotent: bool,
+}
+
+impl Pma {
+    pub const MAIN_MEMORY: Pma = Pma {
+        executable: true,
+        readable: true,
+        writable: true,
+        amo: AmoClass::Arithmetic,
+        lrsc: true,
+        widths: WIDTH_ANY,
+        idempotent: true,
+    };
+
+    /// Device registers, read and written with aligned accesses of `widths` only
+    pub const fn io(widths: u8) -> Pma {
+        Pma {
+            executable: false,
+            readable: true,
+            writable: true,
+            amo: AmoClass::None,
+            lrsc: false,
+            widths,
+            idempotent: false,
+        }
+    }
+
+    /// A split piece of a misaligned access checks against the next power of two
+    pub fn check(&self, access: Access<Physical>, size: usize) -> Result<()> {
+        let allowed = match access.kind {
+            AccessType::Load  => self.readable,
+            AccessType::Store => self.writable,
+            AccessType::Fetch => self.executable,
+            #[cfg(feature = "a")]
+            AccessType::Amo   => self.readable && self.writable,
+        };
+        let width = size.max(1).next_power_of_two();
+        let width_ok = width <= 8 && self.widths & width as u8 != 0;
+        let aligned = access.addr & (width as XReg - 1) == 0;
+
+        if allowed && width_ok && (self.idempotent || aligned) {
+            Ok(())
+        } else {
+            Err(access.into_access_exception())
+        }
+    }
+
+    #[cfg(feature = "a")]
+    pub fn check_amo(&self, access: Access<Physical>, class: AmoClass) -> Result<()> {
+        if self.amo >= class {
+            Ok(())
+        } else {
+            Err(access.into_access_exception())
+        }
+    }
+
+    #[cfg(feature = "a")]
+    pub fn check_lrsc(&self, access: Access<Physical>) -> Result<()> {
+        if self.lrsc {
+            Ok(())
+        } else {
+            Err(access.into_access_exception())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_region() {
+        let uart = Pma::io(WIDTH_B);
+        assert!(uart.check(Access::new(0x5, AccessType::Load), 1).is_ok());
+        assert!(uart.check(Access::new(0x4, AccessType::Store), 4).is_err());
+        assert!(uart.check(Access::new(0x0, AccessType::Fetch), 2).is_err());
+
+        let clint = Pma::io(WIDTH_W | WIDTH_D);
+        assert!(clint.check(Access::new(0x4000, AccessType::Load), 8).is_ok());
+        assert!(clint.check(Access::new(0x4002, AccessType::Load), 4).is_err(), "Device access must be aligned");
+        assert!(clint.check(Access::new(0x4000, AccessType::Store), 2).is_err());
+    }
+
+    #[test]
+    fn test_main_memory() {
+        let ram = Pma::MAIN_MEMORY;
+        assert!(ram.check(Access::new(0x3, AccessType::Load), 3).is_ok(), "Split pieces of main memory");
+        assert!(ram.check(Access::new(0x2, AccessType::Fetch), 4).is_ok());
+        assert!(Pma::default().check(Access::new(0x0, AccessType::Load), 1).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "a")]
+    fn test_amo_class() {
+        let access = Access::new(0x0, AccessType::Amo);
+        let swap_only = Pma { amo: AmoClass::Swap, ..Pma::MAIN_MEMORY };
+        assert!(swap_only.check_amo(access, AmoClass::Swap).is_ok());
+        assert!(swap_only.check_amo(access, AmoClass::Logical).is_err());
+        assert!(Pma::io(WIDTH_W).check_lrsc(access).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+//! Read-only memory, NOR flash with its programming interface, and the boot ROM
+
+use crate::Result;
+use crate::core::access::{Access, AccessType, Physical};
+use crate::core::XReg;
+use super::Device;
+use super::memory::{Memory, PAGE_SIZE};
+
+/// Flash controller register block
+pub const FLASH_CTRL_SIZE: XReg = 0x100;
+/// Erase granularity of the flash array
+pub const FLASH_SECTOR_SIZE: usize = PAGE_SIZE;
+
+// Control: PG lets stores program the array, LOCK keeps CTRL as it is until a reset
+const CTRL: XReg = 0x0;
+const CTRL_PG: u32 = 1 << 0;
+const CTRL_LOCK: u32 = 1 << 1;
+// Writing an offset erases the sector holding it, while PG is set
+const ERASE: XReg = 0x4;
+// Sticky error bit, write 1 to clear
+const STATUS: XReg = 0x8;
+const STATUS_ERR: u32 = 1 << 0;
+
+/// Memory that harts can only read. Images get in through `load`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rom(Memory);
+
+impl Rom {
+    pub fn new(size: usize) -> Self {
+        Rom(Memory::new(size))
+    }
+
+    pub fn size(&self) -> usize {
+        self.0.size
+    }
+
+    pub fn load(&mut self, access: Access<Physical>, data: &[u8]) -> Result<()> {
+        self.0.write_bytes(access, data.len(), data)
+    }
+}
+
+impl Device for Rom {
+    fn read_byte(&self, access: Access<Physical>) -> Result<u8> {
+        self.0.read_byte(access)
+    }
+
+    fn write_byte(&mut self, access: Access<Physical>, _data: u8) -> Result<()> {
+        Err(access.into_access_exception())
+    }
+
+    fn read_bytes(&self, access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
+        self.0.read_bytes(access, size, des)
+    }
+
+    fn write_bytes(&mut self, access: Access<Physical>, _size: usize, _src: &[u8]) -> Result<()> {
+        Err(access.into_access_exception())
+    }
+}
+
+/// NOR flash: stores fault unless `CTRL.PG` is set, and then they can only clear bits.
+/// It starts erased (all ones), and the array survives a reset while the controller does not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flash {
+    array: Rom,
+    ctrl: u32,
+    status: u32,
+}
+
+impl Flash {
+    pub fn new(size: usize) -> Self {
+        let mut array = Rom::new(size);
+        let erased = vec![0xff; array.size()];
+        array.load(Access::new(0, AccessType::Store), &erased).expect("The array fits its own size");
+        Flash { array, ctrl: 0, status: 0 }
+    }
+
+    pub fn size(&self) -> usize {
+        self.array.size()
+    }
+
+    pub fn load(&mut self, access: Access<Physical>, data: &[u8]) -> Result<()> {
+        self.array.load(access, data)
+    }
+
+    pub fn reset(&mut self) {
+        self.ctrl = 0;
+        self.status = 0;
+    }
+
+    fn erase(&mut self, offset: XReg) {
+        let start = offset as usize / FLASH_SECTOR_SIZE * FLASH_SECTOR_SIZE;
+        if self.ctrl & CTRL_PG == 0 || start >= self.size() {
+            self.status |= STATUS_ERR;
+            return;
+        }
+        let sector = Access::new(start as XReg, AccessType::Store);
+        let _ = self.array.load(sector, &[0xff; FLASH_SECTOR_SIZE]);
+    }
+
+    /// Controller registers, 32 bits wide
+    pub fn read_ctrl(&self, access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
+        let reg = match access.addr & !3 {
+            CTRL   => self.ctrl,
+            STATUS => self.status,
+            _      => 0,
+        };
+        let start = (access.addr & 3) as usize;
+        if start + size > 4 {
+            return Err(access.into_access_exception());
+        }
+        des[..size].copy_from_slice(&reg.to_le_bytes()[start..start + size]);
+        Ok(())
+    }
+
+    pub fn write_ctrl(&mut self, access: Access<Physical>, size: usize, src: &[u8]) -> Result<()> {
+        if size != 4 || access.addr & 3 != 0 {
+            return Err(access.into_access_exception());
+        }
+        let data = u32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        match access.addr {
+            CTRL if self.ctrl & CTRL_LOCK == 0 => self.ctrl = data & (CTRL_PG | CTRL_LOCK),
+            ERASE  => self.erase(data as XReg),
+            STATUS => self.status &= !(data & STATUS_ERR),
+            _      => {},
+        }
+        Ok(())
+    }
+}
+
+impl Device for Flash {
+    fn read_byte(&self, access: Access<Physical>) -> Result<u8> {
+        self.array.read_byte(access)
+    }
+
+    fn write_byte(&mut self, access: Access<Physical>, data: u8) -> Result<()> {
+        self.write_bytes(access, 1, &[data])
+    }
+
+    fn read_bytes(&self, access: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
+        self.array.read_bytes(access, size, des)
+    }
+
+    // Programming ANDs into what is there
+    fn write_bytes(&mut self, access: Access<Physical>, size: usize, src: &[u8]) -> Result<()> {
+        if self.ctrl & CTRL_PG == 0 {
+            self.status |= STATUS_ERR;
+            return Err(access.into_access_exception());
+        }
+        let mut data = vec![0; size];
+        self.array.read_bytes(access, size, &mut data)?;
+        data.iter_mut().zip(src).for_each(|(old, new)| *old &= new);
+        self.array.load(access, &data)
+    }
+}
+
+// Hart id in `a0`, the data words in `a1` and `t0`, then jump
+const BOOT_CODE: [u32; 5] = [
+    0x00000297, // auipc t0, 0
+    #[cfg(feature = "zicsr")]
+    0xf1402573, // csrr a0, mhartid
+    #[cfg(not(feature = "zicsr"))]
+    0x00000513, // li a0, 0
+    #[cfg(not(feature = "rv64"))]
+    0x0202a583, // lw a1, 32(t0)
+    #[cfg(feature = "rv64")]
+    0x0202b583, // ld a1, 32(t0)
+    #[cfg(not(feature = "rv64"))]
+    0x0182a283, // lw t0, 24(t0)
+    #[cfg(feature = "rv64")]
+    0x0182b283, // ld t0, 24(t0)
+    0x00028067, // jr t0
+];
+/// Data words the boot code reads: the payload entry and the device tree address
+pub const BOOT_ENTRY: XReg = 0x18;
+pub const BOOT_DTB: XReg = 0x20;
+/// Space the boot ROM takes
+pub const BOOT_ROM_SIZE: usize = PAGE_SIZE;
+
+impl Rom {
+    /// Boot ROM that jumps to `entry` with `a0 = mhartid` and `a1 = 0` like QEMU virt,
+    /// the device tree address is set later
+    pub fn boot(entry: XReg) -> Self {
+        let mut rom = Rom::new(BOOT_ROM_SIZE);
+        let code: Vec<u8> = BOOT_CODE.iter().flat_map(|ins| ins.to_le_bytes()).collect();
+        rom.load(Access::new(0, AccessType::Store), &code).expect("Boot code fits one page");
+        rom.set_word(BOOT_ENTRY, entry);
+        rom.set_word(BOOT_DTB, 0);
+        rom
+    }
+
+    pub fn set_word(&mut self, offset: XReg, data: XReg) {
+        let _ = self.load(Access::new(offset, AccessType::Store), &data.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(addr: XReg) -> Access<Physical> {
+        Access::new(addr, AccessType::Store)
+    }
+
+    #[test]
+    fn test_rom_read_only() {
+        let mut rom = Rom::new(PAGE_SIZE);
+        rom.load(store(0x10), &[1, 2, 3, 4]).unwrap();
+        assert!(rom.write_bytes(store(0x10), 4, &[0; 4]).is_err());
+
+        let mut word = [0; 4];
+        rom.read_bytes(Access::new(0x10, AccessType::Load), 4, &mut word).unwrap();
+        assert_eq!(word, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_flash_program_erase() {
+        let mut flash = Flash::new(2 * FLASH_SECTOR_SIZE);
+        let load = |flash: &Flash, addr| {
+            let mut word = [0; 4];
+            flash.read_bytes(Access::new(addr, AccessType::Load), 4, &mut word).unwrap();
+            u32::from_le_bytes(word)
+        };
+        assert_eq!(load(&flash, 0x0), 0xffff_ffff);
+
+        // Locked out until PG is set
+        assert!(flash.write_bytes(store(0x0), 4, &[0; 4]).is_err());
+        let mut status = [0; 4];
+        flash.read_ctrl(Access::new(STATUS, AccessType::Load), 4, &mut status).unwrap();
+        assert_eq!(status, [1, 0, 0, 0]);
+
+        flash.write_ctrl(store(CTRL), 4, &CTRL_PG.to_le_bytes()).unwrap();
+        flash.write_bytes(store(0x0), 4, &0x1234_5678u32.to_le_bytes()).unwrap();
+        assert_eq!(load(&flash, 0x0), 0x1234_5678);
+        // Programming only clears bits
+        flash.write_bytes(store(0x0), 4, &0xffff_00ffu32.to_le_bytes()).unwrap();
+        assert_eq!(load(&flash, 0x0), 0x1234_0078);
+
+        flash.write_ctrl(store(ERASE), 4, &0x10u32.to_le_bytes()).unwrap();
+        assert_eq!(load(&flash, 0x0), 0xffff_ffff);
+
+        // LOCK freezes CTRL with PG cleared
+        flash.write_ctrl(store(CTRL), 4, &CTRL_LOCK.to_le_bytes()).unwrap();
+        flash.write_ctrl(store(CTRL), 4, &CTRL_PG.to_le_bytes()).unwrap();
+        assert!(flash.write_bytes(store(0x0), 4, &[0; 4]).is_err());
+        flash.reset();
+        flash.write_ctrl(store(CTRL), 4, &CTRL_PG.to_le_bytes()).unwrap();
+        assert!(flash.write_bytes(store(0x0), 4, &[0; 4]).is_ok());
+    }
+}
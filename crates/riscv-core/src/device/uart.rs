@@ -1,28 +1,291 @@
-use std::io::{self, Write};
+//! NS16550A compatible UART
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
 
 use crate::core::access::{Access, Physical};
+use crate::core::XReg;
 use crate::device::Device;
 use crate::Result;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Interrupt source id of the UART on the PLIC, same as QEMU virt
+pub const UART_IRQ: usize = 10;
+
+/// Input clock the device tree gives, transmit timing does not depend on it
+pub const UART_CLOCK_FREQ: u32 = 3_686_400;
+
+const FIFO_SIZE: usize = 16;
+
+// Register offsets
+const RBR_THR_DLL: XReg = 0;
+const IER_DLM: XReg = 1;
+const IIR_FCR: XReg = 2;
+const LCR: XReg = 3;
+const MCR: XReg = 4;
+const LSR: XReg = 5;
+const MSR: XReg = 6;
+const SCR: XReg = 7;
+
+const IER_RDA: u8 = 0x01;
+const IER_THRE: u8 = 0x02;
+
+const IIR_NO_INT: u8 = 0x01;
+const IIR_THRE: u8 = 0x02;
+const IIR_RDA: u8 = 0x04;
+const IIR_FIFO_ENABLED: u8 = 0xc0;
+
+const FCR_ENABLE: u8 = 0x01;
+const FCR_CLEAR_RX: u8 = 0x02;
+
+const LCR_DLAB: u8 = 0x80;
+
+const MCR_LOOP: u8 = 0x10;
+
+const LSR_DR: u8 = 0x01;
+const LSR_THRE: u8 = 0x20;
+const LSR_TEMT: u8 = 0x40;
+
+// DCD | DSR | CTS, a modem that is always ready
+const MSR_READY: u8 = 0xb0;
+
+/// Host side of the receive path. Clones share the same queue
+#[derive(Clone, Default)]
+pub struct UartInput(Arc<Mutex<VecDeque<u8>>>);
+
+impl UartInput {
+    /// Queue bytes for the guest to receive
+    pub fn push(&self, bytes: &[u8]) {
+        if let Ok(mut queue) = self.0.lock() {
+            queue.extend(bytes);
+        }
+    }
+
+    /// Forward host stdin into the UART from a background thread, for headless runs
+    pub fn attach_stdin(&self) {
+        let input = self.clone();
+        thread::spawn(move || {
+            let mut buf = [0; 64];
+            let mut stdin = io::stdin();
+            while let Ok(len @ 1..) = stdin.read(&mut buf) {
+                input.push(&buf[..len]);
+            }
+        });
+    }
+
+    fn pop(&self) -> Option<u8> {
+        self.0.try_lock().ok()?.pop_front()
+    }
+}
+
+impl fmt::Debug for UartInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UartInput")
+    }
+}
+
+impl PartialEq for UartInput {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for UartInput {}
+
+/// Where transmitted bytes go. Default is the process stdout
+#[derive(Clone)]
+pub struct UartOutput(Arc<Mutex<dyn Write + Send>>);
+
+impl UartOutput {
+    pub fn new<W: Write + Send + 'static>(sink: W) -> Self {
+        UartOutput(Arc::new(Mutex::new(sink)))
+    }
+
+    fn write(&self, data: u8) {
+        if let Ok(mut sink) = self.0.lock() {
+            // Losing console output should never stop the guest
+            let _ = sink.write_all(&[data]).and_then(|_| sink.flush());
+        }
+    }
+}
+
+impl Default for UartOutput {
+    fn default() -> Self {
+        UartOutput::new(io::stdout())
+    }
+}
+
+impl fmt::Debug for UartOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UartOutput")
+    }
+}
+
+impl PartialEq for UartOutput {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for UartOutput {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Uart {
-    // thr: u32,
-    // lsr: u32,
+    // Reading RBR pops the FIFO, so it lives in `RefCell`
+    rx_fifo: RefCell<VecDeque<u8>>,
+    ier: u8,
+    // THRE interrupt latch, cleared by reading IIR or writing THR
+    thre_ip: Cell<bool>,
+    fcr: u8,
+    lcr: u8,
+    mcr: u8,
+    scr: u8,
+    dll: u8,
+    dlm: u8,
+
+    input: UartInput,
+    output: UartOutput,
+}
+
+impl Uart {
+    pub fn input(&self) -> UartInput {
+        self.input.clone()
+    }
+
+    pub fn set_output(&mut self, output: UartOutput) {
+        self.output = output;
+    }
+
+    /// Move host input into the receive FIFO while it has room
+    pub fn tick(&mut self) {
+        let size = self.fifo_size();
+        let rx_fifo = self.rx_fifo.get_mut();
+        while rx_fifo.len() < size {
+            match self.input.pop() {
+                Some(data) => rx_fifo.push_back(data),
+                None       => break,
+            }
+        }
+    }
+
+    pub fn irq_pending(&self) -> bool {
+        self.interrupt_id() != IIR_NO_INT
+    }
+
+    /// Clear registers, host input and output stay attached
+    pub fn reset(&mut self) {
+        *self = Uart {
+            input: self.input.clone(),
+            output: self.output.clone(),
+            ..Default::default()
+        };
+    }
+
+    fn fifo_size(&self) -> usize {
+        if self.fcr & FCR_ENABLE != 0 { FIFO_SIZE } else { 1 }
+    }
+
+    fn dlab(&self) -> bool {
+        self.lcr & LCR_DLAB != 0
+    }
+
+    fn interrupt_id(&self) -> u8 {
+        if self.ier & IER_RDA != 0 && !self.rx_fifo.borrow().is_empty() {
+            IIR_RDA
+        } else if self.ier & IER_THRE != 0 && self.thre_ip.get() {
+            IIR_THRE
+        } else {
+            IIR_NO_INT
+        }
+    }
+
+    fn transmit(&mut self, data: u8) {
+        if self.mcr & MCR_LOOP != 0 {
+            let size = self.fifo_size();
+            let rx_fifo = self.rx_fifo.get_mut();
+            if rx_fifo.len() < size {
+                rx_fifo.push_back(data);
+            }
+        } else {
+            self.output.write(data);
+        }
+        // Transmission is instant, THR is empty again
+        self.thre_ip.set(true);
+    }
+
+    // Register value without popping RBR or acknowledging THRE
+    fn peek_reg(&self, offset: XReg) -> u8 {
+        match offset {
+            RBR_THR_DLL if self.dlab() => self.dll,
+            RBR_THR_DLL => self.rx_fifo.borrow().front().copied().unwrap_or(0),
+            IER_DLM if self.dlab() => self.dlm,
+            IER_DLM => self.ier,
+            IIR_FCR => {
+                let fifo = if self.fcr & FCR_ENABLE != 0 { IIR_FIFO_ENABLED } else { 0 };
+                self.interrupt_id() | fifo
+            },
+            LCR => self.lcr,
+            MCR => self.mcr,
+            LSR => {
+                let dr = if self.rx_fifo.borrow().is_empty() { 0 } else { LSR_DR };
+                dr | LSR_THRE | LSR_TEMT
+            },
+            MSR => self.modem_status(),
+            SCR => self.scr,
+            _   => 0,
+        }
+    }
+
+    fn modem_status(&self) -> u8 {
+        if self.mcr & MCR_LOOP != 0 {
+            // RTS -> CTS, DTR -> DSR, OUT1 -> RI, OUT2 -> DCD
+            let mcr = self.mcr;
+            ((mcr & 0x02) << 3) | ((mcr & 0x01) << 5) | ((mcr & 0x04) << 4) | ((mcr & 0x08) << 4)
+        } else {
+            MSR_READY
+        }
+    }
 }
 
 impl Device for Uart {
     fn read_byte(&self, assess: Access<Physical>) -> Result<u8> {
-        Ok(match assess.addr {
-            0x05 => 0x20,
-            _    => 0,
-        })
+        let data = self.peek_reg(assess.addr);
+        match assess.addr {
+            RBR_THR_DLL if !self.dlab() => {
+                self.rx_fifo.borrow_mut().pop_front();
+            },
+            // Reading IIR acknowledges THRE
+            IIR_FCR if self.interrupt_id() == IIR_THRE => self.thre_ip.set(false),
+            _ => {},
+        }
+        Ok(data)
     }
 
     fn write_byte(&mut self, assess: Access<Physical>, data: u8) -> Result<()> {
-        if assess.addr == 0x00 { 
-            print!("{}", data as char);
-   
-            io::stdout().flush().unwrap();
+        match assess.addr {
+            RBR_THR_DLL if self.dlab() => self.dll = data,
+            RBR_THR_DLL => self.transmit(data),
+            IER_DLM if self.dlab() => self.dlm = data,
+            IER_DLM => {
+                // Enabling THRE interrupt while THR is empty raises it right away
+                if data & IER_THRE != 0 && self.ier & IER_THRE == 0 {
+                    self.thre_ip.set(true);
+                }
+                self.ier = data & 0x0f;
+            },
+            IIR_FCR => {
+                if data & FCR_CLEAR_RX != 0 {
+                    self.rx_fifo.get_mut().clear();
+                }
+                self.fcr = data & FCR_ENABLE;
+            },
+            LCR => self.lcr = data,
+            MCR => self.mcr = data & 0x1f,
+            SCR => self.scr = data,
+            _   => {},
         }
         Ok(())
     }
@@ -40,4 +303,123 @@ impl Device for Uart {
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    // A debugger may look at every register at once
+    fn peek_bytes(&self, assess: Access<Physical>, size: usize, des: &mut [u8]) -> Result<()> {
+        for (offset, byte) in des[..size].iter_mut().enumerate() {
+            *byte = self.peek_reg(assess.addr + offset as XReg);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::access::AccessType;
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Sink(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn read(uart: &Uart, offset: XReg) -> u8 {
+        uart.read_byte(Access::new(offset, AccessType::Load)).unwrap()
+    }
+
+    fn write(uart: &mut Uart, offset: XReg, data: u8) {
+        uart.write_byte(Access::new(offset, AccessType::Store), data).unwrap()
+    }
+
+    #[test]
+    fn test_transmit_to_sink() {
+        let sink = Sink::default();
+        let mut uart = Uart::default();
+        uart.set_output(UartOutput::new(sink.clone()));
+
+        for byte in b"hi" {
+            write(&mut uart, RBR_THR_DLL, *byte);
+        }
+        assert_eq!(sink.0.lock().unwrap().as_slice(), b"hi");
+        assert_eq!(read(&uart, LSR) & (LSR_THRE | LSR_TEMT), LSR_THRE | LSR_TEMT);
+    }
+
+    #[test]
+    fn test_receive_fifo() {
+        let mut uart = Uart::default();
+        write(&mut uart, IIR_FCR, FCR_ENABLE);
+        uart.input().push(b"ok");
+        uart.tick();
+
+        assert_eq!(read(&uart, LSR) & LSR_DR, LSR_DR);
+        assert_eq!(read(&uart, RBR_THR_DLL), b'o');
+        assert_eq!(read(&uart, RBR_THR_DLL), b'k');
+        assert_eq!(read(&uart, LSR) & LSR_DR, 0);
+    }
+
+    #[test]
+    fn test_interrupt_identification() {
+        let mut uart = Uart::default();
+        assert!(!uart.irq_pending());
+
+        write(&mut uart, IER_DLM, IER_RDA | IER_THRE);
+        assert_eq!(read(&uart, IIR_FCR), IIR_THRE);
+        // Reading IIR acknowledges THRE
+        assert!(!uart.irq_pending());
+
+        uart.input().push(b"a");
+        uart.tick();
+        assert_eq!(read(&uart, IIR_FCR), IIR_RDA);
+        read(&uart, RBR_THR_DLL);
+        assert_eq!(read(&uart, IIR_FCR), IIR_NO_INT);
+    }
+
+    #[test]
+    fn test_peek_has_no_side_effects() {
+        let mut uart = Uart::default();
+        write(&mut uart, IER_DLM, IER_THRE);
+        uart.input().push(b"a");
+        uart.tick();
+
+        let mut regs = [0; 8];
+        uart.peek_bytes(Access::new(0, AccessType::Load), 8, &mut regs).unwrap();
+        assert_eq!(regs[RBR_THR_DLL as usize], b'a');
+        assert_eq!(regs[IIR_FCR as usize], IIR_THRE);
+
+        // Still there for the guest
+        assert_eq!(read(&uart, IIR_FCR), IIR_THRE);
+        assert_eq!(read(&uart, RBR_THR_DLL), b'a');
+    }
+
+    #[test]
+    fn test_divisor_latch() {
+        let mut uart = Uart::default();
+        write(&mut uart, LCR, LCR_DLAB | 0x03);
+        write(&mut uart, RBR_THR_DLL, 0x01);
+        write(&mut uart, IER_DLM, 0x02);
+        write(&mut uart, LCR, 0x03);
+
+        assert_eq!(uart.dll, 0x01);
+        assert_eq!(uart.dlm, 0x02);
+        assert_eq!(read(&uart, IER_DLM), 0);
+    }
+
+    #[test]
+    fn test_loopback() {
+        let mut uart = Uart::default();
+        write(&mut uart, MCR, MCR_LOOP | 0x0a);
+        assert_eq!(read(&uart, MSR) & 0xf0, 0x90);
+
+        write(&mut uart, RBR_THR_DLL, b'x');
+        assert_eq!(read(&uart, RBR_THR_DLL), b'x');
+    }
+}
@@ -1,106 +1,109 @@
+use crate::core::{SXReg, XLEN, XReg};
+#[cfg(feature = "rv64")]
+use crate::core::sext_w;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Alu;
 
 impl Alu {
-    pub fn add(data1: u32, data2: u32) -> u32 {
+    pub fn add(data1: XReg, data2: XReg) -> XReg {
         data1.wrapping_add(data2)
     }
 
-    pub fn add_signed(data1: u32, data2: i32) -> u32 {
-        data1.wrapping_add_signed(data2)
+    pub fn add_signed(data1: XReg, data2: i32) -> XReg {
+        data1.wrapping_add_signed(data2 as SXReg)
     }
 
-    pub fn sub(data1: u32, data2: u32) -> u32 {
+    pub fn sub(data1: XReg, data2: XReg) -> XReg {
         data1.wrapping_sub(data2)
     }
 
-    pub fn xor(data1: u32, data2: u32) -> u32 {
+    pub fn xor(data1: XReg, data2: XReg) -> XReg {
         data1 ^ data2
     }
 
-    pub fn or(data1: u32, data2: u32) -> u32 {
+    pub fn or(data1: XReg, data2: XReg) -> XReg {
         data1 | data2
     }
 
-    pub fn and(data1: u32, data2: u32) -> u32 {
+    pub fn and(data1: XReg, data2: XReg) -> XReg {
         data1 & data2
     }
 
-    pub fn shl_logic(data: u32, shift: u32) -> u32 {
-        data << (shift % 32)
+    pub fn shl_logic(data: XReg, shift: XReg) -> XReg {
+        data << (shift % XLEN as XReg)
     }
 
-    pub fn shr_logic(data: u32, shift: u32) -> u32 {
-        data >> (shift % 32)
+    pub fn shr_logic(data: XReg, shift: XReg) -> XReg {
+        data >> (shift % XLEN as XReg)
     }
 
-    pub fn shr_ar(data: i32, shift: u32) -> u32 {
-        (data >> (shift % 32)) as u32
+    pub fn shr_ar(data: SXReg, shift: XReg) -> XReg {
+        (data >> (shift % XLEN as XReg)) as XReg
     }
 
-    pub fn set_less_than(data: i32, cmp_data: i32) -> u32 {
+    pub fn set_less_than(data: SXReg, cmp_data: SXReg) -> XReg {
         (data < cmp_data).into()
     }
 
-    pub fn set_less_than_unsigned(data: u32, cmp_data: u32) -> u32 {
+    pub fn set_less_than_unsigned(data: XReg, cmp_data: XReg) -> XReg {
         (data < cmp_data).into()
     }
 
     #[cfg(feature = "m")]
-    pub fn mul(data1: u32, data2: u32) -> u32 {
-        (data1 as i32 as i64)
-            .wrapping_mul(data2 as i32 as i64) as u32
+    pub fn mul(data1: XReg, data2: XReg) -> XReg {
+        data1.wrapping_mul(data2)
     }
 
     #[cfg(feature = "m")]
-    pub fn mulh(data1: u32, data2: u32) -> u32 {
-        ((data1 as i32 as i64)
-            .wrapping_mul(data2 as i32 as i64) >> 32) as u32
+    pub fn mulh(data1: XReg, data2: XReg) -> XReg {
+        ((data1 as SXReg as i128)
+            .wrapping_mul(data2 as SXReg as i128) >> XLEN) as XReg
     }
 
     #[cfg(feature = "m")]
-    pub fn mulh_unsigned(data1: u32, data2: u32) -> u32 {
-        ((data1 as u64)
-            .wrapping_mul(data2 as u64) >> 32) as u32
+    pub fn mulh_unsigned(data1: XReg, data2: XReg) -> XReg {
+        ((data1 as u128)
+            .wrapping_mul(data2 as u128) >> XLEN) as XReg
     }
     
     #[cfg(feature = "m")]
-    pub fn mulh_signed_unsigned(data1: u32, data2: u32) -> u32 {
-        ((data1 as i32 as i64)
-            .wrapping_mul(data2 as i64) >> 32) as u32
+    pub fn mulh_signed_unsigned(data1: XReg, data2: XReg) -> XReg {
+        ((data1 as SXReg as i128)
+            .wrapping_mul(data2 as i128) >> XLEN) as XReg
     }
     
     #[cfg(feature = "m")]
-    pub fn div(data1: u32, data2: u32) -> u32 {
+    pub fn div(data1: XReg, data2: XReg) -> XReg {
         if data2 == 0 {
-            u32::MAX
+            XReg::MAX
         } else {
-            (data1 as i32)
-                .wrapping_div(data2 as i32) as u32
+            (data1 as SXReg)
+                .wrapping_div(data2 as SXReg) as XReg
         }
     }
 
     #[cfg(feature = "m")]
-    pub fn div_unsigned(data1: u32, data2: u32) -> u32 {
+    pub fn div_unsigned(data1: XReg, data2: XReg) -> XReg {
         if data2 == 0 {
-            u32::MAX
+            XReg::MAX
         } else {
             data1.wrapping_div(data2) 
         }
     }
 
     #[cfg(feature = "m")]
-    pub fn rem(data1: u32, data2: u32) -> u32 {
+    pub fn rem(data1: XReg, data2: XReg) -> XReg {
         if data2 == 0 {
             data1
         } else {
-            (data1 as i32)
-                .wrapping_rem(data2 as i32) as u32
+            (data1 as SXReg)
+                .wrapping_rem(data2 as SXReg) as XReg
         }
     }
 
     #[cfg(feature = "m")]
-    pub fn rem_unsigned(data1: u32, data2: u32) -> u32 {
+    pub fn rem_unsigned(data1: XReg, data2: XReg) -> XReg {
         if data2 == 0 {
             data1
         } else {
@@ -108,28 +111,239 @@ impl Alu {
         }
     }
 
-    #[cfg(feature = "a")]
-    pub fn max(data1: u32, data2: u32) -> u32 {
-        std::cmp::max(data1 as i32, data2 as i32) as u32
+    #[cfg(feature = "rv64")]
+    pub fn add_word(data1: XReg, data2: XReg) -> XReg {
+        sext_w((data1 as u32).wrapping_add(data2 as u32))
+    }
+
+    #[cfg(feature = "rv64")]
+    pub fn add_signed_word(data1: XReg, data2: i32) -> XReg {
+        sext_w((data1 as u32).wrapping_add_signed(data2))
+    }
+
+    #[cfg(feature = "rv64")]
+    pub fn sub_word(data1: XReg, data2: XReg) -> XReg {
+        sext_w((data1 as u32).wrapping_sub(data2 as u32))
+    }
+
+    #[cfg(feature = "rv64")]
+    pub fn shl_logic_word(data: XReg, shift: XReg) -> XReg {
+        sext_w((data as u32) << (shift % 32))
+    }
+
+    #[cfg(feature = "rv64")]
+    pub fn shr_logic_word(data: XReg, shift: XReg) -> XReg {
+        sext_w((data as u32) >> (shift % 32))
+    }
+
+    #[cfg(feature = "rv64")]
+    pub fn shr_ar_word(data: XReg, shift: XReg) -> XReg {
+        sext_w(((data as i32) >> (shift % 32)) as u32)
+    }
+
+    #[cfg(all(feature = "m", feature = "rv64"))]
+    pub fn mul_word(data1: XReg, data2: XReg) -> XReg {
+        sext_w((data1 as u32).wrapping_mul(data2 as u32))
+    }
+
+    #[cfg(all(feature = "m", feature = "rv64"))]
+    pub fn div_word(data1: XReg, data2: XReg) -> XReg {
+        match data2 as i32 {
+            0 => XReg::MAX,
+            divisor => sext_w((data1 as i32).wrapping_div(divisor) as u32),
+        }
+    }
+
+    #[cfg(all(feature = "m", feature = "rv64"))]
+    pub fn div_unsigned_word(data1: XReg, data2: XReg) -> XReg {
+        match data2 as u32 {
+            0 => XReg::MAX,
+            divisor => sext_w((data1 as u32) / divisor),
+        }
+    }
+
+    #[cfg(all(feature = "m", feature = "rv64"))]
+    pub fn rem_word(data1: XReg, data2: XReg) -> XReg {
+        match data2 as i32 {
+            0 => sext_w(data1 as u32),
+            divisor => sext_w((data1 as i32).wrapping_rem(divisor) as u32),
+        }
     }
 
-    #[cfg(feature = "a")]
-    pub fn max_unsigned(data1: u32, data2: u32) -> u32 {
+    #[cfg(all(feature = "m", feature = "rv64"))]
+    pub fn rem_unsigned_word(data1: XReg, data2: XReg) -> XReg {
+        match data2 as u32 {
+            0 => sext_w(data1 as u32),
+            divisor => sext_w((data1 as u32) % divisor),
+        }
+    }
+
+    #[cfg(any(feature = "a", feature = "zbb"))]
+    pub fn max(data1: XReg, data2: XReg) -> XReg {
+        std::cmp::max(data1 as SXReg, data2 as SXReg) as XReg
+    }
+
+    #[cfg(any(feature = "a", feature = "zbb"))]
+    pub fn max_unsigned(data1: XReg, data2: XReg) -> XReg {
         std::cmp::max(data1, data2)
     }
 
-    #[cfg(feature = "a")]
-    pub fn min(data1: u32, data2: u32) -> u32 {
-        std::cmp::min(data1 as i32, data2 as i32) as u32
+    #[cfg(any(feature = "a", feature = "zbb"))]
+    pub fn min(data1: XReg, data2: XReg) -> XReg {
+        std::cmp::min(data1 as SXReg, data2 as SXReg) as XReg
     }
 
-    #[cfg(feature = "a")]
-    pub fn min_unsigned(data1: u32, data2: u32) -> u32 {
+    #[cfg(any(feature = "a", feature = "zbb"))]
+    pub fn min_unsigned(data1: XReg, data2: XReg) -> XReg {
         std::cmp::min(data1, data2)
     }
+
+    #[cfg(feature = "zba")]
+    pub fn shift_add(data1: XReg, data2: XReg, shift: u32) -> XReg {
+        (data1 << shift).wrapping_add(data2)
+    }
+
+    #[cfg(all(feature = "zba", feature = "rv64"))]
+    pub fn zero_extend_word(data: XReg) -> XReg {
+        data as u32 as XReg
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn and_not(data1: XReg, data2: XReg) -> XReg {
+        data1 & !data2
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn or_not(data1: XReg, data2: XReg) -> XReg {
+        data1 | !data2
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn xnor(data1: XReg, data2: XReg) -> XReg {
+        !(data1 ^ data2)
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn count_leading_zeros(data: XReg) -> XReg {
+        data.leading_zeros() as XReg
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn count_trailing_zeros(data: XReg) -> XReg {
+        data.trailing_zeros() as XReg
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn count_ones(data: XReg) -> XReg {
+        data.count_ones() as XReg
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn sign_extend_byte(data: XReg) -> XReg {
+        data as i8 as SXReg as XReg
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn sign_extend_half(data: XReg) -> XReg {
+        data as i16 as SXReg as XReg
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn zero_extend_half(data: XReg) -> XReg {
+        data as u16 as XReg
+    }
+
+    #[cfg(feature = "zbb")]
+    #[allow(clippy::unnecessary_cast)]
+    pub fn rotate_left(data: XReg, shift: XReg) -> XReg {
+        data.rotate_left(shift as u32 % XLEN)
+    }
+
+    #[cfg(feature = "zbb")]
+    #[allow(clippy::unnecessary_cast)]
+    pub fn rotate_right(data: XReg, shift: XReg) -> XReg {
+        data.rotate_right(shift as u32 % XLEN)
+    }
+
+    #[cfg(all(feature = "zbb", feature = "rv64"))]
+    pub fn count_leading_zeros_word(data: XReg) -> XReg {
+        (data as u32).leading_zeros() as XReg
+    }
+
+    #[cfg(all(feature = "zbb", feature = "rv64"))]
+    pub fn count_trailing_zeros_word(data: XReg) -> XReg {
+        (data as u32).trailing_zeros() as XReg
+    }
+
+    #[cfg(all(feature = "zbb", feature = "rv64"))]
+    pub fn count_ones_word(data: XReg) -> XReg {
+        (data as u32).count_ones() as XReg
+    }
+
+    #[cfg(all(feature = "zbb", feature = "rv64"))]
+    pub fn rotate_left_word(data: XReg, shift: XReg) -> XReg {
+        sext_w((data as u32).rotate_left((shift % 32) as u32))
+    }
+
+    #[cfg(all(feature = "zbb", feature = "rv64"))]
+    pub fn rotate_right_word(data: XReg, shift: XReg) -> XReg {
+        sext_w((data as u32).rotate_right((shift % 32) as u32))
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn or_combine_byte(data: XReg) -> XReg {
+        XReg::from_le_bytes(data.to_le_bytes().map(|b| if b != 0 { 0xff } else { 0 }))
+    }
+
+    #[cfg(feature = "zbb")]
+    pub fn reverse_bytes(data: XReg) -> XReg {
+        data.swap_bytes()
+    }
+
+    #[cfg(feature = "zbc")]
+    pub fn clmul(data1: XReg, data2: XReg) -> XReg {
+        Self::clmul_full(data1, data2) as XReg
+    }
+
+    #[cfg(feature = "zbc")]
+    pub fn clmulh(data1: XReg, data2: XReg) -> XReg {
+        (Self::clmul_full(data1, data2) >> XLEN) as XReg
+    }
+
+    #[cfg(feature = "zbc")]
+    pub fn clmulr(data1: XReg, data2: XReg) -> XReg {
+        (Self::clmul_full(data1, data2) >> (XLEN - 1)) as XReg
+    }
+
+    #[cfg(feature = "zbc")]
+    fn clmul_full(data1: XReg, data2: XReg) -> u128 {
+        (0..XLEN)
+            .filter(|i| (data2 >> i) & 1 != 0)
+            .fold(0, |acc, i| acc ^ ((data1 as u128) << i))
+    }
+
+    #[cfg(feature = "zbs")]
+    pub fn bit_clear(data: XReg, index: XReg) -> XReg {
+        data & !(1 << (index % XLEN as XReg))
+    }
+
+    #[cfg(feature = "zbs")]
+    pub fn bit_extract(data: XReg, index: XReg) -> XReg {
+        (data >> (index % XLEN as XReg)) & 1
+    }
+
+    #[cfg(feature = "zbs")]
+    pub fn bit_invert(data: XReg, index: XReg) -> XReg {
+        data ^ (1 << (index % XLEN as XReg))
+    }
+
+    #[cfg(feature = "zbs")]
+    pub fn bit_set(data: XReg, index: XReg) -> XReg {
+        data | (1 << (index % XLEN as XReg))
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "rv64")))]
 mod tests {
     use crate::engine::Alu;
 
@@ -203,4 +417,101 @@ mod tests {
         // Remainder should be 0
         assert_eq!(Alu::rem(int_min, -1_i32 as u32), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[cfg(feature = "zba")]
+    fn test_shift_add() {
+        assert_eq!(Alu::shift_add(3, 1, 2), 13);
+        assert_eq!(Alu::shift_add(0x8000_0000, 5, 1), 5);
+    }
+
+    #[test]
+    #[cfg(feature = "zbb")]
+    fn test_basic_bit_manipulation() {
+        assert_eq!(Alu::and_not(0b1100, 0b1010), 0b0100);
+        assert_eq!(Alu::xnor(0, 0), u32::MAX);
+
+        // Counting
+        assert_eq!(Alu::count_leading_zeros(0), 32);
+        assert_eq!(Alu::count_trailing_zeros(0x100), 8);
+        assert_eq!(Alu::count_ones(0xF0F0), 8);
+
+        // Extension
+        assert_eq!(Alu::sign_extend_byte(0x80), 0xFFFFFF80);
+        assert_eq!(Alu::sign_extend_half(0x12348000), 0xFFFF8000);
+        assert_eq!(Alu::zero_extend_half(0xFFFF8000), 0x8000);
+
+        // Rotation only uses the low 5 bits of the shift
+        assert_eq!(Alu::rotate_left(0x80000001, 33), 0x00000003);
+        assert_eq!(Alu::rotate_right(0x80000001, 1), 0xC0000000);
+
+        assert_eq!(Alu::or_combine_byte(0x00120300), 0x00FFFF00);
+        assert_eq!(Alu::reverse_bytes(0x12345678), 0x78563412);
+    }
+
+    #[test]
+    #[cfg(feature = "zbc")]
+    fn test_carry_less_multiplication() {
+        // (x + 1) * (x + 1) = x^2 + 1
+        assert_eq!(Alu::clmul(0b11, 0b11), 0b101);
+        assert_eq!(Alu::clmulh(0x80000000, 0x80000000), 0x40000000);
+        assert_eq!(Alu::clmulr(0x80000000, 0x80000000), 0x80000000);
+    }
+
+    #[test]
+    #[cfg(feature = "zbs")]
+    fn test_single_bit() {
+        // Index wraps to the low 5 bits
+        assert_eq!(Alu::bit_set(0, 33), 2);
+        assert_eq!(Alu::bit_clear(u32::MAX, 31), 0x7FFFFFFF);
+        assert_eq!(Alu::bit_invert(0b101, 2), 0b001);
+        assert_eq!(Alu::bit_extract(0b100, 2), 1);
+    }
+}
+
+#[cfg(all(test, feature = "rv64"))]
+mod rv64_tests {
+    use crate::engine::Alu;
+
+    #[test]
+    fn test_wide_arithmetic() {
+        assert_eq!(Alu::add(u32::MAX as u64, 1), 0x1_0000_0000);
+        assert_eq!(Alu::shl_logic(1, 63), 1 << 63);
+        // Shift amount uses the low 6 bits
+        assert_eq!(Alu::shl_logic(1, 65), 2);
+        assert_eq!(Alu::shr_ar(i64::MIN, 63), u64::MAX);
+    }
+
+    #[test]
+    fn test_word_operations() {
+        // Results are sign-extended from bit 31
+        assert_eq!(Alu::add_word(0x7FFF_FFFF, 1), 0xFFFF_FFFF_8000_0000);
+        assert_eq!(Alu::add_signed_word(0x1_0000_0000, -1), u64::MAX);
+        assert_eq!(Alu::sub_word(0, 1), u64::MAX);
+        assert_eq!(Alu::shl_logic_word(1, 31), 0xFFFF_FFFF_8000_0000);
+        // Shift amount uses the low 5 bits
+        assert_eq!(Alu::shl_logic_word(1, 33), 2);
+        assert_eq!(Alu::shr_logic_word(0xFFFF_FFFF_8000_0000, 31), 1);
+        assert_eq!(Alu::shr_ar_word(0x8000_0000, 31), u64::MAX);
+    }
+
+    #[test]
+    #[cfg(feature = "m")]
+    fn test_wide_multiplication() {
+        assert_eq!(Alu::mulh_unsigned(u64::MAX, 2), 1);
+        assert_eq!(Alu::mulh(u64::MAX, u64::MAX), 0);
+        assert_eq!(Alu::mulh_signed_unsigned(u64::MAX, 2), u64::MAX);
+        assert_eq!(Alu::div(i64::MIN as u64, u64::MAX), i64::MIN as u64);
+    }
+
+    #[test]
+    #[cfg(feature = "m")]
+    fn test_word_division() {
+        assert_eq!(Alu::mul_word(0x1_0000_0002, 0x4000_0000), 0xFFFF_FFFF_8000_0000);
+        assert_eq!(Alu::div_word(100, 0), u64::MAX);
+        assert_eq!(Alu::div_unsigned_word(100, 0x1_0000_0000), u64::MAX);
+        assert_eq!(Alu::div_word(0x8000_0000, u64::MAX), 0xFFFF_FFFF_8000_0000);
+        assert_eq!(Alu::rem_word(0x1_8000_0000, 0), 0xFFFF_FFFF_8000_0000);
+        assert_eq!(Alu::rem_unsigned_word(7, 0x1_0000_0002), 1);
+    }
+}
@@ -1,28 +1,30 @@
+use crate::core::{SXReg, XReg};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Branch;
 
 impl Branch {
-    pub fn equal(data1: u32, data2: u32) -> bool {
+    pub fn equal(data1: XReg, data2: XReg) -> bool {
         data1 == data2
     }
 
-    pub fn not_equal(data1: u32, data2: u32) -> bool {
+    pub fn not_equal(data1: XReg, data2: XReg) -> bool {
         data1 != data2
     }
 
-    pub fn less(data1: i32, data2: i32) -> bool {
+    pub fn less(data1: SXReg, data2: SXReg) -> bool {
         data1 < data2
     }
 
-    pub fn less_unsigned(data1: u32, data2: u32) -> bool {
+    pub fn less_unsigned(data1: XReg, data2: XReg) -> bool {
         data1 < data2
     }
 
-    pub fn greater_eqaul(data1: i32, data2: i32) -> bool {
+    pub fn greater_eqaul(data1: SXReg, data2: SXReg) -> bool {
         data1 >= data2
     }
 
-    pub fn greater_eqaul_unsigned(data1: u32, data2: u32) -> bool {
+    pub fn greater_eqaul_unsigned(data1: XReg, data2: XReg) -> bool {
         data1 >= data2
     }
 }
@@ -30,6 +32,7 @@ impl Branch {
 #[cfg(test)]
 mod tests {
     use super::Branch;
+    use crate::core::XReg;
 
     #[test]
     fn test_equality() {
@@ -53,14 +56,14 @@ mod tests {
 
     #[test]
     fn test_unsigned_comparison() {
-        let small = 10u32;
-        let big = 20u32;
-        let huge = u32::MAX;
+        let small: XReg = 10;
+        let big: XReg = 20;
+        let huge = XReg::MAX;
 
         assert!(Branch::less_unsigned(small, big));
         assert!(Branch::less_unsigned(big, huge));
         
         assert!(Branch::less_unsigned(10, 0xFFFFFFFF));
-        assert!(!Branch::less(10, -1_i32)); 
+        assert!(!Branch::less(10, -1)); 
     }
 }
\ No newline at end of file
@@ -0,0 +1,775 @@
+//! IEEE 754 binary floating point in software, so results and `fflags` never depend on the host FPU
+
+use std::cmp::Ordering;
+
+use RoundingMode::*;
+
+/// Exception flags, in `fflags` bit order
+pub const NX: u8 = 1 << 0;
+pub const UF: u8 = 1 << 1;
+pub const OF: u8 = 1 << 2;
+pub const DZ: u8 = 1 << 3;
+pub const NV: u8 = 1 << 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    #[default]
+    Rne = 0,
+    Rtz = 1,
+    Rdn = 2,
+    Rup = 3,
+    Rmm = 4,
+}
+
+impl TryFrom<u8> for RoundingMode {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Rne,
+            1 => Rtz,
+            2 => Rdn,
+            3 => Rup,
+            4 => Rmm,
+            _ => return Err(value),
+        })
+    }
+}
+
+/// Layout of a binary interchange format. Values are passed around as raw bits in `u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FpFormat {
+    exp_bits: u32,
+    frac_bits: u32,
+}
+
+pub const F32: FpFormat = FpFormat { exp_bits: 8, frac_bits: 23 };
+#[cfg(feature = "d")]
+pub const F64: FpFormat = FpFormat { exp_bits: 11, frac_bits: 52 };
+
+impl FpFormat {
+    fn bias(&self) -> i32 {
+        (1 << (self.exp_bits - 1)) - 1
+    }
+
+    fn max_exp(&self) -> i32 {
+        (1 << self.exp_bits) - 1
+    }
+
+    fn sign_bit(&self) -> u64 {
+        1 << (self.exp_bits + self.frac_bits)
+    }
+
+    fn frac_mask(&self) -> u64 {
+        (1 << self.frac_bits) - 1
+    }
+
+    fn quiet_bit(&self) -> u64 {
+        1 << (self.frac_bits - 1)
+    }
+
+    fn zero(&self, sign: bool) -> u64 {
+        if sign { self.sign_bit() } else { 0 }
+    }
+
+    fn inf(&self, sign: bool) -> u64 {
+        self.zero(sign) | ((self.max_exp() as u64) << self.frac_bits)
+    }
+
+    fn max_finite(&self, sign: bool) -> u64 {
+        self.inf(sign) - 1
+    }
+
+    /// The only NaN RISC-V produces
+    pub fn canonical_nan(&self) -> u64 {
+        self.inf(false) | self.quiet_bit()
+    }
+
+    fn is_nan(&self, bits: u64) -> bool {
+        matches!(self.unpack(bits), Value::Nan { .. })
+    }
+
+    fn is_snan(&self, bits: u64) -> bool {
+        matches!(self.unpack(bits), Value::Nan { signaling: true })
+    }
+
+    fn unpack(&self, bits: u64) -> Value {
+        let sign = bits & self.sign_bit() != 0;
+        let exp = ((bits >> self.frac_bits) as i32) & self.max_exp();
+        let frac = bits & self.frac_mask();
+        let min_exp = 1 - self.bias() - self.frac_bits as i32;
+
+        match (exp, frac) {
+            (0, 0) => Value::Zero(sign),
+            (0, _) => Value::Finite { sign, exp: min_exp, sig: frac as u128 },
+            (e, 0) if e == self.max_exp() => Value::Inf(sign),
+            (e, _) if e == self.max_exp() => Value::Nan { signaling: frac & self.quiet_bit() == 0 },
+            (e, _) => Value::Finite {
+                sign,
+                exp: min_exp + e - 1,
+                sig: (frac | (1 << self.frac_bits)) as u128,
+            },
+        }
+    }
+
+    /// Ordering key of a non-NaN value, where both zeros are equal
+    fn key(&self, bits: u64) -> i128 {
+        let mag = (bits & !self.sign_bit()) as i128;
+        if bits & self.sign_bit() != 0 { -mag } else { mag }
+    }
+}
+
+/// Finite values are `sig * 2^exp`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Nan { signaling: bool },
+    Inf(bool),
+    Zero(bool),
+    Finite { sign: bool, exp: i32, sig: u128 },
+}
+
+/// One operation's worth of floating-point state: the rounding mode in and the flags out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Fpu {
+    rm: RoundingMode,
+    flags: u8,
+}
+
+impl Fpu {
+    pub fn new(rm: RoundingMode) -> Self {
+        Fpu { rm, flags: 0 }
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    pub fn add(&mut self, fmt: FpFormat, a: u64, b: u64) -> u64 {
+        match (fmt.unpack(a), fmt.unpack(b)) {
+            (Value::Nan { .. }, _) | (_, Value::Nan { .. }) => self.propagate_nan(fmt, &[a, b]),
+            (Value::Inf(x), Value::Inf(y)) if x != y => self.invalid(fmt),
+            (Value::Inf(x), _) | (_, Value::Inf(x)) => fmt.inf(x),
+            (Value::Zero(x), Value::Zero(y)) => fmt.zero(if x == y { x } else { self.rm == Rdn }),
+            (Value::Zero(_), _) => b,
+            (_, Value::Zero(_)) => a,
+            (Value::Finite { sign: sa, exp: ea, sig: ma }, Value::Finite { sign: sb, exp: eb, sig: mb }) => {
+                self.add_finite(fmt, (sa, ea, ma), (sb, eb, mb))
+            },
+        }
+    }
+
+    pub fn sub(&mut self, fmt: FpFormat, a: u64, b: u64) -> u64 {
+        self.add(fmt, a, b ^ fmt.sign_bit())
+    }
+
+    pub fn mul(&mut self, fmt: FpFormat, a: u64, b: u64) -> u64 {
+        let sign = (a ^ b) & fmt.sign_bit() != 0;
+
+        match (fmt.unpack(a), fmt.unpack(b)) {
+            (Value::Nan { .. }, _) | (_, Value::Nan { .. }) => self.propagate_nan(fmt, &[a, b]),
+            (Value::Inf(_), Value::Zero(_)) | (Value::Zero(_), Value::Inf(_)) => self.invalid(fmt),
+            (Value::Inf(_), _) | (_, Value::Inf(_)) => fmt.inf(sign),
+            (Value::Zero(_), _) | (_, Value::Zero(_)) => fmt.zero(sign),
+            (Value::Finite { exp: ea, sig: ma, .. }, Value::Finite { exp: eb, sig: mb, .. }) => {
+                self.round_pack(fmt, sign, ea + eb, ma * mb)
+            },
+        }
+    }
+
+    pub fn div(&mut self, fmt: FpFormat, a: u64, b: u64) -> u64 {
+        let sign = (a ^ b) & fmt.sign_bit() != 0;
+
+        match (fmt.unpack(a), fmt.unpack(b)) {
+            (Value::Nan { .. }, _) | (_, Value::Nan { .. }) => self.propagate_nan(fmt, &[a, b]),
+            (Value::Inf(_), Value::Inf(_)) | (Value::Zero(_), Value::Zero(_)) => self.invalid(fmt),
+            (Value::Inf(_), _) => fmt.inf(sign),
+            (_, Value::Zero(_)) => {
+                self.flags |= DZ;
+                fmt.inf(sign)
+            },
+            (_, Value::Inf(_)) | (Value::Zero(_), _) => fmt.zero(sign),
+            (Value::Finite { exp: ea, sig: ma, .. }, Value::Finite { exp: eb, sig: mb, .. }) => {
+                // Dividend up to bit 126 leaves the quotient far more bits than any format needs
+                let shift = ma.leading_zeros() - 1;
+                let num = ma << shift;
+                let sticky = (num % mb != 0) as u128;
+                self.round_pack(fmt, sign, ea - shift as i32 - eb, (num / mb) | sticky)
+            },
+        }
+    }
+
+    pub fn sqrt(&mut self, fmt: FpFormat, a: u64) -> u64 {
+        match fmt.unpack(a) {
+            Value::Nan { .. } => self.propagate_nan(fmt, &[a]),
+            Value::Zero(_) => a,
+            Value::Inf(false) => a,
+            Value::Inf(true) | Value::Finite { sign: true, .. } => self.invalid(fmt),
+            Value::Finite { sign: false, mut exp, mut sig } => {
+                if exp & 1 != 0 {
+                    sig <<= 1;
+                    exp -= 1;
+                }
+                // Even shift, so the exponent can still be halved exactly
+                let shift = (sig.leading_zeros() - 1) & !1;
+                sig <<= shift;
+                exp -= shift as i32;
+
+                let (root, rem) = isqrt(sig);
+                self.round_pack(fmt, false, exp / 2, root | (rem != 0) as u128)
+            },
+        }
+    }
+
+    /// `a * b + c` with a single rounding
+    pub fn fma(&mut self, fmt: FpFormat, a: u64, b: u64, c: u64) -> u64 {
+        let (va, vb, vc) = (fmt.unpack(a), fmt.unpack(b), fmt.unpack(c));
+        let sign = (a ^ b) & fmt.sign_bit() != 0;
+
+        // Invalid even when the addend is a quiet NaN
+        if matches!((va, vb), (Value::Inf(_), Value::Zero(_)) | (Value::Zero(_), Value::Inf(_))) {
+            return self.invalid(fmt);
+        }
+        if [a, b, c].iter().any(|x| fmt.is_nan(*x)) {
+            return self.propagate_nan(fmt, &[a, b, c]);
+        }
+
+        match (va, vb, vc) {
+            (Value::Inf(_), _, Value::Inf(sc)) | (_, Value::Inf(_), Value::Inf(sc)) if sc != sign => self.invalid(fmt),
+            (Value::Inf(_), _, _) | (_, Value::Inf(_), _) => fmt.inf(sign),
+            (_, _, Value::Inf(sc)) => fmt.inf(sc),
+            (Value::Zero(_), _, Value::Zero(sc)) | (_, Value::Zero(_), Value::Zero(sc)) => {
+                fmt.zero(if sc == sign { sign } else { self.rm == Rdn })
+            },
+            (Value::Zero(_), _, _) | (_, Value::Zero(_), _) => c,
+            (Value::Finite { exp: ea, sig: ma, .. }, Value::Finite { exp: eb, sig: mb, .. }, vc) => {
+                match vc {
+                    Value::Finite { sign: sc, exp: ec, sig: mc } => {
+                        self.add_finite(fmt, (sign, ea + eb, ma * mb), (sc, ec, mc))
+                    },
+                    _ => self.round_pack(fmt, sign, ea + eb, ma * mb),
+                }
+            },
+            _ => unreachable!("NaN is handled above"),
+        }
+    }
+
+    pub fn min(&mut self, fmt: FpFormat, a: u64, b: u64) -> u64 {
+        self.min_max(fmt, a, b, Ordering::Less)
+    }
+
+    pub fn max(&mut self, fmt: FpFormat, a: u64, b: u64) -> u64 {
+        self.min_max(fmt, a, b, Ordering::Greater)
+    }
+
+    /// `feq` is a quiet comparison, only signaling NaN is invalid
+    pub fn equal(&mut self, fmt: FpFormat, a: u64, b: u64) -> bool {
+        if fmt.is_snan(a) || fmt.is_snan(b) {
+            self.flags |= NV;
+        }
+        !fmt.is_nan(a) && !fmt.is_nan(b) && fmt.key(a) == fmt.key(b)
+    }
+
+    /// `flt` and `fle` are signaling, any NaN is invalid
+    pub fn less(&mut self, fmt: FpFormat, a: u64, b: u64) -> bool {
+        self.compare(fmt, a, b).is_some_and(|ord| ord.is_lt())
+    }
+
+    pub fn less_equal(&mut self, fmt: FpFormat, a: u64, b: u64) -> bool {
+        self.compare(fmt, a, b).is_some_and(|ord| ord.is_le())
+    }
+
+    /// Round to an integer of `bits` width. Out of range and NaN saturate and raise invalid
+    pub fn float_to_int(&mut self, fmt: FpFormat, a: u64, signed: bool, bits: u32) -> u64 {
+        let (min, max) = if signed {
+            (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+        } else {
+            (0, (1i128 << bits) - 1)
+        };
+
+        let value = match fmt.unpack(a) {
+            Value::Nan { .. } | Value::Inf(false) => {
+                self.flags |= NV;
+                max
+            },
+            Value::Inf(true) => {
+                self.flags |= NV;
+                min
+            },
+            Value::Zero(_) => 0,
+            // Far beyond any integer width
+            Value::Finite { sign, exp, .. } if exp > 64 => {
+                self.flags |= NV;
+                if sign { min } else { max }
+            },
+            Value::Finite { sign, exp, sig } => {
+                let (mag, inexact) = if exp >= 0 {
+                    (sig << exp, false)
+                } else {
+                    self.shift_round(sign, sig, -exp)
+                };
+                let value = if sign { -(mag as i128) } else { mag as i128 };
+
+                if value < min || value > max {
+                    self.flags |= NV;
+                    if sign { min } else { max }
+                } else {
+                    if inexact {
+                        self.flags |= NX;
+                    }
+                    value
+                }
+            },
+        };
+        value as u64
+    }
+
+    /// `value` is sign-extended to 64 bits when `signed`
+    pub fn int_to_float(&mut self, fmt: FpFormat, value: u64, signed: bool) -> u64 {
+        let (sign, mag) = if signed && (value as i64) < 0 {
+            (true, (value as i64).unsigned_abs())
+        } else {
+            (false, value)
+        };
+        self.round_pack(fmt, sign, 0, mag as u128)
+    }
+
+    /// Convert between formats, e.g. `fcvt.s.d`
+    #[cfg(feature = "d")]
+    pub fn convert(&mut self, from: FpFormat, to: FpFormat, a: u64) -> u64 {
+        match from.unpack(a) {
+            Value::Nan { .. } => {
+                self.propagate_nan(from, &[a]);
+                to.canonical_nan()
+            },
+            Value::Inf(sign) => to.inf(sign),
+            Value::Zero(sign) => to.zero(sign),
+            Value::Finite { sign, exp, sig } => self.round_pack(to, sign, exp, sig),
+        }
+    }
+
+    pub fn sgnj(fmt: FpFormat, a: u64, b: u64) -> u64 {
+        (a & !fmt.sign_bit()) | (b & fmt.sign_bit())
+    }
+
+    pub fn sgnjn(fmt: FpFormat, a: u64, b: u64) -> u64 {
+        (a & !fmt.sign_bit()) | (!b & fmt.sign_bit())
+    }
+
+    pub fn sgnjx(fmt: FpFormat, a: u64, b: u64) -> u64 {
+        a ^ (b & fmt.sign_bit())
+    }
+
+    /// `fclass` mask, one bit set
+    pub fn classify(fmt: FpFormat, a: u64) -> u32 {
+        let bit = match fmt.unpack(a) {
+            Value::Inf(true) => 0,
+            Value::Finite { sign: true, .. } if a & (fmt.max_exp() as u64) << fmt.frac_bits != 0 => 1,
+            Value::Finite { sign: true, .. } => 2,
+            Value::Zero(true) => 3,
+            Value::Zero(false) => 4,
+            Value::Finite { sign: false, .. } if a & (fmt.max_exp() as u64) << fmt.frac_bits == 0 => 5,
+            Value::Finite { sign: false, .. } => 6,
+            Value::Inf(false) => 7,
+            Value::Nan { signaling: true } => 8,
+            Value::Nan { signaling: false } => 9,
+        };
+        1 << bit
+    }
+
+    fn min_max(&mut self, fmt: FpFormat, a: u64, b: u64, want: Ordering) -> u64 {
+        if fmt.is_snan(a) || fmt.is_snan(b) {
+            self.flags |= NV;
+        }
+        match (fmt.is_nan(a), fmt.is_nan(b)) {
+            (true, true) => fmt.canonical_nan(),
+            (true, false) => b,
+            (false, true) => a,
+            // -0 is less than +0 here
+            _ => match fmt.key(a).cmp(&fmt.key(b)) {
+                Ordering::Equal => if (a & fmt.sign_bit() != 0) == (want == Ordering::Less) { a } else { b },
+                ord => if ord == want { a } else { b },
+            },
+        }
+    }
+
+    fn compare(&mut self, fmt: FpFormat, a: u64, b: u64) -> Option<Ordering> {
+        if fmt.is_nan(a) || fmt.is_nan(b) {
+            self.flags |= NV;
+            None
+        } else {
+            Some(fmt.key(a).cmp(&fmt.key(b)))
+        }
+    }
+
+    fn invalid(&mut self, fmt: FpFormat) -> u64 {
+        self.flags |= NV;
+        fmt.canonical_nan()
+    }
+
+    fn propagate_nan(&mut self, fmt: FpFormat, ops: &[u64]) -> u64 {
+        if ops.iter().any(|op| fmt.is_snan(*op)) {
+            self.flags |= NV;
+        }
+        fmt.canonical_nan()
+    }
+
+    fn add_finite(&mut self, fmt: FpFormat, a: (bool, i32, u128), b: (bool, i32, u128)) -> u64 {
+        // Both leading bits at 125, then the smaller one is shifted down with a sticky bit
+        let normalize = |(sign, exp, sig): (bool, i32, u128)| {
+            let shift = sig.leading_zeros() - 2;
+            (sign, exp - shift as i32, sig << shift)
+        };
+        let (a, b) = (normalize(a), normalize(b));
+        let ((s_hi, e_hi, m_hi), (s_lo, e_lo, m_lo)) = if a.1 >= b.1 { (a, b) } else { (b, a) };
+        let m_lo = shift_right_jam(m_lo, (e_hi - e_lo) as u32);
+
+        if s_hi == s_lo {
+            return self.round_pack(fmt, s_hi, e_hi, m_hi + m_lo);
+        }
+        match m_hi.cmp(&m_lo) {
+            Ordering::Greater => self.round_pack(fmt, s_hi, e_hi, m_hi - m_lo),
+            Ordering::Less    => self.round_pack(fmt, s_lo, e_hi, m_lo - m_hi),
+            Ordering::Equal   => fmt.zero(self.rm == Rdn),
+        }
+    }
+
+    /// Round `sig * 2^exp` to `fmt`, raising NX, UF and OF as needed.
+    /// Tininess is detected after rounding, as RISC-V requires
+    fn round_pack(&mut self, fmt: FpFormat, sign: bool, exp: i32, sig: u128) -> u64 {
+        if sig == 0 {
+            return fmt.zero(sign);
+        }
+        let prec = fmt.frac_bits as i32 + 1;
+        let len = 128 - sig.leading_zeros() as i32;
+        // Biased exponent of the leading bit
+        let e = exp + len - 1 + fmt.bias();
+
+        if e >= fmt.max_exp() {
+            return self.overflow(fmt, sign);
+        }
+
+        let tiny = e < 0 || (e == 0 && self.shift_round(sign, sig, len - prec).0 >> prec == 0);
+
+        let (kept, inexact) = if e >= 1 {
+            self.shift_round(sign, sig, len - prec)
+        } else {
+            self.shift_round(sign, sig, len - prec + 1 - e)
+        };
+        // The implicit bit of `kept` carries into the exponent field, so does a rounding carry
+        let base = if e >= 1 { (e - 1) as u64 } else { 0 };
+        let bits = (base << fmt.frac_bits) + kept as u64;
+
+        if bits >= fmt.inf(false) {
+            return self.overflow(fmt, sign);
+        }
+        if inexact {
+            self.flags |= NX;
+            if tiny {
+                self.flags |= UF;
+            }
+        }
+        bits | fmt.zero(sign)
+    }
+
+    fn overflow(&mut self, fmt: FpFormat, sign: bool) -> u64 {
+        self.flags |= OF | NX;
+        let to_inf = match self.rm {
+            Rne | Rmm => true,
+            Rtz       => false,
+            Rdn       => sign,
+            Rup       => !sign,
+        };
+        if to_inf { fmt.inf(sign) } else { fmt.max_finite(sign) }
+    }
+
+    /// Drop the low `shift` bits with rounding, a negative shift moves left.
+    /// Returns the rounded value and whether anything was lost
+    fn shift_round(&self, sign: bool, sig: u128, shift: i32) -> (u128, bool) {
+        if shift <= 0 {
+            return (sig << -shift, false);
+        }
+        let (kept, rem) = if shift >= 128 {
+            (0, sig)
+        } else {
+            (sig >> shift, sig & ((1 << shift) - 1))
+        };
+        if rem == 0 {
+            return (kept, false);
+        }
+        // Past 128 bits every remainder is below half
+        let half = if shift > 128 { Ordering::Less } else { rem.cmp(&(1 << (shift - 1))) };
+
+        let up = match self.rm {
+            Rne => half == Ordering::Greater || (half == Ordering::Equal && kept & 1 == 1),
+            Rmm => half != Ordering::Less,
+            Rtz => false,
+            Rdn => sign,
+            Rup => !sign,
+        };
+        (kept + up as u128, true)
+    }
+}
+
+fn shift_right_jam(sig: u128, shift: u32) -> u128 {
+    if shift == 0 {
+        sig
+    } else if shift >= 128 {
+        (sig != 0) as u128
+    } else {
+        (sig >> shift) | ((sig & ((1 << shift) - 1)) != 0) as u128
+    }
+}
+
+/// Integer square root with the remainder
+fn isqrt(n: u128) -> (u128, u128) {
+    let mut rem = n;
+    let mut root = 0u128;
+    let mut bit = 1u128 << 126;
+
+    while bit > n {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if rem >= root + bit {
+            rem -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    (root, rem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE: u64 = 0x3f80_0000;
+    const TWO: u64 = 0x4000_0000;
+    const THREE: u64 = 0x4040_0000;
+
+    /// Random bit patterns with every exponent class well represented
+    fn patterns() -> Vec<u32> {
+        let mut seed = 0x1234_5678_9abc_def0u64;
+        let mut next = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 32) as u32
+        };
+        let mut res: Vec<u32> = (0..400).map(|_| next()).collect();
+        // Near the subnormal boundary and the top of the range
+        res.extend((0..200).map(|_| next() & 0x80ff_ffff));
+        res.extend((0..200).map(|_| (next() & 0x807f_ffff) | 0x7f00_0000));
+        res.extend([0, 0x8000_0000, 1, 0x0080_0000, 0x7f7f_ffff, 0x7f80_0000, 0xff80_0000]);
+        res
+    }
+
+    fn same(ours: u64, host: f32) -> bool {
+        if host.is_nan() {
+            ours == F32.canonical_nan()
+        } else {
+            ours == host.to_bits() as u64
+        }
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.add(F32, ONE, TWO), THREE);
+        assert_eq!(fpu.sub(F32, ONE, ONE), 0);
+        assert_eq!(fpu.mul(F32, TWO, THREE), 0x40c0_0000);
+        assert_eq!(fpu.sqrt(F32, 0x4080_0000), TWO);
+        assert_eq!(fpu.flags(), 0);
+
+        // 1 / 3 is inexact
+        assert_eq!(fpu.div(F32, ONE, THREE), 0x3eaa_aaab);
+        assert_eq!(fpu.flags(), NX);
+    }
+
+    #[test]
+    fn test_against_host() {
+        let pats = patterns();
+        for (i, &a) in pats.iter().enumerate() {
+            let b = pats[(i * 7 + 3) % pats.len()];
+            let c = pats[(i * 13 + 5) % pats.len()];
+            let (fa, fb, fc) = (f32::from_bits(a), f32::from_bits(b), f32::from_bits(c));
+            let (a, b, c) = (a as u64, b as u64, c as u64);
+            let mut fpu = Fpu::new(Rne);
+
+            assert!(same(fpu.add(F32, a, b), fa + fb), "{:#x} + {:#x}", a, b);
+            assert!(same(fpu.sub(F32, a, b), fa - fb), "{:#x} - {:#x}", a, b);
+            assert!(same(fpu.mul(F32, a, b), fa * fb), "{:#x} * {:#x}", a, b);
+            assert!(same(fpu.div(F32, a, b), fa / fb), "{:#x} / {:#x}", a, b);
+            assert!(same(fpu.sqrt(F32, a), fa.sqrt()), "sqrt {:#x}", a);
+            assert!(same(fpu.fma(F32, a, b, c), fa.mul_add(fb, fc)), "fma {:#x} {:#x} {:#x}", a, b, c);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "d")]
+    fn test_double_against_host() {
+        let pats: Vec<u64> = patterns().chunks(2)
+            .map(|pair| ((pair[0] as u64) << 32) | pair[pair.len() - 1] as u64)
+            .chain([0, 1, 0x0010_0000_0000_0000, 0x7fef_ffff_ffff_ffff, 0x7ff0_0000_0000_0000])
+            .collect();
+        let same = |ours: u64, host: f64| if host.is_nan() {
+            ours == F64.canonical_nan()
+        } else {
+            ours == host.to_bits()
+        };
+
+        for (i, &a) in pats.iter().enumerate() {
+            let b = pats[(i * 7 + 3) % pats.len()];
+            let c = pats[(i * 13 + 5) % pats.len()];
+            let (fa, fb, fc) = (f64::from_bits(a), f64::from_bits(b), f64::from_bits(c));
+            let mut fpu = Fpu::new(Rne);
+
+            assert!(same(fpu.add(F64, a, b), fa + fb), "{:#x} + {:#x}", a, b);
+            assert!(same(fpu.mul(F64, a, b), fa * fb), "{:#x} * {:#x}", a, b);
+            assert!(same(fpu.div(F64, a, b), fa / fb), "{:#x} / {:#x}", a, b);
+            assert!(same(fpu.sqrt(F64, a), fa.sqrt()), "sqrt {:#x}", a);
+            assert!(same(fpu.fma(F64, a, b, c), fa.mul_add(fb, fc)), "fma {:#x} {:#x} {:#x}", a, b, c);
+            let single = if (fa as f32).is_nan() { F32.canonical_nan() } else { (fa as f32).to_bits() as u64 };
+            assert_eq!(fpu.convert(F64, F32, a), single, "fcvt.s.d {:#x}", a);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "d")]
+    fn test_convert() {
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.convert(F32, F64, ONE), 1.0f64.to_bits());
+        assert_eq!(fpu.convert(F32, F64, 0x0000_0001), (f32::from_bits(1) as f64).to_bits());
+        assert_eq!(fpu.flags(), 0);
+
+        // Signaling NaN is invalid and becomes the canonical NaN of the target
+        assert_eq!(fpu.convert(F32, F64, 0x7f80_0001), F64.canonical_nan());
+        assert_eq!(fpu.flags(), NV);
+
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.convert(F64, F32, f64::MAX.to_bits()), 0x7f80_0000);
+        assert_eq!(fpu.flags(), OF | NX);
+
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.float_to_int(F64, (-2.5f64).to_bits(), true, 32) as u32, -2i32 as u32);
+        assert_eq!(fpu.int_to_float(F64, i32::MIN as i64 as u64, true), (i32::MIN as f64).to_bits());
+        assert_eq!(fpu.flags(), NX);
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        // 1 + 2^-24 is exactly half an ulp above 1
+        let half_ulp = 0x3380_0000;
+        let expect = [(Rne, ONE), (Rtz, ONE), (Rdn, ONE), (Rup, ONE + 1), (Rmm, ONE + 1)];
+        for (rm, res) in expect {
+            let mut fpu = Fpu::new(rm);
+            assert_eq!(fpu.add(F32, ONE, half_ulp), res, "{:?}", rm);
+            assert_eq!(fpu.flags(), NX);
+        }
+
+        // x - x is -0 only when rounding down
+        assert_eq!(Fpu::new(Rdn).sub(F32, ONE, ONE), 0x8000_0000);
+    }
+
+    #[test]
+    fn test_flags() {
+        let max = 0x7f7f_ffff;
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.mul(F32, max, TWO), 0x7f80_0000);
+        assert_eq!(fpu.flags(), OF | NX);
+
+        // Round toward zero saturates at the largest finite value
+        let mut fpu = Fpu::new(Rtz);
+        assert_eq!(fpu.mul(F32, max, TWO), max);
+
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.div(F32, ONE, 0), 0x7f80_0000);
+        assert_eq!(fpu.flags(), DZ);
+
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.sqrt(F32, 0xbf80_0000), F32.canonical_nan());
+        assert_eq!(fpu.flags(), NV);
+
+        // Smallest normal / 3 is tiny and inexact
+        let mut fpu = Fpu::new(Rne);
+        fpu.div(F32, 0x0080_0000, THREE);
+        assert_eq!(fpu.flags(), UF | NX);
+
+        // Tininess after rounding: rounds up to the smallest normal, so no underflow
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.mul(F32, 0x007f_ffff, 0x3f80_0001), 0x0080_0000);
+        assert_eq!(fpu.flags(), NX);
+
+        // Invalid even with a quiet NaN addend
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.fma(F32, 0x7f80_0000, 0, 0x7fc0_0000), F32.canonical_nan());
+        assert_eq!(fpu.flags(), NV);
+    }
+
+    #[test]
+    fn test_compare_and_min_max() {
+        let snan = 0x7f80_0001;
+        let qnan = 0x7fc0_0000;
+        let mut fpu = Fpu::new(Rne);
+
+        assert!(fpu.equal(F32, 0, 0x8000_0000));
+        assert!(!fpu.equal(F32, qnan, qnan));
+        assert_eq!(fpu.flags(), 0);
+        assert!(!fpu.equal(F32, snan, ONE));
+        assert_eq!(fpu.flags(), NV);
+
+        let mut fpu = Fpu::new(Rne);
+        assert!(!fpu.less(F32, qnan, ONE));
+        assert_eq!(fpu.flags(), NV);
+        assert!(fpu.less_equal(F32, 0xbf80_0000, 0x8000_0000));
+
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.min(F32, 0, 0x8000_0000), 0x8000_0000);
+        assert_eq!(fpu.max(F32, 0x8000_0000, 0), 0);
+        assert_eq!(fpu.min(F32, qnan, TWO), TWO);
+        assert_eq!(fpu.max(F32, qnan, qnan), F32.canonical_nan());
+        assert_eq!(fpu.flags(), 0);
+        assert_eq!(fpu.max(F32, snan, TWO), TWO);
+        assert_eq!(fpu.flags(), NV);
+    }
+
+    #[test]
+    fn test_int_conversion() {
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.float_to_int(F32, 0x3fc0_0000, true, 32), 2); // 1.5 ties to even
+        assert_eq!(fpu.float_to_int(F32, 0x4020_0000, true, 32), 2); // 2.5
+        assert_eq!(fpu.flags(), NX);
+
+        let mut fpu = Fpu::new(Rtz);
+        assert_eq!(fpu.float_to_int(F32, 0xbfc0_0000, true, 32) as u32, -1i32 as u32);
+        // -0.5 rounds to 0, which is in range for unsigned
+        assert_eq!(fpu.float_to_int(F32, 0xbf00_0000, false, 32), 0);
+        assert_eq!(fpu.flags(), NX);
+
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.float_to_int(F32, 0xbf80_0000, false, 32), 0);
+        assert_eq!(fpu.float_to_int(F32, 0x7fc0_0000, true, 32), i32::MAX as u64);
+        assert_eq!(fpu.float_to_int(F32, 0x4f00_0000, true, 32), i32::MAX as u64); // 2^31
+        assert_eq!(fpu.float_to_int(F32, 0xff80_0000, false, 32), 0);
+        assert_eq!(fpu.flags(), NV);
+
+        let mut fpu = Fpu::new(Rne);
+        assert_eq!(fpu.int_to_float(F32, -3i64 as u64, true), 0xc040_0000);
+        assert_eq!(fpu.int_to_float(F32, u32::MAX as u64, false), 0x4f80_0000);
+        assert_eq!(fpu.flags(), NX);
+    }
+
+    #[test]
+    fn test_classify_and_sign() {
+        assert_eq!(Fpu::classify(F32, 0xff80_0000), 1 << 0);
+        assert_eq!(Fpu::classify(F32, 0x8000_0001), 1 << 2);
+        assert_eq!(Fpu::classify(F32, 0), 1 << 4);
+        assert_eq!(Fpu::classify(F32, ONE), 1 << 6);
+        assert_eq!(Fpu::classify(F32, 0x7f80_0001), 1 << 8);
+        assert_eq!(Fpu::classify(F32, 0x7fc0_0000), 1 << 9);
+
+        assert_eq!(Fpu::sgnjn(F32, ONE, ONE), 0xbf80_0000);
+        assert_eq!(Fpu::sgnjx(F32, 0xbf80_0000, 0xbf80_0000), ONE);
+        assert_eq!(Fpu::sgnj(F32, ONE, 0x8000_0000), 0xbf80_0000);
+    }
+}
@@ -1,14 +1,45 @@
+use std::cell::RefMut;
+
 use crate::{Exception, Result};
 #[cfg(feature = "zicsr")]
 use crate::core::{CsrFile, PrivilegeMode};
 #[cfg(feature = "s")]
 use crate::core::Mmu;
 use crate::core::access::{Access, AccessType, Physical, Virtual};
-use crate::device::bus::SystemBus;
+use crate::core::{SXReg, XReg};
+use crate::device::bus::{SharedBus, SystemBus};
+use crate::device::memory::PAGE_SIZE;
+#[cfg(feature = "a")]
+use crate::device::pma::AmoClass;
+use crate::device::Device;
+
+/// What a load or store that is not aligned to its size does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MisalignedPolicy {
+    /// Done in pieces by the emulator, a page-crossing access is translated and checked per page
+    #[default]
+    Emulate,
+    /// Raise an address-misaligned exception with the address in `mtval`
+    Trap,
+}
 
-#[derive(Debug, PartialEq, Eq)]
+impl std::fmt::Display for MisalignedPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MisalignedPolicy::Emulate => f.write_str("Emulate"),
+            MisalignedPolicy::Trap    => f.write_str("Trap"),
+        }
+    }
+}
+
+// Physical pieces of one access, the second one only exists when it crosses a page
+type Split = (Access<Physical>, usize, Option<Access<Physical>>);
+
+/// Borrows the shared bus for as long as it lives
+#[derive(Debug)]
 pub struct Lsu<'a> {
-    bus: &'a mut SystemBus,
+    bus: RefMut<'a, SystemBus>,
+    misaligned: MisalignedPolicy,
     #[cfg(feature = "s")] mmu: &'a mut Mmu,
     #[cfg(feature = "zicsr")] csrs: &'a CsrFile,
     #[cfg(feature = "zicsr")] mode: PrivilegeMode, 
@@ -16,124 +47,123 @@ pub struct Lsu<'a> {
 
 impl<'a> Lsu<'a> {
     pub fn new(
-        bus: &'a mut SystemBus, 
+        bus: &'a SharedBus, 
+        misaligned: MisalignedPolicy,
         #[cfg(feature = "s")] mmu: &'a mut Mmu, 
         #[cfg(feature = "zicsr")] csrs: &'a CsrFile, 
         #[cfg(feature = "zicsr")] mode: PrivilegeMode
     ) -> Self {
         Self { 
-            bus,
+            bus: bus.borrow_mut(),
+            misaligned,
             #[cfg(feature = "s")] mmu,  
             #[cfg(feature = "zicsr")] csrs, 
             #[cfg(feature = "zicsr")] mode 
         }
     }
 
-    pub fn load(&mut self, src: u32, offset: i32, num: usize) -> Result<u32> {
-        let addr = src.wrapping_add_signed(offset);
-        let va_access = Access::new(addr, AccessType::Load);
-        let pa_access = self.pre_work(va_access, num)?;
+    pub fn load(&mut self, src: XReg, offset: i32, num: usize) -> Result<XReg> {
+        let addr = src.wrapping_add_signed(offset as SXReg);
+        let mut bytes = [0; size_of::<XReg>()];
+        self.read(addr, &mut bytes[..num])?;
+        Ok(XReg::from_le_bytes(bytes))
+    }
 
-        self.bus.read_u32_bytes(pa_access, num, false).map_err(|e| match e {
-            Exception::LoadAccessFault(_)  => Exception::LoadAccessFault(addr),
-            _ => e,
-        })
+    pub fn load_signed(&mut self, src: XReg, offset: i32, num: usize) -> Result<XReg> {
+        let addr = src.wrapping_add_signed(offset as SXReg);
+        let mut bytes = [0; size_of::<XReg>()];
+        self.read(addr, &mut bytes[..num])?;
+
+        if bytes[num - 1] & 0x80 != 0 {
+            bytes[num..].fill(0xff);
+        }
+        Ok(XReg::from_le_bytes(bytes))
     }
 
-    pub fn load_signed(&mut self, src: u32, offset: i32, num: usize) -> Result<u32> {
-        let addr = src.wrapping_add_signed(offset);
-        let va_access = Access::new(addr, AccessType::Load);
-        let pa_access = self.pre_work(va_access, num)?;
+    /// `fld` moves 64 bits at once, wider than any integer access on RV32
+    #[cfg(feature = "d")]
+    pub fn load_double(&mut self, src: XReg, offset: i32) -> Result<u64> {
+        let addr = src.wrapping_add_signed(offset as SXReg);
+        let mut bytes = [0; 8];
+        self.read(addr, &mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
 
-        self.bus.read_u32_bytes(pa_access, num, true).map_err(|e| match e {
-            Exception::LoadAccessFault(_)  => Exception::LoadAccessFault(addr),
-            _ => e,
-        })
+    #[cfg(feature = "d")]
+    pub fn store_double(&mut self, des: XReg, src: u64, offset: i32) -> Result<()> {
+        let addr = des.wrapping_add_signed(offset as SXReg);
+        self.write(addr, &src.to_le_bytes())
     }
 
+    /// `lr`, the reservation is made on the bus under `hart`
     #[cfg(feature = "a")]
-    pub fn atomic_load(&mut self, src: u32) -> Result<(u32, u32)> {
+    pub fn atomic_load(&mut self, src: XReg, num: usize, hart: usize) -> Result<XReg> {
         let addr = src;
-        if addr & 0b11 != 0 {
-            return Err(Exception::LoadAddressMisaligned);
+        if addr & (num as XReg - 1) != 0 {
+            return Err(Exception::LoadAddressMisaligned(addr));
         }
 
         let va_access = Access::new(addr, AccessType::Load);
-        let pa_access = self.pre_work(va_access, 4)?;
+        let pa_access = self.pre_work(va_access, num)?;
+        self.bus.pma(pa_access.addr).check_lrsc(va_access.bypass())?;
 
-        let res = self.bus.read_u32_bytes(pa_access, 4, false).map_err(|e| match e {
+        let res = self.bus.read_xreg_bytes(pa_access, num, true).map_err(|e| match e {
             Exception::LoadAccessFault(_)  => Exception::LoadAccessFault(addr),
             _ => e,
         })?;
-        Ok((res, pa_access.addr))
+        self.bus.reserve(hart, pa_access.addr);
+        Ok(res)
     }
 
-    pub fn store(&mut self, des: u32, src: u32, offset: i32, num: usize,
-        #[cfg(feature = "a")] reservation: &mut Option<u32>) -> Result<()> {
-        let addr = des.wrapping_add_signed(offset);
-        let va_access = Access::new(addr, AccessType::Store);
-        let pa_access = self.pre_work(va_access, num)?;
-
-        #[cfg(feature = "a")]
-        if let Some(addr) = *reservation && addr == pa_access.addr {
-            *reservation = None;
-        }
-
-
-        self.bus.write_u32_bytes(pa_access, src, num).map_err(|e| match e {
-            Exception::StoreOrAmoAccessFault(_) => Exception::StoreOrAmoAccessFault(addr),
-            _ => e,
-        })
+    /// Plain store, the bus drops any reservation it overlaps
+    pub fn store(&mut self, des: XReg, src: XReg, offset: i32, num: usize) -> Result<()> {
+        let addr = des.wrapping_add_signed(offset as SXReg);
+        self.write(addr, &src.to_le_bytes()[..num])
     }
 
+    /// Misaligned AMOs always trap, whatever the policy
     #[cfg(feature = "a")]
-    pub fn atomic_store(&mut self, des: u32, src: u32, reservation: &mut Option<u32>) -> Result<bool> {
+    pub fn atomic_store(&mut self, des: XReg, src: XReg, num: usize, hart: usize) -> Result<bool> {
         let addr = des;
-        if addr & 0b11 != 0 {
-            return Err(Exception::LoadAddressMisaligned);
+        if addr & (num as XReg - 1) != 0 {
+            return Err(Exception::StoreOrAmoAddressMisaligned(addr));
         }
         let va_access = Access::new(addr, AccessType::Store);
-        let pa_access = self.pre_work(va_access, 4)?;
+        let pa_access = self.pre_work(va_access, num)?;
+        self.bus.pma(pa_access.addr).check_lrsc(va_access.bypass())?;
 
-        match reservation {
-            Some(addr) => if *addr != pa_access.addr {
-                return Ok(false);
-            },
-            None => return Ok(false),
+        if self.bus.take_reservation(hart) != Some(pa_access.addr) {
+            return Ok(false);
         }
 
-        *reservation = None;
-
-        self.bus.write_u32_bytes(pa_access, src, 4).map_err(|e| match e {
+        self.bus.write_xreg_bytes(pa_access, src, num).map_err(|e| match e {
             Exception::StoreOrAmoAccessFault(_) => Exception::StoreOrAmoAccessFault(addr),
             _ => e,
         })?;
         Ok(true)
     }
 
+    /// `class` is what the region must support for this AMO
     #[cfg(feature = "a")]
-    pub fn atomic_operate<F>(&mut self, des: u32, data: u32, ope: F, reservation: &mut Option<u32>) -> Result<u32> 
-        where F: Fn(u32, u32) -> u32
+    pub fn atomic_operate<F>(&mut self, des: XReg, data: XReg, num: usize, class: AmoClass, ope: F) -> Result<XReg> 
+        where F: Fn(XReg, XReg) -> XReg
     {
         let addr = des;
-        if addr & 0b11 != 0 {
-            return Err(Exception::LoadAddressMisaligned);
+        if addr & (num as XReg - 1) != 0 {
+            return Err(Exception::StoreOrAmoAddressMisaligned(addr));
         }
         let va_access = Access::new(addr, AccessType::Amo);
-        let pa_access = self.pre_work(va_access, 4)?;
-
-        if let Some(addr) = *reservation && addr == pa_access.addr {
-            *reservation = None;
-        }
+        let pa_access = self.pre_work(va_access, num)?;
+        self.bus.pma(pa_access.addr).check_amo(va_access.bypass(), class)?;
 
-        let tmp = self.bus.read_u32_bytes(pa_access, 4, false).map_err(|e| match e {
+        let tmp = self.bus.read_xreg_bytes(pa_access, num, true).map_err(|e| match e {
             Exception::StoreOrAmoAccessFault(_)  => Exception::StoreOrAmoAccessFault(addr),
             _ => e,
         })?;
 
         let res_data = ope(tmp, data);
 
-        self.bus.write_u32_bytes(pa_access, res_data, 4).map_err(|e| match e {
+        self.bus.write_xreg_bytes(pa_access, res_data, num).map_err(|e| match e {
             Exception::StoreOrAmoAccessFault(_) => Exception::StoreOrAmoAccessFault(addr),
             _ => e,
         })?;
@@ -141,126 +171,248 @@ impl<'a> Lsu<'a> {
         Ok(tmp)
     }
 
+    fn read(&mut self, addr: XReg, des: &mut [u8]) -> Result<()> {
+        let (head, head_len, tail) = self.split(Access::new(addr, AccessType::Load), des.len())?;
+        let (head_des, tail_des) = des.split_at_mut(head_len);
+
+        let mut res = self.bus.read_bytes(head, head_len, head_des);
+        if let Some(tail) = tail && res.is_ok() {
+            res = self.bus.read_bytes(tail, tail_des.len(), tail_des);
+        }
+        res.map_err(|e| match e {
+            Exception::LoadAccessFault(_)  => Exception::LoadAccessFault(addr),
+            _ => e,
+        })
+    }
+
+    fn write(&mut self, addr: XReg, src: &[u8]) -> Result<()> {
+        let (head, head_len, tail) = self.split(Access::new(addr, AccessType::Store), src.len())?;
+        let (head_src, tail_src) = src.split_at(head_len);
+
+        let mut res = self.bus.write_bytes(head, head_len, head_src);
+        if let Some(tail) = tail && res.is_ok() {
+            res = self.bus.write_bytes(tail, tail_src.len(), tail_src);
+        }
+        res.map_err(|e| match e {
+            Exception::StoreOrAmoAccessFault(_) => Exception::StoreOrAmoAccessFault(addr),
+            _ => e,
+        })
+    }
+
+    /// Apply the misaligned policy, then translate and check both pages before any byte moves
+    fn split(&mut self, va_access: Access<Virtual>, num: usize) -> Result<Split> {
+        let addr = va_access.addr;
+        if addr & (num as XReg - 1) == 0 {
+            return Ok((self.pre_work(va_access, num)?, num, None));
+        }
+
+        if self.misaligned == MisalignedPolicy::Trap {
+            return Err(match va_access.kind {
+                AccessType::Load => Exception::LoadAddressMisaligned(addr),
+                _ => Exception::StoreOrAmoAddressMisaligned(addr),
+            });
+        }
+
+        let head_len = num.min(PAGE_SIZE - addr as usize % PAGE_SIZE);
+        let head = self.pre_work(va_access, head_len)?;
+        let tail = if head_len < num {
+            let tail_access = Access::new(addr.wrapping_add(head_len as XReg), va_access.kind);
+            Some(self.pre_work(tail_access, num - head_len)?)
+        } else {
+            None
+        };
+        Ok((head, head_len, tail))
+    }
+
     #[allow(unused_variables)]
     fn pre_work(&mut self, va_access: Access<Virtual>, num: usize) -> Result<Access<Physical>> { 
+        #[cfg(feature = "zicsr")]
+        self.csrs.check_triggers(va_access.kind, va_access.addr, num, self.mode)?;
+
         #[cfg(not(feature = "s"))]
         let pa_access = va_access.bypass();
 
         #[cfg(feature = "s")]
             let pa_access = self.mmu.translate(
-                va_access, self.mode, self.csrs, self.bus
+                va_access, self.mode, self.csrs, &mut self.bus
             )?;   
             
-        #[cfg(feature = "zicsr")] {
-            self.csrs.pmp_check(pa_access, num, self.mode).map_err(|e| match e {
-                Exception::LoadAccessFault(_)  => Exception::LoadAccessFault(va_access.addr),
-                Exception::StoreOrAmoAccessFault(_) => Exception::StoreOrAmoAccessFault(va_access.addr),
-                _ => e,
-            })?;
-        }
+        let to_virtual = |e| match e {
+            Exception::LoadAccessFault(_)  => Exception::LoadAccessFault(va_access.addr),
+            Exception::StoreOrAmoAccessFault(_) => Exception::StoreOrAmoAccessFault(va_access.addr),
+            _ => e,
+        };
+        #[cfg(feature = "zicsr")]
+        self.csrs.pmp_check(pa_access, num, self.csrs.data_mode(self.mode)).map_err(to_virtual)?;
+        self.bus.pma_check(pa_access, num).map_err(to_virtual)?;
         Ok(pa_access)      
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Lsu;
-    use crate::device::bus::{SystemBus, DRAM_BASE_ADDR};
+    use super::{Lsu, MisalignedPolicy};
+    #[cfg(feature = "a")]
+    use crate::device::pma::AmoClass;
+    use crate::Exception;
+    use crate::device::memory::PAGE_SIZE;
+    use crate::core::{SXReg, XReg};
+    use crate::device::bus::{SharedBus, DRAM_BASE_ADDR};
     #[cfg(feature = "zicsr")]
     use crate::core::{CsrFile, PrivilegeMode};
     #[cfg(feature = "s")]
     use crate::core::Mmu;
 
-    #[test]
-    fn test_store_load_word() {
-        let mut bus = SystemBus::default();
+    /// Runs `test` on an `Lsu` over a fresh bus, in machine mode with no translation
+    fn with_lsu<F>(misaligned: MisalignedPolicy, test: F)
+        where F: FnOnce(&mut Lsu)
+    {
+        let bus = SharedBus::default();
         #[cfg(feature = "s")]
         let mut mmu = Mmu::default();
         #[cfg(feature = "zicsr")]
         let csrs = CsrFile::default();
         #[cfg(feature = "zicsr")]
         let mode = PrivilegeMode::Machine;
+
         let mut lsu = Lsu::new(
-            &mut bus,
+            &bus,
+            misaligned,
             #[cfg(feature = "s")] &mut mmu,
             #[cfg(feature = "zicsr")] &csrs, 
             #[cfg(feature = "zicsr")] mode
         );
-        let addr = DRAM_BASE_ADDR;
-        let val = 0xDEADBEEF;
-
-        lsu.store(addr, val, 0, 4,
-            #[cfg(feature = "a")]&mut None).expect("Store failed");
-        
-        let res = lsu.load(addr, 0, 4).expect("Load failed");
-        assert_eq!(res, val, "Read back value mismatch");
+        test(&mut lsu);
+    }
+
+    #[test]
+    fn test_store_load_word() {
+        with_lsu(MisalignedPolicy::default(), |lsu| {
+            let addr = DRAM_BASE_ADDR;
+            let val = 0xDEADBEEF;
+
+            lsu.store(addr, val, 0, 4).expect("Store failed");
+            
+            let res = lsu.load(addr, 0, 4).expect("Load failed");
+            assert_eq!(res, val, "Read back value mismatch");
+        });
     }
 
     #[test]
     fn test_offset_handling() {
-        let mut bus = SystemBus::default();
-        #[cfg(feature = "s")]
-        let mut mmu = Mmu::default();
-        #[cfg(feature = "zicsr")]
-        let csrs = CsrFile::default();
-        #[cfg(feature = "zicsr")]
-        let mode = PrivilegeMode::Machine;
+        with_lsu(MisalignedPolicy::default(), |lsu| {
+            let base = DRAM_BASE_ADDR + 0x100;
+            let offset = -4; 
+            let val = 0x12345678;
 
-        let mut lsu = Lsu::new(
-            &mut bus,
-            #[cfg(feature = "s")] &mut mmu,
-            #[cfg(feature = "zicsr")] &csrs, 
-            #[cfg(feature = "zicsr")] mode
-        );
-        let base = DRAM_BASE_ADDR + 0x100;
-        let offset = -4; 
-        let val = 0x12345678;
-
-        lsu.store(base, val, offset, 4,
-            #[cfg(feature = "a")]&mut None).unwrap();
-        
-        let res = lsu.load(base, offset, 4).unwrap();
-        assert_eq!(res, val);
-        
-        let actual_addr = (base as i32 + offset) as u32;
-        let direct_res = lsu.load(actual_addr, 0, 4).unwrap();
-        assert_eq!(direct_res, val);
+            lsu.store(base, val, offset, 4).unwrap();
+            
+            let res = lsu.load(base, offset, 4).unwrap();
+            assert_eq!(res, val);
+            
+            let actual_addr = base.wrapping_add_signed(offset as SXReg);
+            let direct_res = lsu.load(actual_addr, 0, 4).unwrap();
+            assert_eq!(direct_res, val);
+        });
     }
 
     #[test]
     fn test_sign_extension() {
-        let mut bus = SystemBus::default();
-        let addr = DRAM_BASE_ADDR + 0x20;     
-        #[cfg(feature = "s")]
-        let mut mmu = Mmu::default();
-        #[cfg(feature = "zicsr")]
-        let csrs = CsrFile::default();
-        #[cfg(feature = "zicsr")]
-        let mode = PrivilegeMode::Machine;
+        with_lsu(MisalignedPolicy::default(), |lsu| {
+            let addr = DRAM_BASE_ADDR + 0x20;     
 
-        let mut lsu = Lsu::new(
-            &mut bus,
-            #[cfg(feature = "s")] &mut mmu,
-            #[cfg(feature = "zicsr")] &csrs, 
-            #[cfg(feature = "zicsr")] mode
-        );
+            lsu.store(addr, 0xFF, 0, 1).unwrap();
+
+            let lbu = lsu.load(addr, 0, 1).unwrap();
+            assert_eq!(lbu, 0x000000FF, "Lbu failed: expected zero extension");
 
-        lsu.store(addr, 0xFF, 0, 1,
-            #[cfg(feature = "a")]&mut None).unwrap();
-
-        let lbu = lsu.load(addr, 0, 1).unwrap();
-        assert_eq!(lbu, 0x000000FF, "Lbu failed: expected zero extension");
-
-        let lb = lsu.load_signed(addr, 0, 1).unwrap();
-        assert_eq!(lb, 0xFFFFFFFF, "Lb failed: expected sign extension");
-        
-        lsu.store(addr + 4, 0xFFAA, 0, 2,
-            #[cfg(feature = "a")]&mut None).unwrap();
-        
-        let lh = lsu.load_signed(addr + 4, 0, 2).unwrap();
-        assert_eq!(lh, 0xFFFF_FFAA, "Lh failed");
-        
-        let lhu = lsu.load(addr + 4, 0, 2).unwrap();
-        assert_eq!(lhu, 0x0000_FFAA, "Lhu failed");
+            let lb = lsu.load_signed(addr, 0, 1).unwrap();
+            assert_eq!(lb, XReg::MAX, "Lb failed: expected sign extension");
+            
+            lsu.store(addr + 4, 0xFFAA, 0, 2).unwrap();
+            
+            let lh = lsu.load_signed(addr + 4, 0, 2).unwrap();
+            assert_eq!(lh, 0xFFAAu16 as i16 as XReg, "Lh failed");
+            
+            let lhu = lsu.load(addr + 4, 0, 2).unwrap();
+            assert_eq!(lhu, 0x0000_FFAA, "Lhu failed");
+        });
+    }
+
+    #[test]
+    fn test_misaligned_emulate_crosses_page() {
+        with_lsu(MisalignedPolicy::Emulate, |lsu| {
+            // Two bytes on each side of the page boundary
+            let addr = DRAM_BASE_ADDR + PAGE_SIZE as XReg - 2;
+
+            lsu.store(addr, 0x12345678, 0, 4).unwrap();
+            assert_eq!(lsu.load(addr, 0, 4).unwrap(), 0x12345678);
+            assert_eq!(lsu.load(addr + 2, 0, 2).unwrap(), 0x1234, "High half should be on the next page");
+            assert_eq!(lsu.load(addr + 1, 0, 2).unwrap(), 0x3456);
+        });
+    }
+
+    #[test]
+    fn test_misaligned_trap_load_reports_address() {
+        with_lsu(MisalignedPolicy::Trap, |lsu| {
+            let base = DRAM_BASE_ADDR + 0x40;
+
+            assert_eq!(lsu.load(base, 1, 4), Err(Exception::LoadAddressMisaligned(base + 1)));
+            assert_eq!(lsu.load_signed(base, 6, 4), Err(Exception::LoadAddressMisaligned(base + 6)));
+        });
+    }
+
+    #[test]
+    fn test_misaligned_trap_store_reports_address() {
+        with_lsu(MisalignedPolicy::Trap, |lsu| {
+            let base = DRAM_BASE_ADDR + 0x40;
+
+            assert_eq!(lsu.store(base, 0xAB, 3, 2), Err(Exception::StoreOrAmoAddressMisaligned(base + 3)));
+        });
+    }
+
+    #[test]
+    fn test_misaligned_trap_allows_bytes() {
+        with_lsu(MisalignedPolicy::Trap, |lsu| {
+            let base = DRAM_BASE_ADDR + 0x40;
+
+            // A byte is never misaligned
+            lsu.store(base, 0xAB, 3, 1).unwrap();
+            assert_eq!(lsu.load(base, 3, 1).unwrap(), 0xAB);
+        });
+    }
+
+    // AMOs never get emulated
+    #[test]
+    #[cfg(feature = "a")]
+    fn test_misaligned_lr_reports_address() {
+        with_lsu(MisalignedPolicy::Emulate, |lsu| {
+            let addr = DRAM_BASE_ADDR + 0x42;
+
+            assert_eq!(lsu.atomic_load(addr, 4, 0), Err(Exception::LoadAddressMisaligned(addr)));
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "a")]
+    fn test_misaligned_sc_reports_address() {
+        with_lsu(MisalignedPolicy::Emulate, |lsu| {
+            let addr = DRAM_BASE_ADDR + 0x42;
+
+            assert_eq!(lsu.atomic_store(addr, 1, 4, 0), Err(Exception::StoreOrAmoAddressMisaligned(addr)));
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "a")]
+    fn test_misaligned_amo_reports_address() {
+        with_lsu(MisalignedPolicy::Emulate, |lsu| {
+            let addr = DRAM_BASE_ADDR + 0x42;
+
+            assert_eq!(
+                lsu.atomic_operate(addr, 1, 4, AmoClass::Arithmetic, |a, b| a + b),
+                Err(Exception::StoreOrAmoAddressMisaligned(addr))
+            );
+        });
     }
-}
\ No newline at end of file
+}
@@ -1,7 +1,11 @@
 mod alu;
 mod branch;
+#[cfg(feature = "f")]
+pub mod fpu;
 mod lsu;
 
 pub use alu::Alu;
 pub use branch::Branch;
-pub use lsu::Lsu;
+#[cfg(feature = "f")]
+pub use fpu::Fpu;
+pub use lsu::{Lsu, MisalignedPolicy};
@@ -8,6 +8,18 @@ pub enum RiscVError {
     #[error("Can not set zero in memory")]
     BssInitFailed,
 
+    #[error("Memory or device region is empty, out of the address space or overlaps another one")]
+    DeviceOverlap,
+
+    #[error("Interrupt source is out of range or already used")]
+    IrqUnavailable,
+
+    #[error("Number of harts is 0 or more than MAX_HARTS")]
+    HartCount,
+
+    #[error("Initrd runs past the end of the address space")]
+    InitrdOutOfRange,
+
     #[cfg(not(feature = "zicsr"))]
     #[error("Exception had raised")]
     Exception,
@@ -0,0 +1,56 @@
+use crate::core::XReg;
+
+use Interrupt::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    #[cfg(feature = "s")] SupervisorSoftware,
+    MachineSoftware,
+    #[cfg(feature = "s")] SupervisorTimer,
+    MachineTimer,
+    #[cfg(feature = "s")] SupervisorExternal,
+    MachineExternal,
+}
+
+/// Standard priority order for simultaneous pending interrupts: MEI, MSI, MTI, SEI, SSI, STI
+pub(crate) const PRIORITY: &[Interrupt] = &[
+    MachineExternal,
+    MachineSoftware,
+    MachineTimer,
+    #[cfg(feature = "s")] SupervisorExternal,
+    #[cfg(feature = "s")] SupervisorSoftware,
+    #[cfg(feature = "s")] SupervisorTimer,
+];
+
+impl Interrupt {
+    /// Bit of this interrupt in `mip` / `mie`
+    pub fn mask(&self) -> XReg {
+        1 << u32::from(*self)
+    }
+}
+
+impl From<Interrupt> for u32 {
+    fn from(value: Interrupt) -> Self {
+        match value {
+            #[cfg(feature = "s")] SupervisorSoftware => 1,
+            MachineSoftware                          => 3,
+            #[cfg(feature = "s")] SupervisorTimer    => 5,
+            MachineTimer                             => 7,
+            #[cfg(feature = "s")] SupervisorExternal => 9,
+            MachineExternal                          => 11,
+        }
+    }
+}
+
+impl std::fmt::Display for Interrupt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "s")] SupervisorSoftware => f.write_str(" 1: Supervisor Software Interrupt"),
+            MachineSoftware                          => f.write_str(" 3: Machine Software Interrupt"),
+            #[cfg(feature = "s")] SupervisorTimer    => f.write_str(" 5: Supervisor Timer Interrupt"),
+            MachineTimer                             => f.write_str(" 7: Machine Timer Interrupt"),
+            #[cfg(feature = "s")] SupervisorExternal => f.write_str(" 9: Supervisor External Interrupt"),
+            MachineExternal                          => f.write_str("11: Machine External Interrupt"),
+        }
+    }
+}
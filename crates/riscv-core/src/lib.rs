@@ -3,17 +3,37 @@ mod device;
 mod engine;
 mod error;
 mod exception;
+#[cfg(feature = "zicsr")]
+mod interrupt;
+mod trap;
 
 pub mod debug;
 pub mod prelude;
+/// Plugging custom peripherals into the bus
+pub mod mmio {
+    pub use crate::device::mmio::{MmioDevice, MmioFault};
+    pub use crate::device::pma::{AmoClass, Pma, WIDTH_ANY, WIDTH_B, WIDTH_D, WIDTH_H, WIDTH_W};
+}
 pub mod constance {
     pub use crate::device::memory::PAGE_SIZE;
     pub use crate::device::bus::DRAM_BASE_ADDR;
+    pub use crate::device::MAX_HARTS;
 }
 
-pub use core::RiscV;
+pub use core::{BankKind, MachineConfig, MemoryBank, RiscV, DEFAULT_QUANTUM, XLEN, XReg};
+pub use device::clint::TimeSource;
+pub use device::uart::{UartInput, UartOutput};
+pub use engine::MisalignedPolicy;
 pub use error::RiscVError;
 pub use exception::Exception;
+#[cfg(feature = "zicsr")]
+pub use core::HartIds;
+#[cfg(feature = "zicsr")]
+pub use interrupt::Interrupt;
+pub use trap::Trap;
+
+pub use riscv_decoder::IsaError;
+pub use riscv_decoder::isa::{Extension, Isa};
 
 pub type StdResult<T, E> = std::result::Result<T, E>;
 pub type Result<T> = std::result::Result<T, Exception>;
\ No newline at end of file
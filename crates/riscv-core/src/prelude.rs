@@ -1,5 +1,9 @@
 pub use crate::constance;
 pub use crate::core::*;
 pub use crate::debug::*;
+pub use crate::mmio;
 pub use crate::error::*;
 pub use crate::exception::*;
+#[cfg(feature = "zicsr")]
+pub use crate::interrupt::*;
+pub use crate::trap::*;
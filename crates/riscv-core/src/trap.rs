@@ -0,0 +1,68 @@
+use crate::core::{XLEN, XReg};
+use crate::Exception;
+#[cfg(feature = "zicsr")]
+use crate::Interrupt;
+
+const INTERRUPT_BIT: XReg = 1 << (XLEN - 1);
+
+/// Anything that redirects the hart into a trap handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    Exception(Exception),
+    #[cfg(feature = "zicsr")]
+    Interrupt(Interrupt),
+    /// Resumable non-maskable interrupt with its implementation-defined cause
+    #[cfg(feature = "zicsr")]
+    Nmi(u32),
+}
+
+impl Trap {
+    pub fn is_interrupt(&self) -> bool {
+        !matches!(self, Trap::Exception(_))
+    }
+
+    /// Exception or interrupt code without the interrupt bit
+    pub fn code(&self) -> u32 {
+        match self {
+            Trap::Exception(except) => (*except).into(),
+            #[cfg(feature = "zicsr")]
+            Trap::Interrupt(irq)    => (*irq).into(),
+            #[cfg(feature = "zicsr")]
+            Trap::Nmi(cause)        => *cause,
+        }
+    }
+
+    /// Value written to `mcause` / `scause`
+    pub fn cause(&self) -> XReg {
+        if self.is_interrupt() {
+            INTERRUPT_BIT | self.code() as XReg
+        } else {
+            self.code() as XReg
+        }
+    }
+}
+
+impl From<Exception> for Trap {
+    fn from(value: Exception) -> Self {
+        Trap::Exception(value)
+    }
+}
+
+#[cfg(feature = "zicsr")]
+impl From<Interrupt> for Trap {
+    fn from(value: Interrupt) -> Self {
+        Trap::Interrupt(value)
+    }
+}
+
+impl std::fmt::Display for Trap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trap::Exception(except) => except.fmt(f),
+            #[cfg(feature = "zicsr")]
+            Trap::Interrupt(irq)    => irq.fmt(f),
+            #[cfg(feature = "zicsr")]
+            Trap::Nmi(cause)        => write!(f, "Non-Maskable Interrupt ({})", cause),
+        }
+    }
+}
@@ -2,17 +2,25 @@ use std::path::Path;
 
 use riscv_core::RiscV;
 use riscv_core::debug::DebugInterface; 
-use riscv_loader;
 
 const MAX_CYCLES: u64 = 1_000_000;
 
+#[allow(dead_code)]
 pub fn run_test_file(path: &Path) {
+    run_test_file_with(path, |_| {});
+}
+
+/// Like `run_test_file`, but `setup` configures the machine first
+pub fn run_test_file_with<F>(path: &Path, setup: F)
+    where F: FnOnce(&mut RiscV)
+{
     let filename = path.file_name().unwrap().to_string_lossy();
     println!("Running Test: {}", filename);
 
     let info = riscv_loader::load(&path).expect("Failed to load ELF file");
 
     let mut machine = RiscV::default();
+    setup(&mut machine);
 
     machine.load_info(&info).expect("Failed to load ELF info");
 
@@ -23,7 +31,7 @@ pub fn run_test_file(path: &Path) {
         .expect("ELF missing 'tohost' symbol");
 
     for cycle in 0..MAX_CYCLES {
-        machine.step().expect(&format!("CPU Fault at cycle {}", cycle));
+        machine.step().unwrap_or_else(|_| panic!("CPU Fault at cycle {}", cycle));
 
         // Check tohost
         let mem_bytes = machine.inspect_bus(tohost_addr, 4);
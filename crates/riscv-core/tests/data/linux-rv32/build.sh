@@ -0,0 +1,26 @@
+#!/bin/sh
+# Build fw_jump.elf, Image and rootfs.cpio for linux_boot_test.rs into this
+# directory with a pinned Buildroot release. Needs network access and the
+# Buildroot host dependencies (https://buildroot.org/downloads/manual/manual.html#requirement).
+# The build directory defaults to $HOME/.cache and takes about 10 GB.
+set -eu
+
+BUILDROOT_VERSION=${BUILDROOT_VERSION:-2024.02.1}
+BUILD_DIR=${BUILD_DIR:-${XDG_CACHE_HOME:-$HOME/.cache}/rsriscv-buildroot-$BUILDROOT_VERSION}
+OUT=$(cd "$(dirname "$0")" && pwd)
+
+if [ ! -d "$BUILD_DIR" ]; then
+    git clone --depth 1 --branch "$BUILDROOT_VERSION" https://gitlab.com/buildroot.org/buildroot.git "$BUILD_DIR"
+fi
+cd "$BUILD_DIR"
+
+# OpenSBI fw_jump and the kernel as in QEMU virt, with the root filesystem as an initramfs
+make qemu_riscv32_virt_defconfig
+cat >> .config <<EOF
+BR2_TARGET_ROOTFS_CPIO=y
+BR2_TARGET_ROOTFS_CPIO_NONE=y
+EOF
+make olddefconfig
+make
+
+cp output/images/fw_jump.elf output/images/Image output/images/rootfs.cpio "$OUT/"
@@ -0,0 +1,38 @@
+#!/bin/sh
+# Rebuild the test binaries in ../rv32*-local and ../rv64*-local from the sources here.
+# Needs llvm-mc and ld.lld from LLVM 14 or newer, no RISC-V GCC toolchain.
+set -eu
+
+cd "$(dirname "$0")"
+MC=${MC:-llvm-mc}
+LD=${LD:-ld.lld}
+TMP=$(mktemp -d)
+trap 'rm -rf "$TMP"' EXIT
+
+# suite: -mattr for llvm-mc, the triple follows the suite's XLEN
+build_suite() {
+    suite=$1
+    attrs=$2
+    case $suite in
+        rv64*) triple=riscv64 ;;
+        *) triple=riscv32 ;;
+    esac
+    mkdir -p "../$suite-local"
+    for src in "$suite"/*.S; do
+        name=$(basename "$src" .S)
+        "$MC" -triple=$triple -mattr="$attrs" -filetype=obj -I . "$src" -o "$TMP/$name.o"
+        "$LD" -T link.ld --no-relax "$TMP/$name.o" -o "../$suite-local/$suite-local-$name"
+    done
+}
+
+build_suite rv32uf +m,+a,+f
+build_suite rv32ud +m,+a,+f,+d
+build_suite rv32uzba +m,+a,+zba
+build_suite rv32uzbb +m,+a,+zbb
+build_suite rv32uzbc +m,+a,+zbc
+build_suite rv32uzbs +m,+a,+zbs
+build_suite rv32mi +m,+a
+build_suite rv64ui +m,+a
+build_suite rv64um +m,+a
+build_suite rv64ua +m,+a
+build_suite rv64mi +m,+a
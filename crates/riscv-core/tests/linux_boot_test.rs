@@ -0,0 +1,86 @@
+#![cfg(all(feature = "full", not(feature = "rv64")))]
+
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use riscv_core::{RiscV, UartOutput};
+use riscv_core::constance::DRAM_BASE_ADDR;
+
+// Addresses of OpenSBI's generic `fw_jump` on rv32
+const DRAM_SIZE: usize = 128 * 1024 * 1024;
+const KERNEL_ADDR: u32 = 0x8040_0000;
+const INITRD_ADDR: u32 = 0x8400_0000;
+
+const BOOTARGS: &str = "console=ttyS0 earlycon";
+const MAX_INSTRUCTIONS: u64 = 500_000_000;
+// How often the console is checked
+const POLL_INTERVAL: u64 = 100_000;
+
+#[derive(Clone, Default)]
+struct Console(Arc<Mutex<Vec<u8>>>);
+
+impl Console {
+    fn contains(&self, text: &str) -> bool {
+        let buf = self.0.lock().unwrap();
+        buf.windows(text.len()).any(|window| window == text.as_bytes())
+    }
+}
+
+impl Write for Console {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_linux_boot() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let image_dir = Path::new(manifest_dir).join("tests/data/linux-rv32");
+
+    // The images are built by tests/data/linux-rv32/build.sh, CI must have them
+    let [firmware, kernel, initrd] = ["fw_jump.elf", "Image", "rootfs.cpio"].map(|file| image_dir.join(file));
+    if ![&firmware, &kernel, &initrd].iter().all(|path| path.exists()) {
+        assert!(env::var_os("CI").is_none(),
+            "fw_jump.elf, Image and rootfs.cpio not found at {:?}, run build.sh there", image_dir);
+        println!("SKIP: no Linux images at {:?}, run build.sh there", image_dir);
+        return;
+    }
+
+    let mut machine = RiscV::builder().dram(DRAM_BASE_ADDR, DRAM_SIZE).build().unwrap();
+    let console = Console::default();
+    machine.set_uart_output(UartOutput::new(console.clone()));
+
+    let firmware = riscv_loader::load(&firmware).expect("Failed to load OpenSBI");
+    let kernel = riscv_loader::load_at(&kernel, KERNEL_ADDR).expect("Failed to load the kernel");
+    let initrd = riscv_loader::load_at(&initrd, INITRD_ADDR).expect("Failed to load the initramfs");
+    machine.load_info(&firmware).unwrap();
+    machine.load_image(&kernel).unwrap();
+    machine.load_image(&initrd).unwrap();
+
+    machine.set_initrd(INITRD_ADDR, initrd.code[0].0.len()).unwrap();
+    machine.set_bootargs(BOOTARGS);
+    machine.load_fdt().unwrap();
+
+    let mut seen_opensbi = false;
+    for count in 0..MAX_INSTRUCTIONS {
+        machine.step().unwrap_or_else(|_| panic!("CPU Fault after {} instructions", count));
+
+        if count % POLL_INTERVAL == 0 {
+            seen_opensbi = seen_opensbi || console.contains("OpenSBI");
+            if seen_opensbi && console.contains("Linux version") {
+                println!("\x1b[32mPASS\x1b[0m: Linux banner after {} instructions", count);
+                return;
+            }
+        }
+    }
+
+    let output = String::from_utf8_lossy(&console.0.lock().unwrap()).into_owned();
+    panic!("TIMEOUT: no OpenSBI and Linux banner within {} instructions\n{}", MAX_INSTRUCTIONS, output);
+}
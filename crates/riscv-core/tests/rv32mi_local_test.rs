@@ -0,0 +1,26 @@
+#![cfg(all(feature = "zicsr", feature = "a", not(feature = "rv64")))]
+
+mod common;
+
+use std::path::Path;
+
+use riscv_core::MisalignedPolicy;
+
+fn run_ma_addr(name: &str, policy: MisalignedPolicy) {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let path = Path::new(manifest_dir).join("tests/data/rv32mi-local").join(name);
+
+    assert!(path.exists(), "{} not found at {:?}, see tests/data/README.md", name, path);
+
+    common::run_test_file_with(&path, |machine| machine.set_misaligned(policy));
+}
+
+#[test]
+fn test_rv32mi_local_ma_addr_trap() {
+    run_ma_addr("rv32mi-local-ma_addr_trap", MisalignedPolicy::Trap);
+}
+
+#[test]
+fn test_rv32mi_local_ma_addr_emulate() {
+    run_ma_addr("rv32mi-local-ma_addr_emulate", MisalignedPolicy::Emulate);
+}
@@ -1,4 +1,4 @@
-#![cfg(feature = "s")]
+#![cfg(all(feature = "s", not(feature = "rv64")))]
 
 mod common;
 
@@ -1,4 +1,4 @@
-#![cfg(feature = "a")]
+#![cfg(all(feature = "a", not(feature = "rv64")))]
 
 mod common;
 
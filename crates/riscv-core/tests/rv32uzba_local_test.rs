@@ -0,0 +1,29 @@
+#![cfg(all(feature = "zba", feature = "zicsr", not(feature = "rv64")))]
+
+mod common;
+
+use std::path::Path;
+use std::fs;
+
+#[test]
+fn test_rv32uzba_local() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let test_dir = Path::new(manifest_dir).join("tests/data/rv32uzba-local");
+
+    assert!(test_dir.exists(), "rv32uzba-local tests not found at {:?}, see tests/data/README.md", test_dir);
+
+    let mut paths: Vec<_> = fs::read_dir(&test_dir)
+        .unwrap()
+        .map(|res| res.unwrap().path())
+        .filter(|path| {
+            path.is_file() && !path.file_name().unwrap().to_string_lossy().starts_with('.')
+        })
+        .collect();
+    
+    paths.sort();
+    assert!(!paths.is_empty(), "No rv32uzba-local tests in {:?}", test_dir);
+
+    for path in paths {
+        common::run_test_file(&path);
+    }
+}
\ No newline at end of file
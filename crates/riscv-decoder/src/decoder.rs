@@ -1,5 +1,8 @@
 use crate::bits_op::BitsOp;
 use crate::error::DecodeError;
+use crate::isa::Isa;
+#[cfg(feature = "c")]
+use crate::isa::Extension;
 use super::instruction::*;
 use super::opcode::OpCode;
 use super::instruction::Instruction::*;
@@ -30,12 +33,30 @@ pub fn decode(raw: u32) -> Result<Instruction, DecodeError> {
         // imm [11:0] | rs1 [4:0] | funct3 [2:0] | rd [4:0] | opcode [6:0]
         itype @ (OpCode::ItypeAr | OpCode::ItypeLoad | OpCode::ItypeJump | OpCode::ItypeFence) => {
             let imm = raw.get_bits_signed(20, 12);
+            // RV64 shift amounts take one more bit from funct7
+            #[cfg(feature = "rv64")]
+            let funct7 = if itype == OpCode::ItypeAr { funct7 & !1 } else { funct7 };
+            #[cfg(any(feature = "zbb", feature = "zbs"))]
+            let shamt = raw.get_bits(20, if cfg!(feature = "rv64") { 6 } else { 5 }) as i32;
             
             if let Some(op) = Rv32iOp::decode_itype(itype, funct3, funct7) {
                 let res = Base(op, InstructionData { rd, rs1, rs2, imm });
                 return Ok(res);
             } 
             
+            #[cfg(feature = "zbb")]
+            if itype == OpCode::ItypeAr && let Some(op) = ZbbOp::decode_itype(funct3, raw.get_bits(20, 12) as u16) {
+                let imm = if op.is_imm() { shamt } else { 0 };
+                let res = Zbb(op, InstructionData { rd, rs1, rs2: 0, imm });
+                return Ok(res);
+            }
+
+            #[cfg(feature = "zbs")]
+            if itype == OpCode::ItypeAr && let Some(op) = ZbsOp::decode_itype(funct3, funct7) {
+                let res = Zbs(op, InstructionData { rd, rs1, rs2: 0, imm: shamt });
+                return Ok(res);
+            }
+
             #[cfg(feature = "zifencei")]
             if itype == OpCode::ItypeFence && let Some(op) =  ZifenceiOp::decode(funct3) {
                 let res = Zifencei(op, InstructionData { rd, rs1, rs2, imm });
@@ -44,6 +65,57 @@ pub fn decode(raw: u32) -> Result<Instruction, DecodeError> {
                 
             Err(DecodeError::UnknownInstruction(itype, raw))
         },
+        #[cfg(feature = "rv64")]
+        itype @ OpCode::ItypeArW => {
+            let imm = raw.get_bits_signed(20, 12);
+
+            if let Some(op) = Rv32iOp::decode_itype(itype, funct3, funct7) {
+                let res = Base(op, InstructionData { rd, rs1, rs2, imm });
+                return Ok(res);
+            }
+
+            #[cfg(feature = "zba")]
+            if let Some(op) = ZbaOp::decode_itype_w(funct3, raw.get_bits(20, 12) as u16) {
+                let res = Zba(op, InstructionData { rd, rs1, rs2: 0, imm: raw.get_bits(20, 6) as i32 });
+                return Ok(res);
+            }
+
+            #[cfg(feature = "zbb")]
+            if let Some(op) = ZbbOp::decode_itype_w(funct3, raw.get_bits(20, 12) as u16) {
+                let imm = if op.is_imm() { raw.get_bits(20, 5) as i32 } else { 0 };
+                let res = Zbb(op, InstructionData { rd, rs1, rs2: 0, imm });
+                return Ok(res);
+            }
+
+            Err(DecodeError::UnknownInstruction(itype, raw))
+        },
+        #[cfg(feature = "rv64")]
+        rtype @ OpCode::RtypeW => {
+            if let Some(op) = Rv32iOp::decode_rtype_w(funct3, funct7) {
+                let res = Base(op, InstructionData { rd, rs1, rs2, imm: 0 });
+                return Ok(res);
+            }
+
+            #[cfg(feature = "m")]
+            if let Some(op) = MOp::decode_w(funct3, funct7) {
+                let res = M(op, InstructionData { rd, rs1, rs2, imm: 0 });
+                return Ok(res);
+            }
+
+            #[cfg(feature = "zba")]
+            if let Some(op) = ZbaOp::decode_w(funct3, funct7) {
+                let res = Zba(op, InstructionData { rd, rs1, rs2, imm: 0 });
+                return Ok(res);
+            }
+
+            #[cfg(feature = "zbb")]
+            if let Some(op) = ZbbOp::decode_rtype_w(funct3, funct7, rs2) {
+                let res = Zbb(op, InstructionData { rd, rs1, rs2, imm: 0 });
+                return Ok(res);
+            }
+
+            Err(DecodeError::UnknownInstruction(rtype, raw))
+        },
         // funct7 [6:0] | rs2 [4:0] | rs1 [4:0] | funct3 [2:0]  | rd [4:0] | opcode [6:0]
         rtype @ OpCode::Rtype => {
             if let Some(op) = Rv32iOp::decode_rtype(funct3, funct7) {
@@ -57,6 +129,30 @@ pub fn decode(raw: u32) -> Result<Instruction, DecodeError> {
                 return Ok(res);
             } 
 
+            #[cfg(feature = "zba")]
+            if let Some(op) = ZbaOp::decode(funct3, funct7) {
+                let res = Zba(op, InstructionData { rd, rs1, rs2, imm: 0 });
+                return Ok(res);
+            }
+
+            #[cfg(feature = "zbb")]
+            if let Some(op) = ZbbOp::decode_rtype(funct3, funct7, rs2) {
+                let res = Zbb(op, InstructionData { rd, rs1, rs2, imm: 0 });
+                return Ok(res);
+            }
+
+            #[cfg(feature = "zbc")]
+            if let Some(op) = ZbcOp::decode(funct3, funct7) {
+                let res = Zbc(op, InstructionData { rd, rs1, rs2, imm: 0 });
+                return Ok(res);
+            }
+
+            #[cfg(feature = "zbs")]
+            if let Some(op) = ZbsOp::decode_rtype(funct3, funct7) {
+                let res = Zbs(op, InstructionData { rd, rs1, rs2, imm: 0 });
+                return Ok(res);
+            }
+
             Err(DecodeError::UnknownInstruction(rtype, raw))
         },
         // imm [11:5] | rs2 [4:0] | rs1 [4:0] | funct3 [2:0] | imm [4:0] | opcode [6:0]
@@ -122,6 +218,74 @@ pub fn decode(raw: u32) -> Result<Instruction, DecodeError> {
 
             Err(DecodeError::UnknownInstruction(atomic, raw))
         },
+        #[cfg(feature = "f")]
+        load @ OpCode::LoadFp => {
+            let imm = raw.get_bits_signed(20, 12);
+
+            if let Some(op) = FOp::decode_load(funct3) {
+                let res = F(op, FpInsData { rd, rs1, rs2, rs3: 0, rm: 0, imm }, raw);
+                return Ok(res);
+            }
+
+            #[cfg(feature = "d")]
+            if let Some(op) = DOp::decode_load(funct3) {
+                let res = D(op, FpInsData { rd, rs1, rs2, rs3: 0, rm: 0, imm }, raw);
+                return Ok(res);
+            }
+
+            Err(DecodeError::UnknownInstruction(load, raw))
+        },
+        #[cfg(feature = "f")]
+        store @ OpCode::StoreFp => {
+            let imm = (raw.get_bits_signed(25, 7) << 5) | raw.get_bits(7, 5) as i32;
+
+            if let Some(op) = FOp::decode_store(funct3) {
+                let res = F(op, FpInsData { rd, rs1, rs2, rs3: 0, rm: 0, imm }, raw);
+                return Ok(res);
+            }
+
+            #[cfg(feature = "d")]
+            if let Some(op) = DOp::decode_store(funct3) {
+                let res = D(op, FpInsData { rd, rs1, rs2, rs3: 0, rm: 0, imm }, raw);
+                return Ok(res);
+            }
+
+            Err(DecodeError::UnknownInstruction(store, raw))
+        },
+        // rs3 [4:0] | fmt [1:0] | rs2 [4:0] | rs1 [4:0] | rm [2:0] | rd [4:0] | opcode [6:0]
+        #[cfg(feature = "f")]
+        fused @ (OpCode::Madd | OpCode::Msub | OpCode::Nmsub | OpCode::Nmadd) => {
+            let rs3 = raw.get_bits(27, 5) as u8;
+            let fmt = raw.get_bits(25, 2) as u8;
+
+            if let Some(op) = FOp::decode_fused(fused, fmt) {
+                let res = F(op, FpInsData { rd, rs1, rs2, rs3, rm: funct3, imm: 0 }, raw);
+                return Ok(res);
+            }
+
+            #[cfg(feature = "d")]
+            if let Some(op) = DOp::decode_fused(fused, fmt) {
+                let res = D(op, FpInsData { rd, rs1, rs2, rs3, rm: funct3, imm: 0 }, raw);
+                return Ok(res);
+            }
+
+            Err(DecodeError::UnknownInstruction(fused, raw))
+        },
+        #[cfg(feature = "f")]
+        op_fp @ OpCode::OpFp => {
+            if let Some(op) = FOp::decode_op(funct7, funct3, rs2) {
+                let res = F(op, FpInsData { rd, rs1, rs2, rs3: 0, rm: funct3, imm: 0 }, raw);
+                return Ok(res);
+            }
+
+            #[cfg(feature = "d")]
+            if let Some(op) = DOp::decode_op(funct7, funct3, rs2) {
+                let res = D(op, FpInsData { rd, rs1, rs2, rs3: 0, rm: funct3, imm: 0 }, raw);
+                return Ok(res);
+            }
+
+            Err(DecodeError::UnknownInstruction(op_fp, raw))
+        },
         system @ OpCode::System => {
             let imm = raw.get_bits(20, 12) as i32;
 
@@ -147,6 +311,41 @@ pub fn decode(raw: u32) -> Result<Instruction, DecodeError> {
     }
 }
 
+/// `decode` for a hart that only has the extensions in `isa`.
+/// Instructions of any other extension give `DisabledExtension`
+///
+/// ## Example
+/// ```rust
+/// # use riscv_decoder::prelude::*;
+/// # use decoder::decode_with;
+/// let isa = Isa::default().without(Extension::M);
+/// // add x5, x6, x7
+/// assert!(decode_with(0x007302b3, &isa).is_ok());
+/// # #[cfg(feature = "m")]
+/// // mul x5, x6, x7
+/// assert_eq!(decode_with(0x027302b3, &isa), Err(DecodeError::DisabledExtension(Extension::M, 0x027302b3)));
+/// ```
+pub fn decode_with(raw: u32, isa: &Isa) -> Result<Instruction, DecodeError> {
+    let ins = decode(raw)?;
+    match ins.extension() {
+        ext if isa.has(ext) => Ok(ins),
+        ext => Err(DecodeError::DisabledExtension(ext, raw)),
+    }
+}
+
+/// `decompress` for a hart that only has the extensions in `isa`
+#[cfg(feature = "c")]
+pub fn decompress_with(raw: u16, isa: &Isa) -> Result<Instruction, DecodeError> {
+    if !isa.has(Extension::C) {
+        return Err(DecodeError::DisabledExtension(Extension::C, raw as u32));
+    }
+    let ins = decompress(raw)?;
+    match ins.extension() {
+        ext if isa.has(ext) => Ok(ins),
+        ext => Err(DecodeError::DisabledExtension(ext, raw as u32)),
+    }
+}
+
 #[cfg(feature = "c")]
 pub fn decompress(raw: u16) -> Result<Instruction, DecodeError> {
     let opcode = raw.get_bits(0, 2) as u8;
@@ -197,7 +396,13 @@ pub fn decompress(raw: u16) -> Result<Instruction, DecodeError> {
                         (raw.get_bits_signed(12, 1) as i32) << 17 |
                         (raw.get_bits(2, 5) as i32) << 12
                     },
-                    COp::Lwsp => {
+                    op if op.is_double_width() => {
+                        rs1 = 2;
+                        (raw.get_bits(2, 3)  << 6 |
+                        raw.get_bits(12, 1) << 5 |
+                        raw.get_bits(5, 2)  << 3) as i32
+                    },
+                    op if op.is_load_sp() => {
                         rs1 = 2;
                         (raw.get_bits(2, 2)  << 6 |
                         raw.get_bits(12, 1) << 5 |
@@ -213,7 +418,10 @@ pub fn decompress(raw: u16) -> Result<Instruction, DecodeError> {
             CFormat::Css => {
                 rd = 0;
                 rs1 = 2;
-                imm = (raw.get_bits(7, 2) << 6 | raw.get_bits(9, 3) << 2) as i32;
+                imm = match ins {
+                    op if op.is_double_width() => (raw.get_bits(7, 3) << 6 | raw.get_bits(10, 3) << 3) as i32,
+                    _ => (raw.get_bits(7, 2) << 6 | raw.get_bits(9, 3) << 2) as i32,
+                };
             },
             CFormat::Ciw => {
                 rd = raw.get_bits(2, 3) as u8 + 8;
@@ -226,8 +434,11 @@ pub fn decompress(raw: u16) -> Result<Instruction, DecodeError> {
                 rd = raw.get_bits(2, 3) as u8 + 8;
                 rs1 = raw.get_bits(7, 3) as u8 + 8;
                 rs2 = rd;
-                imm = (raw.get_bits(5, 1) << 6  | raw.get_bits(10, 3) << 3 |
-                    raw.get_bits(6, 1) << 2) as i32;
+                imm = match ins {
+                    op if op.is_double_width() => (raw.get_bits(5, 2) << 6 | raw.get_bits(10, 3) << 3) as i32,
+                    _ => (raw.get_bits(5, 1) << 6  | raw.get_bits(10, 3) << 3 |
+                        raw.get_bits(6, 1) << 2) as i32,
+                };
             },
             CFormat::Ca => {
                 rd = raw.get_bits(7, 3) as u8 + 8;
@@ -260,6 +471,18 @@ pub fn decompress(raw: u16) -> Result<Instruction, DecodeError> {
                 raw.get_bits(2, 1) << 5 | raw.get_bits(11, 1) << 4 | raw.get_bits(3, 3) << 1) as i32;
             }
         }
+        #[cfg(all(feature = "f", not(feature = "rv64")))]
+        if let Some(op) = ins.into_f() {
+            let res = Instruction::F(op, FpInsData { rd, rs1, rs2, rs3: 0, rm: 0, imm }, raw as u32);
+            return Ok(res);
+        }
+
+        #[cfg(feature = "d")]
+        if let Some(op) = ins.into_d() {
+            let res = Instruction::D(op, FpInsData { rd, rs1, rs2, rs3: 0, rm: 0, imm }, raw as u32);
+            return Ok(res);
+        }
+
         let res = Instruction::Base(ins.into_base(), InstructionData { rd, rs1, rs2, imm });
         return Ok(res);
     }
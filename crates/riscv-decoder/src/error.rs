@@ -1,5 +1,6 @@
 use thiserror::Error;
 
+use crate::isa::Extension;
 use crate::opcode::OpCode;
 
 #[derive(Error, Debug, Clone, Copy,PartialEq, Eq)]
@@ -10,6 +11,9 @@ pub enum DecodeError {
     #[error("Unknown Instruction from opcode: {0} Raw data: {1:#010x}")]
     UnknownInstruction(OpCode, u32),
 
+    #[error("Extension {0} is disabled. Raw data: {1:#010x}")]
+    DisabledExtension(Extension, u32),
+
     #[cfg(feature = "c")]
     #[error("Not a Compress Instruction from opcode: 0b11")]
     NotCompressInstruction,
@@ -17,4 +21,22 @@ pub enum DecodeError {
     #[cfg(feature = "c")]
     #[error("Unknown Compress Instruction from opcode: {0:#04b} Raw data: {1:#06x}")]
     UnknownCompressInstruction(u8, u16)
-}
\ No newline at end of file
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum IsaError {
+    #[error("ISA string should start with the XLEN this build has (rv32 or rv64): {0}")]
+    BadPrefix(String),
+
+    #[error("ISA string should have the base I (or G) right after the XLEN")]
+    MissingBase,
+
+    #[error("Unknown extension: {0}")]
+    UnknownExtension(String),
+
+    #[error("Extension {0} is not compiled in, enable its cargo feature")]
+    NotCompiled(Extension),
+
+    #[error("Extension {0} requires {1}")]
+    MissingDependency(Extension, Extension),
+}
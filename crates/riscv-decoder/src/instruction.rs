@@ -6,6 +6,18 @@ mod m;
 mod a;
 #[cfg(feature = "c")]
 mod c;
+#[cfg(feature = "f")]
+mod f;
+#[cfg(feature = "d")]
+mod d;
+#[cfg(feature = "zba")]
+mod zba;
+#[cfg(feature = "zbb")]
+mod zbb;
+#[cfg(feature = "zbc")]
+mod zbc;
+#[cfg(feature = "zbs")]
+mod zbs;
 #[cfg(feature = "zicsr")]
 mod zicsr;
 #[cfg(feature = "zifencei")]
@@ -22,6 +34,14 @@ pub use a::AOp;
 pub(crate) use c::COp;
 #[cfg(feature = "c")]
 pub(crate) use c::CFormat;
+#[cfg(feature = "zba")]
+pub use zba::ZbaOp;
+#[cfg(feature = "zbb")]
+pub use zbb::ZbbOp;
+#[cfg(feature = "zbc")]
+pub use zbc::ZbcOp;
+#[cfg(feature = "zbs")]
+pub use zbs::ZbsOp;
 #[cfg(feature = "zicsr")]
 pub use zicsr::ZicsrOp;
 #[cfg(feature = "zifencei")]
@@ -30,6 +50,12 @@ pub use zifencei::ZifenceiOp;
 pub use privileged::PrivilegeOp;
 #[cfg(feature = "a")]
 pub use a::AmoInsData;
+#[cfg(feature = "f")]
+pub use f::{FOp, FpInsData};
+#[cfg(feature = "d")]
+pub use d::DOp;
+
+use crate::isa::Extension;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct InstructionData {
@@ -49,8 +75,53 @@ pub enum Instruction {
     M(MOp, InstructionData),
     #[cfg(feature = "a")]
     A(AOp, AmoInsData),
+    #[cfg(feature = "f")]
+    F(FOp, FpInsData, u32),
+    #[cfg(feature = "d")]
+    D(DOp, FpInsData, u32),
+    #[cfg(feature = "zba")]
+    Zba(ZbaOp, InstructionData),
+    #[cfg(feature = "zbb")]
+    Zbb(ZbbOp, InstructionData),
+    #[cfg(feature = "zbc")]
+    Zbc(ZbcOp, InstructionData),
+    #[cfg(feature = "zbs")]
+    Zbs(ZbsOp, InstructionData),
     #[cfg(feature = "zicsr")]
     Zicsr(ZicsrOp, InstructionData, u32),
     #[cfg(feature = "zifencei")]
     Zifencei(ZifenceiOp, InstructionData), 
 }
+
+impl Instruction {
+    /// Extension a hart needs to run it. Compressed forms need `C` on top of this
+    pub fn extension(&self) -> Extension {
+        match self {
+            Instruction::Base(..)  => Extension::I,
+            #[cfg(feature = "s")]
+            Instruction::Privileged(PrivilegeOp::Sret | PrivilegeOp::SfenceVma(_), _) => Extension::S,
+            #[cfg(feature = "zicsr")]
+            Instruction::Privileged(..) => Extension::I,
+            #[cfg(feature = "m")]
+            Instruction::M(..) => Extension::M,
+            #[cfg(feature = "a")]
+            Instruction::A(..) => Extension::A,
+            #[cfg(feature = "f")]
+            Instruction::F(..) => Extension::F,
+            #[cfg(feature = "d")]
+            Instruction::D(..) => Extension::D,
+            #[cfg(feature = "zba")]
+            Instruction::Zba(..) => Extension::Zba,
+            #[cfg(feature = "zbb")]
+            Instruction::Zbb(..) => Extension::Zbb,
+            #[cfg(feature = "zbc")]
+            Instruction::Zbc(..) => Extension::Zbc,
+            #[cfg(feature = "zbs")]
+            Instruction::Zbs(..) => Extension::Zbs,
+            #[cfg(feature = "zicsr")]
+            Instruction::Zicsr(..) => Extension::Zicsr,
+            #[cfg(feature = "zifencei")]
+            Instruction::Zifencei(..) => Extension::Zifencei,
+        }
+    }
+}
@@ -15,6 +15,18 @@ pub enum AOp {
 
     AmoSwapW, AmoAddW, AmoAndW, AmoOrW, AmoXorW,
     AmoMaxW, AmoMinW, AmoMaxuW, AmoMinuW,
+
+    #[cfg(feature = "rv64")] LrD,
+    #[cfg(feature = "rv64")] ScD,
+    #[cfg(feature = "rv64")] AmoSwapD,
+    #[cfg(feature = "rv64")] AmoAddD,
+    #[cfg(feature = "rv64")] AmoAndD,
+    #[cfg(feature = "rv64")] AmoOrD,
+    #[cfg(feature = "rv64")] AmoXorD,
+    #[cfg(feature = "rv64")] AmoMaxD,
+    #[cfg(feature = "rv64")] AmoMinD,
+    #[cfg(feature = "rv64")] AmoMaxuD,
+    #[cfg(feature = "rv64")] AmoMinuD,
 }
 
 impl AOp {
@@ -35,12 +47,42 @@ impl AOp {
                 0x1c => AmoMaxuW,
                 _    => return None,
             },
+            #[cfg(feature = "rv64")]
+            0x3 => match funct5 {
+                0x02 if rs2 == 0 => LrD,
+                0x03 => ScD,
+
+                0x01 => AmoSwapD,
+                0x00 => AmoAddD,
+                0x04 => AmoXorD,
+                0x0c => AmoAndD,
+                0x08 => AmoOrD,
+                0x10 => AmoMinD,
+                0x14 => AmoMaxD,
+                0x18 => AmoMinuD,
+                0x1c => AmoMaxuD,
+                _    => return None,
+            },
             _ => return None,
         })
     }
 
     pub fn is_load(&self) -> bool {
-        self == &LrW
+        match self {
+            LrW => true,
+            #[cfg(feature = "rv64")]
+            LrD => true,
+            _ => false,
+        }
+    }
+
+    /// Operates on a 64 bits doubleword
+    #[cfg(feature = "rv64")]
+    pub fn is_doubleword(&self) -> bool {
+        matches!(self, 
+            LrD | ScD | AmoSwapD | AmoAddD | AmoAndD | AmoOrD | AmoXorD |
+            AmoMaxD | AmoMinD | AmoMaxuD | AmoMinuD
+        )
     }
 }
 
@@ -58,7 +100,18 @@ impl std::fmt::Display for AOp {
                 AmoMinW  => "amomin.w",
                 AmoMaxW  => "amomax.w",
                 AmoMinuW => "amominu.w",
-                AmoMaxuW => "amomaxu.w"
+                AmoMaxuW => "amomaxu.w",
+                #[cfg(feature = "rv64")] LrD      => "lr.d",
+                #[cfg(feature = "rv64")] ScD      => "sc.d",
+                #[cfg(feature = "rv64")] AmoSwapD => "amoswap.d",
+                #[cfg(feature = "rv64")] AmoAddD  => "amoadd.d",
+                #[cfg(feature = "rv64")] AmoXorD  => "amoxor.d",
+                #[cfg(feature = "rv64")] AmoAndD  => "amoand.d",
+                #[cfg(feature = "rv64")] AmoOrD   => "amoor.d",
+                #[cfg(feature = "rv64")] AmoMinD  => "amomin.d",
+                #[cfg(feature = "rv64")] AmoMaxD  => "amomax.d",
+                #[cfg(feature = "rv64")] AmoMinuD => "amominu.d",
+                #[cfg(feature = "rv64")] AmoMaxuD => "amomaxu.d",
             }
         )
     }
@@ -2,14 +2,33 @@ use COp::*;
 use CFormat::*;
 
 use crate::prelude::Rv32iOp;
+#[cfg(all(feature = "f", not(feature = "rv64")))]
+use crate::prelude::FOp;
+#[cfg(feature = "d")]
+use crate::prelude::DOp;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum COp {
     Addi4spn, Lw, Sw,
-    Nop, Addi, Jal, Li, Addi16sp, Lui, Srli,
+    Nop, Addi, Li, Addi16sp, Lui, Srli,
     Srai, Andi, Sub, Xor, Or, And, J, Beqz, Bnez,
     Slli, Lwsp, Jr, Mv, Ebreak, Jalr, Add, Swsp,
-    // Some F and D extension not implement yet
+    #[cfg(not(feature = "rv64"))] Jal,
+    #[cfg(all(feature = "f", not(feature = "rv64")))] Flw,
+    #[cfg(all(feature = "f", not(feature = "rv64")))] Fsw,
+    #[cfg(all(feature = "f", not(feature = "rv64")))] Flwsp,
+    #[cfg(all(feature = "f", not(feature = "rv64")))] Fswsp,
+    #[cfg(feature = "d")] Fld,
+    #[cfg(feature = "d")] Fsd,
+    #[cfg(feature = "d")] Fldsp,
+    #[cfg(feature = "d")] Fsdsp,
+    #[cfg(feature = "rv64")] Ld,
+    #[cfg(feature = "rv64")] Sd,
+    #[cfg(feature = "rv64")] Ldsp,
+    #[cfg(feature = "rv64")] Sdsp,
+    #[cfg(feature = "rv64")] Addiw,
+    #[cfg(feature = "rv64")] Addw,
+    #[cfg(feature = "rv64")] Subw,
 }
 
 pub(crate) enum CFormat {
@@ -28,8 +47,20 @@ impl COp {
     pub(crate) fn decode_q0(raw: u16, funct3: u8) -> Option<(COp, CFormat)> {
         Some(match funct3 {
             0x0 if raw & 0x1fe != 0 => (Addi4spn, Ciw),
+            #[cfg(feature = "d")]
+            0x1 => (Fld, Cl),
             0x2 => (Lw, Cl),
+            #[cfg(all(feature = "f", not(feature = "rv64")))]
+            0x3 => (Flw, Cl),
+            #[cfg(feature = "rv64")]
+            0x3 => (Ld, Cl),
+            #[cfg(feature = "d")]
+            0x5 => (Fsd, Cs),
             0x6 => (Sw, Cs),
+            #[cfg(all(feature = "f", not(feature = "rv64")))]
+            0x7 => (Fsw, Cs),
+            #[cfg(feature = "rv64")]
+            0x7 => (Sd, Cs),
             _ => return None,
         })
     }
@@ -41,7 +72,10 @@ impl COp {
         
         Some(match funct3 {
             0x0 if rd != 0 => (Addi, Ci),
+            #[cfg(not(feature = "rv64"))]
             0x1 => (Jal, Cj),
+            #[cfg(feature = "rv64")]
+            0x1 if rd != 0 => (Addiw, Ci),
             0x2 => (Li, Ci),
             0x3 => match rd {
                 0x02 if raw & 0x1000 != 0 || raw & 0x007c != 0 => (Addi16sp, Ci),
@@ -52,6 +86,12 @@ impl COp {
                 0x0 => (Srli, Cb),
                 0x1 => (Srai, Cb),
                 0x2 => (Andi, Cb),
+                #[cfg(feature = "rv64")]
+                _   if (raw & 0x1000) != 0 => match (raw & 0x60) >> 5 {
+                    0x0 => (Subw, Ca),
+                    0x1 => (Addw, Ca),
+                    _   => return None,
+                },
                 _   => if (raw & 0x1000) != 0 {
                     return None;
                 } else {
@@ -78,7 +118,13 @@ impl COp {
         
         Some(match funct3 {
             0x0 => (Slli, Ci),
+            #[cfg(feature = "d")]
+            0x1 => (Fldsp, Ci),
             0x2 if rd != 0 => (Lwsp, Ci),
+            #[cfg(all(feature = "f", not(feature = "rv64")))]
+            0x3 => (Flwsp, Ci),
+            #[cfg(feature = "rv64")]
+            0x3 if rd != 0 => (Ldsp, Ci),
             0x4 => match (raw & 0x1000) >> 12 {
                 0x0 if rd != 0 && rs2 == 0 => (Jr, Cr),
                 0x0 if rs2 != 0 => (Mv, Cr),
@@ -86,7 +132,13 @@ impl COp {
                 0x1 if rs2 != 0 => (Add, Cr),
                 _   => return None,
             }
+            #[cfg(feature = "d")]
+            0x5 => (Fsdsp, Css),
             0x6 => (Swsp, Css),
+            #[cfg(all(feature = "f", not(feature = "rv64")))]
+            0x7 => (Fswsp, Css),
+            #[cfg(feature = "rv64")]
+            0x7 => (Sdsp, Css),
             _   => return None,
         })
     }
@@ -96,12 +148,53 @@ impl COp {
             Addi4spn | Lw | Sw | Srli | Srai | Slli | Lwsp | Swsp )
     }
 
+    /// Word load relative to `sp`, which has its own immediate layout
+    pub(crate) fn is_load_sp(&self) -> bool {
+        match self {
+            Lwsp => true,
+            #[cfg(all(feature = "f", not(feature = "rv64")))]
+            Flwsp => true,
+            _ => false,
+        }
+    }
+
+    /// Doubleword access, which scales its immediate by 8
+    pub(crate) fn is_double_width(&self) -> bool {
+        match self {
+            #[cfg(feature = "d")]
+            Fld | Fsd | Fldsp | Fsdsp => true,
+            #[cfg(feature = "rv64")]
+            Ld | Sd | Ldsp | Sdsp => true,
+            _ => false,
+        }
+    }
+
+    #[cfg(all(feature = "f", not(feature = "rv64")))]
+    pub(crate) fn into_f(self) -> Option<FOp> {
+        Some(match self {
+            Flw | Flwsp => FOp::Flw,
+            Fsw | Fswsp => FOp::Fsw,
+            _ => return None,
+        })
+    }
+
+    #[cfg(feature = "d")]
+    pub(crate) fn into_d(self) -> Option<DOp> {
+        Some(match self {
+            Fld | Fldsp => DOp::Fld,
+            Fsd | Fsdsp => DOp::Fsd,
+            _ => return None,
+        })
+    }
+
     pub(crate) fn into_base(self) -> Rv32iOp {
         match self {
             Lwsp | Lw      => Rv32iOp::Lw,
             Swsp | Sw      => Rv32iOp::Sw,
             Addi4spn       => Rv32iOp::Addi,
-            J | Jal        => Rv32iOp::Jal,
+            J              => Rv32iOp::Jal,
+            #[cfg(not(feature = "rv64"))]
+            Jal            => Rv32iOp::Jal,
             Jr | Jalr      => Rv32iOp::Jalr,
             Beqz           => Rv32iOp::Beq,
             Bnez           => Rv32iOp::Bne,
@@ -118,6 +211,22 @@ impl COp {
             Srai           => Rv32iOp::Srai,
             Andi           => Rv32iOp::Andi,
             Ebreak         => Rv32iOp::Ebreak,      
+            #[cfg(feature = "rv64")]
+            Ld | Ldsp      => Rv32iOp::Ld,
+            #[cfg(feature = "rv64")]
+            Sd | Sdsp      => Rv32iOp::Sd,
+            #[cfg(feature = "rv64")]
+            Addiw          => Rv32iOp::Addiw,
+            #[cfg(feature = "rv64")]
+            Addw           => Rv32iOp::Addw,
+            #[cfg(feature = "rv64")]
+            Subw           => Rv32iOp::Subw,
+            #[cfg(all(feature = "f", not(feature = "rv64")))]
+            Flw | Fsw | 
+            Flwsp | Fswsp  => unreachable!("Converted by `into_f`"),
+            #[cfg(feature = "d")]
+            Fld | Fsd | 
+            Fldsp | Fsdsp  => unreachable!("Converted by `into_d`"),
         }
     }
 }
@@ -131,6 +240,7 @@ impl std::fmt::Display for COp {
                 Sw       => "c.sw",
                 Nop      => "c.nop",
                 Addi     => "c.addi",
+                #[cfg(not(feature = "rv64"))]
                 Jal      => "c.jal",
                 Li       => "c.li",
                 Addi16sp => "c.addi16sp",
@@ -153,6 +263,36 @@ impl std::fmt::Display for COp {
                 Jalr     => "c.jalr", 
                 Add      => "c.add",
                 Swsp     => "c.swsp",
+                #[cfg(all(feature = "f", not(feature = "rv64")))]
+                Flw      => "c.flw",
+                #[cfg(all(feature = "f", not(feature = "rv64")))]
+                Fsw      => "c.fsw",
+                #[cfg(all(feature = "f", not(feature = "rv64")))]
+                Flwsp    => "c.flwsp",
+                #[cfg(all(feature = "f", not(feature = "rv64")))]
+                Fswsp    => "c.fswsp",
+                #[cfg(feature = "d")]
+                Fld      => "c.fld",
+                #[cfg(feature = "d")]
+                Fsd      => "c.fsd",
+                #[cfg(feature = "d")]
+                Fldsp    => "c.fldsp",
+                #[cfg(feature = "d")]
+                Fsdsp    => "c.fsdsp",
+                #[cfg(feature = "rv64")]
+                Ld       => "c.ld",
+                #[cfg(feature = "rv64")]
+                Sd       => "c.sd",
+                #[cfg(feature = "rv64")]
+                Ldsp     => "c.ldsp",
+                #[cfg(feature = "rv64")]
+                Sdsp     => "c.sdsp",
+                #[cfg(feature = "rv64")]
+                Addiw    => "c.addiw",
+                #[cfg(feature = "rv64")]
+                Addw     => "c.addw",
+                #[cfg(feature = "rv64")]
+                Subw     => "c.subw",
             }
         )
     }
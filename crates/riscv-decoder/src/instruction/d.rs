@@ -0,0 +1,200 @@
+use DOp::*;
+
+use crate::opcode::OpCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DOp {
+    Fld, Fsd,
+    FmaddD, FmsubD, FnmsubD, FnmaddD,
+    FaddD, FsubD, FmulD, FdivD, FsqrtD,
+    FsgnjD, FsgnjnD, FsgnjxD, FminD, FmaxD,
+    FcvtSD, FcvtDS,
+    FcvtWD, FcvtWuD, FcvtDW, FcvtDWu,
+    FeqD, FltD, FleD, FclassD,
+    #[cfg(feature = "rv64")] FcvtLD,
+    #[cfg(feature = "rv64")] FcvtLuD,
+    #[cfg(feature = "rv64")] FcvtDL,
+    #[cfg(feature = "rv64")] FcvtDLu,
+    #[cfg(feature = "rv64")] FmvXD,
+    #[cfg(feature = "rv64")] FmvDX,
+}
+
+impl DOp {
+    pub(crate) fn decode_load(funct3: u8) -> Option<DOp> {
+        match funct3 {
+            0x3 => Some(Fld),
+            _   => None,
+        }
+    }
+
+    pub(crate) fn decode_store(funct3: u8) -> Option<DOp> {
+        match funct3 {
+            0x3 => Some(Fsd),
+            _   => None,
+        }
+    }
+
+    pub(crate) fn decode_fused(opcode: OpCode, fmt: u8) -> Option<DOp> {
+        Some(match (opcode, fmt) {
+            (OpCode::Madd, 1)  => FmaddD,
+            (OpCode::Msub, 1)  => FmsubD,
+            (OpCode::Nmsub, 1) => FnmsubD,
+            (OpCode::Nmadd, 1) => FnmaddD,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn decode_op(funct7: u8, funct3: u8, rs2: u8) -> Option<DOp> {
+        Some(match funct7 {
+            0x01 => FaddD,
+            0x05 => FsubD,
+            0x09 => FmulD,
+            0x0d => FdivD,
+            0x2d if rs2 == 0 => FsqrtD,
+            0x11 => match funct3 {
+                0x0 => FsgnjD,
+                0x1 => FsgnjnD,
+                0x2 => FsgnjxD,
+                _   => return None,
+            },
+            0x15 => match funct3 {
+                0x0 => FminD,
+                0x1 => FmaxD,
+                _   => return None,
+            },
+            0x20 if rs2 == 1 => FcvtSD,
+            0x21 if rs2 == 0 => FcvtDS,
+            0x61 => match rs2 {
+                0x0 => FcvtWD,
+                0x1 => FcvtWuD,
+                #[cfg(feature = "rv64")]
+                0x2 => FcvtLD,
+                #[cfg(feature = "rv64")]
+                0x3 => FcvtLuD,
+                _   => return None,
+            },
+            0x69 => match rs2 {
+                0x0 => FcvtDW,
+                0x1 => FcvtDWu,
+                #[cfg(feature = "rv64")]
+                0x2 => FcvtDL,
+                #[cfg(feature = "rv64")]
+                0x3 => FcvtDLu,
+                _   => return None,
+            },
+            0x71 if rs2 == 0 && funct3 == 1 => FclassD,
+            #[cfg(feature = "rv64")]
+            0x71 if rs2 == 0 && funct3 == 0 => FmvXD,
+            #[cfg(feature = "rv64")]
+            0x79 if rs2 == 0 && funct3 == 0 => FmvDX,
+            0x51 => match funct3 {
+                0x0 => FleD,
+                0x1 => FltD,
+                0x2 => FeqD,
+                _   => return None,
+            },
+            _ => return None,
+        })
+    }
+
+    pub fn is_load(&self) -> bool {
+        matches!(self, Fld)
+    }
+
+    pub fn is_store(&self) -> bool {
+        matches!(self, Fsd)
+    }
+
+    pub fn is_fused(&self) -> bool {
+        matches!(self, FmaddD | FmsubD | FnmsubD | FnmaddD)
+    }
+
+    /// `rm` selects a rounding mode instead of an operation. 
+    /// `fcvt.d.s` and `fcvt.d.w` are exact but still carry `rm`
+    pub fn has_rm(&self) -> bool {
+        self.is_fused() || match self {
+            FaddD | FsubD | FmulD | FdivD | FsqrtD | FcvtSD | FcvtDS |
+            FcvtWD | FcvtWuD | FcvtDW | FcvtDWu => true,
+            #[cfg(feature = "rv64")]
+            FcvtLD | FcvtLuD | FcvtDL | FcvtDLu => true,
+            _ => false,
+        }
+    }
+
+    /// Only reads `rs1`
+    pub fn is_unary(&self) -> bool {
+        match self {
+            FsqrtD | FcvtSD | FcvtDS | FcvtWD | FcvtWuD | FcvtDW | FcvtDWu | FclassD => true,
+            #[cfg(feature = "rv64")]
+            FcvtLD | FcvtLuD | FcvtDL | FcvtDLu | FmvXD | FmvDX => true,
+            _ => false,
+        }
+    }
+
+    /// Result goes to an integer register
+    pub fn is_rd_int(&self) -> bool {
+        match self {
+            FcvtWD | FcvtWuD | FeqD | FltD | FleD | FclassD => true,
+            #[cfg(feature = "rv64")]
+            FcvtLD | FcvtLuD | FmvXD => true,
+            _ => false,
+        }
+    }
+
+    /// Source comes from an integer register
+    pub fn is_rs1_int(&self) -> bool {
+        match self {
+            FcvtDW | FcvtDWu => true,
+            #[cfg(feature = "rv64")]
+            FcvtDL | FcvtDLu | FmvDX => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for DOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(
+            match self {
+                Fld      => "fld",
+                Fsd      => "fsd",
+                FmaddD   => "fmadd.d",
+                FmsubD   => "fmsub.d",
+                FnmsubD  => "fnmsub.d",
+                FnmaddD  => "fnmadd.d",
+                FaddD    => "fadd.d",
+                FsubD    => "fsub.d",
+                FmulD    => "fmul.d",
+                FdivD    => "fdiv.d",
+                FsqrtD   => "fsqrt.d",
+                FsgnjD   => "fsgnj.d",
+                FsgnjnD  => "fsgnjn.d",
+                FsgnjxD  => "fsgnjx.d",
+                FminD    => "fmin.d",
+                FmaxD    => "fmax.d",
+                FcvtSD   => "fcvt.s.d",
+                FcvtDS   => "fcvt.d.s",
+                FcvtWD   => "fcvt.w.d",
+                FcvtWuD  => "fcvt.wu.d",
+                FcvtDW   => "fcvt.d.w",
+                FcvtDWu  => "fcvt.d.wu",
+                FeqD     => "feq.d",
+                FltD     => "flt.d",
+                FleD     => "fle.d",
+                FclassD  => "fclass.d",
+                #[cfg(feature = "rv64")]
+                FcvtLD   => "fcvt.l.d",
+                #[cfg(feature = "rv64")]
+                FcvtLuD  => "fcvt.lu.d",
+                #[cfg(feature = "rv64")]
+                FcvtDL   => "fcvt.d.l",
+                #[cfg(feature = "rv64")]
+                FcvtDLu  => "fcvt.d.lu",
+                #[cfg(feature = "rv64")]
+                FmvXD    => "fmv.x.d",
+                #[cfg(feature = "rv64")]
+                FmvDX    => "fmv.d.x",
+            }
+        )
+    }
+}
@@ -0,0 +1,202 @@
+use FOp::*;
+
+use crate::opcode::OpCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FOp {
+    Flw, Fsw,
+    FmaddS, FmsubS, FnmsubS, FnmaddS,
+    FaddS, FsubS, FmulS, FdivS, FsqrtS,
+    FsgnjS, FsgnjnS, FsgnjxS, FminS, FmaxS,
+    FcvtWS, FcvtWuS, FcvtSW, FcvtSWu,
+    FmvXW, FmvWX, FeqS, FltS, FleS, FclassS,
+    #[cfg(feature = "rv64")] FcvtLS,
+    #[cfg(feature = "rv64")] FcvtLuS,
+    #[cfg(feature = "rv64")] FcvtSL,
+    #[cfg(feature = "rv64")] FcvtSLu,
+}
+
+/// Floating-point instructions need a third source and the rounding mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FpInsData {
+    pub rd: u8,
+    pub rs1: u8,
+    pub rs2: u8,
+    pub rs3: u8,
+    /// `funct3`, only meaningful for instructions that round
+    pub rm: u8,
+    pub imm: i32,
+}
+
+impl FOp {
+    pub(crate) fn decode_load(funct3: u8) -> Option<FOp> {
+        match funct3 {
+            0x2 => Some(Flw),
+            _   => None,
+        }
+    }
+
+    pub(crate) fn decode_store(funct3: u8) -> Option<FOp> {
+        match funct3 {
+            0x2 => Some(Fsw),
+            _   => None,
+        }
+    }
+
+    pub(crate) fn decode_fused(opcode: OpCode, fmt: u8) -> Option<FOp> {
+        Some(match (opcode, fmt) {
+            (OpCode::Madd, 0)  => FmaddS,
+            (OpCode::Msub, 0)  => FmsubS,
+            (OpCode::Nmsub, 0) => FnmsubS,
+            (OpCode::Nmadd, 0) => FnmaddS,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn decode_op(funct7: u8, funct3: u8, rs2: u8) -> Option<FOp> {
+        Some(match funct7 {
+            0x00 => FaddS,
+            0x04 => FsubS,
+            0x08 => FmulS,
+            0x0c => FdivS,
+            0x2c if rs2 == 0 => FsqrtS,
+            0x10 => match funct3 {
+                0x0 => FsgnjS,
+                0x1 => FsgnjnS,
+                0x2 => FsgnjxS,
+                _   => return None,
+            },
+            0x14 => match funct3 {
+                0x0 => FminS,
+                0x1 => FmaxS,
+                _   => return None,
+            },
+            0x60 => match rs2 {
+                0x0 => FcvtWS,
+                0x1 => FcvtWuS,
+                #[cfg(feature = "rv64")]
+                0x2 => FcvtLS,
+                #[cfg(feature = "rv64")]
+                0x3 => FcvtLuS,
+                _   => return None,
+            },
+            0x68 => match rs2 {
+                0x0 => FcvtSW,
+                0x1 => FcvtSWu,
+                #[cfg(feature = "rv64")]
+                0x2 => FcvtSL,
+                #[cfg(feature = "rv64")]
+                0x3 => FcvtSLu,
+                _   => return None,
+            },
+            0x70 if rs2 == 0 => match funct3 {
+                0x0 => FmvXW,
+                0x1 => FclassS,
+                _   => return None,
+            },
+            0x78 if rs2 == 0 && funct3 == 0 => FmvWX,
+            0x50 => match funct3 {
+                0x0 => FleS,
+                0x1 => FltS,
+                0x2 => FeqS,
+                _   => return None,
+            },
+            _ => return None,
+        })
+    }
+
+    pub fn is_load(&self) -> bool {
+        matches!(self, Flw)
+    }
+
+    pub fn is_store(&self) -> bool {
+        matches!(self, Fsw)
+    }
+
+    pub fn is_fused(&self) -> bool {
+        matches!(self, FmaddS | FmsubS | FnmsubS | FnmaddS)
+    }
+
+    /// `rm` selects a rounding mode instead of an operation
+    pub fn has_rm(&self) -> bool {
+        self.is_fused() || match self {
+            FaddS | FsubS | FmulS | FdivS | FsqrtS | FcvtWS | FcvtWuS | FcvtSW | FcvtSWu => true,
+            #[cfg(feature = "rv64")]
+            FcvtLS | FcvtLuS | FcvtSL | FcvtSLu => true,
+            _ => false,
+        }
+    }
+
+    /// Only reads `rs1`
+    pub fn is_unary(&self) -> bool {
+        match self {
+            FsqrtS | FcvtWS | FcvtWuS | FcvtSW | FcvtSWu | FmvXW | FmvWX | FclassS => true,
+            #[cfg(feature = "rv64")]
+            FcvtLS | FcvtLuS | FcvtSL | FcvtSLu => true,
+            _ => false,
+        }
+    }
+
+    /// Result goes to an integer register
+    pub fn is_rd_int(&self) -> bool {
+        match self {
+            FcvtWS | FcvtWuS | FmvXW | FeqS | FltS | FleS | FclassS => true,
+            #[cfg(feature = "rv64")]
+            FcvtLS | FcvtLuS => true,
+            _ => false,
+        }
+    }
+
+    /// Source comes from an integer register
+    pub fn is_rs1_int(&self) -> bool {
+        match self {
+            FcvtSW | FcvtSWu | FmvWX => true,
+            #[cfg(feature = "rv64")]
+            FcvtSL | FcvtSLu => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for FOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(
+            match self {
+                Flw      => "flw",
+                Fsw      => "fsw",
+                FmaddS   => "fmadd.s",
+                FmsubS   => "fmsub.s",
+                FnmsubS  => "fnmsub.s",
+                FnmaddS  => "fnmadd.s",
+                FaddS    => "fadd.s",
+                FsubS    => "fsub.s",
+                FmulS    => "fmul.s",
+                FdivS    => "fdiv.s",
+                FsqrtS   => "fsqrt.s",
+                FsgnjS   => "fsgnj.s",
+                FsgnjnS  => "fsgnjn.s",
+                FsgnjxS  => "fsgnjx.s",
+                FminS    => "fmin.s",
+                FmaxS    => "fmax.s",
+                FcvtWS   => "fcvt.w.s",
+                FcvtWuS  => "fcvt.wu.s",
+                FcvtSW   => "fcvt.s.w",
+                FcvtSWu  => "fcvt.s.wu",
+                FmvXW    => "fmv.x.w",
+                FmvWX    => "fmv.w.x",
+                FeqS     => "feq.s",
+                FltS     => "flt.s",
+                FleS     => "fle.s",
+                FclassS  => "fclass.s",
+                #[cfg(feature = "rv64")]
+                FcvtLS   => "fcvt.l.s",
+                #[cfg(feature = "rv64")]
+                FcvtLuS  => "fcvt.lu.s",
+                #[cfg(feature = "rv64")]
+                FcvtSL   => "fcvt.s.l",
+                #[cfg(feature = "rv64")]
+                FcvtSLu  => "fcvt.s.lu",
+            }
+        )
+    }
+}
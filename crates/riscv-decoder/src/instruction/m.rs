@@ -4,6 +4,11 @@ use MOp::*;
 pub enum MOp {
     Mul, Mulh, Mulhu, Mulhsu,
     Div, Divu, Rem, Remu,
+    #[cfg(feature = "rv64")] Mulw,
+    #[cfg(feature = "rv64")] Divw,
+    #[cfg(feature = "rv64")] Divuw,
+    #[cfg(feature = "rv64")] Remw,
+    #[cfg(feature = "rv64")] Remuw,
 }
 
 impl MOp {
@@ -23,6 +28,18 @@ impl MOp {
             _ => return None,
         })
     }
+
+    #[cfg(feature = "rv64")]
+    pub(crate) fn decode_w(funct3: u8, funct7: u8) -> Option<MOp> {
+        Some(match (funct7, funct3) {
+            (0x01, 0x0) => Mulw,
+            (0x01, 0x4) => Divw,
+            (0x01, 0x5) => Divuw,
+            (0x01, 0x6) => Remw,
+            (0x01, 0x7) => Remuw,
+            _ => return None,
+        })
+    }
 }
 
 impl std::fmt::Display for MOp {
@@ -37,6 +54,11 @@ impl std::fmt::Display for MOp {
                 Divu   => "divu",
                 Rem    => "rem",
                 Remu   => "remu",
+                #[cfg(feature = "rv64")] Mulw  => "mulw",
+                #[cfg(feature = "rv64")] Divw  => "divw",
+                #[cfg(feature = "rv64")] Divuw => "divuw",
+                #[cfg(feature = "rv64")] Remw  => "remw",
+                #[cfg(feature = "rv64")] Remuw => "remuw",
             }
         )
     }
@@ -3,7 +3,10 @@ use PrivilegeOp::*;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PrivilegeOp {
     Mret, 
+    Mnret,
+    Dret,
     #[cfg(feature = "s")] Sret,
+    Wfi(u32),
     #[cfg(feature = "s")] SfenceVma(u32),
 }
 
@@ -13,6 +16,9 @@ impl PrivilegeOp {
         Some(match raw {
             #[cfg(feature = "s")] 0x10200073 => Sret,
             0x30200073 => Mret,
+            0x70200073 => Mnret,
+            0x7b200073 => Dret,
+            0x10500073 => Wfi(raw),
             _          => match funct3 {
                 0x0 => match funct7 {
                     #[cfg(feature = "s")] 0x09 if rd == 0 => {
@@ -36,7 +42,10 @@ impl std::fmt::Display for PrivilegeOp {
         f.pad( 
             match self {
                 Mret         => "mret",
+                Mnret        => "mnret",
+                Dret         => "dret",
                 #[cfg(feature = "s")] Sret         => "sret",
+                Wfi(_)       => "wfi",
                 #[cfg(feature = "s")] SfenceVma(_) => "sfence.vma",
             }
         )
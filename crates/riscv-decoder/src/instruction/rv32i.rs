@@ -28,14 +28,28 @@ pub enum Rv32iOp {
     Jal,
     // Utype
     Lui, Auipc,
+    // RV64I
+    #[cfg(feature = "rv64")] Lwu,
+    #[cfg(feature = "rv64")] Ld,
+    #[cfg(feature = "rv64")] Sd,
+    #[cfg(feature = "rv64")] Addiw,
+    #[cfg(feature = "rv64")] Slliw,
+    #[cfg(feature = "rv64")] Srliw,
+    #[cfg(feature = "rv64")] Sraiw,
+    #[cfg(feature = "rv64")] Addw,
+    #[cfg(feature = "rv64")] Subw,
+    #[cfg(feature = "rv64")] Sllw,
+    #[cfg(feature = "rv64")] Srlw,
+    #[cfg(feature = "rv64")] Sraw,
 }
 
 impl Rv32iOp {
+    /// `funct7` of shifts excludes `shamt[5]` on RV64
     pub(crate) fn decode_itype(opcode: OpCode, funct3: u8, funct7: u8) -> Option<Rv32iOp> {
         Some(match opcode {
             OpCode::ItypeAr => match funct3 {
                 0x0 => Addi,
-                0x1 => Slli,
+                0x1 if funct7 == 0 => Slli,
                 0x2 => Slti,
                 0x3 => Sltiu,
                 0x4 => Xori,
@@ -52,8 +66,12 @@ impl Rv32iOp {
                 0x0 => Lb,
                 0x1 => Lh,
                 0x2 => Lw,
+                #[cfg(feature = "rv64")]
+                0x3 => Ld,
                 0x4 => Lbu,
                 0x5 => Lhu,
+                #[cfg(feature = "rv64")]
+                0x6 => Lwu,
                 _   => return None,
             },
             OpCode::ItypeJump => match funct3 {
@@ -64,6 +82,17 @@ impl Rv32iOp {
                 0x0 => Fence,
                 _   => return None,
             },
+            #[cfg(feature = "rv64")]
+            OpCode::ItypeArW => match funct3 {
+                0x0 => Addiw,
+                0x1 if funct7 == 0 => Slliw,
+                0x5 => match funct7 {
+                    0x00 => Srliw,
+                    0x20 => Sraiw,
+                    _    => return None,
+                },
+                _   => return None,
+            },
             _ => return None,
         })
     }
@@ -96,10 +125,24 @@ impl Rv32iOp {
             0x0 => Sb,
             0x1 => Sh,
             0x2 => Sw,
+            #[cfg(feature = "rv64")]
+            0x3 => Sd,
             _   => return None,
         })
     }
 
+    #[cfg(feature = "rv64")]
+    pub(crate) fn decode_rtype_w(funct3: u8, funct7: u8) -> Option<Rv32iOp> {
+        Some(match (funct7, funct3) {
+            (0x00, 0x0) => Addw,
+            (0x20, 0x0) => Subw,
+            (0x00, 0x1) => Sllw,
+            (0x00, 0x5) => Srlw,
+            (0x20, 0x5) => Sraw,
+            _ => return None,
+        })
+    }
+
     pub(crate) fn decode_btype(funct3: u8) -> Option<Rv32iOp> {
         Some(match funct3 {
             0x0 => Beq,
@@ -136,20 +179,26 @@ impl Rv32iOp {
     }
 
     pub fn is_itype_ar(&self) -> bool {
-        matches!(self, 
-                Addi | Slli  |
-                Slti | Sltiu |
-                Xori | Srli  |
-                Srai | Ori   |
-                Andi
-        )
+        match self {
+            Addi | Slli  |
+            Slti | Sltiu |
+            Xori | Srli  |
+            Srai | Ori   |
+            Andi => true,
+            #[cfg(feature = "rv64")]
+            Addiw | Slliw | Srliw | Sraiw => true,
+            _ => false,
+        }
     }
 
     pub fn is_itype_load(&self) -> bool {
-        matches!(self, 
+        match self {
             Lb  | Lh | Lw |
-            Lbu | Lhu
-        )
+            Lbu | Lhu => true,
+            #[cfg(feature = "rv64")]
+            Lwu | Ld => true,
+            _ => false,
+        }
     }
 
     pub fn is_itype_jump(&self) -> bool {
@@ -165,17 +214,31 @@ impl Rv32iOp {
     }
 
     pub fn is_rtype(&self) -> bool {
-        matches!(self, 
+        match self {
             Add  | Sub |
             Sll  | Slt |
             Sltu | Xor |
             Srl  | Sra |
-            Or   | And
-        )
+            Or   | And => true,
+            #[cfg(feature = "rv64")]
+            Addw | Subw | Sllw | Srlw | Sraw => true,
+            _ => false,
+        }
     }
 
     pub fn is_stype(&self) -> bool {
-        matches!(self, Sb | Sh | Sw)
+        match self {
+            Sb | Sh | Sw => true,
+            #[cfg(feature = "rv64")]
+            Sd => true,
+            _ => false,
+        }
+    }
+
+    /// Operates on the low 32 bits and sign-extends the result
+    #[cfg(feature = "rv64")]
+    pub fn is_word(&self) -> bool {
+        matches!(self, Addiw | Slliw | Srliw | Sraiw | Addw | Subw | Sllw | Srlw | Sraw)
     }
 
     pub fn is_btype(&self) -> bool {
@@ -227,6 +290,19 @@ impl std::fmt::Display for Rv32iOp {
 
             Ecall  => "ecall", 
             Ebreak => "ebreak",
+
+            #[cfg(feature = "rv64")] Lwu   => "lwu",
+            #[cfg(feature = "rv64")] Ld    => "ld",
+            #[cfg(feature = "rv64")] Sd    => "sd",
+            #[cfg(feature = "rv64")] Addiw => "addiw",
+            #[cfg(feature = "rv64")] Slliw => "slliw",
+            #[cfg(feature = "rv64")] Srliw => "srliw",
+            #[cfg(feature = "rv64")] Sraiw => "sraiw",
+            #[cfg(feature = "rv64")] Addw  => "addw",
+            #[cfg(feature = "rv64")] Subw  => "subw",
+            #[cfg(feature = "rv64")] Sllw  => "sllw",
+            #[cfg(feature = "rv64")] Srlw  => "srlw",
+            #[cfg(feature = "rv64")] Sraw  => "sraw",
         };
 
         f.pad(op_str)
@@ -0,0 +1,91 @@
+use ZbaOp::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZbaOp {
+    Sh1add, Sh2add, Sh3add,
+    #[cfg(feature = "rv64")] AddUw,
+    #[cfg(feature = "rv64")] Sh1addUw,
+    #[cfg(feature = "rv64")] Sh2addUw,
+    #[cfg(feature = "rv64")] Sh3addUw,
+    #[cfg(feature = "rv64")] SlliUw,
+}
+
+impl ZbaOp {
+    pub(crate) fn decode(funct3: u8, funct7: u8) -> Option<ZbaOp> {
+        Some(match funct7 {
+            0x10 => match funct3 {
+                0x2 => Sh1add,
+                0x4 => Sh2add,
+                0x6 => Sh3add,
+                _   => return None,
+            },
+            _ => return None,
+        })
+    }
+
+    #[cfg(feature = "rv64")]
+    pub(crate) fn decode_w(funct3: u8, funct7: u8) -> Option<ZbaOp> {
+        Some(match (funct7, funct3) {
+            (0x04, 0x0) => AddUw,
+            (0x10, 0x2) => Sh1addUw,
+            (0x10, 0x4) => Sh2addUw,
+            (0x10, 0x6) => Sh3addUw,
+            _ => return None,
+        })
+    }
+
+    /// `imm` is the whole 12 bits immediate, `slli.uw` takes a 6 bits shift amount
+    #[cfg(feature = "rv64")]
+    pub(crate) fn decode_itype_w(funct3: u8, imm: u16) -> Option<ZbaOp> {
+        Some(match (funct3, imm >> 6) {
+            (0x1, 0x02) => SlliUw,
+            _ => return None,
+        })
+    }
+
+    /// How far `rs1` is shifted before the add
+    pub fn shift(&self) -> u32 {
+        match self {
+            Sh1add => 1,
+            Sh2add => 2,
+            Sh3add => 3,
+            #[cfg(feature = "rv64")] AddUw | SlliUw => 0,
+            #[cfg(feature = "rv64")] Sh1addUw => 1,
+            #[cfg(feature = "rv64")] Sh2addUw => 2,
+            #[cfg(feature = "rv64")] Sh3addUw => 3,
+        }
+    }
+
+    /// Zero-extends the low word of `rs1` first
+    pub fn is_unsigned_word(&self) -> bool {
+        match self {
+            #[cfg(feature = "rv64")] AddUw | Sh1addUw | Sh2addUw | Sh3addUw | SlliUw => true,
+            _ => false,
+        }
+    }
+
+    /// Takes a shift amount instead of `rs2`
+    pub fn is_imm(&self) -> bool {
+        match self {
+            #[cfg(feature = "rv64")] SlliUw => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for ZbaOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(
+            match self {
+                Sh1add => "sh1add",
+                Sh2add => "sh2add",
+                Sh3add => "sh3add",
+                #[cfg(feature = "rv64")] AddUw => "add.uw",
+                #[cfg(feature = "rv64")] Sh1addUw => "sh1add.uw",
+                #[cfg(feature = "rv64")] Sh2addUw => "sh2add.uw",
+                #[cfg(feature = "rv64")] Sh3addUw => "sh3add.uw",
+                #[cfg(feature = "rv64")] SlliUw => "slli.uw",
+            }
+        )
+    }
+}
@@ -0,0 +1,141 @@
+use ZbbOp::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZbbOp {
+    // Rtype
+    Andn, Orn, Xnor,
+    Min, Minu, Max, Maxu,
+    Rol, Ror, ZextH,
+    // Itype Ar
+    Clz, Ctz, Cpop, SextB, SextH,
+    Rori, OrcB, Rev8,
+    // RV64 word ops
+    #[cfg(feature = "rv64")] Rolw,
+    #[cfg(feature = "rv64")] Rorw,
+    #[cfg(feature = "rv64")] Clzw,
+    #[cfg(feature = "rv64")] Ctzw,
+    #[cfg(feature = "rv64")] Cpopw,
+    #[cfg(feature = "rv64")] Roriw,
+}
+
+impl ZbbOp {
+    pub(crate) fn decode_rtype(funct3: u8, funct7: u8, rs2: u8) -> Option<ZbbOp> {
+        Some(match funct7 {
+            0x20 => match funct3 {
+                0x4 => Xnor,
+                0x6 => Orn,
+                0x7 => Andn,
+                _   => return None,
+            },
+            0x05 => match funct3 {
+                0x4 => Min,
+                0x5 => Minu,
+                0x6 => Max,
+                0x7 => Maxu,
+                _   => return None,
+            },
+            0x30 => match funct3 {
+                0x1 => Rol,
+                0x5 => Ror,
+                _   => return None,
+            },
+            0x04 if !cfg!(feature = "rv64") && funct3 == 0x4 && rs2 == 0 => ZextH,
+            _ => return None,
+        })
+    }
+
+    /// `zext.h` is encoded in OP-32 on RV64
+    #[cfg(feature = "rv64")]
+    pub(crate) fn decode_rtype_w(funct3: u8, funct7: u8, rs2: u8) -> Option<ZbbOp> {
+        Some(match (funct7, funct3, rs2) {
+            (0x04, 0x4, 0) => ZextH,
+            (0x30, 0x1, _) => Rolw,
+            (0x30, 0x5, _) => Rorw,
+            _ => return None,
+        })
+    }
+
+    /// `imm` is the whole 12 bits immediate, `roriw` takes a 5 bits shift amount
+    #[cfg(feature = "rv64")]
+    pub(crate) fn decode_itype_w(funct3: u8, imm: u16) -> Option<ZbbOp> {
+        Some(match (funct3, imm) {
+            (0x1, 0x600) => Clzw,
+            (0x1, 0x601) => Ctzw,
+            (0x1, 0x602) => Cpopw,
+            (0x5, imm) if imm >> 5 == 0x30 => Roriw,
+            _ => return None,
+        })
+    }
+
+    /// `imm` is the whole 12 bits immediate, which selects the operation
+    pub(crate) fn decode_itype(funct3: u8, imm: u16) -> Option<ZbbOp> {
+        Some(match (funct3, imm) {
+            (0x1, 0x600) => Clz,
+            (0x1, 0x601) => Ctz,
+            (0x1, 0x602) => Cpop,
+            (0x1, 0x604) => SextB,
+            (0x1, 0x605) => SextH,
+            (0x5, 0x287) => OrcB,
+            #[cfg(not(feature = "rv64"))]
+            (0x5, 0x698) => Rev8,
+            #[cfg(feature = "rv64")]
+            (0x5, 0x6b8) => Rev8,
+            #[cfg(not(feature = "rv64"))]
+            (0x5, imm) if imm >> 5 == 0x30 => Rori,
+            #[cfg(feature = "rv64")]
+            (0x5, imm) if imm >> 6 == 0x18 => Rori,
+            _ => return None,
+        })
+    }
+
+    /// Only reads `rs1`
+    pub fn is_unary(&self) -> bool {
+        match self {
+            Clz | Ctz | Cpop | SextB | SextH | ZextH | OrcB | Rev8 => true,
+            #[cfg(feature = "rv64")] Clzw | Ctzw | Cpopw => true,
+            _ => false,
+        }
+    }
+
+    /// Takes a shift amount instead of `rs2`
+    pub fn is_imm(&self) -> bool {
+        match self {
+            Rori => true,
+            #[cfg(feature = "rv64")] Roriw => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for ZbbOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(
+            match self {
+                Andn  => "andn",
+                Orn   => "orn",
+                Xnor  => "xnor",
+                Min   => "min",
+                Minu  => "minu",
+                Max   => "max",
+                Maxu  => "maxu",
+                Rol   => "rol",
+                Ror   => "ror",
+                ZextH => "zext.h",
+                Clz   => "clz",
+                Ctz   => "ctz",
+                Cpop  => "cpop",
+                SextB => "sext.b",
+                SextH => "sext.h",
+                Rori  => "rori",
+                OrcB  => "orc.b",
+                Rev8  => "rev8",
+                #[cfg(feature = "rv64")] Rolw  => "rolw",
+                #[cfg(feature = "rv64")] Rorw  => "rorw",
+                #[cfg(feature = "rv64")] Clzw  => "clzw",
+                #[cfg(feature = "rv64")] Ctzw  => "ctzw",
+                #[cfg(feature = "rv64")] Cpopw => "cpopw",
+                #[cfg(feature = "rv64")] Roriw => "roriw",
+            }
+        )
+    }
+}
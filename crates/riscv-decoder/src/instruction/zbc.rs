@@ -0,0 +1,32 @@
+use ZbcOp::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZbcOp {
+    Clmul, Clmulr, Clmulh,
+}
+
+impl ZbcOp {
+    pub(crate) fn decode(funct3: u8, funct7: u8) -> Option<ZbcOp> {
+        Some(match funct7 {
+            0x05 => match funct3 {
+                0x1 => Clmul,
+                0x2 => Clmulr,
+                0x3 => Clmulh,
+                _   => return None,
+            },
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for ZbcOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(
+            match self {
+                Clmul  => "clmul",
+                Clmulr => "clmulr",
+                Clmulh => "clmulh",
+            }
+        )
+    }
+}
@@ -0,0 +1,53 @@
+use ZbsOp::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZbsOp {
+    // Rtype
+    Bclr, Bext, Binv, Bset,
+    // Itype Ar
+    Bclri, Bexti, Binvi, Bseti,
+}
+
+impl ZbsOp {
+    pub(crate) fn decode_rtype(funct3: u8, funct7: u8) -> Option<ZbsOp> {
+        Some(match (funct7, funct3) {
+            (0x24, 0x1) => Bclr,
+            (0x24, 0x5) => Bext,
+            (0x34, 0x1) => Binv,
+            (0x14, 0x1) => Bset,
+            _ => return None,
+        })
+    }
+
+    /// RV32 shift amounts are 5 bits, so `funct7` is the upper part of the immediate
+    pub(crate) fn decode_itype(funct3: u8, funct7: u8) -> Option<ZbsOp> {
+        Some(match Self::decode_rtype(funct3, funct7)? {
+            Bclr => Bclri,
+            Bext => Bexti,
+            Binv => Binvi,
+            Bset => Bseti,
+            _    => unreachable!("Only register forms are decoded"),
+        })
+    }
+
+    pub fn is_imm(&self) -> bool {
+        matches!(self, Bclri | Bexti | Binvi | Bseti)
+    }
+}
+
+impl std::fmt::Display for ZbsOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(
+            match self {
+                Bclr  => "bclr",
+                Bext  => "bext",
+                Binv  => "binv",
+                Bset  => "bset",
+                Bclri => "bclri",
+                Bexti => "bexti",
+                Binvi => "binvi",
+                Bseti => "bseti",
+            }
+        )
+    }
+}
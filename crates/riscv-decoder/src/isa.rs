@@ -0,0 +1,211 @@
+//! Runtime ISA description. Cargo features decide what is compiled in,
+//! an `Isa` picks which of those a hart actually has
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::IsaError;
+
+use Extension::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extension {
+    I, M, A, F, D, C,
+    /// Supervisor mode
+    S,
+    Zicsr, Zifencei,
+    Zba, Zbb, Zbc, Zbs,
+}
+
+const EXTENSIONS: [Extension; 13] = [I, M, A, F, D, C, S, Zicsr, Zifencei, Zba, Zbb, Zbc, Zbs];
+
+impl Extension {
+    /// Bit in `misa`, multi-letter extensions have none
+    pub fn misa_bit(&self) -> Option<u32> {
+        Some(match self {
+            A => 0,
+            C => 2,
+            D => 3,
+            F => 5,
+            I => 8,
+            M => 12,
+            S => 18,
+            _ => return None,
+        })
+    }
+
+    /// Enabled by its cargo feature
+    pub fn is_compiled(&self) -> bool {
+        match self {
+            I        => true,
+            M        => cfg!(feature = "m"),
+            A        => cfg!(feature = "a"),
+            F        => cfg!(feature = "f"),
+            D        => cfg!(feature = "d"),
+            C        => cfg!(feature = "c"),
+            S        => cfg!(feature = "s"),
+            Zicsr    => cfg!(feature = "zicsr"),
+            Zifencei => cfg!(feature = "zifencei"),
+            Zba      => cfg!(feature = "zba"),
+            Zbb      => cfg!(feature = "zbb"),
+            Zbc      => cfg!(feature = "zbc"),
+            Zbs      => cfg!(feature = "zbs"),
+        }
+    }
+
+    /// Extension that has to be present as well
+    pub fn requires(&self) -> Option<Extension> {
+        match self {
+            D     => Some(F),
+            F | S => Some(Zicsr),
+            _     => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            I        => "i",
+            M        => "m",
+            A        => "a",
+            F        => "f",
+            D        => "d",
+            C        => "c",
+            S        => "s",
+            Zicsr    => "zicsr",
+            Zifencei => "zifencei",
+            Zba      => "zba",
+            Zbb      => "zbb",
+            Zbc      => "zbc",
+            Zbs      => "zbs",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Extension> {
+        EXTENSIONS.into_iter().find(|ext| ext.name() == name)
+    }
+
+    fn mask(&self) -> u32 {
+        1 << *self as u32
+    }
+}
+
+impl fmt::Display for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// Set of extensions, the default is everything compiled in
+///
+/// ## Example
+/// ```rust
+/// # use riscv_decoder::isa::{Extension, Isa};
+/// # let xlen = if cfg!(feature = "rv64") { "rv64" } else { "rv32" };
+/// let isa: Isa = format!("{xlen}i").parse().unwrap();
+///
+/// assert!(isa.has(Extension::I));
+/// assert!(!isa.has(Extension::M));
+/// assert_eq!(isa.to_string(), format!("{xlen}i"));
+/// assert_eq!(Isa::default().without(Extension::M).misa() & (1 << 12), 0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Isa(u32);
+
+impl Default for Isa {
+    fn default() -> Self {
+        Isa(EXTENSIONS.iter()
+            .filter(|ext| ext.is_compiled())
+            .fold(0, |mask, ext| mask | ext.mask()))
+    }
+}
+
+impl Isa {
+    pub fn has(&self, ext: Extension) -> bool {
+        self.0 & ext.mask() != 0
+    }
+
+    /// Drop `ext` and everything that requires it. `I` always stays
+    pub fn without(self, ext: Extension) -> Self {
+        if ext == I {
+            return self;
+        }
+        let mut isa = Isa(self.0 & !ext.mask());
+        for other in EXTENSIONS {
+            if other.requires() == Some(ext) {
+                isa = isa.without(other);
+            }
+        }
+        isa
+    }
+
+    /// Single-letter extensions as `misa` bits
+    pub fn misa(&self) -> u32 {
+        EXTENSIONS.iter()
+            .filter(|ext| self.has(**ext))
+            .filter_map(Extension::misa_bit)
+            .fold(0, |bits, bit| bits | (1 << bit))
+    }
+
+    /// Drop the single-letter extensions whose bit is clear in `misa`
+    pub fn with_misa(self, misa: u32) -> Self {
+        EXTENSIONS.iter()
+            .filter(|ext| ext.misa_bit().is_some_and(|bit| misa & (1 << bit) == 0))
+            .fold(self, |isa, ext| isa.without(*ext))
+    }
+}
+
+impl FromStr for Isa {
+    type Err = IsaError;
+
+    /// Parse an ISA string like `rv32imac_zicsr_zifencei`. `g` is `imafd_zicsr_zifencei`
+    /// and `s` adds supervisor mode. Version numbers are not supported
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        let xlen = if cfg!(feature = "rv64") { "rv64" } else { "rv32" };
+        let Some(rest) = lower.strip_prefix(xlen) else {
+            return Err(IsaError::BadPrefix(s.to_string()));
+        };
+
+        let mut names: Vec<&str> = Vec::new();
+        let mut tokens = rest.split('_');
+        // Single letters, optionally followed by the first multi-letter extension
+        let letters = tokens.next().unwrap_or_default();
+        let (single, multi) = letters.split_at(letters.find('z').unwrap_or(letters.len()));
+        match single.get(..1) {
+            Some("g") => names.extend(["i", "m", "a", "f", "d", "zicsr", "zifencei"]),
+            Some("i") => names.push("i"),
+            _         => return Err(IsaError::MissingBase),
+        }
+        names.extend((1..single.len()).map(|idx| &single[idx..idx + 1]));
+        names.extend(std::iter::once(multi).chain(tokens).filter(|name| !name.is_empty()));
+
+        let mut isa = Isa(0);
+        for name in names {
+            let ext = Extension::from_name(name)
+                .ok_or_else(|| IsaError::UnknownExtension(name.to_string()))?;
+            if !ext.is_compiled() {
+                return Err(IsaError::NotCompiled(ext));
+            }
+            isa.0 |= ext.mask();
+        }
+
+        for ext in EXTENSIONS {
+            if let Some(req) = ext.requires() && isa.has(ext) && !isa.has(req) {
+                return Err(IsaError::MissingDependency(ext, req));
+            }
+        }
+        Ok(isa)
+    }
+}
+
+impl fmt::Display for Isa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if cfg!(feature = "rv64") { "rv64" } else { "rv32" })?;
+        for ext in EXTENSIONS.iter().filter(|ext| self.has(**ext)) {
+            if ext.misa_bit().is_none() {
+                f.write_str("_")?;
+            }
+            f.write_str(ext.name())?;
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,10 @@
 pub mod decoder;
 pub mod instruction;
+pub mod isa;
 pub mod prelude;
 
 mod bits_op;
 mod error;
 mod opcode;
 
-pub use error::DecodeError;
+pub use error::{DecodeError, IsaError};
@@ -11,7 +11,11 @@ pub enum OpCode {
     ItypeLoad = 0x03,
     ItypeJump = 0x67,
     ItypeFence = 0x0f,
+    #[cfg(feature = "rv64")]
+    ItypeArW = 0x1b,
     Rtype = 0x33,
+    #[cfg(feature = "rv64")]
+    RtypeW = 0x3b,
     Stype = 0x23,
     Btype = 0x63,
     Jtype = 0x6f,
@@ -20,6 +24,20 @@ pub enum OpCode {
     System = 0x73,
     #[cfg(feature = "a")]
     Amo = 0x2f, // Atomic Memory Operation
+    #[cfg(feature = "f")]
+    LoadFp = 0x07,
+    #[cfg(feature = "f")]
+    StoreFp = 0x27,
+    #[cfg(feature = "f")]
+    Madd = 0x43,
+    #[cfg(feature = "f")]
+    Msub = 0x47,
+    #[cfg(feature = "f")]
+    Nmsub = 0x4b,
+    #[cfg(feature = "f")]
+    Nmadd = 0x4f,
+    #[cfg(feature = "f")]
+    OpFp = 0x53,
 }
 
 impl TryFrom<u8> for OpCode {
@@ -33,7 +51,11 @@ impl TryFrom<u8> for OpCode {
             0x03 => ItypeLoad,
             0x67 => ItypeJump,
             0x0f => ItypeFence,
+            #[cfg(feature = "rv64")]
+            0x1b => ItypeArW,
             0x33 => Rtype,
+            #[cfg(feature = "rv64")]
+            0x3b => RtypeW,
             0x23 => Stype,
             0x63 => Btype,
             0x6f => Jtype,
@@ -42,6 +64,20 @@ impl TryFrom<u8> for OpCode {
             0x73 => System, 
             #[cfg(feature = "a")]
             0x2f => Amo, 
+            #[cfg(feature = "f")]
+            0x07 => LoadFp,
+            #[cfg(feature = "f")]
+            0x27 => StoreFp,
+            #[cfg(feature = "f")]
+            0x43 => Madd,
+            #[cfg(feature = "f")]
+            0x47 => Msub,
+            #[cfg(feature = "f")]
+            0x4b => Nmsub,
+            #[cfg(feature = "f")]
+            0x4f => Nmadd,
+            #[cfg(feature = "f")]
+            0x53 => OpFp,
             _    => return Err(DecodeError::UnknownOpcode(value)),
         })
     }
@@ -61,7 +97,11 @@ impl std::fmt::Display for OpCode {
             ItypeLoad   => "I-type: load",
             ItypeJump   => "I-type: jump",
             ItypeFence  => "I-type: fence",
+            #[cfg(feature = "rv64")]
+            ItypeArW    => "I-type: word",
             Rtype       => "R-type",
+            #[cfg(feature = "rv64")]
+            RtypeW      => "R-type: word",
             Stype       => "S-type",
             Btype       => "B-type",
             Jtype       => "J-type",
@@ -70,6 +110,14 @@ impl std::fmt::Display for OpCode {
             System      => "System",  
             #[cfg(feature = "a")]
             Amo         => "AMO",
+            #[cfg(feature = "f")]
+            LoadFp      => "I-type: load-fp",
+            #[cfg(feature = "f")]
+            StoreFp     => "S-type: store-fp",
+            #[cfg(feature = "f")]
+            Madd | Msub | Nmsub | Nmadd => "R4-type",
+            #[cfg(feature = "f")]
+            OpFp        => "R-type: op-fp",
         };
         
         f.pad(&format!("{:#02x}({})", opcode, op_str))
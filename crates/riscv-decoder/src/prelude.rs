@@ -1,3 +1,4 @@
 pub use crate::decoder;
 pub use crate::error::*;
 pub use crate::instruction::*;
+pub use crate::isa::{Extension, Isa};
@@ -73,6 +73,52 @@ fn trap_tests() {
     assert_eq!(decompress(ins4).unwrap(), expect4);
 }
 
+#[test]
+#[cfg(all(feature = "f", not(feature = "rv64")))]
+fn float_tests() {
+    use riscv_decoder::prelude::{FOp, FpInsData};
+
+    fn build_f_data(op: FOp, rd: u8, rs1: u8, rs2: u8, imm: i32, raw: u16) -> Instruction {
+        let data = FpInsData { rd, rs1, rs2, rs3: 0, rm: 0, imm };
+        Instruction::F(op, data, raw as u32)
+    }
+
+    let ins1 = 0x60c0; // c.flw f8, 4(x9)
+    assert_eq!(decompress(ins1).unwrap(), build_f_data(FOp::Flw, 8, 9, 8, 4, ins1));
+
+    let ins2 = 0xe504; // c.fsw f9, 8(x10)
+    assert_eq!(decompress(ins2).unwrap(), build_f_data(FOp::Fsw, 9, 10, 9, 8, ins2)); // rd don't care
+
+    let ins3 = 0x60b2; // c.flwsp f1, 12(x2)
+    assert_eq!(decompress(ins3).unwrap(), build_f_data(FOp::Flw, 1, 2, 0, 12, ins3));
+
+    let ins4 = 0xe80a; // c.fswsp f2, 16(x2)
+    assert_eq!(decompress(ins4).unwrap(), build_f_data(FOp::Fsw, 0, 2, 2, 16, ins4));
+}
+
+#[test]
+#[cfg(feature = "d")]
+fn double_tests() {
+    use riscv_decoder::prelude::{DOp, FpInsData};
+
+    fn build_d_data(op: DOp, rd: u8, rs1: u8, rs2: u8, imm: i32, raw: u16) -> Instruction {
+        let data = FpInsData { rd, rs1, rs2, rs3: 0, rm: 0, imm };
+        Instruction::D(op, data, raw as u32)
+    }
+
+    let ins1 = 0x3ca0; // c.fld f8, 120(x9)
+    assert_eq!(decompress(ins1).unwrap(), build_d_data(DOp::Fld, 8, 9, 8, 120, ins1));
+
+    let ins2 = 0xa564; // c.fsd f9, 200(x10)
+    assert_eq!(decompress(ins2).unwrap(), build_d_data(DOp::Fsd, 9, 10, 9, 200, ins2)); // rd don't care
+
+    let ins3 = 0x30fe; // c.fldsp f1, 504(x2)
+    assert_eq!(decompress(ins3).unwrap(), build_d_data(DOp::Fld, 1, 2, 0, 504, ins3));
+
+    let ins4 = 0xa60a; // c.fsdsp f2, 264(x2)
+    assert_eq!(decompress(ins4).unwrap(), build_d_data(DOp::Fsd, 0, 2, 2, 264, ins4));
+}
+
 #[test]
 fn illegal_tests() {
     let ill1 = 0x0000;
@@ -81,8 +127,14 @@ fn illegal_tests() {
     let ill2 = 0x6001;
     assert!(decompress(ill2).is_err());
 
+    // c.fsd f8, 0(x8) when D is not enabled
+    #[cfg(not(feature = "d"))] {
     let ill3 = 0xA000;
     assert!(decompress(ill3).is_err());
+    }
+
+    let ill4 = 0x8000; // Reserved
+    assert!(decompress(ill4).is_err());
 
     let not_compress = 0x0073;
     assert!(decompress(not_compress).is_err());
@@ -0,0 +1,58 @@
+#![cfg(feature = "d")]
+
+use riscv_decoder::decoder::decode;
+use riscv_decoder::instruction::{DOp, FpInsData, Instruction};
+
+#[allow(clippy::too_many_arguments)]
+fn build_d_data(op: DOp, rd: u8, rs1: u8, rs2: u8, rs3: u8, rm: u8, imm: i32, raw: u32) -> Instruction {
+    let data = FpInsData { rd, rs1, rs2, rs3, rm, imm };
+    Instruction::D(op, data, raw)
+}
+
+#[test]
+fn test_load_store() {
+    // fld f1, 8(x2)
+    let ins1 = 0x00813087;
+    let expect1 = build_d_data(DOp::Fld, 1, 2, 8, 0, 0, 8, ins1);
+    // fsd f3, -8(x5)
+    let ins2 = 0xfe32bc27;
+    let expect2 = build_d_data(DOp::Fsd, 24, 5, 3, 0, 0, -8, ins2);
+
+    assert_eq!(decode(ins1), Ok(expect1));
+    assert_eq!(decode(ins2), Ok(expect2));
+}
+
+#[test]
+fn test_fused() {
+    // fmadd.d f1, f2, f3, f4
+    let ins1 = 0x223170c3;
+    let expect1 = build_d_data(DOp::FmaddD, 1, 2, 3, 4, 7, 0, ins1);
+
+    assert_eq!(decode(ins1), Ok(expect1));
+}
+
+#[test]
+fn test_op_fp() {
+    let cases = [
+        (0x027372d3, DOp::FaddD),   // fadd.d f5, f6, f7
+        (0x5a0170d3, DOp::FsqrtD),  // fsqrt.d f1, f2
+        (0x401170d3, DOp::FcvtSD),  // fcvt.s.d f1, f2
+        (0x420100d3, DOp::FcvtDS),  // fcvt.d.s f1, f2
+        (0xc2009553, DOp::FcvtWD),  // fcvt.w.d x10, f1, rtz
+        (0xd21500d3, DOp::FcvtDWu), // fcvt.d.wu f1, x10
+        (0xa220a553, DOp::FeqD),    // feq.d x10, f1, f2
+        (0xe2009553, DOp::FclassD), // fclass.d x10, f1
+        (0x2a3100d3, DOp::FminD),   // fmin.d f1, f2, f3
+        (0x223110d3, DOp::FsgnjnD), // fsgnjn.d f1, f2, f3
+    ];
+
+    for (raw, op) in cases {
+        let Ok(Instruction::D(decoded, _, _)) = decode(raw) else {
+            panic!("{:#010x} should decode as D", raw);
+        };
+        assert_eq!(decoded, op, "Raw: {:#010x}", raw);
+    }
+
+    // fcvt.s.d with rs2 = 0 would be fcvt.s.s
+    assert!(decode(0x400170d3).is_err());
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "f")]
+
+use riscv_decoder::decoder::decode;
+use riscv_decoder::instruction::{FOp, FpInsData, Instruction};
+
+#[allow(clippy::too_many_arguments)]
+fn build_f_data(op: FOp, rd: u8, rs1: u8, rs2: u8, rs3: u8, rm: u8, imm: i32, raw: u32) -> Instruction {
+    let data = FpInsData { rd, rs1, rs2, rs3, rm, imm };
+    Instruction::F(op, data, raw)
+}
+
+#[test]
+fn test_load_store() {
+    // flw f1, 8(x2)
+    let ins1 = 0x00812087;
+    let expect1 = build_f_data(FOp::Flw, 1, 2, 8, 0, 0, 8, ins1);
+    // fsw f3, -4(x5)
+    let ins2 = 0xfe32ae27;
+    let expect2 = build_f_data(FOp::Fsw, 28, 5, 3, 0, 0, -4, ins2);
+
+    assert_eq!(decode(ins1), Ok(expect1));
+    assert_eq!(decode(ins2), Ok(expect2));
+}
+
+#[test]
+fn test_fused() {
+    // fmadd.s f1, f2, f3, f4
+    let ins1 = 0x203170c3;
+    let expect1 = build_f_data(FOp::FmaddS, 1, 2, 3, 4, 7, 0, ins1);
+    // fnmsub.s f1, f2, f3, f4, rtz
+    let ins2 = 0x203110cb;
+    let expect2 = build_f_data(FOp::FnmsubS, 1, 2, 3, 4, 1, 0, ins2);
+
+    assert_eq!(decode(ins1), Ok(expect1));
+    assert_eq!(decode(ins2), Ok(expect2));
+    // fmt = 0b11 is quad precision
+    assert!(decode(0x263170c3).is_err());
+}
+
+#[test]
+fn test_op_fp() {
+    let cases = [
+        (0x007372d3, FOp::FaddS),   // fadd.s f5, f6, f7
+        (0x580170d3, FOp::FsqrtS),  // fsqrt.s f1, f2
+        (0x203120d3, FOp::FsgnjxS), // fsgnjx.s f1, f2, f3
+        (0x283110d3, FOp::FmaxS),   // fmax.s f1, f2, f3
+        (0xc0109553, FOp::FcvtWuS), // fcvt.wu.s x10, f1, rtz
+        (0xd00570d3, FOp::FcvtSW),  // fcvt.s.w f1, x10
+        (0xe0008553, FOp::FmvXW),   // fmv.x.w x10, f1
+        (0xf00500d3, FOp::FmvWX),   // fmv.w.x f1, x10
+        (0xa0209553, FOp::FltS),    // flt.s x10, f1, f2
+        (0xe0009553, FOp::FclassS), // fclass.s x10, f1
+    ];
+
+    for (raw, op) in cases {
+        let Ok(Instruction::F(decoded, _, _)) = decode(raw) else {
+            panic!("{:#010x} should decode as F", raw);
+        };
+        assert_eq!(decoded, op, "Raw: {:#010x}", raw);
+    }
+
+    if let Ok(Instruction::F(op, data, _)) = decode(0xc0109553) {
+        assert!(op.is_rd_int());
+        assert!(op.is_unary());
+        assert_eq!(data.rm, 1);
+    }
+    // fsqrt.s with rs2 != 0 is reserved
+    assert!(decode(0x581170d3).is_err());
+}
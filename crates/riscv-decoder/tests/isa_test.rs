@@ -0,0 +1,79 @@
+use riscv_decoder::IsaError;
+use riscv_decoder::isa::{Extension, Isa};
+
+const XLEN: &str = if cfg!(feature = "rv64") { "rv64" } else { "rv32" };
+
+fn parse(ext: &str) -> Result<Isa, IsaError> {
+    format!("{}{}", XLEN, ext).parse()
+}
+
+#[test]
+fn test_parse_base() {
+    let isa = parse("i").unwrap();
+    assert!(isa.has(Extension::I));
+    assert!(!isa.has(Extension::M));
+    assert_eq!(isa.to_string(), format!("{}i", XLEN));
+    assert_eq!(isa.misa(), 1 << 8);
+
+    assert_eq!("rv128i".parse::<Isa>(), Err(IsaError::BadPrefix("rv128i".to_string())));
+    assert_eq!(parse("mac"), Err(IsaError::MissingBase));
+    assert_eq!(parse("i_zfoo"), Err(IsaError::UnknownExtension("zfoo".to_string())));
+}
+
+#[test]
+#[cfg(all(feature = "m", feature = "a", feature = "c", feature = "zicsr"))]
+fn test_parse_extensions() {
+    let isa = parse("IMAC_Zicsr_Zifencei").unwrap();
+    for ext in [Extension::M, Extension::A, Extension::C, Extension::Zicsr, Extension::Zifencei] {
+        assert!(isa.has(ext), "{} should be enabled", ext);
+    }
+    assert_eq!(isa.to_string(), format!("{}imac_zicsr_zifencei", XLEN));
+    // The first multi-letter extension may follow the letters directly
+    assert_eq!(parse("imaczicsr_zifencei"), Ok(isa));
+}
+
+#[test]
+#[cfg(feature = "d")]
+fn test_parse_general() {
+    let isa = parse("gc").unwrap();
+    for ext in [Extension::M, Extension::A, Extension::F, Extension::D, Extension::Zicsr, Extension::Zifencei] {
+        assert!(isa.has(ext), "{} should be enabled", ext);
+    }
+
+    assert_eq!(parse("id_zicsr"), Err(IsaError::MissingDependency(Extension::D, Extension::F)));
+    // Dropping F takes D with it
+    assert!(!isa.without(Extension::F).has(Extension::D));
+    assert!(!isa.with_misa(isa.misa() & !(1 << 5)).has(Extension::D));
+}
+
+#[test]
+#[cfg(not(feature = "m"))]
+fn test_parse_not_compiled() {
+    assert_eq!(parse("im"), Err(IsaError::NotCompiled(Extension::M)));
+}
+
+#[test]
+#[cfg(feature = "m")]
+fn test_decode_disabled_extension() {
+    use riscv_decoder::DecodeError;
+    use riscv_decoder::decoder::decode_with;
+
+    let isa = parse("i").unwrap();
+    // add x5, x6, x7
+    assert!(decode_with(0x007302b3, &isa).is_ok());
+    // mul x5, x6, x7
+    assert_eq!(decode_with(0x027302b3, &isa), Err(DecodeError::DisabledExtension(Extension::M, 0x027302b3)));
+    assert!(decode_with(0x027302b3, &Isa::default()).is_ok());
+}
+
+#[test]
+#[cfg(feature = "c")]
+fn test_decompress_disabled_extension() {
+    use riscv_decoder::DecodeError;
+    use riscv_decoder::decoder::decompress_with;
+
+    // c.addi x1, 1
+    assert!(decompress_with(0x0085, &Isa::default()).is_ok());
+    let isa = Isa::default().without(Extension::C);
+    assert_eq!(decompress_with(0x0085, &isa), Err(DecodeError::DisabledExtension(Extension::C, 0x0085)));
+}
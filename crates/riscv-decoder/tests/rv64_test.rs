@@ -0,0 +1,172 @@
+#![cfg(feature = "rv64")]
+
+use riscv_decoder::decoder::decode;
+use riscv_decoder::instruction::{Instruction, InstructionData, Rv32iOp};
+
+fn build_base_data(op: Rv32iOp, rd: u8, rs1: u8, rs2: u8, imm: i32) -> Instruction {
+    let data = InstructionData { rd, rs1, rs2, imm };
+    Instruction::Base(op, data)
+}
+
+#[test]
+fn test_load_store_doubleword() {
+    // ld x5, 8(x6)
+    assert_eq!(decode(0x00833283), Ok(build_base_data(Rv32iOp::Ld, 5, 6, 8, 8)));
+    // lwu x5, -4(x6)
+    assert_eq!(decode(0xffc36283), Ok(build_base_data(Rv32iOp::Lwu, 5, 6, 28, -4)));
+    // sd x7, 16(x6)
+    assert_eq!(decode(0x00733823), Ok(build_base_data(Rv32iOp::Sd, 16, 6, 7, 16)));
+}
+
+#[test]
+fn test_shift_amount() {
+    // slli x5, x6, 63
+    assert_eq!(decode(0x03f31293), Ok(build_base_data(Rv32iOp::Slli, 5, 6, 31, 63)));
+    // srai x5, x6, 40
+    let Ok(Instruction::Base(Rv32iOp::Srai, data)) = decode(0x42835293) else {
+        panic!("srai with shamt[5] set should decode");
+    };
+    assert_eq!(data.imm & 0x3f, 40);
+}
+
+#[test]
+fn test_word_ops() {
+    // addiw x5, x6, -1
+    assert_eq!(decode(0xfff3029b), Ok(build_base_data(Rv32iOp::Addiw, 5, 6, 31, -1)));
+    // slliw x5, x6, 31
+    assert_eq!(decode(0x01f3129b), Ok(build_base_data(Rv32iOp::Slliw, 5, 6, 31, 31)));
+    // sraiw x5, x6, 3
+    assert!(matches!(decode(0x4033529b), Ok(Instruction::Base(Rv32iOp::Sraiw, _))));
+    // slliw with shamt[5] set is reserved
+    assert!(decode(0x0213129b).is_err());
+
+    let cases = [
+        (0x007302bb, Rv32iOp::Addw), // addw x5, x6, x7
+        (0x407302bb, Rv32iOp::Subw), // subw x5, x6, x7
+        (0x407352bb, Rv32iOp::Sraw), // sraw x5, x6, x7
+    ];
+    for (raw, op) in cases {
+        assert_eq!(decode(raw), Ok(build_base_data(op, 5, 6, 7, 0)), "Raw: {:#010x}", raw);
+    }
+}
+
+#[test]
+#[cfg(feature = "m")]
+fn test_m_word_ops() {
+    use riscv_decoder::instruction::MOp;
+
+    // mulw x5, x6, x7
+    assert_eq!(decode(0x027302bb), Ok(Instruction::M(MOp::Mulw, InstructionData { rd: 5, rs1: 6, rs2: 7, imm: 0 })));
+    // remuw x5, x6, x7
+    assert_eq!(decode(0x027372bb), Ok(Instruction::M(MOp::Remuw, InstructionData { rd: 5, rs1: 6, rs2: 7, imm: 0 })));
+}
+
+#[test]
+#[cfg(feature = "a")]
+fn test_a_doubleword() {
+    use riscv_decoder::instruction::{AOp, AmoInsData};
+
+    // lr.d x5, (x6)
+    assert_eq!(decode(0x100332af), Ok(Instruction::A(AOp::LrD, AmoInsData { rd: 5, rs1: 6, rs2: 0, rl: 0, aq: 0 })));
+    // amoadd.d x5, x7, (x6)
+    assert_eq!(decode(0x007332af), Ok(Instruction::A(AOp::AmoAddD, AmoInsData { rd: 5, rs1: 6, rs2: 7, rl: 0, aq: 0 })));
+}
+
+#[test]
+#[cfg(feature = "c")]
+fn test_compressed() {
+    use riscv_decoder::decoder::decompress;
+
+    // c.ld x9, 8(x10)
+    assert_eq!(decompress(0x6504), Ok(build_base_data(Rv32iOp::Ld, 9, 10, 9, 8)));
+    // c.sd x9, 16(x10)
+    assert_eq!(decompress(0xe904), Ok(build_base_data(Rv32iOp::Sd, 9, 10, 9, 16)));
+    // c.ldsp x5, 24(x2)
+    assert_eq!(decompress(0x62e2), Ok(build_base_data(Rv32iOp::Ld, 5, 2, 0, 24)));
+    // c.sdsp x5, 32(x2)
+    assert_eq!(decompress(0xf016), Ok(build_base_data(Rv32iOp::Sd, 0, 2, 5, 32)));
+    // c.addiw x5, -3
+    assert_eq!(decompress(0x32f5), Ok(build_base_data(Rv32iOp::Addiw, 5, 5, 0, -3)));
+    // c.addw x8, x9
+    assert_eq!(decompress(0x9c25), Ok(build_base_data(Rv32iOp::Addw, 8, 8, 9, 0)));
+    // c.subw x8, x9
+    assert_eq!(decompress(0x9c05), Ok(build_base_data(Rv32iOp::Subw, 8, 8, 9, 0)));
+}
+
+#[test]
+#[cfg(feature = "d")]
+fn test_fp_long() {
+    use riscv_decoder::instruction::{DOp, FOp};
+
+    // fcvt.l.s x5, f6
+    assert!(matches!(decode(0xc02372d3), Ok(Instruction::F(FOp::FcvtLS, _, _))));
+    // fcvt.d.lu f5, x6
+    assert!(matches!(decode(0xd23372d3), Ok(Instruction::D(DOp::FcvtDLu, _, _))));
+    // fmv.x.d x5, f6
+    assert!(matches!(decode(0xe20302d3), Ok(Instruction::D(DOp::FmvXD, _, _))));
+    // fmv.d.x f5, x6
+    assert!(matches!(decode(0xf20302d3), Ok(Instruction::D(DOp::FmvDX, _, _))));
+}
+
+#[test]
+#[cfg(feature = "zbb")]
+fn test_zbb_encodings() {
+    use riscv_decoder::instruction::ZbbOp;
+
+    // rev8 x5, x6
+    assert!(matches!(decode(0x6b835293), Ok(Instruction::Zbb(ZbbOp::Rev8, _))));
+    // rori x5, x6, 40
+    let Ok(Instruction::Zbb(ZbbOp::Rori, data)) = decode(0x62835293) else {
+        panic!("rori with shamt[5] set should decode");
+    };
+    assert_eq!(data.imm, 40);
+    // zext.h x5, x6
+    assert!(matches!(decode(0x080342bb), Ok(Instruction::Zbb(ZbbOp::ZextH, _))));
+
+    let cases = [
+        (0x6003129b, ZbbOp::Clzw),  // clzw x5, x6
+        (0x6013129b, ZbbOp::Ctzw),  // ctzw x5, x6
+        (0x6023129b, ZbbOp::Cpopw), // cpopw x5, x6
+    ];
+    for (raw, op) in cases {
+        assert_eq!(decode(raw), Ok(Instruction::Zbb(op, InstructionData { rd: 5, rs1: 6, rs2: 0, imm: 0 })), "Raw: {:#010x}", raw);
+    }
+    // rolw x5, x6, x7
+    assert_eq!(decode(0x607312bb), Ok(Instruction::Zbb(ZbbOp::Rolw, InstructionData { rd: 5, rs1: 6, rs2: 7, imm: 0 })));
+    // rorw x5, x6, x7
+    assert_eq!(decode(0x607352bb), Ok(Instruction::Zbb(ZbbOp::Rorw, InstructionData { rd: 5, rs1: 6, rs2: 7, imm: 0 })));
+    // roriw x5, x6, 7
+    assert_eq!(decode(0x6073529b), Ok(Instruction::Zbb(ZbbOp::Roriw, InstructionData { rd: 5, rs1: 6, rs2: 0, imm: 7 })));
+    // roriw with shamt[5] set is reserved
+    assert!(decode(0x6273529b).is_err());
+}
+
+#[test]
+#[cfg(feature = "zba")]
+fn test_zba_encodings() {
+    use riscv_decoder::instruction::ZbaOp;
+
+    let cases = [
+        (0x087302bb, ZbaOp::AddUw),    // add.uw x5, x6, x7
+        (0x207322bb, ZbaOp::Sh1addUw), // sh1add.uw x5, x6, x7
+        (0x207342bb, ZbaOp::Sh2addUw), // sh2add.uw x5, x6, x7
+        (0x207362bb, ZbaOp::Sh3addUw), // sh3add.uw x5, x6, x7
+    ];
+    for (raw, op) in cases {
+        assert_eq!(decode(raw), Ok(Instruction::Zba(op, InstructionData { rd: 5, rs1: 6, rs2: 7, imm: 0 })), "Raw: {:#010x}", raw);
+    }
+    // slli.uw x5, x6, 40
+    assert_eq!(decode(0x0a83129b), Ok(Instruction::Zba(ZbaOp::SlliUw, InstructionData { rd: 5, rs1: 6, rs2: 0, imm: 40 })));
+}
+
+#[test]
+#[cfg(feature = "zbs")]
+fn test_zbs_encodings() {
+    use riscv_decoder::instruction::ZbsOp;
+
+    // bseti x5, x6, 50
+    let Ok(Instruction::Zbs(ZbsOp::Bseti, data)) = decode(0x2b231293) else {
+        panic!("bseti with shamt[5] set should decode");
+    };
+    assert_eq!(data.imm, 50);
+}
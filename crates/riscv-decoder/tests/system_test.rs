@@ -2,7 +2,7 @@
 
 use riscv_decoder::decoder::decode;
 use riscv_decoder::instruction::{Instruction, InstructionData};
-#[cfg(feature = "s")]
+#[cfg(feature = "zicsr")]
 use riscv_decoder::instruction::PrivilegeOp;
 #[cfg(feature = "zicsr")]
 use riscv_decoder::instruction::ZicsrOp; 
@@ -73,5 +73,17 @@ fn  test_privileged() {
 
     assert!(matches!(decode(ins1), Ok(Instruction::Privileged(PrivilegeOp::Sret, _))));
     assert!(matches!(decode(ins2), Ok(Instruction::Privileged(PrivilegeOp::Mret, _))));
+    // mnret
+    assert!(matches!(decode(0x70200073), Ok(Instruction::Privileged(PrivilegeOp::Mnret, _))));
+    // dret
+    assert!(matches!(decode(0x7b200073), Ok(Instruction::Privileged(PrivilegeOp::Dret, _))));
     assert_eq!(decode(ins3), Ok(expect3));
+}
+
+#[test]
+#[cfg(feature = "zicsr")]
+fn test_wfi() {
+    let ins = 0x10500073;
+
+    assert!(matches!(decode(ins), Ok(Instruction::Privileged(PrivilegeOp::Wfi(0x10500073), _))));
 }
\ No newline at end of file
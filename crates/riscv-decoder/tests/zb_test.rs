@@ -0,0 +1,89 @@
+#![cfg(any(feature = "zba", feature = "zbb", feature = "zbc", feature = "zbs"))]
+
+use riscv_decoder::decoder::decode;
+use riscv_decoder::instruction::{Instruction, InstructionData};
+
+fn data(rd: u8, rs1: u8, rs2: u8, imm: i32) -> InstructionData {
+    InstructionData { rd, rs1, rs2, imm }
+}
+
+#[test]
+#[cfg(feature = "zba")]
+fn test_zba() {
+    use riscv_decoder::instruction::ZbaOp;
+
+    // sh1add x1, x2, x3
+    assert_eq!(decode(0x203120b3), Ok(Instruction::Zba(ZbaOp::Sh1add, data(1, 2, 3, 0))));
+    // sh3add x1, x2, x3
+    assert_eq!(decode(0x203160b3), Ok(Instruction::Zba(ZbaOp::Sh3add, data(1, 2, 3, 0))));
+}
+
+#[test]
+#[cfg(feature = "zbb")]
+fn test_zbb() {
+    use riscv_decoder::instruction::ZbbOp;
+
+    let cases = [
+        (0x403170b3, ZbbOp::Andn),  // andn x1, x2, x3
+        (0x403140b3, ZbbOp::Xnor),  // xnor x1, x2, x3
+        (0x0a3160b3, ZbbOp::Max),   // max x1, x2, x3
+        (0x0a3150b3, ZbbOp::Minu),  // minu x1, x2, x3
+        (0x603110b3, ZbbOp::Rol),   // rol x1, x2, x3
+        (0x603150b3, ZbbOp::Ror),   // ror x1, x2, x3
+        #[cfg(not(feature = "rv64"))]
+        (0x080140b3, ZbbOp::ZextH), // zext.h x1, x2
+        (0x60011093, ZbbOp::Clz),   // clz x1, x2
+        (0x60111093, ZbbOp::Ctz),   // ctz x1, x2
+        (0x60211093, ZbbOp::Cpop),  // cpop x1, x2
+        (0x60411093, ZbbOp::SextB), // sext.b x1, x2
+        (0x60511093, ZbbOp::SextH), // sext.h x1, x2
+        (0x28715093, ZbbOp::OrcB),  // orc.b x1, x2
+        #[cfg(not(feature = "rv64"))]
+        (0x69815093, ZbbOp::Rev8),  // rev8 x1, x2
+    ];
+    for (raw, op) in cases {
+        let Ok(Instruction::Zbb(decoded, _)) = decode(raw) else {
+            panic!("{:#010x} should decode as Zbb", raw);
+        };
+        assert_eq!(decoded, op, "Raw: {:#010x}", raw);
+    }
+
+    // rori x1, x2, 7
+    assert_eq!(decode(0x60715093), Ok(Instruction::Zbb(ZbbOp::Rori, data(1, 2, 0, 7))));
+    // Reserved unary encoding
+    assert!(decode(0x60311093).is_err());
+    use riscv_decoder::instruction::Rv32iOp;
+
+    // slli x1, x2, 3 is unaffected
+    assert!(matches!(decode(0x00311093), Ok(Instruction::Base(Rv32iOp::Slli, _))));
+}
+
+#[test]
+#[cfg(feature = "zbc")]
+fn test_zbc() {
+    use riscv_decoder::instruction::ZbcOp;
+
+    // clmul x1, x2, x3
+    assert_eq!(decode(0x0a3110b3), Ok(Instruction::Zbc(ZbcOp::Clmul, data(1, 2, 3, 0))));
+    // clmulr x1, x2, x3
+    assert_eq!(decode(0x0a3120b3), Ok(Instruction::Zbc(ZbcOp::Clmulr, data(1, 2, 3, 0))));
+    // clmulh x1, x2, x3
+    assert_eq!(decode(0x0a3130b3), Ok(Instruction::Zbc(ZbcOp::Clmulh, data(1, 2, 3, 0))));
+}
+
+#[test]
+#[cfg(feature = "zbs")]
+fn test_zbs() {
+    use riscv_decoder::instruction::ZbsOp;
+
+    // bclr x1, x2, x3
+    assert_eq!(decode(0x483110b3), Ok(Instruction::Zbs(ZbsOp::Bclr, data(1, 2, 3, 0))));
+    // binv x1, x2, x3
+    assert_eq!(decode(0x683110b3), Ok(Instruction::Zbs(ZbsOp::Binv, data(1, 2, 3, 0))));
+    // bclri x1, x2, 31
+    assert_eq!(decode(0x49f11093), Ok(Instruction::Zbs(ZbsOp::Bclri, data(1, 2, 0, 31))));
+    // bexti x1, x2, 5
+    assert_eq!(decode(0x48515093), Ok(Instruction::Zbs(ZbsOp::Bexti, data(1, 2, 0, 5))));
+    // bseti x1, x2, 0
+    assert_eq!(decode(0x28011093), Ok(Instruction::Zbs(ZbsOp::Bseti, data(1, 2, 0, 0))));
+}
@@ -3,10 +3,15 @@ pub use CsrAddr::*;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CsrAddr {
     Ustatus,
+    Fflags,
+    Frm,
+    Fcsr,
 
     Sstatus,
     Sie,
     Stvec,
+    Scounteren,
+    Senvcfg,
     Sscratch,
     Sepc,
     Scause,
@@ -15,20 +20,62 @@ pub enum CsrAddr {
     Satp,
 
     Mstatus,
+    Misa,
     Medeleg,
     Mideleg,
     Mie,
     Mtvec,
+    Mcounteren,
+    Menvcfg,
+    MenvcfgH,
+    Mcountinhibit,
+    Mhpmevent(u32),
     Mscratch,
     Mepc,
     Mcause,
     Mtval,
     Mip,
-    Pmpcfg0,
-    Pmpaddr0,
+    Pmpcfg(u32),
+    Pmpaddr(u32),
 
+    Mcycle,
+    Minstret,
+    Mhpmcounter(u32),
+    McycleH,
+    MinstretH,
+    MhpmcounterH(u32),
+
+    Cycle,
+    Time,
+    Instret,
+    Hpmcounter(u32),
+    CycleH,
+    TimeH,
+    InstretH,
+    HpmcounterH(u32),
+
+    Mnscratch,
+    Mnepc,
+    Mncause,
     Mnstatus,
+    Mseccfg,
+    MseccfgH,
+
+    Tselect,
+    Tdata1,
+    Tdata2,
+    Tdata3,
+    Tinfo,
+    Dcsr,
+    Dpc,
+    Dscratch0,
+    Dscratch1,
+
+    Mvendorid,
+    Marchid,
+    Mimpid,
     Mhartid,
+    Mconfigptr,
 }
 
 impl std::fmt::Display for CsrAddr {
@@ -36,10 +83,15 @@ impl std::fmt::Display for CsrAddr {
         f.pad( 
             match self {
                 Ustatus   => "ustatus",
+                Fflags    => "fflags",
+                Frm       => "frm",
+                Fcsr      => "fcsr",
 
                 Sstatus   => "sstatus",
                 Sie       => "sie",
                 Stvec     => "stvec",
+                Scounteren => "scounteren",
+                Senvcfg   => "senvcfg",
                 Sscratch  => "sscratch",
                 Sepc      => "sepc",
                 Scause    => "scause",
@@ -48,19 +100,58 @@ impl std::fmt::Display for CsrAddr {
                 Satp      => "satp",
 
                 Mstatus   => "mstatus",
+                Misa      => "misa",
                 Medeleg   => "medeleg",
                 Mideleg   => "mideleg",
                 Mie       => "mie",
                 Mtvec     => "mtvec",
+                Mcounteren => "mcounteren",
+                Menvcfg   => "menvcfg",
+                MenvcfgH  => "menvcfgh",
+                Mcountinhibit => "mcountinhibit",
                 Mscratch  => "mscratch",
                 Mepc      => "mepc",
                 Mcause    => "mcause",
                 Mtval     => "mtval",
                 Mip       => "mip",
-                Pmpcfg0   => "pmpcfg0",
-                Pmpaddr0  => "pmpaddr0",
+                Mcycle    => "mcycle",
+                Minstret  => "minstret",
+                McycleH   => "mcycleh",
+                MinstretH => "minstreth",
+                Cycle     => "cycle",
+                Time      => "time",
+                Instret   => "instret",
+                CycleH    => "cycleh",
+                TimeH     => "timeh",
+                InstretH  => "instreth",
+                Mnscratch => "mnscratch",
+                Mnepc     => "mnepc",
+                Mncause   => "mncause",
                 Mnstatus  => "mnstatus",
+                Mseccfg   => "mseccfg",
+                MseccfgH  => "mseccfgh",
+                Tselect   => "tselect",
+                Tdata1    => "tdata1",
+                Tdata2    => "tdata2",
+                Tdata3    => "tdata3",
+                Tinfo     => "tinfo",
+                Dcsr      => "dcsr",
+                Dpc       => "dpc",
+                Dscratch0 => "dscratch0",
+                Dscratch1 => "dscratch1",
+                Mvendorid => "mvendorid",
+                Marchid   => "marchid",
+                Mimpid    => "mimpid",
                 Mhartid   => "mhartid",
+                Mconfigptr => "mconfigptr",
+
+                Pmpcfg(n)       => return f.pad(&format!("pmpcfg{}", n)),
+                Pmpaddr(n)      => return f.pad(&format!("pmpaddr{}", n)),
+                Mhpmevent(n)    => return f.pad(&format!("mhpmevent{}", n)),
+                Mhpmcounter(n)  => return f.pad(&format!("mhpmcounter{}", n)),
+                MhpmcounterH(n) => return f.pad(&format!("mhpmcounter{}h", n)),
+                Hpmcounter(n)   => return f.pad(&format!("hpmcounter{}", n)),
+                HpmcounterH(n)  => return f.pad(&format!("hpmcounter{}h", n)),
             }
         )
     }
@@ -71,10 +162,15 @@ impl TryFrom<u32> for CsrAddr {
     fn try_from(value: u32) -> Result<Self, Self::Error> {
         Ok(match value {
             0x000 => Ustatus,
+            0x001 => Fflags,
+            0x002 => Frm,
+            0x003 => Fcsr,
 
             0x100 => Sstatus,
             0x104 => Sie,
             0x105 => Stvec,
+            0x106 => Scounteren,
+            0x10a => Senvcfg,
             0x140 => Sscratch,
             0x141 => Sepc,
             0x142 => Scause,
@@ -83,20 +179,60 @@ impl TryFrom<u32> for CsrAddr {
             0x180 => Satp,
 
             0x300 => Mstatus,
+            0x301 => Misa,
             0x302 => Medeleg,
             0x303 => Mideleg,
             0x304 => Mie,
             0x305 => Mtvec,
+            0x306 => Mcounteren,
+            0x30a => Menvcfg,
+            0x31a => MenvcfgH,
+            0x320 => Mcountinhibit,
+            0x323..=0x33f => Mhpmevent(value - 0x320),
             0x340 => Mscratch,
             0x341 => Mepc,
             0x342 => Mcause,
             0x343 => Mtval,
             0x344 => Mip,
-            0x3a0 => Pmpcfg0,
-            0x3b0 => Pmpaddr0,
+            0x3a0..=0x3af => Pmpcfg(value - 0x3a0),
+            0x3b0..=0x3ef => Pmpaddr(value - 0x3b0),
+
+            0xb00 => Mcycle,
+            0xb02 => Minstret,
+            0xb03..=0xb1f => Mhpmcounter(value - 0xb00),
+            0xb80 => McycleH,
+            0xb82 => MinstretH,
+            0xb83..=0xb9f => MhpmcounterH(value - 0xb80),
+
+            0xc00 => Cycle,
+            0xc01 => Time,
+            0xc02 => Instret,
+            0xc03..=0xc1f => Hpmcounter(value - 0xc00),
+            0xc80 => CycleH,
+            0xc81 => TimeH,
+            0xc82 => InstretH,
+            0xc83..=0xc9f => HpmcounterH(value - 0xc80),
 
+            0x740 => Mnscratch,
+            0x741 => Mnepc,
+            0x742 => Mncause,
             0x744 => Mnstatus,
-            0xf14 => Mhartid,   
+            0x747 => Mseccfg,
+            0x757 => MseccfgH,
+            0x7a0 => Tselect,
+            0x7a1 => Tdata1,
+            0x7a2 => Tdata2,
+            0x7a3 => Tdata3,
+            0x7a4 => Tinfo,
+            0x7b0 => Dcsr,
+            0x7b1 => Dpc,
+            0x7b2 => Dscratch0,
+            0x7b3 => Dscratch1,
+            0xf11 => Mvendorid,
+            0xf12 => Marchid,
+            0xf13 => Mimpid,
+            0xf14 => Mhartid,
+            0xf15 => Mconfigptr,
             _     => return Err(value),
         })
     }
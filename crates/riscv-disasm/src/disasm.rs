@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use riscv_loader::LoadInfo;
+use riscv_loader::{Addr, LoadInfo};
 use riscv_decoder::decoder::decode;
 #[cfg(feature = "c")]
 use riscv_decoder::decoder::decompress;
@@ -8,7 +8,7 @@ use riscv_decoder::decoder::decompress;
 use crate::instructions::ins_to_string;
 
 #[cfg(not(feature = "c"))]
-pub fn disassembler(info: &LoadInfo) -> Vec<(u32, String)> {
+pub fn disassembler(info: &LoadInfo) -> Vec<(Addr, String)> {
     let empty_table = HashMap::new();
     let sym_table = info.symbols.as_ref().unwrap_or(&empty_table);
 
@@ -16,7 +16,7 @@ pub fn disassembler(info: &LoadInfo) -> Vec<(u32, String)> {
         code.chunks_exact(4)
             .enumerate()
             .flat_map(move |(i, chunk)| {
-                let curr_addr = base + i as u32 * 4;
+                let curr_addr = base + i as Addr * 4;
                 let raw = u32::from_le_bytes(chunk.try_into().unwrap());
             
                 let mut lines = Vec::new();
@@ -39,7 +39,7 @@ pub fn disassembler(info: &LoadInfo) -> Vec<(u32, String)> {
 }
 
 #[cfg(feature = "c")]
-pub fn disassembler(info: &LoadInfo) -> Vec<(u32, String)> {
+pub fn disassembler(info: &LoadInfo) -> Vec<(Addr, String)> {
     let empty_table = HashMap::new();
     let sym_table = info.symbols.as_ref().unwrap_or(&empty_table);
 
@@ -50,7 +50,7 @@ pub fn disassembler(info: &LoadInfo) -> Vec<(u32, String)> {
         let len = code_bytes.len();
 
         while offset < len {
-            let curr_addr = base_addr + offset as u32;
+            let curr_addr = base_addr + offset as Addr;
 
             if offset + 2 > len {
                 res.push((curr_addr, format!("{:#010x}: (Incomplete)", curr_addr)));
@@ -1,18 +1,19 @@
 use std::collections::HashMap;
 
 use riscv_decoder::instruction::Instruction::{self, *};
+use riscv_loader::Addr;
 
 #[cfg(feature = "zicsr")]
 use crate::csr_addr::CsrAddr;
 
-pub fn ins_to_string(ins: Instruction, addr: u32, sym_table: &HashMap<u32, String>) -> String {   
+pub fn ins_to_string(ins: Instruction, addr: Addr, sym_table: &HashMap<Addr, String>) -> String {   
     match ins {
         Base(op, data) => {
             if op.is_itype_ar() {
                 format!("{:<7} x{}, x{}, {}", op, data.rd, data.rs1, data.imm)
             } else if op.is_itype_load() | op.is_itype_jump() {
                 format!("{:<7} x{}, {}(x{})", op, data.rd, 
-                    sym_table.get(&(addr.wrapping_add_signed(data.imm))).unwrap_or(&data.imm.to_string()), 
+                    sym_table.get(&(addr.wrapping_add(data.imm as Addr))).unwrap_or(&data.imm.to_string()), 
                     data.rs1)
             } else if op.is_itype_fence() {
                 match data.imm & 0xf00 {
@@ -36,7 +37,7 @@ pub fn ins_to_string(ins: Instruction, addr: u32, sym_table: &HashMap<u32, Strin
                 format!("{:<7} x{}, x{}, {}", op, data.rs1, data.rs2, data.imm)
             } else if op.is_jtype() {
                 format!("{:<7} x{}, {}", op, data.rd, 
-                    sym_table.get(&(addr.wrapping_add_signed(data.imm))).unwrap_or(&data.imm.to_string())
+                    sym_table.get(&(addr.wrapping_add(data.imm as Addr))).unwrap_or(&data.imm.to_string())
                 )
             } else if op.is_utype() {
                 format!("{:<7} x{}, {:#x}", op, data.rd, (data.imm as u32) >> 12)
@@ -84,6 +85,72 @@ pub fn ins_to_string(ins: Instruction, addr: u32, sym_table: &HashMap<u32, Strin
         Zifencei(op, _)=> {
             format!("{:<7}", op)
         },
+        #[cfg(feature = "f")]
+        F(op, data, _) => {
+            if op.is_load() {
+                format!("{:<9} f{}, {}(x{})", op, data.rd, data.imm, data.rs1)
+            } else if op.is_store() {
+                format!("{:<9} f{}, {}(x{})", op, data.rs2, data.imm, data.rs1)
+            } else if op.is_fused() {
+                format!("{:<9} f{}, f{}, f{}, f{}", op, data.rd, data.rs1, data.rs2, data.rs3)
+            } else {
+                let rd = if op.is_rd_int() { 'x' } else { 'f' };
+                let rs1 = if op.is_rs1_int() { 'x' } else { 'f' };
+                if op.is_unary() {
+                    format!("{:<9} {}{}, {}{}", op, rd, data.rd, rs1, data.rs1)
+                } else {
+                    format!("{:<9} {}{}, f{}, f{}", op, rd, data.rd, data.rs1, data.rs2)
+                }
+            }
+        },
+        #[cfg(feature = "d")]
+        D(op, data, _) => {
+            if op.is_load() {
+                format!("{:<9} f{}, {}(x{})", op, data.rd, data.imm, data.rs1)
+            } else if op.is_store() {
+                format!("{:<9} f{}, {}(x{})", op, data.rs2, data.imm, data.rs1)
+            } else if op.is_fused() {
+                format!("{:<9} f{}, f{}, f{}, f{}", op, data.rd, data.rs1, data.rs2, data.rs3)
+            } else {
+                let rd = if op.is_rd_int() { 'x' } else { 'f' };
+                let rs1 = if op.is_rs1_int() { 'x' } else { 'f' };
+                if op.is_unary() {
+                    format!("{:<9} {}{}, {}{}", op, rd, data.rd, rs1, data.rs1)
+                } else {
+                    format!("{:<9} {}{}, f{}, f{}", op, rd, data.rd, data.rs1, data.rs2)
+                }
+            }
+        },
+        #[cfg(feature = "zba")]
+        Zba(op, data) => {
+            if op.is_imm() {
+                format!("{:<7} x{}, x{}, {}", op, data.rd, data.rs1, data.imm)
+            } else {
+                format!("{:<7} x{}, x{}, x{}", op, data.rd, data.rs1, data.rs2)
+            }
+        },
+        #[cfg(feature = "zbb")]
+        Zbb(op, data) => {
+            if op.is_unary() {
+                format!("{:<7} x{}, x{}", op, data.rd, data.rs1)
+            } else if op.is_imm() {
+                format!("{:<7} x{}, x{}, {}", op, data.rd, data.rs1, data.imm)
+            } else {
+                format!("{:<7} x{}, x{}, x{}", op, data.rd, data.rs1, data.rs2)
+            }
+        },
+        #[cfg(feature = "zbc")]
+        Zbc(op, data) => {
+            format!("{:<7} x{}, x{}, x{}", op, data.rd, data.rs1, data.rs2)
+        },
+        #[cfg(feature = "zbs")]
+        Zbs(op, data) => {
+            if op.is_imm() {
+                format!("{:<7} x{}, x{}, {}", op, data.rd, data.rs1, data.imm)
+            } else {
+                format!("{:<7} x{}, x{}, x{}", op, data.rd, data.rs1, data.rs2)
+            }
+        },
     }
 }
 
@@ -146,4 +213,99 @@ mod tests {
         let result = ins_to_string(ins, addr, &sym_table);
         assert_eq!(result, "jal     x1, target_label");
     }
+
+    #[test]
+    #[cfg(feature = "f")]
+    fn test_float_disasm() {
+        use riscv_decoder::instruction::{FOp, FpInsData};
+
+        let sym_table = HashMap::new();
+        let addr = 0x80000000;
+        let data = FpInsData { rd: 1, rs1: 2, rs2: 3, rs3: 4, rm: 7, imm: 8 };
+
+        let ins = Instruction::F(FOp::Flw, data, 0);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "flw       f1, 8(x2)");
+
+        let ins = Instruction::F(FOp::FmaddS, data, 0);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "fmadd.s   f1, f2, f3, f4");
+
+        let ins = Instruction::F(FOp::FcvtWS, data, 0);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "fcvt.w.s  x1, f2");
+
+        let ins = Instruction::F(FOp::FltS, data, 0);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "flt.s     x1, f2, f3");
+
+        #[cfg(feature = "d")] {
+        use riscv_decoder::instruction::DOp;
+
+        let ins = Instruction::D(DOp::Fsd, data, 0);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "fsd       f3, 8(x2)");
+
+        let ins = Instruction::D(DOp::FcvtDWu, data, 0);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "fcvt.d.wu f1, x2");
+        }
+    }
+    #[test]
+    #[cfg(all(feature = "zbb", feature = "zbs"))]
+    fn test_bit_manipulation_disasm() {
+        use riscv_decoder::instruction::{ZbbOp, ZbsOp};
+
+        let sym_table = HashMap::new();
+        let addr = 0x80000000;
+        let data = InstructionData { rd: 1, rs1: 2, rs2: 3, imm: 7 };
+
+        let ins = Instruction::Zbb(ZbbOp::Andn, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "andn    x1, x2, x3");
+
+        let ins = Instruction::Zbb(ZbbOp::SextB, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "sext.b  x1, x2");
+
+        let ins = Instruction::Zbb(ZbbOp::Rori, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "rori    x1, x2, 7");
+
+        let ins = Instruction::Zbs(ZbsOp::Bseti, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "bseti   x1, x2, 7");
+    }
+
+    #[test]
+    #[cfg(feature = "rv64")]
+    fn test_rv64_disasm() {
+        let sym_table = HashMap::new();
+        let addr = 0x80000000;
+        let data = InstructionData { rd: 1, rs1: 2, rs2: 3, imm: -8 };
+
+        let ins = Instruction::Base(Rv32iOp::Ld, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "ld      x1, -8(x2)");
+
+        let ins = Instruction::Base(Rv32iOp::Sd, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "sd      x3, -8(x2)");
+
+        let ins = Instruction::Base(Rv32iOp::Addiw, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "addiw   x1, x2, -8");
+
+        let ins = Instruction::Base(Rv32iOp::Subw, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "subw    x1, x2, x3");
+    }
+
+    #[test]
+    #[cfg(all(feature = "rv64", feature = "zba", feature = "zbb"))]
+    fn test_rv64_bit_manipulation_disasm() {
+        use riscv_decoder::instruction::{ZbaOp, ZbbOp};
+
+        let sym_table = HashMap::new();
+        let addr = 0x80000000;
+        let data = InstructionData { rd: 1, rs1: 2, rs2: 3, imm: 40 };
+
+        let ins = Instruction::Zba(ZbaOp::Sh2addUw, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "sh2add.uw x1, x2, x3");
+
+        let ins = Instruction::Zba(ZbaOp::SlliUw, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "slli.uw x1, x2, 40");
+
+        let ins = Instruction::Zbb(ZbbOp::Cpopw, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "cpopw   x1, x2");
+
+        let ins = Instruction::Zbb(ZbbOp::Rolw, data);
+        assert_eq!(ins_to_string(ins, addr, &sym_table), "rolw    x1, x2, x3");
+    }
 }
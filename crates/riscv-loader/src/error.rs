@@ -11,12 +11,15 @@ pub enum LoadError {
     #[error("Can not read raw binary: {0}")]
     ReadRawBinaryFailed(String),
 
-    #[error("Only support 32-bit now")]
+    #[error("ELF class does not match the target XLEN")]
     NotSupportClass,
 
     #[error("Can not read .elf's program headers")]
     ReadProgramHeadersFailed,
 
+    #[error("Not a device tree blob")]
+    NotDtbFile,
+
     #[error("Not .elf file")]
     NotElfFile(Vec<u8>),
 
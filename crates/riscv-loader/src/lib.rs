@@ -3,5 +3,11 @@ mod loader;
 mod load_info;
 
 pub use error::LoadError;
-pub use loader::load;
-pub use load_info::LoadInfo;
\ No newline at end of file
+pub use loader::{load, load_at, load_dtb};
+pub use load_info::LoadInfo;
+
+/// Address width of the target, `XLEN`
+#[cfg(not(feature = "rv64"))]
+pub type Addr = u32;
+#[cfg(feature = "rv64")]
+pub type Addr = u64;
\ No newline at end of file
@@ -1,15 +1,19 @@
 //! Handle ELF file load by file path 
 
 mod binary;
+mod dtb;
 mod elf;
 
 use std::path::Path;
 
+use crate::Addr;
 use crate::error::LoadError;
 use crate::load_info::LoadInfo;
 
 use elf::load_elf;
 
+pub use dtb::load_dtb;
+
 /// Dispatch `filepath` to `load_elf`
 /// Return `LoadInfo` for Risc-V to load into memory
 /// If the target file is not ELF file, call `read_binary` instead
@@ -27,3 +31,18 @@ pub fn load<P: AsRef<Path>>(filepath: &P) -> Result<LoadInfo, LoadError> {
         _ => Err(e),
     })
 }
+
+/// Like `load`, but a raw binary goes to `addr` and its entry point is `addr`
+/// An ELF file keeps the addresses in its program headers
+/// Use it for images at fixed addresses, like a kernel or initramfs after the firmware
+/// ## Example
+/// ```rust,no_run
+/// # use riscv_loader::load_at;
+/// let kernel = load_at(&"Image", 0x8040_0000).expect("Get LoadInfo successed");
+/// ```
+pub fn load_at<P: AsRef<Path>>(filepath: &P, addr: Addr) -> Result<LoadInfo, LoadError> {
+    load_elf(filepath).or_else(|e| match e {
+        LoadError::NotElfFile(raw_binary) => Ok(LoadInfo::new(addr, raw_binary, addr)),
+        _ => Err(e),
+    })
+}
@@ -0,0 +1,31 @@
+//! Handle device tree blob load
+
+use std::path::Path;
+
+use crate::error::LoadError;
+
+use super::binary::read_binary;
+
+const FDT_MAGIC: u32 = 0xd00d_feed;
+
+/// Read a flattened device tree blob (`.dtb`) from `filepath`
+/// Check its magic number and size, anything after `totalsize` is dropped
+/// ## Example
+/// ```rust,no_run
+/// # use riscv_loader::load_dtb;
+/// let dtb = load_dtb(&"machine.dtb").expect("Get device tree successed");
+/// ```
+pub fn load_dtb<P: AsRef<Path>>(filepath: &P) -> Result<Vec<u8>, LoadError> {
+    let mut content = read_binary(filepath)?;
+
+    // The header is big-endian: magic, then totalsize
+    let word = |offset: usize| content.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+    let size = match (word(0), word(4)) {
+        (Some(FDT_MAGIC), Some(size)) if size as usize <= content.len() => size as usize,
+        _ => return Err(LoadError::NotDtbFile),
+    };
+
+    content.truncate(size);
+    Ok(content)
+}
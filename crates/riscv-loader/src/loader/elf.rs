@@ -8,6 +8,7 @@ use elf::{ElfBytes, abi};
 use elf::endian::LittleEndian;
 use elf::file::Class;
 
+use crate::Addr;
 use crate::error::LoadError;
 use crate::load_info::LoadInfo;
 
@@ -29,14 +30,18 @@ pub fn load_elf<P: AsRef<Path>>(filepath: &P) -> Result<LoadInfo, LoadError> {
     if file.ehdr.e_machine != abi::EM_RISCV {
         return Err(LoadError::NotRiscVArc(file.ehdr.e_machine));
     }    
-    if file.ehdr.class != Class::ELF32 {
+    #[cfg(not(feature = "rv64"))]
+    let class = Class::ELF32;
+    #[cfg(feature = "rv64")]
+    let class = Class::ELF64;
+    if file.ehdr.class != class {
         return Err(LoadError::NotSupportClass);
     }
 
     
     let mut info = get_elf_program_header(&file, &file_data)?;
         
-    info.pc_entry = file.ehdr.e_entry as u32;
+    info.pc_entry = file.ehdr.e_entry as Addr;
 
     info.header_sections = get_elf_header_sections(&file).ok();
 
@@ -55,7 +60,7 @@ fn get_elf_program_header(elf_file : &ElfBytes<LittleEndian>, file_data: &[u8])
             continue;
         }
 
-        let addr = seg.p_vaddr as u32;
+        let addr = seg.p_vaddr as Addr;
         let mem_size = seg.p_memsz as usize;
         let file_size = seg.p_filesz as usize;
         let offset = seg.p_offset as usize;
@@ -75,7 +80,7 @@ fn get_elf_program_header(elf_file : &ElfBytes<LittleEndian>, file_data: &[u8])
 
             if mem_size > file_size {
                 let bss_size = mem_size - file_size;
-                let bss_start = addr + file_size as u32;
+                let bss_start = addr + file_size as Addr;
                 info.set_bss(bss_start, bss_size);
             }
         } else {
@@ -86,7 +91,7 @@ fn get_elf_program_header(elf_file : &ElfBytes<LittleEndian>, file_data: &[u8])
     Ok(info)
 }
 
-fn get_elf_header_sections(elf_file : &ElfBytes<LittleEndian>) -> Result<Vec<(String, u32)>, LoadError> {
+fn get_elf_header_sections(elf_file : &ElfBytes<LittleEndian>) -> Result<Vec<(String, Addr)>, LoadError> {
     let (shdrs_opt, strtab_opt) = elf_file.section_headers_with_strtab()
         .map_err(|e| LoadError::GetElfSectionHeaderFailed(e.to_string()))?;
 
@@ -97,7 +102,7 @@ fn get_elf_header_sections(elf_file : &ElfBytes<LittleEndian>) -> Result<Vec<(St
             .map(|shdr| {
                 let name = strtab.get(shdr.sh_name as usize)
                     .map_err(|e| LoadError::GetElfSectionHeaderNameFailed(e.to_string()))?;
-                Ok((name.to_string(), shdr.sh_addr as u32))
+                Ok((name.to_string(), shdr.sh_addr as Addr))
             })
             .collect()
         },
@@ -105,7 +110,7 @@ fn get_elf_header_sections(elf_file : &ElfBytes<LittleEndian>) -> Result<Vec<(St
     }
 }
 
-fn get_elf_symtab(elf_file : &ElfBytes<LittleEndian>) -> Result<HashMap<u32, String>, LoadError> {
+fn get_elf_symtab(elf_file : &ElfBytes<LittleEndian>) -> Result<HashMap<Addr, String>, LoadError> {
     let opt = elf_file.symbol_table()
         .map_err(|e| LoadError::GetElfSectionHeaderFailed(e.to_string()))?;
 
@@ -116,7 +121,7 @@ fn get_elf_symtab(elf_file : &ElfBytes<LittleEndian>) -> Result<HashMap<u32, Str
             .map(|sym| {
                 let name = strtab.get(sym.st_name as usize)
                     .map_err(|e| LoadError::GetElfSectionHeaderNameFailed(e.to_string()));
-                (sym.st_value as u32, name)
+                (sym.st_value as Addr, name)
             })
             .filter_map(|(val, name_res)| {
                 match name_res {